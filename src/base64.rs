@@ -0,0 +1,135 @@
+//! Base64 (RFC 4648) with the standard alphabet and padding, the PEM armor
+//! built on it (also used for OpenSSH keys and signatures), and the unpadded
+//! URL-safe form JOSE uses.
+
+use alloc::{string::String, vec::Vec};
+
+#[cfg(any(feature = "x509", feature = "ssh"))]
+use crate::secret::wipe;
+
+#[cfg(any(feature = "x509", feature = "ssh"))]
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+#[cfg(feature = "jose")]
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn encode_with(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+  let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+  for chunk in data.chunks(3) {
+    let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(alphabet[(n >> (18 - 6 * i)) as usize & 63] as char);
+      } else if pad {
+        out.push('=');
+      }
+    }
+  }
+  out
+}
+
+/// Decodes `s`, which must be canonical: no whitespace, padding exactly
+/// when `pad` is set, and zero bits after the last byte.
+fn decode_with(s: &str, alphabet: &[u8; 64], pad: bool) -> Result<Vec<u8>, &'static str> {
+  let mut s = s.as_bytes();
+  if pad {
+    if !s.len().is_multiple_of(4) {
+      return Err("Invalid base64 length");
+    }
+    let padding = s.iter().rev().take_while(|&&c| c == b'=').count();
+    if padding > 2 {
+      return Err("Invalid base64 padding");
+    }
+    s = &s[..s.len() - padding];
+  }
+  if s.len() % 4 == 1 {
+    return Err("Invalid base64 length");
+  }
+  let mut out = Vec::with_capacity(s.len() * 3 / 4);
+  for chunk in s.chunks(4) {
+    let mut n = 0u32;
+    for (i, &c) in chunk.iter().enumerate() {
+      let v = alphabet.iter().position(|&a| a == c).ok_or("Invalid base64 character")?;
+      n |= (v as u32) << (18 - 6 * i);
+    }
+    let bytes = n.to_be_bytes();
+    let len = chunk.len() - 1;
+    out.extend_from_slice(&bytes[1..1 + len]);
+    if bytes[1 + len..].iter().any(|&b| b != 0) {
+      return Err("Invalid base64 trailing bits");
+    }
+  }
+  Ok(out)
+}
+
+#[cfg(any(feature = "x509", feature = "ssh"))]
+pub fn encode(data: &[u8]) -> String {
+  encode_with(data, STANDARD, true)
+}
+
+#[cfg(any(feature = "x509", feature = "ssh"))]
+pub fn decode(s: &str) -> Result<Vec<u8>, &'static str> {
+  decode_with(s, STANDARD, true)
+}
+
+/// base64url without padding (RFC 7515, section 2).
+#[cfg(feature = "jose")]
+pub fn encode_url(data: &[u8]) -> String {
+  encode_with(data, URL_SAFE, false)
+}
+
+#[cfg(feature = "jose")]
+pub fn decode_url(s: &str) -> Result<Vec<u8>, &'static str> {
+  decode_with(s, URL_SAFE, false)
+}
+
+/// PEM armor with 64-character lines.
+#[cfg(feature = "x509")]
+pub fn pem_encode(label: &str, der: &[u8]) -> String {
+  pem_encode_width(label, der, 64)
+}
+
+/// PEM-style armor with `width`-character lines; OpenSSH wraps at 70.
+///
+/// `data` may be a private key, so the output is allocated once and the
+/// unwrapped base64 is wiped: no copy of it is left in freed memory.
+#[cfg(any(feature = "x509", feature = "ssh"))]
+pub fn pem_encode_width(label: &str, data: &[u8], width: usize) -> String {
+  let body = encode(data);
+  let armor = "-----BEGIN -----\n-----END -----\n".len() + 2 * label.len();
+  let mut out = String::with_capacity(armor + body.len() + body.len().div_ceil(width));
+  out.push_str("-----BEGIN ");
+  out.push_str(label);
+  out.push_str("-----\n");
+  for line in body.as_bytes().chunks(width) {
+    out.push_str(core::str::from_utf8(line).unwrap());
+    out.push('\n');
+  }
+  out.push_str("-----END ");
+  out.push_str(label);
+  out.push_str("-----\n");
+  wipe(body.into_bytes());
+  out
+}
+
+/// The contents of the first `label` block in `pem`. Line breaks inside
+/// the body are ignored. As with `pem_encode_width`, the intermediate
+/// base64 is allocated once and wiped; the caller owns the result.
+#[cfg(any(feature = "x509", feature = "ssh"))]
+pub fn pem_decode(label: &str, pem: &str) -> Result<Vec<u8>, &'static str> {
+  let mut begin = String::from("-----BEGIN ");
+  begin.push_str(label);
+  begin.push_str("-----");
+  let mut end = String::from("-----END ");
+  end.push_str(label);
+  end.push_str("-----");
+  let start = pem.find(&begin).ok_or("PEM label not found")? + begin.len();
+  let stop = start + pem[start..].find(&end).ok_or("Unterminated PEM block")?;
+  let mut body = String::with_capacity(stop - start);
+  for piece in pem[start..stop].split_whitespace() {
+    body.push_str(piece);
+  }
+  let data = decode(&body);
+  wipe(body.into_bytes());
+  data
+}
@@ -0,0 +1,248 @@
+//! The CBOR (RFC 8949) subset COSE needs. The writer builds each item from
+//! already-encoded children and sorts map keys in the deterministic order;
+//! the reader takes whole items with definite lengths, shortest-form heads
+//! and unique map keys.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+const MALFORMED: &str = "Malformed CBOR";
+const MAX_DEPTH: usize = 16;
+
+const UINT: u8 = 0;
+const NINT: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+  Int(i64),
+  Bytes(Vec<u8>),
+  Text(String),
+  Array(Vec<Value>),
+  Map(Vec<(Value, Value)>),
+  Tag(u64, Box<Value>),
+  Bool(bool),
+  Null,
+  Undefined,
+  Simple(u8),
+  Float(f64),
+}
+
+impl Value {
+  /// Decodes `data`, which must hold exactly one item.
+  pub fn decode(data: &[u8]) -> Result<Value, &'static str> {
+    let mut p = Parser { data, pos: 0 };
+    let value = p.item(0)?;
+    if p.pos != data.len() {
+      return Err("Trailing CBOR data");
+    }
+    Ok(value)
+  }
+
+  pub fn as_int(&self) -> Option<i64> {
+    match self {
+      Value::Int(n) => Some(*n),
+      _ => None,
+    }
+  }
+
+  pub fn as_bytes(&self) -> Option<&[u8]> {
+    match self {
+      Value::Bytes(b) => Some(b),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Value]> {
+    match self {
+      Value::Array(a) => Some(a),
+      _ => None,
+    }
+  }
+
+  pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+    match self {
+      Value::Map(m) => Some(m),
+      _ => None,
+    }
+  }
+
+  /// The entry of a map under the integer key `label`.
+  pub fn get(&self, label: i64) -> Option<&Value> {
+    self.as_map()?.iter().find(|(k, _)| *k == Value::Int(label)).map(|(_, v)| v)
+  }
+
+  /// Like `get`, but mutable, so that a secret can be moved out and wiped.
+  pub fn get_mut(&mut self, label: i64) -> Option<&mut Value> {
+    match self {
+      Value::Map(m) => m.iter_mut().find(|(k, _)| *k == Value::Int(label)).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+}
+
+fn head(major: u8, n: u64) -> Vec<u8> {
+  let mut out = Vec::with_capacity(9);
+  let m = major << 5;
+  match n {
+    0..24 => out.push(m | n as u8),
+    24..0x100 => out.extend_from_slice(&[m | 24, n as u8]),
+    0x100..0x10000 => {
+      out.push(m | 25);
+      out.extend_from_slice(&(n as u16).to_be_bytes());
+    }
+    0x10000..0x1_0000_0000 => {
+      out.push(m | 26);
+      out.extend_from_slice(&(n as u32).to_be_bytes());
+    }
+    _ => {
+      out.push(m | 27);
+      out.extend_from_slice(&n.to_be_bytes());
+    }
+  }
+  out
+}
+
+pub fn int(n: i64) -> Vec<u8> {
+  if n < 0 { head(NINT, !n as u64) } else { head(UINT, n as u64) }
+}
+
+pub fn bytes(b: &[u8]) -> Vec<u8> {
+  let mut out = head(BYTES, b.len() as u64);
+  out.extend_from_slice(b);
+  out
+}
+
+pub fn text(s: &str) -> Vec<u8> {
+  let mut out = head(TEXT, s.len() as u64);
+  out.extend_from_slice(s.as_bytes());
+  out
+}
+
+pub fn array(items: &[&[u8]]) -> Vec<u8> {
+  let mut out = head(ARRAY, items.len() as u64);
+  for item in items {
+    out.extend_from_slice(item);
+  }
+  out
+}
+
+/// A map with its keys in the bytewise order of their encodings, as
+/// deterministic encoding (RFC 8949, section 4.2.1) requires. `entries` is
+/// sorted in place and left to the caller, which may hold secrets in it; the
+/// output is allocated once, so no partial copy is left in freed memory.
+pub fn map(entries: &mut [(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+  entries.sort();
+  let len: usize = entries.iter().map(|(k, v)| k.len() + v.len()).sum();
+  let mut out = Vec::with_capacity(9 + len);
+  out.extend_from_slice(&head(MAP, entries.len() as u64));
+  for (key, value) in entries.iter() {
+    out.extend_from_slice(key);
+    out.extend_from_slice(value);
+  }
+  out
+}
+
+pub fn tag(n: u64, item: &[u8]) -> Vec<u8> {
+  let mut out = head(TAG, n);
+  out.extend_from_slice(item);
+  out
+}
+
+/// A binary16 float widened to `f64`.
+fn half_to_f64(h: u16) -> f64 {
+  let sign = (h as u64 >> 15) << 63;
+  let exp = (h >> 10) & 0x1f;
+  let mant = (h & 0x3ff) as u64;
+  let magnitude = match exp {
+    0 => mant as f64 / (1u64 << 24) as f64,
+    31 => f64::from_bits(0x7ff << 52 | mant << 42),
+    _ => f64::from_bits((exp as u64 + 1023 - 15) << 52 | mant << 42),
+  };
+  f64::from_bits(sign | magnitude.to_bits())
+}
+
+struct Parser<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn take(&mut self, n: u64) -> Result<&'a [u8], &'static str> {
+    if n > (self.data.len() - self.pos) as u64 {
+      return Err(MALFORMED);
+    }
+    let out = &self.data[self.pos..self.pos + n as usize];
+    self.pos += n as usize;
+    Ok(out)
+  }
+
+  /// The major type and argument of the next head.
+  fn head(&mut self) -> Result<(u8, u8, u64), &'static str> {
+    let ib = self.take(1)?[0];
+    let (major, ai) = (ib >> 5, ib & 0x1f);
+    let (n, min) = match ai {
+      0..24 => return Ok((major, ai, ai as u64)),
+      24 => (self.take(1)?[0] as u64, 24),
+      25 => (u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64, 0x100),
+      26 => (u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64, 0x10000),
+      27 => (u64::from_be_bytes(self.take(8)?.try_into().unwrap()), 0x1_0000_0000),
+      31 if major != SIMPLE => return Err("Unsupported indefinite-length CBOR"),
+      _ => return Err(MALFORMED),
+    };
+    // Floats use the wider heads for their width, not their value.
+    if major != SIMPLE && n < min {
+      return Err(MALFORMED);
+    }
+    Ok((major, ai, n))
+  }
+
+  fn item(&mut self, depth: usize) -> Result<Value, &'static str> {
+    if depth > MAX_DEPTH {
+      return Err("CBOR nested too deeply");
+    }
+    let (major, ai, n) = self.head()?;
+    Ok(match major {
+      UINT => Value::Int(i64::try_from(n).map_err(|_| "CBOR integer out of range")?),
+      NINT => Value::Int(-1 - i64::try_from(n).map_err(|_| "CBOR integer out of range")?),
+      BYTES => Value::Bytes(self.take(n)?.to_vec()),
+      TEXT => Value::Text(String::from_utf8(self.take(n)?.to_vec()).map_err(|_| MALFORMED)?),
+      ARRAY => {
+        // Every item takes at least a byte, which bounds the allocation.
+        let mut items = Vec::with_capacity(n.min((self.data.len() - self.pos) as u64) as usize);
+        for _ in 0..n {
+          items.push(self.item(depth + 1)?);
+        }
+        Value::Array(items)
+      }
+      MAP => {
+        let mut entries: Vec<(Value, Value)> = Vec::with_capacity(n.min((self.data.len() - self.pos) as u64) as usize);
+        for _ in 0..n {
+          let key = self.item(depth + 1)?;
+          if entries.iter().any(|(k, _)| *k == key) {
+            return Err("Duplicate CBOR map key");
+          }
+          let value = self.item(depth + 1)?;
+          entries.push((key, value));
+        }
+        Value::Map(entries)
+      }
+      TAG => Value::Tag(n, Box::new(self.item(depth + 1)?)),
+      _ => match ai {
+        20 => Value::Bool(false),
+        21 => Value::Bool(true),
+        22 => Value::Null,
+        23 => Value::Undefined,
+        24 if n < 32 => return Err(MALFORMED),
+        0..20 | 24 => Value::Simple(n as u8),
+        25 => Value::Float(half_to_f64(n as u16)),
+        26 => Value::Float(f32::from_bits(n as u32) as f64),
+        _ => Value::Float(f64::from_bits(n)),
+      },
+    })
+  }
+}
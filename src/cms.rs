@@ -0,0 +1,510 @@
+//! CMS SignedData (RFC 5652) with ML-DSA signers, as profiled by RFC 9882.
+//!
+//! [`SignedDataBuilder`] signs content, encapsulated or detached, for one or
+//! more signers identified by their certificates; [`SignedData`] parses the
+//! result and verifies every signer against the certificates it carries.
+//! Each signer uses one of the two forms RFC 9882 allows:
+//!
+//! - [`SignatureMode::Pure`]: no signed attributes; ML-DSA signs the
+//!   content itself.
+//! - [`SignatureMode::SignedAttributes`]: the content is hashed and ML-DSA
+//!   signs the signed attributes (content-type, message-digest and optionally
+//!   signing-time) instead, so only the digest ties the signature to the
+//!   content.
+//!
+//! HashML-DSA is not used, as RFC 9882 requires: ML-DSA always signs the
+//! content or the attributes directly. Message digests are SHA-512, which
+//! RFC 9882 recommends, SHA-256, SHA-3 or SHAKE256 (RFC 8702). See [FIPS 204 interoperability](crate#fips-204-interoperability)
+//! for which verifiers accept the signatures.
+
+use alloc::{string::String, vec, vec::Vec};
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+use crate::base64;
+use crate::der::{self, Reader, OCTET_STRING, OID, SEQUENCE, SET};
+use crate::sha2::{sha256, sha512};
+use crate::sha3::{sha3_256, sha3_384, sha3_512, shake256};
+use crate::x509::{algorithm_identifier, read_algorithm, Certificate, Name, SubjectPublicKeyInfo};
+use crate::{Algorithm, Keypair};
+
+const ID_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+const ID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const ID_CONTENT_TYPE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+const ID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+const ID_SIGNING_TIME: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05];
+
+const ID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const ID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const ID_SHA3_256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x08];
+const ID_SHA3_384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x09];
+const ID_SHA3_512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x0a];
+const ID_SHAKE256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x0c];
+
+/// The hash that computes the message-digest attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+    /// The digest RFC 9882 recommends for every ML-DSA parameter set.
+    Sha512,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    /// SHAKE256 with 512 bits of output, as RFC 8702 fixes for CMS.
+    Shake256,
+}
+
+impl DigestAlgorithm {
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha256 => sha256(data).to_vec(),
+            DigestAlgorithm::Sha512 => sha512(data).to_vec(),
+            DigestAlgorithm::Sha3_256 => sha3_256(data).to_vec(),
+            DigestAlgorithm::Sha3_384 => sha3_384(data).to_vec(),
+            DigestAlgorithm::Sha3_512 => sha3_512(data).to_vec(),
+            DigestAlgorithm::Shake256 => {
+                let mut out = vec![0u8; 64];
+                shake256(data, &mut out);
+                out
+            }
+        }
+    }
+
+    fn oid(&self) -> &'static [u8] {
+        match self {
+            DigestAlgorithm::Sha256 => ID_SHA256,
+            DigestAlgorithm::Sha512 => ID_SHA512,
+            DigestAlgorithm::Sha3_256 => ID_SHA3_256,
+            DigestAlgorithm::Sha3_384 => ID_SHA3_384,
+            DigestAlgorithm::Sha3_512 => ID_SHA3_512,
+            DigestAlgorithm::Shake256 => ID_SHAKE256,
+        }
+    }
+
+    fn to_der(self) -> Vec<u8> {
+        der::seq(&[&der::oid(self.oid())])
+    }
+
+    fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let mut alg = Reader::new(r.read(SEQUENCE)?);
+        let oid = alg.read(OID)?;
+        if !alg.is_empty() {
+            return Err("Unexpected algorithm parameters");
+        }
+        match oid {
+            ID_SHA256 => Ok(DigestAlgorithm::Sha256),
+            ID_SHA512 => Ok(DigestAlgorithm::Sha512),
+            ID_SHA3_256 => Ok(DigestAlgorithm::Sha3_256),
+            ID_SHA3_384 => Ok(DigestAlgorithm::Sha3_384),
+            ID_SHA3_512 => Ok(DigestAlgorithm::Sha3_512),
+            ID_SHAKE256 => Ok(DigestAlgorithm::Shake256),
+            _ => Err("Unsupported digest algorithm"),
+        }
+    }
+}
+
+/// How a signer covers the content; see the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureMode {
+    Pure,
+    /// `signing_time` is in seconds after the Unix epoch.
+    SignedAttributes { digest: DigestAlgorithm, signing_time: Option<u64> },
+}
+
+struct Signer<'a> {
+    certificate: &'a Certificate,
+    keypair: &'a Keypair,
+    mode: SignatureMode,
+}
+
+/// Collects the content, signers and extra certificates of a SignedData.
+pub struct SignedDataBuilder<'a> {
+    content: &'a [u8],
+    detached: bool,
+    certificates: Vec<&'a Certificate>,
+    signers: Vec<Signer<'a>>,
+}
+
+impl<'a> SignedDataBuilder<'a> {
+    pub fn new(content: &'a [u8]) -> Self {
+        SignedDataBuilder { content, detached: false, certificates: Vec::new(), signers: Vec::new() }
+    }
+
+    /// Leaves the content out of the SignedData; verifiers supply it.
+    pub fn detached(mut self) -> Self {
+        self.detached = true;
+        self
+    }
+
+    /// Adds a certificate, such as an intermediate CA, for verifiers to build
+    /// a path with. Signer certificates are added automatically.
+    pub fn add_certificate(mut self, certificate: &'a Certificate) -> Self {
+        self.certificates.push(certificate);
+        self
+    }
+
+    /// Adds a signer holding `keypair`, the key of `certificate`.
+    pub fn add_signer(mut self, certificate: &'a Certificate, keypair: &'a Keypair, mode: SignatureMode) -> Self {
+        self.signers.push(Signer { certificate, keypair, mode });
+        self
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn build(&self) -> Result<SignedData, &'static str> {
+        self.build_with_rng(&mut OsRng)
+    }
+
+    pub fn build_with_rng<R: RngCore>(&self, rng: &mut R) -> Result<SignedData, &'static str> {
+        if self.signers.is_empty() {
+            return Err("No signers");
+        }
+        let mut digest_algorithms = Vec::new();
+        let mut signer_infos = Vec::new();
+        let mut certificates: Vec<Vec<u8>> = self.certificates.iter().map(|c| c.to_der().to_vec()).collect();
+        for signer in &self.signers {
+            let cert = signer.certificate;
+            if *cert.public_key() != SubjectPublicKeyInfo::from_keypair(signer.keypair) {
+                return Err("Signer key does not match its certificate");
+            }
+            let (digest, signed_attrs, signature) = match signer.mode {
+                SignatureMode::Pure => {
+                    let signature = signer.keypair.sign_with_rng(self.content, rng);
+                    (PURE_DIGEST, Vec::new(), signature)
+                }
+                SignatureMode::SignedAttributes { digest, signing_time } => {
+                    let mut attrs = vec![
+                        attribute(ID_CONTENT_TYPE, &der::oid(ID_DATA)),
+                        attribute(ID_MESSAGE_DIGEST, &der::octet_string(&digest.digest(self.content))),
+                    ];
+                    if let Some(time) = signing_time {
+                        attrs.push(attribute(ID_SIGNING_TIME, &der::time(time)?));
+                    }
+                    // Signed as a SET OF, carried as [0] IMPLICIT.
+                    let mut attrs = der::set_of(attrs);
+                    let signature = signer.keypair.sign_with_rng(&attrs, rng);
+                    attrs[0] = der::context(0);
+                    (digest, attrs, signature)
+                }
+            };
+            let sid = der::seq(&[&cert.issuer().to_der(), &der::uint(cert.serial_number())]);
+            signer_infos.push(der::seq(&[
+                &der::small_uint(1),
+                &sid,
+                &digest.to_der(),
+                &signed_attrs,
+                &algorithm_identifier(signer.keypair.algorithm()),
+                &der::octet_string(signature.bytes()),
+            ]));
+            let digest = digest.to_der();
+            if !digest_algorithms.contains(&digest) {
+                digest_algorithms.push(digest);
+            }
+            if !certificates.iter().any(|c| c == cert.to_der()) {
+                certificates.push(cert.to_der().to_vec());
+            }
+        }
+        let econtent = if self.detached {
+            Vec::new()
+        } else {
+            der::tlv(der::context(0), &der::octet_string(self.content))
+        };
+        let mut certificates = der::set_of(certificates);
+        certificates[0] = der::context(0);
+        let signed_data = der::seq(&[
+            &der::small_uint(1),
+            &der::set_of(digest_algorithms),
+            &der::seq(&[&der::oid(ID_DATA), &econtent]),
+            &certificates,
+            &der::set_of(signer_infos),
+        ]);
+        SignedData::from_der(&der::seq(&[&der::oid(ID_SIGNED_DATA), &der::tlv(der::context(0), &signed_data)]))
+    }
+}
+
+/// The digestAlgorithm recorded for pure signers, which hash nothing:
+/// RFC 9882 names SHA-512.
+const PURE_DIGEST: DigestAlgorithm = DigestAlgorithm::Sha512;
+
+fn attribute(oid: &[u8], value: &[u8]) -> Vec<u8> {
+    der::seq(&[&der::oid(oid), &der::set_of(vec![value.to_vec()])])
+}
+
+/// How a SignerInfo names its certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignerIdentifier {
+    IssuerAndSerialNumber(Name, Vec<u8>),
+    SubjectKeyIdentifier(Vec<u8>),
+}
+
+impl SignerIdentifier {
+    pub fn matches(&self, certificate: &Certificate) -> bool {
+        match self {
+            SignerIdentifier::IssuerAndSerialNumber(issuer, serial) => {
+                certificate.issuer() == issuer && certificate.serial_number() == &serial[..]
+            }
+            SignerIdentifier::SubjectKeyIdentifier(id) => certificate.subject_key_id() == Some(&id[..]),
+        }
+    }
+}
+
+/// The signed attributes this module interprets.
+#[derive(Default)]
+struct SignedAttributes {
+    content_type: Vec<u8>,
+    message_digest: Vec<u8>,
+    signing_time: Option<u64>,
+}
+
+impl SignedAttributes {
+    fn parse(content: &[u8]) -> Result<Self, &'static str> {
+        let mut parsed = SignedAttributes::default();
+        let mut attrs = Reader::new(content);
+        let mut seen: Vec<&[u8]> = Vec::new();
+        while !attrs.is_empty() {
+            let mut attr = Reader::new(attrs.read(SEQUENCE)?);
+            let oid = attr.read(OID)?;
+            let mut values = Reader::new(attr.read(SET)?);
+            attr.finish()?;
+            if seen.contains(&oid) {
+                return Err("Duplicate signed attribute");
+            }
+            seen.push(oid);
+            match oid {
+                ID_CONTENT_TYPE => parsed.content_type = values.read(OID)?.to_vec(),
+                ID_MESSAGE_DIGEST => parsed.message_digest = values.read(OCTET_STRING)?.to_vec(),
+                ID_SIGNING_TIME => parsed.signing_time = Some(values.read_time()?),
+                _ => continue,
+            }
+            values.finish().map_err(|_| "Signed attribute with several values")?;
+        }
+        if parsed.content_type.is_empty() || parsed.message_digest.is_empty() {
+            return Err("Missing content-type or message-digest attribute");
+        }
+        Ok(parsed)
+    }
+}
+
+/// A parsed SignerInfo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignerInfo {
+    sid: SignerIdentifier,
+    digest_algorithm: DigestAlgorithm,
+    /// The signed attributes re-tagged as the SET OF that was signed.
+    signed_attrs: Option<Vec<u8>>,
+    content_type: Vec<u8>,
+    message_digest: Vec<u8>,
+    signing_time: Option<u64>,
+    signature_algorithm: Algorithm,
+    signature: Vec<u8>,
+}
+
+impl SignerInfo {
+    fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let mut si = Reader::new(r.read(SEQUENCE)?);
+        let version = si.read_small_uint()?;
+        let sid = match si.peek_tag() {
+            Some(SEQUENCE) if version == 1 => {
+                let mut ias = Reader::new(si.read(SEQUENCE)?);
+                let issuer = Name::read(&mut ias)?;
+                let serial = ias.read_uint()?.to_vec();
+                ias.finish()?;
+                SignerIdentifier::IssuerAndSerialNumber(issuer, serial)
+            }
+            Some(0x80) if version == 3 => SignerIdentifier::SubjectKeyIdentifier(si.read(0x80)?.to_vec()),
+            _ => return Err("Unsupported signer identifier"),
+        };
+        let digest_algorithm = DigestAlgorithm::read(&mut si)?;
+        let mut attrs = SignedAttributes::default();
+        let signed_attrs = match si.peek_tag() {
+            Some(tag) if tag == der::context(0) => {
+                let (_, content, raw) = si.read_any()?;
+                attrs = SignedAttributes::parse(content)?;
+                let mut signed = raw.to_vec();
+                signed[0] = SET;
+                Some(signed)
+            }
+            _ => None,
+        };
+        let signature_algorithm = read_algorithm(&mut si)?;
+        let signature = si.read(OCTET_STRING)?.to_vec();
+        // Unsigned attributes are not interpreted.
+        si.read_optional(der::context(1))?;
+        si.finish()?;
+        Ok(SignerInfo {
+            sid,
+            digest_algorithm,
+            signed_attrs,
+            content_type: attrs.content_type,
+            message_digest: attrs.message_digest,
+            signing_time: attrs.signing_time,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    pub fn sid(&self) -> &SignerIdentifier {
+        &self.sid
+    }
+
+    pub fn digest_algorithm(&self) -> DigestAlgorithm {
+        self.digest_algorithm
+    }
+
+    pub fn signature_algorithm(&self) -> Algorithm {
+        self.signature_algorithm
+    }
+
+    /// `SignedAttributes` when signed attributes are present, `Pure` otherwise.
+    pub fn mode(&self) -> SignatureMode {
+        match self.signed_attrs {
+            Some(_) => SignatureMode::SignedAttributes { digest: self.digest_algorithm, signing_time: self.signing_time },
+            None => SignatureMode::Pure,
+        }
+    }
+
+    /// The signing-time attribute, in seconds after the Unix epoch.
+    pub fn signing_time(&self) -> Option<u64> {
+        self.signing_time
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Verifies this signer over `content` of type `content_type` with the
+    /// key in `certificate`.
+    fn verify(&self, content_type: &[u8], content: &[u8], certificate: &Certificate) -> Result<(), &'static str> {
+        let key = certificate.public_key();
+        if key.algorithm() != self.signature_algorithm {
+            return Err("Signature algorithm does not match the signer key");
+        }
+        match &self.signed_attrs {
+            None => key.verify(&self.signature, content),
+            Some(signed) => {
+                if self.content_type != content_type {
+                    return Err("Content type attribute mismatch");
+                }
+                if self.message_digest != self.digest_algorithm.digest(content) {
+                    return Err("Message digest mismatch");
+                }
+                key.verify(&self.signature, signed)
+            }
+        }
+    }
+}
+
+/// A parsed ContentInfo holding a SignedData.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedData {
+    der: Vec<u8>,
+    content_type: Vec<u8>,
+    content: Option<Vec<u8>>,
+    certificates: Vec<Certificate>,
+    signers: Vec<SignerInfo>,
+}
+
+impl SignedData {
+    pub fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        let mut ci = Reader::new(der::parse(der, SEQUENCE)?);
+        if ci.read(OID)? != ID_SIGNED_DATA {
+            return Err("Not a SignedData");
+        }
+        let signed_data = der::parse(ci.read(der::context(0))?, SEQUENCE)?;
+        ci.finish()?;
+
+        let mut r = Reader::new(signed_data);
+        let version = r.read_small_uint()?;
+        if !matches!(version, 1 | 3) {
+            return Err("Unsupported SignedData version");
+        }
+        let mut digest_algorithms = Reader::new(r.read(SET)?);
+        while !digest_algorithms.is_empty() {
+            DigestAlgorithm::read(&mut digest_algorithms)?;
+        }
+        let mut encap = Reader::new(r.read(SEQUENCE)?);
+        let content_type = encap.read(OID)?.to_vec();
+        let content = match encap.read_optional(der::context(0))? {
+            Some(econtent) => Some(der::parse(econtent, OCTET_STRING)?.to_vec()),
+            None => None,
+        };
+        encap.finish()?;
+        let mut certificates = Vec::new();
+        if let Some(certs) = r.read_optional(der::context(0))? {
+            let mut certs = Reader::new(certs);
+            while !certs.is_empty() {
+                certificates.push(Certificate::from_der(certs.read_raw(SEQUENCE)?)?);
+            }
+        }
+        // Revocation information is not interpreted.
+        r.read_optional(der::context(1))?;
+        let mut signer_infos = Reader::new(r.read(SET)?);
+        r.finish()?;
+        let mut signers = Vec::new();
+        while !signer_infos.is_empty() {
+            signers.push(SignerInfo::read(&mut signer_infos)?);
+        }
+
+        Ok(SignedData { der: der.to_vec(), content_type, content, certificates, signers })
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, &'static str> {
+        Self::from_der(&base64::pem_decode("CMS", pem)?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        base64::pem_encode("CMS", &self.der)
+    }
+
+    /// The encapsulated content, or `None` for a detached signature.
+    pub fn content(&self) -> Option<&[u8]> {
+        self.content.as_deref()
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.content.is_none()
+    }
+
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    pub fn signers(&self) -> &[SignerInfo] {
+        &self.signers
+    }
+
+    /// Verifies every signer of encapsulated content with the certificates
+    /// carried alongside, returning the signer certificates in signer order.
+    /// Whether those certificates are trusted is for the caller to decide,
+    /// e.g. with [`crate::x509::validate_path`].
+    pub fn verify(&self) -> Result<Vec<&Certificate>, &'static str> {
+        let content = self.content.as_deref().ok_or("Detached content required")?;
+        self.verify_signers(content)
+    }
+
+    /// `verify` for a detached signature over `content`.
+    pub fn verify_detached(&self, content: &[u8]) -> Result<Vec<&Certificate>, &'static str> {
+        if self.content.is_some() {
+            return Err("Content is encapsulated");
+        }
+        self.verify_signers(content)
+    }
+
+    fn verify_signers(&self, content: &[u8]) -> Result<Vec<&Certificate>, &'static str> {
+        if self.signers.is_empty() {
+            return Err("No signers");
+        }
+        self.signers
+            .iter()
+            .map(|signer| {
+                let cert = self.certificates.iter().find(|c| signer.sid.matches(c)).ok_or("Signer certificate not found")?;
+                signer.verify(&self.content_type, content, cert)?;
+                Ok(cert)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,295 @@
+//! COSE with ML-DSA keys: COSE_Sign1 messages (RFC 9052) and `AKP`
+//! COSE_Key structures.
+//!
+//! [`CoseSign1`] signs a payload under a protected header and checks it
+//! against a [`CoseKey`] through the RFC 9052 `Sig_structure`, with optional
+//! externally supplied data; the parsed payload is available before that
+//! check, and is only trustworthy after it. Parsing accepts the message
+//! bare, with its COSE_Sign1 tag (18) or inside a CWT tag (61), so CBOR Web
+//! Tokens signed with `pure_dsa` keys check the same way.
+//!
+//! Keys use the `AKP` key type (7) with the public key under `pub` (-1) and
+//! the 32-byte seed of the [`SeedKey`] under `priv` (-2);
+//! the algorithms are ML-DSA-44, -65 and -87 (-48, -49 and -50); see
+//! [FIPS 204 interoperability](crate#fips-204-interoperability) for which
+//! verifiers accept the messages and which implementations can use the seed.
+//! Detached payloads are not supported, and any `crit` header parameter is
+//! rejected.
+
+use alloc::{vec, vec::Vec};
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+use crate::cbor::{self, Value};
+use crate::secret::wipe;
+use crate::{Algorithm, Keypair, SeedKey};
+
+const TAG_COSE_SIGN1: u64 = 18;
+const TAG_CWT: u64 = 61;
+
+// Header parameters (RFC 9052, section 3.1).
+const HEADER_ALG: i64 = 1;
+const HEADER_CRIT: i64 = 2;
+const HEADER_KID: i64 = 4;
+
+// COSE_Key parameters (RFC 9052, section 7.1) and the AKP ones.
+const KEY_KTY: i64 = 1;
+const KEY_KID: i64 = 2;
+const KEY_ALG: i64 = 3;
+const KEY_PUB: i64 = -1;
+const KEY_PRIV: i64 = -2;
+const KTY_AKP: i64 = 7;
+
+fn algorithm_id(alg: Algorithm) -> i64 {
+    match alg {
+        #[cfg(feature = "mode2")]
+        Algorithm::Mode2 => -48,
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3 => -49,
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5 => -50,
+    }
+}
+
+fn algorithm_from_id(id: i64) -> Result<Algorithm, &'static str> {
+    Algorithm::find(|alg| algorithm_id(alg) == id).ok_or("Unsupported algorithm")
+}
+
+fn optional_bytes(map: &Value, label: i64) -> Result<Option<Vec<u8>>, &'static str> {
+    match map.get(label) {
+        None => Ok(None),
+        Some(v) => v.as_bytes().map(|b| Some(b.to_vec())).ok_or("Invalid COSE parameter type"),
+    }
+}
+
+/// A COSE_Key of the `AKP` type: a [`SeedKey`] and an optional `kid`.
+#[derive(Debug)]
+pub struct CoseKey {
+    key: SeedKey,
+    kid: Option<Vec<u8>>,
+}
+
+impl From<SeedKey> for CoseKey {
+    fn from(key: SeedKey) -> Self {
+        CoseKey { key, kid: None }
+    }
+}
+
+impl CoseKey {
+    pub fn with_kid(mut self, kid: &[u8]) -> Self {
+        self.kid = Some(kid.to_vec());
+        self
+    }
+
+    pub fn key(&self) -> &SeedKey {
+        &self.key
+    }
+
+    pub fn kid(&self) -> Option<&[u8]> {
+        self.kid.as_deref()
+    }
+
+    /// The same COSE_Key, `kid` included, without `priv`.
+    pub fn to_public(&self) -> CoseKey {
+        CoseKey { key: self.key.to_public(), kid: self.kid.clone() }
+    }
+
+    /// The deterministic CBOR encoding; `priv` is included for private keys.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut entries = vec![
+            (cbor::int(KEY_KTY), cbor::int(KTY_AKP)),
+            (cbor::int(KEY_ALG), cbor::int(algorithm_id(self.key.algorithm()))),
+            (cbor::int(KEY_PUB), cbor::bytes(self.key.public_key())),
+        ];
+        if let Some(kid) = &self.kid {
+            entries.push((cbor::int(KEY_KID), cbor::bytes(kid)));
+        }
+        if let Some(seed) = self.key.seed() {
+            entries.push((cbor::int(KEY_PRIV), cbor::bytes(seed)));
+        }
+        let encoded = cbor::map(&mut entries);
+        for (key, value) in entries {
+            if key == cbor::int(KEY_PRIV) {
+                wipe(value);
+            }
+        }
+        encoded
+    }
+
+    /// Parses an `AKP` COSE_Key. Parameters other than `kty`, `kid`, `alg`,
+    /// `pub` and `priv` are ignored; a `priv` seed must derive the given
+    /// `pub`.
+    pub fn from_cbor(data: &[u8]) -> Result<Self, &'static str> {
+        let mut map = Value::decode(data)?;
+        // Moved out of the decoded map before anything else can fail, so
+        // that it is wiped on every path.
+        let seed = match map.get_mut(KEY_PRIV) {
+            None => None,
+            Some(Value::Bytes(seed)) => Some(core::mem::take(seed)),
+            Some(_) => return Err("Invalid COSE parameter type"),
+        };
+        let key = Self::read(&map, seed.as_deref());
+        if let Some(seed) = seed {
+            wipe(seed);
+        }
+        key
+    }
+
+    fn read(map: &Value, seed: Option<&[u8]>) -> Result<Self, &'static str> {
+        if map.as_map().is_none() {
+            return Err("Expected a CBOR map");
+        }
+        if map.get(KEY_KTY).and_then(Value::as_int) != Some(KTY_AKP) {
+            return Err("Unsupported key type");
+        }
+        let alg = map.get(KEY_ALG).ok_or("Missing COSE parameter")?;
+        let algorithm = algorithm_from_id(alg.as_int().ok_or("Invalid COSE parameter type")?)?;
+        let public_key = optional_bytes(map, KEY_PUB)?.ok_or("Missing COSE parameter")?;
+        Ok(CoseKey { key: SeedKey::read(algorithm, &public_key, seed)?, kid: optional_bytes(map, KEY_KID)? })
+    }
+}
+
+/// The COSE header parameters this crate understands. `alg` is always sent
+/// protected and `kid` unprotected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub alg: Algorithm,
+    pub kid: Option<Vec<u8>>,
+}
+
+impl Header {
+    pub fn new(alg: Algorithm) -> Self {
+        Header { alg, kid: None }
+    }
+
+    pub fn with_kid(mut self, kid: &[u8]) -> Self {
+        self.kid = Some(kid.to_vec());
+        self
+    }
+
+    /// The header from both buckets, which must not share a label. `alg`
+    /// has to be protected.
+    fn read(protected: &Value, unprotected: &Value) -> Result<Self, &'static str> {
+        let (p, u) = match (protected.as_map(), unprotected.as_map()) {
+            (Some(p), Some(u)) => (p, u),
+            _ => return Err("Expected a CBOR map"),
+        };
+        if p.iter().any(|(k, _)| u.iter().any(|(l, _)| k == l)) {
+            return Err("Duplicate header parameter");
+        }
+        if protected.get(HEADER_CRIT).is_some() || unprotected.get(HEADER_CRIT).is_some() {
+            return Err("Unsupported critical header parameter");
+        }
+        let alg = match protected.get(HEADER_ALG) {
+            Some(alg) => algorithm_from_id(alg.as_int().ok_or("Invalid COSE parameter type")?)?,
+            None if unprotected.get(HEADER_ALG).is_some() => return Err("Unprotected alg header parameter"),
+            None => return Err("Missing alg header parameter"),
+        };
+        Ok(Header { alg, kid: optional_bytes(unprotected, HEADER_KID)?.or(optional_bytes(protected, HEADER_KID)?) })
+    }
+}
+
+/// `Sig_structure` for a COSE_Sign1 (RFC 9052, section 4.4).
+fn sig_structure(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Vec<u8> {
+    cbor::array(&[&cbor::text("Signature1"), &cbor::bytes(protected), &cbor::bytes(external_aad), &cbor::bytes(payload)])
+}
+
+/// A COSE_Sign1 message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoseSign1 {
+    encoded: Vec<u8>,
+    protected: Vec<u8>,
+    header: Header,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    #[cfg(feature = "getrandom")]
+    pub fn sign(header: &Header, payload: &[u8], external_aad: &[u8], keypair: &Keypair) -> Result<Self, &'static str> {
+        Self::sign_with_rng(header, payload, external_aad, keypair, &mut OsRng)
+    }
+
+    /// Signs `payload` and `external_aad`, which is not carried in the
+    /// message; `header.alg` must be the keypair's algorithm.
+    pub fn sign_with_rng<R: RngCore>(
+        header: &Header,
+        payload: &[u8],
+        external_aad: &[u8],
+        keypair: &Keypair,
+        rng: &mut R,
+    ) -> Result<Self, &'static str> {
+        if header.alg != keypair.algorithm() {
+            return Err("Header alg does not match the key");
+        }
+        let protected = cbor::map(&mut [(cbor::int(HEADER_ALG), cbor::int(algorithm_id(header.alg)))]);
+        let unprotected = cbor::map(&mut header.kid.iter().map(|kid| (cbor::int(HEADER_KID), cbor::bytes(kid))).collect::<Vec<_>>());
+        let signature = keypair.sign_with_rng(&sig_structure(&protected, external_aad, payload), rng).bytes().to_vec();
+        let message = cbor::array(&[&cbor::bytes(&protected), &unprotected, &cbor::bytes(payload), &cbor::bytes(&signature)]);
+        Ok(CoseSign1 {
+            encoded: cbor::tag(TAG_COSE_SIGN1, &message),
+            protected,
+            header: header.clone(),
+            payload: payload.to_vec(),
+            signature,
+        })
+    }
+
+    /// Parses a COSE_Sign1, untagged, tagged or wrapped in a CWT tag.
+    pub fn from_cbor(data: &[u8]) -> Result<Self, &'static str> {
+        let mut value = &Value::decode(data)?;
+        if let Value::Tag(TAG_CWT, inner) = value {
+            value = inner;
+        }
+        if let Value::Tag(TAG_COSE_SIGN1, inner) = value {
+            value = inner;
+        }
+        let [protected, unprotected, payload, signature] = value.as_array().ok_or("Expected a COSE_Sign1")? else {
+            return Err("Expected a COSE_Sign1");
+        };
+        let protected = protected.as_bytes().ok_or("Invalid COSE_Sign1 field")?;
+        // An empty protected header stands for an empty map.
+        let protected_map = if protected.is_empty() { Value::Map(Vec::new()) } else { Value::decode(protected)? };
+        let header = Header::read(&protected_map, unprotected)?;
+        let payload = match payload {
+            Value::Null => return Err("Detached payload not supported"),
+            p => p.as_bytes().ok_or("Invalid COSE_Sign1 field")?,
+        };
+        Ok(CoseSign1 {
+            encoded: data.to_vec(),
+            protected: protected.to_vec(),
+            header,
+            payload: payload.to_vec(),
+            signature: signature.as_bytes().ok_or("Invalid COSE_Sign1 field")?.to_vec(),
+        })
+    }
+
+    /// The message as given to `from_cbor`, or tagged as a COSE_Sign1 when
+    /// signed here.
+    pub fn to_cbor(&self) -> &[u8] {
+        &self.encoded
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Checks the signature with `key` and the same `external_aad` as the
+    /// signer used, and returns the payload.
+    pub fn verify(&self, key: &CoseKey, external_aad: &[u8]) -> Result<&[u8], &'static str> {
+        if self.header.alg != key.key().algorithm() {
+            return Err("Key algorithm does not match the header");
+        }
+        key.key().verify(&self.signature, &sig_structure(&self.protected, external_aad, &self.payload))?;
+        Ok(&self.payload)
+    }
+}
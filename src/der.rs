@@ -0,0 +1,302 @@
+//! The DER (X.690) subset the certificate and CMS containers need. The
+//! writer builds each TLV from already-encoded children; the reader is
+//! strict: definite minimal lengths, low tag numbers and no trailing data.
+
+use alloc::vec::Vec;
+
+pub const BOOLEAN: u8 = 0x01;
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OID: u8 = 0x06;
+pub const UTF8_STRING: u8 = 0x0c;
+pub const PRINTABLE_STRING: u8 = 0x13;
+pub const IA5_STRING: u8 = 0x16;
+pub const UTC_TIME: u8 = 0x17;
+pub const GENERALIZED_TIME: u8 = 0x18;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+/// `[n]` constructed, as used for EXPLICIT tagging and IMPLICIT SEQUENCEs.
+pub const fn context(n: u8) -> u8 {
+  0xa0 | n
+}
+
+/// `[n]` primitive, as used for IMPLICIT strings.
+pub const fn context_primitive(n: u8) -> u8 {
+  0x80 | n
+}
+
+/// 9999-12-31T23:59:59Z, the last instant a `GeneralizedTime` can hold.
+pub const MAX_TIME: u64 = 253402300799;
+
+const MALFORMED: &str = "Malformed DER";
+
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+  cons(tag, &[content])
+}
+
+/// A TLV whose content is the concatenation of `parts`.
+pub fn cons(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+  let len: usize = parts.iter().map(|p| p.len()).sum();
+  let mut out = Vec::with_capacity(len + 6);
+  out.push(tag);
+  if len < 0x80 {
+    out.push(len as u8);
+  } else {
+    let bytes = len.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    out.push(0x80 | (bytes.len() - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+  }
+  for part in parts {
+    out.extend_from_slice(part);
+  }
+  out
+}
+
+pub fn seq(parts: &[&[u8]]) -> Vec<u8> {
+  cons(SEQUENCE, parts)
+}
+
+/// A SET OF, with the elements in the ascending order DER requires.
+pub fn set_of(mut items: Vec<Vec<u8>>) -> Vec<u8> {
+  items.sort();
+  let parts: Vec<&[u8]> = items.iter().map(|i| &i[..]).collect();
+  cons(SET, &parts)
+}
+
+/// An INTEGER holding the unsigned big-endian `value`.
+pub fn uint(value: &[u8]) -> Vec<u8> {
+  let skip = value.iter().take_while(|&&b| b == 0).count().min(value.len().saturating_sub(1));
+  let value = if value.is_empty() { &[0u8][..] } else { &value[skip..] };
+  if value[0] & 0x80 != 0 {
+    cons(INTEGER, &[&[0], value])
+  } else {
+    tlv(INTEGER, value)
+  }
+}
+
+pub fn small_uint(n: u64) -> Vec<u8> {
+  uint(&n.to_be_bytes())
+}
+
+pub fn boolean(b: bool) -> Vec<u8> {
+  tlv(BOOLEAN, &[if b { 0xff } else { 0 }])
+}
+
+/// A BIT STRING of whole bytes.
+pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+  cons(BIT_STRING, &[&[0], bytes])
+}
+
+pub fn octet_string(bytes: &[u8]) -> Vec<u8> {
+  tlv(OCTET_STRING, bytes)
+}
+
+/// An OBJECT IDENTIFIER from its encoded content octets.
+pub fn oid(content: &[u8]) -> Vec<u8> {
+  tlv(OID, content)
+}
+
+/// `secs` after the Unix epoch as UTCTime through 2049 and GeneralizedTime
+/// from 2050 on, the RFC 5280 rule.
+pub fn time(secs: u64) -> Result<Vec<u8>, &'static str> {
+  if secs > MAX_TIME {
+    return Err("Time out of range");
+  }
+  let (y, mo, d) = civil_from_days((secs / 86400) as i64);
+  let rem = secs % 86400;
+  let fields = [mo as u64, d as u64, rem / 3600, rem / 60 % 60, rem % 60];
+  let mut s = Vec::with_capacity(15);
+  let tag = if y < 2050 {
+    push_digits(&mut s, y as u64 % 100, 2);
+    UTC_TIME
+  } else {
+    push_digits(&mut s, y as u64, 4);
+    GENERALIZED_TIME
+  };
+  for f in fields {
+    push_digits(&mut s, f, 2);
+  }
+  s.push(b'Z');
+  Ok(tlv(tag, &s))
+}
+
+fn push_digits(out: &mut Vec<u8>, n: u64, width: u32) {
+  for i in (0..width).rev() {
+    out.push(b'0' + (n / 10u64.pow(i) % 10) as u8);
+  }
+}
+
+// Howard Hinnant's days <-> proleptic Gregorian date conversions.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+  let y = if m <= 2 { y - 1 } else { y };
+  let era = y.div_euclid(400);
+  let yoe = y - era * 400;
+  let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+  let z = z + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let d = doy - (153 * mp + 2) / 5 + 1;
+  let m = if mp < 10 { mp + 3 } else { mp - 9 };
+  (yoe + era * 400 + (m <= 2) as i64, m, d)
+}
+
+/// Reads a run of DER elements from a byte slice.
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  pub fn new(data: &'a [u8]) -> Self {
+    Reader { data }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn peek_tag(&self) -> Option<u8> {
+    self.data.first().copied()
+  }
+
+  /// The next element as `(tag, content, whole encoding)`.
+  pub fn read_any(&mut self) -> Result<(u8, &'a [u8], &'a [u8]), &'static str> {
+    let d = self.data;
+    if d.len() < 2 || d[0] & 0x1f == 0x1f {
+      return Err(MALFORMED);
+    }
+    let (len, header) = match d[1] {
+      l if l < 0x80 => (l as usize, 2),
+      0x80 => return Err(MALFORMED),
+      l => {
+        let n = (l & 0x7f) as usize;
+        if n > core::mem::size_of::<usize>() || d.len() < 2 + n || d[2] == 0 {
+          return Err(MALFORMED);
+        }
+        let len = d[2..2 + n].iter().fold(0usize, |acc, &b| acc << 8 | b as usize);
+        if len < 0x80 {
+          return Err(MALFORMED);
+        }
+        (len, 2 + n)
+      }
+    };
+    if d.len() - header < len {
+      return Err(MALFORMED);
+    }
+    self.data = &d[header + len..];
+    Ok((d[0], &d[header..header + len], &d[..header + len]))
+  }
+
+  /// The content of the next element, which must carry `tag`.
+  pub fn read(&mut self, tag: u8) -> Result<&'a [u8], &'static str> {
+    match self.read_any()? {
+      (t, content, _) if t == tag => Ok(content),
+      _ => Err("Unexpected DER tag"),
+    }
+  }
+
+  /// Like `read`, but the whole encoding including the header.
+  pub fn read_raw(&mut self, tag: u8) -> Result<&'a [u8], &'static str> {
+    match self.read_any()? {
+      (t, _, raw) if t == tag => Ok(raw),
+      _ => Err("Unexpected DER tag"),
+    }
+  }
+
+  /// The content of the next element if it carries `tag`.
+  pub fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>, &'static str> {
+    if self.peek_tag() == Some(tag) { self.read(tag).map(Some) } else { Ok(None) }
+  }
+
+  /// A non-negative INTEGER's magnitude, without the sign octet.
+  pub fn read_uint(&mut self) -> Result<&'a [u8], &'static str> {
+    let c = self.read(INTEGER)?;
+    match c {
+      [] => Err(MALFORMED),
+      [b, ..] if b & 0x80 != 0 => Err("Negative INTEGER"),
+      [0, b, ..] if b & 0x80 == 0 => Err(MALFORMED),
+      [0, rest @ ..] if !rest.is_empty() => Ok(rest),
+      _ => Ok(c),
+    }
+  }
+
+  pub fn read_small_uint(&mut self) -> Result<u64, &'static str> {
+    let c = self.read_uint()?;
+    if c.len() > 8 {
+      return Err("INTEGER out of range");
+    }
+    Ok(c.iter().fold(0u64, |acc, &b| acc << 8 | b as u64))
+  }
+
+  pub fn read_bool(&mut self) -> Result<bool, &'static str> {
+    match self.read(BOOLEAN)? {
+      [0] => Ok(false),
+      [0xff] => Ok(true),
+      _ => Err(MALFORMED),
+    }
+  }
+
+  /// A BIT STRING of whole bytes.
+  pub fn read_bit_string(&mut self) -> Result<&'a [u8], &'static str> {
+    match self.read(BIT_STRING)? {
+      [0, rest @ ..] => Ok(rest),
+      _ => Err("Unexpected unused bits in BIT STRING"),
+    }
+  }
+
+  /// A UTCTime or GeneralizedTime of the form RFC 5280 allows, as seconds
+  /// after the Unix epoch.
+  pub fn read_time(&mut self) -> Result<u64, &'static str> {
+    let (tag, c, _) = self.read_any()?;
+    let (year, rest) = match (tag, c.len()) {
+      (UTC_TIME, 13) => {
+        let yy = digits(&c[..2])?;
+        (if yy < 50 { 2000 + yy } else { 1900 + yy }, &c[2..])
+      }
+      (GENERALIZED_TIME, 15) => (digits(&c[..4])?, &c[4..]),
+      _ => return Err("Invalid time"),
+    };
+    if rest[10] != b'Z' {
+      return Err("Invalid time");
+    }
+    let f: Vec<i64> = rest[..10].chunks(2).map(digits).collect::<Result<_, _>>()?;
+    let (m, d, h, mi, s) = (f[0], f[1], f[2], f[3], f[4]);
+    let days = days_from_civil(year, m, d);
+    if !(1..=12).contains(&m) || d < 1 || civil_from_days(days) != (year, m, d) || h > 23 || mi > 59 || s > 59 {
+      return Err("Invalid time");
+    }
+    if year < 1970 {
+      return Err("Time out of range");
+    }
+    Ok(days as u64 * 86400 + (h * 3600 + mi * 60 + s) as u64)
+  }
+
+  pub fn finish(&self) -> Result<(), &'static str> {
+    if self.data.is_empty() { Ok(()) } else { Err("Trailing DER data") }
+  }
+}
+
+fn digits(s: &[u8]) -> Result<i64, &'static str> {
+  s.iter().try_fold(0i64, |acc, &c| {
+    if c.is_ascii_digit() { Ok(acc * 10 + (c - b'0') as i64) } else { Err("Invalid time") }
+  })
+}
+
+/// The content of `data`, which must be exactly one element with `tag`.
+pub fn parse(data: &[u8], tag: u8) -> Result<&[u8], &'static str> {
+  let mut r = Reader::new(data);
+  let content = r.read(tag)?;
+  r.finish()?;
+  Ok(content)
+}
@@ -1,16 +1,18 @@
-#![allow(dead_code)]
-
-use crate::poly::SEEDBYTES;
+use core::fmt;
 #[cfg(feature = "zeroize")]
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 const NROUNDS: usize = 24;
+/// Rounds of Keccak-p[1600, 12] used by TurboSHAKE and KangarooTwelve.
+pub const TURBOSHAKE_ROUNDS: usize = 12;
 pub const SHAKE128_RATE: usize = 168;
 pub const SHAKE256_RATE: usize = 136;
+pub const SHA3_224_RATE: usize = 144;
 pub const SHA3_256_RATE: usize = 136;
+pub const SHA3_384_RATE: usize = 104;
 pub const SHA3_512_RATE: usize = 72;
 
-const KECCAKF_ROUND_CONSTANTS: [u64; NROUNDS] = [
+pub(crate) const KECCAKF_ROUND_CONSTANTS: [u64; NROUNDS] = [
   0x0000000000000001u64,
   0x0000000000008082u64,
   0x800000000000808au64,
@@ -37,61 +39,112 @@ const KECCAKF_ROUND_CONSTANTS: [u64; NROUNDS] = [
   0x8000000080008008u64,
 ];
 #[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct KeccakState {
     pub s: [u64; 25],
     pub pos: usize,
+    /// Rounds per permutation: 24 for Keccak-f, 12 for TurboSHAKE.
+    pub rounds: usize,
 }
+
+/// The lanes and position stay out of `Debug`: for KMAC, or any sponge that
+/// absorbed a secret, they are as good as the secret itself.
+impl fmt::Debug for KeccakState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeccakState({} rounds, <redacted>)", self.rounds)
+    }
+}
+
 impl Default for KeccakState {
-  fn default() -> Self {
-    KeccakState {
-      s: [0u64; 25],
-      pos: 0usize,
+    fn default() -> Self {
+        Self::with_rounds(NROUNDS)
     }
-  }
 }
 
 impl KeccakState {
-    pub fn init(&mut self) {
-        self.s.fill(0);
+    pub fn with_rounds(rounds: usize) -> Self {
+        KeccakState { s: [0u64; 25], pos: 0, rounds }
+    }
+
+    pub fn permute(&mut self) {
+        keccakf1600_state_permute(&mut self.s, self.rounds);
+    }
+
+    pub fn absorb(&mut self, rate: usize, input: &[u8]) {
+        keccak_absorb(self, rate, input);
+    }
+
+    /// Pads with the domain byte `pad` and hands the state to a reader.
+    ///
+    /// `self` is left empty, ready to absorb a new message. The state is
+    /// copied into the reader rather than moved, so the absorbed input never
+    /// lingers in a moved-from stack slot that nothing wipes.
+    pub fn finalize(&mut self, rate: usize, pad: u8) -> XofReader {
+        let state = KeccakState { s: self.s, pos: rate, rounds: self.rounds };
+        let mut reader = XofReader { state, rate };
+        keccak_finalize(&mut reader.state.s, self.pos, rate, pad);
+        self.s = [0u64; 25];
         self.pos = 0;
+        reader
     }
 
     pub fn shake128_absorb(&mut self, input: &[u8]) {
-        keccak_absorb(self, SHAKE128_RATE, input, input.len());
+        self.absorb(SHAKE128_RATE, input);
     }
 
-    pub fn shake128_finalize(&mut self) {
-        keccak_finalize(&mut self.s, self.pos, SHAKE128_RATE, 0x1F);
-        self.pos = SHAKE128_RATE;
-    }
-    pub fn shake128_squeeze(&mut self, out: &mut [u8]) {
-        self.pos = keccak_squeeze(out, SEEDBYTES, &mut self.s, self.pos, SHAKE128_RATE);
+    pub fn shake128_finalize(&mut self) -> XofReader {
+        self.finalize(SHAKE128_RATE, 0x1F)
     }
 
-    pub fn shake128_squeezeblocks(&mut self, out: &mut [u8], nblocks: usize) {
-        keccak_squeezeblocks(out, nblocks, &mut self.s, SHAKE128_RATE);
+    pub fn shake256_absorb(&mut self, input: &[u8]) {
+        self.absorb(SHAKE256_RATE, input);
     }
 
-    pub fn shake256_absorb(&mut self, input: &[u8], inlen: usize) {
-        keccak_absorb(self, SHAKE256_RATE, input, inlen);
+    pub fn shake256_finalize(&mut self) -> XofReader {
+        self.finalize(SHAKE256_RATE, 0x1F)
     }
+}
+
+/// Output stream of a finalized sponge.
+///
+/// Reads may have any length: output is the same however it is split, so
+/// reading 10 and then 200 bytes gives the 210 bytes a single read would.
+/// Block-aligned reads copy whole lanes.
+///
+/// Neither `Clone` nor `Debug` exposes the state, since a reader seeded with
+/// a secret would let anyone holding a copy predict its output.
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+pub struct XofReader {
+    state: KeccakState,
+    rate: usize,
+}
 
-    pub fn shake256_finalize(&mut self) {
-        keccak_finalize(&mut self.s, self.pos, SHAKE256_RATE, 0x1F);
-        self.pos = SHAKE256_RATE;
+impl fmt::Debug for XofReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("XofReader(<redacted>)")
     }
+}
 
-    pub fn shake256_squeeze(&mut self, out: &mut [u8], outlen: usize) {
-        self.pos = keccak_squeeze(out, outlen, &mut self.s, self.pos, SHAKE256_RATE);
+impl XofReader {
+    /// Fills `out` with the next `out.len()` bytes of output.
+    pub fn read(&mut self, out: &mut [u8]) {
+        keccak_squeeze(out, &mut self.state, self.rate);
     }
+}
 
-    pub fn shake256_absorb_once(&mut self, input: &[u8], inlen: usize) {
-        keccak_absorb_once(&mut self.s, SHAKE256_RATE, input, inlen,0x1F);
-        self.pos = SHAKE256_RATE;
+#[cfg(feature = "std")]
+impl std::io::Read for XofReader {
+    /// Never fails and always fills `buf`; the stream does not end.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        XofReader::read(self, buf);
+        Ok(buf.len())
     }
-    pub fn shake256_squeezeblocks(&mut self, out: &mut [u8], nblocks: usize) {
-        keccak_squeezeblocks(out, nblocks, &mut self.s, SHAKE256_RATE);
+}
+
+#[cfg(feature = "digest")]
+impl digest::XofReader for XofReader {
+    fn read(&mut self, buffer: &mut [u8]) {
+        XofReader::read(self, buffer);
     }
 }
 
@@ -116,7 +169,10 @@ pub fn store64(u: u64, x: &mut [u8]) {
   }
 }
 
-pub fn keccakf1600_state_permute(state: &mut [u64]) {
+/// Applies the last `nrounds` rounds of Keccak-p[1600]: 24 is Keccak-f[1600],
+/// 12 the TurboSHAKE permutation. `nrounds` must be even and at most 24.
+pub fn keccakf1600_state_permute(state: &mut [u64], nrounds: usize) {
+  debug_assert!(nrounds.is_multiple_of(2) && nrounds <= NROUNDS);
   let mut aba = state[0];
   let mut abe = state[1];
   let mut abi = state[2];
@@ -143,7 +199,7 @@ pub fn keccakf1600_state_permute(state: &mut [u64]) {
   let mut aso = state[23];
   let mut asu = state[24];
 
-  for round in (0..NROUNDS).step_by(2) {
+  for round in (NROUNDS - nrounds..NROUNDS).step_by(2) {
     let mut bca = aba ^ aga ^ aka ^ ama ^ asa;
     let mut bce = abe ^ age ^ ake ^ ame ^ ase;
     let mut bci = abi ^ agi ^ aki ^ ami ^ asi;
@@ -358,113 +414,61 @@ pub fn keccakf1600_state_permute(state: &mut [u64]) {
   state[24] = asu;
 }
 
-fn keccak_init(s: &mut [u64; 25]) {
-    *s = [0u64; 25];
-}
-
-fn keccak_absorb(
-  state: &mut KeccakState,
-  r: usize,
-  input: &[u8],
-  mut inlen: usize,
-) {
+/// Absorbs `input` from `state.pos` on; like squeezing, whole blocks are
+/// added lane by lane. A block is permuted as soon as it is full.
+fn keccak_absorb(state: &mut KeccakState, r: usize, input: &[u8]) {
   let mut idx = 0;
-  let mut pos = state.pos;
-  while pos + inlen >= r {
-    for i in pos..r {
-      state.s[i / 8] ^= (input[idx] as u64) << 8 * (i % 8);
-      idx += 1;
+  while idx < input.len() {
+    let n = (r - state.pos).min(input.len() - idx);
+    if n == r {
+      for i in 0..r / 8 {
+        state.s[i] ^= load64(&input[idx + 8 * i..]);
+      }
+    } else {
+      for i in state.pos..state.pos + n {
+        state.s[i / 8] ^= (input[idx + i - state.pos] as u64) << 8 * (i % 8);
+      }
+    }
+    idx += n;
+    state.pos += n;
+    if state.pos == r {
+      state.permute();
+      state.pos = 0;
     }
-    inlen -= r - pos;
-    keccakf1600_state_permute(&mut state.s);
-    pos = 0;
-  }
-  let mut i = pos;
-  while i < pos + inlen {
-    state.s[i / 8] ^= (input[idx] as u64) << 8 * (i % 8);
-    idx += 1;
-    i += 1
   }
-  state.pos = i;
 }
 
-
 fn keccak_finalize(s: &mut [u64; 25], pos: usize, r: usize, p: u8) {
     s[pos / 8] ^= (p as u64) << (8 * (pos % 8));
     s[r / 8 - 1] ^= 1u64 << 63;
 }
 
-fn keccak_squeeze(
-  out: &mut [u8],
-  mut outlen: usize,
-  s: &mut [u64; 25],
-  mut pos: usize,
-  r: usize,
-) -> usize {
-  while outlen != 0 {
-    if pos == r {
-      keccakf1600_state_permute(s);
-      pos = 0;
-    }
-    let mut i = pos;
-    let mut idx = 0;
-    while i < r && i < pos + outlen {
-      out[idx] = (s[i / 8] >> 8 * (i % 8)) as u8;
-      idx += 1;
-      i += 1;
-    }
-    outlen -= i - pos;
-    pos = i;
-  }
-
-  return pos;
-}
-
-fn keccak_absorb_once(
-  s: &mut [u64; 25],
-  r: usize,
-  input: &[u8],
-  mut inlen: usize,
-  p: u8,
-) {
-  s.fill(0);
+/// Squeezes `out.len()` bytes, continuing from `state.pos`. A read that
+/// starts on a block boundary and spans the whole block copies lanes.
+fn keccak_squeeze(out: &mut [u8], state: &mut KeccakState, r: usize) {
   let mut idx = 0;
-  while inlen >= r {
-    for i in 0..r / 8 {
-      s[i] ^= load64(&input[idx + 8 * i..]);
+  while idx < out.len() {
+    if state.pos == r {
+      state.permute();
+      state.pos = 0;
     }
-    idx += r;
-    inlen -= r;
-    keccakf1600_state_permute(s);
-  }
-
-  for i in 0..inlen {
-    s[i / 8] ^= (input[idx + i] as u64) << 8 * (i % 8);
-  }
-
-  s[inlen / 8] ^= (p as u64) << 8 * (inlen % 8);
-  s[(r - 1) / 8] ^= 1u64 << 63;
-}
-
-fn keccak_squeezeblocks(out: &mut [u8], mut nblocks: usize, s: &mut [u64], r: usize) {
-
-  let mut idx = 0usize;
-  while nblocks > 0 {
-    keccakf1600_state_permute(s);
-    for i in 0..(r >> 3) {
-      store64( s[i], &mut out[idx + 8 * i..]);
+    let n = (r - state.pos).min(out.len() - idx);
+    if n == r {
+      for i in 0..r / 8 {
+        store64(state.s[i], &mut out[idx + 8 * i..]);
+      }
+    } else {
+      for i in state.pos..state.pos + n {
+        out[idx + i - state.pos] = (state.s[i / 8] >> 8 * (i % 8)) as u8;
+      }
     }
-    idx += r;
-    nblocks -= 1;
+    idx += n;
+    state.pos += n;
   }
 }
 
-pub fn shake256(out: &mut [u8], input: &[u8], mut outlen: usize, inlen: usize) {
+pub fn shake256(out: &mut [u8], input: &[u8]) {
     let mut state = KeccakState::default();
-    state.shake256_absorb_once(input, inlen);
-    let nblocks = out.len() / SHAKE256_RATE;
-    state.shake256_squeezeblocks(out, nblocks);
-    outlen -= nblocks * SHAKE256_RATE;
-    let idx = nblocks * SHAKE256_RATE;
-    state.shake256_squeeze(&mut out[idx..], outlen);
+    state.shake256_absorb(input);
+    state.shake256_finalize().read(out);
 }
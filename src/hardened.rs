@@ -0,0 +1,141 @@
+//! Fault-hardened signing for the `hardened` feature.
+//!
+//! A single glitched instruction in the signer can turn its output into a
+//! key-recovery oracle, e.g. a skipped addition that releases `y + c*s1` with
+//! part of `y` missing. `crypto_sign_signature_hardened` wraps the normal
+//! rejection loop with redundant checks:
+//!
+//! - the public key is re-derived from `sk`, and the stored `t0` and `tr` are
+//!   checked against it, which validates `s1` and `s2` as unpacked;
+//! - a digest of `s1`/`s2` taken right after expansion is recomputed once the
+//!   signature is produced;
+//! - the challenge is recomputed from `mu` and `w1` and compared with the one
+//!   that was signed, both as bytes and as a polynomial;
+//! - the signature is verified against the derived public key.
+//!
+//! If anything disagrees the output is wiped and `FAULT_DETECTED` is returned.
+//! All of this roughly doubles the cost of signing.
+//!
+//! The `fault_injection` feature lets `tests/hardened.rs` corrupt the signing
+//! state after the rejection loop, where only these checks can notice it.
+
+use rand_core::RngCore;
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::{
+    params::{DilithiumParams, MAX_PUBLIC_KEY_BYTES},
+    sign::*,
+    polyvec::*,
+    fips202::*,
+    poly::*
+};
+
+/// Returned instead of a signature when a redundant check disagrees.
+pub const FAULT_DETECTED: &str = "FAULT DETECTED";
+
+/// State corrupted by `Algorithm::sign_into_hardened_faulted` once a signature
+/// has been accepted, to exercise the checks that run after the rejection loop.
+#[cfg(feature = "fault_injection")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+  /// Nothing is corrupted; signing must succeed.
+  None,
+  /// The secret `s1`/`s2` held for signing, caught by the digest check.
+  Secret,
+  /// The `w1` the challenge was hashed from, caught by the re-derivation.
+  W1,
+  /// The challenge polynomial, caught by the polynomial comparison.
+  Challenge,
+}
+
+pub fn crypto_sign_signature_hardened<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+) -> Result<(), &'static str> {
+  sign_wiped::<P, R>(sig, m, sk, rng, |_, _| {})
+}
+
+#[cfg(feature = "fault_injection")]
+pub fn crypto_sign_signature_faulted<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+  fault: Fault,
+) -> Result<(), &'static str> {
+  sign_wiped::<P, R>(sig, m, sk, rng, |key, ws| match fault {
+    Fault::None => {}
+    Fault::Secret => key.inject_fault(),
+    Fault::W1 => ws.w1_mut().vec_mut()[0].coeffs[0] ^= 1,
+    Fault::Challenge => ws.challenge_mut().coeffs[0] ^= 1,
+  })
+}
+
+fn sign_wiped<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+  inject: impl FnOnce(&mut SigningKey<P>, &mut SignScratch<P>),
+) -> Result<(), &'static str> {
+  let mut pk = [0u8; MAX_PUBLIC_KEY_BYTES];
+  let result = sign_checked::<P, R>(sig, &mut pk[..P::PUBLIC_KEY_BYTES], m, sk, rng, inject);
+  if result.is_err() {
+    sig.fill(0);
+  }
+  result
+}
+
+fn sign_checked<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  pk: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+  inject: impl FnOnce(&mut SigningKey<P>, &mut SignScratch<P>),
+) -> Result<(), &'static str> {
+  crypto_sign_public_key::<P>(pk, sk).map_err(|_| FAULT_DETECTED)?;
+
+  let mut key = SigningKey::<P>::new();
+  key.expand(m, sk, rng);
+  let digest = key.secret_digest();
+
+  let mut ws = SignScratch::<P>::default();
+  let mut nonce = 0u16;
+  while !crypto_sign_attempt::<P>(&key, &mut ws, sig, nonce) {
+    nonce += 1;
+  }
+  inject(&mut key, &mut ws);
+
+  let mut ok = check_challenge::<P>(key.mu(), ws.w1(), ws.challenge(), sig);
+  ok &= key.secret_digest().ct_eq(&digest);
+  if !bool::from(ok) {
+    return Err(FAULT_DETECTED);
+  }
+  crypto_sign_verify::<P>(sig, m, pk).map_err(|_| FAULT_DETECTED)
+}
+
+/// Second, independent derivation of the challenge from `mu` and `w1`.
+fn check_challenge<P: DilithiumParams>(mu: &[u8], w1: &P::Polyveck, cp: &Poly, sig: &[u8]) -> Choice {
+  // Large enough for K * POLYW1_PACKEDBYTES in every mode.
+  let mut buf = [0u8; 8 * 128];
+  let mut c = [0u8; SEEDBYTES];
+  let mut cp2 = Poly::default();
+  let mut state = KeccakState::default();
+
+  polyveck_pack_w1::<P>(&mut buf, w1);
+  state.shake256_absorb(&mu[..CRHBYTES]);
+  state.shake256_absorb(&buf[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut c);
+
+  poly_challenge::<P>(&mut cp2, &c);
+  poly_ntt::<P>(&mut cp2);
+
+  let mut ok = c.ct_eq(&sig[..SEEDBYTES]);
+  for (a, b) in cp.coeffs.iter().zip(cp2.coeffs.iter()) {
+    ok &= a.ct_eq(b);
+  }
+  ok
+}
@@ -0,0 +1,493 @@
+//! JOSE with ML-DSA keys: JWS signatures (RFC 7515) in the compact and JSON
+//! serializations, and `AKP` JSON Web Keys (RFC 7517).
+//!
+//! [`CompactJws`] is the `header.payload.signature` form JWTs use;
+//! [`JwsBuilder`] and [`JsonJws`] produce and check the general and
+//! flattened JSON serializations, with one or more signers; a parsed JWS
+//! hands out its payload before checking it, so nothing it says can be
+//! trusted until `verify` succeeds. Keys travel as [`Jwk`]s of the `AKP` key
+//! type, with `pub` holding the encoded public key and `priv` the 32-byte
+//! seed of the [`SeedKey`].
+//!
+//! Algorithms are named `ML-DSA-44`, `ML-DSA-65` and `ML-DSA-87`, as in the
+//! IETF JOSE ML-DSA draft; see
+//! [FIPS 204 interoperability](crate#fips-204-interoperability) for which
+//! verifiers accept the tokens and why `priv` only works between `pure_dsa`
+//! users. Unencoded (`b64: false`) and detached payloads are not supported,
+//! and any `crit` header parameter is rejected.
+
+use alloc::{string::String, vec, vec::Vec};
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+use crate::base64;
+use crate::json::{self, Value};
+use crate::secret::wipe;
+use crate::{Algorithm, Keypair, SeedKey};
+
+fn algorithm_name(alg: Algorithm) -> &'static str {
+    match alg {
+        #[cfg(feature = "mode2")]
+        Algorithm::Mode2 => "ML-DSA-44",
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3 => "ML-DSA-65",
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5 => "ML-DSA-87",
+    }
+}
+
+fn algorithm_from_name(name: &str) -> Result<Algorithm, &'static str> {
+    Algorithm::find(|alg| algorithm_name(alg) == name).ok_or("Unsupported algorithm")
+}
+
+fn parse_object(s: &str) -> Result<Value, &'static str> {
+    let value = Value::parse(s)?;
+    if value.as_object().is_none() {
+        return Err("Expected a JSON object");
+    }
+    Ok(value)
+}
+
+fn optional_string(value: &Value, key: &str) -> Result<Option<String>, &'static str> {
+    match value.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err("Invalid JSON member type"),
+    }
+}
+
+fn required_string<'a>(value: &'a Value, key: &str) -> Result<&'a str, &'static str> {
+    match value.get(key) {
+        None => Err("Missing JSON member"),
+        Some(v) => v.as_str().ok_or("Invalid JSON member type"),
+    }
+}
+
+/// A JSON Web Key of the `AKP` type: a [`SeedKey`] and an optional `kid`.
+#[derive(Debug)]
+pub struct Jwk {
+    key: SeedKey,
+    kid: Option<String>,
+}
+
+impl From<SeedKey> for Jwk {
+    fn from(key: SeedKey) -> Self {
+        Jwk { key, kid: None }
+    }
+}
+
+impl Jwk {
+    pub fn with_kid(mut self, kid: &str) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    pub fn key(&self) -> &SeedKey {
+        &self.key
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// The same JWK, `kid` included, without `priv`.
+    pub fn to_public(&self) -> Jwk {
+        Jwk { key: self.key.to_public(), kid: self.kid.clone() }
+    }
+
+    /// The JWK as a JSON object; `priv` is included for private keys.
+    pub fn to_json(&self) -> String {
+        let kid = self.kid.as_deref().map(json::string);
+        let alg = json::string(algorithm_name(self.key.algorithm()));
+        let public = json::string(&base64::encode_url(self.key.public_key()));
+        let private = self.key.seed().map(|s| {
+            let encoded = base64::encode_url(s);
+            let quoted = json::string(&encoded);
+            wipe(encoded.into_bytes());
+            quoted
+        });
+        let mut members = vec![("kty", "\"AKP\"")];
+        if let Some(kid) = &kid {
+            members.push(("kid", kid));
+        }
+        members.push(("alg", &alg));
+        members.push(("pub", &public));
+        if let Some(private) = &private {
+            members.push(("priv", private));
+        }
+        let json = json::object(&members);
+        if let Some(private) = private {
+            wipe(private.into_bytes());
+        }
+        json
+    }
+
+    /// Parses an `AKP` JWK. Members other than `kty`, `alg`, `pub`, `priv`
+    /// and `kid` are ignored; a `priv` seed must derive the given `pub`.
+    pub fn from_json(s: &str) -> Result<Self, &'static str> {
+        Self::read(&mut parse_object(s)?)
+    }
+
+    fn read(value: &mut Value) -> Result<Self, &'static str> {
+        // Moved out of the parsed object before anything else can fail, so
+        // that it is wiped on every path.
+        let private = match value.get_mut("priv") {
+            None => None,
+            Some(Value::String(s)) => Some(core::mem::take(s)),
+            Some(_) => return Err("Invalid JSON member type"),
+        };
+        let jwk = Self::read_members(value, private.as_deref());
+        if let Some(private) = private {
+            wipe(private.into_bytes());
+        }
+        jwk
+    }
+
+    fn read_members(value: &Value, private: Option<&str>) -> Result<Self, &'static str> {
+        if required_string(value, "kty")? != "AKP" {
+            return Err("Unsupported key type");
+        }
+        let algorithm = algorithm_from_name(required_string(value, "alg")?)?;
+        let public_key = base64::decode_url(required_string(value, "pub")?)?;
+        let seed = private.map(base64::decode_url).transpose()?;
+        let key = SeedKey::read(algorithm, &public_key, seed.as_deref());
+        if let Some(seed) = seed {
+            wipe(seed);
+        }
+        Ok(Jwk { key: key?, kid: optional_string(value, "kid")? })
+    }
+}
+
+/// The JOSE header parameters this crate understands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub alg: Algorithm,
+    pub kid: Option<String>,
+    pub typ: Option<String>,
+    pub cty: Option<String>,
+}
+
+impl Header {
+    pub fn new(alg: Algorithm) -> Self {
+        Header { alg, kid: None, typ: None, cty: None }
+    }
+
+    pub fn with_kid(mut self, kid: &str) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    pub fn with_typ(mut self, typ: &str) -> Self {
+        self.typ = Some(typ.into());
+        self
+    }
+
+    pub fn with_cty(mut self, cty: &str) -> Self {
+        self.cty = Some(cty.into());
+        self
+    }
+
+    fn to_json(&self) -> String {
+        let alg = json::string(algorithm_name(self.alg));
+        let optional = [("kid", &self.kid), ("typ", &self.typ), ("cty", &self.cty)];
+        let values: Vec<(&str, String)> =
+            optional.iter().filter_map(|(k, v)| v.as_deref().map(|v| (*k, json::string(v)))).collect();
+        let mut members = vec![("alg", &alg[..])];
+        members.extend(values.iter().map(|(k, v)| (*k, &v[..])));
+        json::object(&members)
+    }
+
+    /// The header formed by the protected and unprotected parameters,
+    /// which must not overlap.
+    fn read(protected: &Value, unprotected: Option<&Value>) -> Result<Self, &'static str> {
+        let mut members: Vec<(String, Value)> = protected.as_object().ok_or("Expected a JSON object")?.to_vec();
+        if let Some(unprotected) = unprotected {
+            for member in unprotected.as_object().ok_or("Expected a JSON object")? {
+                if members.iter().any(|(k, _)| *k == member.0) {
+                    return Err("Duplicate header parameter");
+                }
+                members.push(member.clone());
+            }
+        }
+        let merged = Value::Object(members);
+        if merged.get("crit").is_some() {
+            return Err("Unsupported critical header parameter");
+        }
+        let alg = match merged.get("alg") {
+            None => return Err("Missing alg header parameter"),
+            Some(v) => algorithm_from_name(v.as_str().ok_or("Invalid JSON member type")?)?,
+        };
+        Ok(Header {
+            alg,
+            kid: optional_string(&merged, "kid")?,
+            typ: optional_string(&merged, "typ")?,
+            cty: optional_string(&merged, "cty")?,
+        })
+    }
+}
+
+/// `BASE64URL(protected) || '.' || BASE64URL(payload)`.
+fn signing_input(protected: &str, payload: &str) -> String {
+    let mut input = String::with_capacity(protected.len() + payload.len() + 1);
+    input.push_str(protected);
+    input.push('.');
+    input.push_str(payload);
+    input
+}
+
+fn sign_input<R: RngCore>(
+    header: &Header,
+    payload: &str,
+    keypair: &Keypair,
+    rng: &mut R,
+) -> Result<(String, Vec<u8>), &'static str> {
+    if header.alg != keypair.algorithm() {
+        return Err("Header alg does not match the key");
+    }
+    let protected = base64::encode_url(header.to_json().as_bytes());
+    let signature = keypair.sign_with_rng(signing_input(&protected, payload).as_bytes(), rng);
+    Ok((protected, signature.bytes().to_vec()))
+}
+
+fn decode_header(protected: &str) -> Result<Value, &'static str> {
+    let bytes = base64::decode_url(protected)?;
+    parse_object(core::str::from_utf8(&bytes).map_err(|_| "Malformed JSON")?)
+}
+
+/// A JWS in the compact serialization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactJws {
+    serialized: String,
+    header: Header,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl CompactJws {
+    #[cfg(feature = "getrandom")]
+    pub fn sign(header: &Header, payload: &[u8], keypair: &Keypair) -> Result<Self, &'static str> {
+        Self::sign_with_rng(header, payload, keypair, &mut OsRng)
+    }
+
+    /// Signs `payload` under a protected header; `header.alg` must be the
+    /// keypair's algorithm.
+    pub fn sign_with_rng<R: RngCore>(
+        header: &Header,
+        payload: &[u8],
+        keypair: &Keypair,
+        rng: &mut R,
+    ) -> Result<Self, &'static str> {
+        let encoded = base64::encode_url(payload);
+        let (protected, signature) = sign_input(header, &encoded, keypair, rng)?;
+        let mut serialized = signing_input(&protected, &encoded);
+        serialized.push('.');
+        serialized.push_str(&base64::encode_url(&signature));
+        Ok(CompactJws { serialized, header: header.clone(), payload: payload.to_vec(), signature })
+    }
+
+    pub fn parse(s: &str) -> Result<Self, &'static str> {
+        let parts: Vec<&str> = s.split('.').collect();
+        let [protected, payload, signature] = parts[..] else {
+            return Err("Malformed JWS");
+        };
+        let header = Header::read(&decode_header(protected)?, None)?;
+        Ok(CompactJws {
+            serialized: s.into(),
+            header,
+            payload: base64::decode_url(payload)?,
+            signature: base64::decode_url(signature)?,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.serialized
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Checks the signature with `key` and returns the payload.
+    pub fn verify(&self, key: &Jwk) -> Result<&[u8], &'static str> {
+        if self.header.alg != key.key().algorithm() {
+            return Err("Key algorithm does not match the header");
+        }
+        let input = &self.serialized[..self.serialized.rfind('.').unwrap()];
+        key.key().verify(&self.signature, input.as_bytes())?;
+        Ok(&self.payload)
+    }
+}
+
+/// One signature of a [`JsonJws`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct JwsSignature {
+    protected: String,
+    unprotected: Option<Value>,
+    header: Header,
+    signature: Vec<u8>,
+}
+
+impl JwsSignature {
+    /// The protected and unprotected header parameters together.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    fn members(&self) -> (String, Option<String>, String) {
+        (
+            json::string(&self.protected),
+            self.unprotected.as_ref().map(Value::to_json),
+            json::string(&base64::encode_url(&self.signature)),
+        )
+    }
+
+    fn read(value: &Value) -> Result<Self, &'static str> {
+        let protected = required_string(value, "protected")?;
+        let unprotected = value.get("header");
+        Ok(JwsSignature {
+            protected: protected.into(),
+            unprotected: unprotected.cloned(),
+            header: Header::read(&decode_header(protected)?, unprotected)?,
+            signature: base64::decode_url(required_string(value, "signature")?)?,
+        })
+    }
+}
+
+/// Signs one payload with any number of keys, for the JSON serialization.
+pub struct JwsBuilder<'a> {
+    payload: &'a [u8],
+    signers: Vec<(Header, &'a Keypair)>,
+}
+
+impl<'a> JwsBuilder<'a> {
+    pub fn new(payload: &'a [u8]) -> Self {
+        JwsBuilder { payload, signers: Vec::new() }
+    }
+
+    /// Adds a signature under `header`, which is sent fully protected.
+    pub fn add_signer(mut self, header: Header, keypair: &'a Keypair) -> Self {
+        self.signers.push((header, keypair));
+        self
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn build(&self) -> Result<JsonJws, &'static str> {
+        self.build_with_rng(&mut OsRng)
+    }
+
+    pub fn build_with_rng<R: RngCore>(&self, rng: &mut R) -> Result<JsonJws, &'static str> {
+        if self.signers.is_empty() {
+            return Err("No signers");
+        }
+        let encoded = base64::encode_url(self.payload);
+        let mut signatures = Vec::with_capacity(self.signers.len());
+        for (header, keypair) in &self.signers {
+            let (protected, signature) = sign_input(header, &encoded, keypair, rng)?;
+            signatures.push(JwsSignature { protected, unprotected: None, header: header.clone(), signature });
+        }
+        Ok(JsonJws { payload: self.payload.to_vec(), encoded_payload: encoded, signatures })
+    }
+}
+
+/// A JWS in the general or flattened JSON serialization.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonJws {
+    payload: Vec<u8>,
+    encoded_payload: String,
+    signatures: Vec<JwsSignature>,
+}
+
+impl JsonJws {
+    /// Parses either JSON serialization.
+    pub fn from_json(s: &str) -> Result<Self, &'static str> {
+        let value = parse_object(s)?;
+        let encoded_payload = required_string(&value, "payload")?;
+        let signatures = match (value.get("signatures"), value.get("signature")) {
+            (Some(list), None) => {
+                if ["protected", "header"].iter().any(|k| value.get(k).is_some()) {
+                    return Err("Malformed JWS");
+                }
+                let list = list.as_array().ok_or("Invalid JSON member type")?;
+                if list.is_empty() {
+                    return Err("No signatures");
+                }
+                list.iter().map(JwsSignature::read).collect::<Result<_, _>>()?
+            }
+            (None, Some(_)) => vec![JwsSignature::read(&value)?],
+            _ => return Err("Malformed JWS"),
+        };
+        Ok(JsonJws {
+            payload: base64::decode_url(encoded_payload)?,
+            encoded_payload: encoded_payload.into(),
+            signatures,
+        })
+    }
+
+    /// The general serialization, with a `signatures` array.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .signatures
+            .iter()
+            .map(|s| {
+                let (protected, header, signature) = s.members();
+                let mut members = vec![("protected", &protected[..])];
+                if let Some(header) = &header {
+                    members.push(("header", header));
+                }
+                members.push(("signature", &signature));
+                json::object(&members)
+            })
+            .collect();
+        let payload = json::string(&self.encoded_payload);
+        json::object(&[("payload", &payload), ("signatures", &json::array(&entries))])
+    }
+
+    /// The flattened serialization, for a JWS with exactly one signature.
+    pub fn to_flattened_json(&self) -> Result<String, &'static str> {
+        let [s] = &self.signatures[..] else {
+            return Err("Flattened serialization needs exactly one signature");
+        };
+        let (protected, header, signature) = s.members();
+        let payload = json::string(&self.encoded_payload);
+        let mut members = vec![("payload", &payload[..]), ("protected", &protected)];
+        if let Some(header) = &header {
+            members.push(("header", header));
+        }
+        members.push(("signature", &signature));
+        Ok(json::object(&members))
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn signatures(&self) -> &[JwsSignature] {
+        &self.signatures
+    }
+
+    /// Returns the payload if one of the signatures whose `alg` matches
+    /// `key` verifies with it.
+    pub fn verify(&self, key: &Jwk) -> Result<&[u8], &'static str> {
+        let valid = self.signatures.iter().filter(|s| s.header.alg == key.key().algorithm()).any(|s| {
+            let input = signing_input(&s.protected, &self.encoded_payload);
+            key.key().verify(&s.signature, input.as_bytes()).is_ok()
+        });
+        if !valid {
+            return Err("Invalid signature");
+        }
+        Ok(&self.payload)
+    }
+}
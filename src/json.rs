@@ -0,0 +1,325 @@
+//! The JSON (RFC 8259) subset JOSE needs. The writer builds objects from
+//! already-encoded member values; the reader takes whole documents, keeps
+//! numbers as their source text and rejects duplicate member names.
+
+use alloc::{string::String, vec::Vec};
+
+const MALFORMED: &str = "Malformed JSON";
+const MAX_DEPTH: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+  Null,
+  Bool(bool),
+  Number(String),
+  String(String),
+  Array(Vec<Value>),
+  Object(Vec<(String, Value)>),
+}
+
+impl Value {
+  /// Parses `s`, which must hold exactly one value.
+  pub fn parse(s: &str) -> Result<Value, &'static str> {
+    let mut p = Parser { s: s.as_bytes(), pos: 0 };
+    let value = p.value(0)?;
+    p.skip_ws();
+    if p.pos != p.s.len() {
+      return Err(MALFORMED);
+    }
+    Ok(value)
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Value::String(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Value]> {
+    match self {
+      Value::Array(a) => Some(a),
+      _ => None,
+    }
+  }
+
+  pub fn as_object(&self) -> Option<&[(String, Value)]> {
+    match self {
+      Value::Object(o) => Some(o),
+      _ => None,
+    }
+  }
+
+  /// The member `key` of an object.
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    self.as_object()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+  }
+
+  /// Like `get`, but mutable, so that a secret can be moved out and wiped.
+  pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+    match self {
+      Value::Object(o) => o.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+
+  /// The compact encoding of the value, members in their parsed order.
+  pub fn to_json(&self) -> String {
+    match self {
+      Value::Null => String::from("null"),
+      Value::Bool(b) => String::from(if *b { "true" } else { "false" }),
+      Value::Number(n) => n.clone(),
+      Value::String(s) => string(s),
+      Value::Array(a) => array(&a.iter().map(Value::to_json).collect::<Vec<_>>()),
+      Value::Object(o) => {
+        let values: Vec<String> = o.iter().map(|(_, v)| v.to_json()).collect();
+        let members: Vec<(&str, &str)> = o.iter().zip(&values).map(|((k, _), v)| (&k[..], &v[..])).collect();
+        object(&members)
+      }
+    }
+  }
+}
+
+/// `s` as a JSON string literal.
+pub fn string(s: &str) -> String {
+  let mut out = String::with_capacity(s.len() + 2);
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        out.push_str("\\u00");
+        out.push(HEX[c as usize >> 4] as char);
+        out.push(HEX[c as usize & 15] as char);
+      }
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+/// An object whose member values are already encoded, in the given order.
+/// A value may be a private key, so the output is allocated once (unless a
+/// key needs escaping) and never leaves a partial copy in freed memory.
+pub fn object(members: &[(&str, &str)]) -> String {
+  let len: usize = members.iter().map(|(k, v)| k.len() + v.len() + 4).sum();
+  let mut out = String::with_capacity(len + 2);
+  out.push('{');
+  for (i, (key, value)) in members.iter().enumerate() {
+    if i > 0 {
+      out.push(',');
+    }
+    out.push_str(&string(key));
+    out.push(':');
+    out.push_str(value);
+  }
+  out.push('}');
+  out
+}
+
+/// An array of already-encoded values.
+pub fn array(items: &[String]) -> String {
+  let mut out = String::from("[");
+  out.push_str(&items.join(","));
+  out.push(']');
+  out
+}
+
+struct Parser<'a> {
+  s: &'a [u8],
+  pos: usize,
+}
+
+impl Parser<'_> {
+  fn skip_ws(&mut self) {
+    while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.s.get(self.pos) {
+      self.pos += 1;
+    }
+  }
+
+  fn peek(&self) -> Option<u8> {
+    self.s.get(self.pos).copied()
+  }
+
+  fn expect(&mut self, c: u8) -> Result<(), &'static str> {
+    if self.peek() != Some(c) {
+      return Err(MALFORMED);
+    }
+    self.pos += 1;
+    Ok(())
+  }
+
+  fn literal(&mut self, word: &[u8], value: Value) -> Result<Value, &'static str> {
+    if !self.s[self.pos..].starts_with(word) {
+      return Err(MALFORMED);
+    }
+    self.pos += word.len();
+    Ok(value)
+  }
+
+  fn value(&mut self, depth: usize) -> Result<Value, &'static str> {
+    if depth > MAX_DEPTH {
+      return Err("JSON nested too deeply");
+    }
+    self.skip_ws();
+    match self.peek().ok_or(MALFORMED)? {
+      b'{' => {
+        self.pos += 1;
+        let mut members: Vec<(String, Value)> = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+          self.pos += 1;
+          return Ok(Value::Object(members));
+        }
+        loop {
+          self.skip_ws();
+          let key = self.string()?;
+          if members.iter().any(|(k, _)| *k == key) {
+            return Err("Duplicate JSON member");
+          }
+          self.skip_ws();
+          self.expect(b':')?;
+          let value = self.value(depth + 1)?;
+          members.push((key, value));
+          self.skip_ws();
+          match self.peek() {
+            Some(b',') => self.pos += 1,
+            Some(b'}') => {
+              self.pos += 1;
+              return Ok(Value::Object(members));
+            }
+            _ => return Err(MALFORMED),
+          }
+        }
+      }
+      b'[' => {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+          self.pos += 1;
+          return Ok(Value::Array(items));
+        }
+        loop {
+          items.push(self.value(depth + 1)?);
+          self.skip_ws();
+          match self.peek() {
+            Some(b',') => self.pos += 1,
+            Some(b']') => {
+              self.pos += 1;
+              return Ok(Value::Array(items));
+            }
+            _ => return Err(MALFORMED),
+          }
+        }
+      }
+      b'"' => self.string().map(Value::String),
+      b't' => self.literal(b"true", Value::Bool(true)),
+      b'f' => self.literal(b"false", Value::Bool(false)),
+      b'n' => self.literal(b"null", Value::Null),
+      _ => self.number(),
+    }
+  }
+
+  fn digits(&mut self) -> usize {
+    let start = self.pos;
+    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    self.pos - start
+  }
+
+  fn number(&mut self) -> Result<Value, &'static str> {
+    let start = self.pos;
+    if self.peek() == Some(b'-') {
+      self.pos += 1;
+    }
+    let first = self.peek();
+    let int = self.digits();
+    if int == 0 || (int > 1 && first == Some(b'0')) {
+      return Err(MALFORMED);
+    }
+    if self.peek() == Some(b'.') {
+      self.pos += 1;
+      if self.digits() == 0 {
+        return Err(MALFORMED);
+      }
+    }
+    if let Some(b'e' | b'E') = self.peek() {
+      self.pos += 1;
+      if let Some(b'+' | b'-') = self.peek() {
+        self.pos += 1;
+      }
+      if self.digits() == 0 {
+        return Err(MALFORMED);
+      }
+    }
+    // Only ASCII was consumed, so the slice is valid UTF-8.
+    Ok(Value::Number(String::from_utf8(self.s[start..self.pos].to_vec()).unwrap()))
+  }
+
+  fn hex4(&mut self) -> Result<u32, &'static str> {
+    let hex = self.s.get(self.pos..self.pos + 4).ok_or(MALFORMED)?;
+    self.pos += 4;
+    hex.iter().try_fold(0u32, |acc, &c| Ok(acc << 4 | (c as char).to_digit(16).ok_or(MALFORMED)?))
+  }
+
+  fn string(&mut self) -> Result<String, &'static str> {
+    self.expect(b'"')?;
+    // Escapes only shrink, so the raw length bounds the decoded one and
+    // `out` never reallocates: a secret string leaves no copy behind.
+    let mut end = self.pos;
+    while end < self.s.len() && self.s[end] != b'"' {
+      end += if self.s[end] == b'\\' { 2 } else { 1 };
+    }
+    let mut out = Vec::with_capacity(end.min(self.s.len()) - self.pos);
+    loop {
+      let c = self.peek().ok_or(MALFORMED)?;
+      self.pos += 1;
+      match c {
+        b'"' => break,
+        b'\\' => {
+          let e = self.peek().ok_or(MALFORMED)?;
+          self.pos += 1;
+          let decoded = match e {
+            b'"' | b'\\' | b'/' => e as char,
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+              let hi = self.hex4()?;
+              let cp = if (0xd800..0xdc00).contains(&hi) {
+                if !self.s[self.pos..].starts_with(b"\\u") {
+                  return Err(MALFORMED);
+                }
+                self.pos += 2;
+                let lo = self.hex4()?;
+                if !(0xdc00..0xe000).contains(&lo) {
+                  return Err(MALFORMED);
+                }
+                0x10000 + ((hi - 0xd800) << 10 | (lo - 0xdc00))
+              } else {
+                hi
+              };
+              char::from_u32(cp).ok_or(MALFORMED)?
+            }
+            _ => return Err(MALFORMED),
+          };
+          let mut buf = [0u8; 4];
+          out.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+        }
+        c if c < 0x20 => return Err(MALFORMED),
+        c => out.push(c),
+      }
+    }
+    String::from_utf8(out).map_err(|_| MALFORMED)
+  }
+}
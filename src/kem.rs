@@ -0,0 +1,543 @@
+//! ML-KEM (FIPS 203) on the crate's Keccak: `G` is SHA3-512, `H` SHA3-256,
+//! `J` and `PRF` SHAKE256 and the matrix XOF SHAKE128.
+//!
+//! Coefficients are kept fully reduced in `[0, q)`. Every reduction of a
+//! secret-dependent value, including the rounding divisions of `Compress`,
+//! is a multiply and shift by a constant, never a hardware division.
+
+use rand_core::RngCore;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::{
+  fips202::{KeccakState, SHA3_256_RATE, SHA3_512_RATE},
+  randombytes::randombytes,
+  secret::SecretBytes,
+};
+
+const N: usize = 256;
+const Q: u32 = 3329;
+/// `floor(2^36 / q)`: `(x * BARRETT) >> 36` is `x / q` for all `x < 3q^2`.
+const BARRETT: u64 = 20642679;
+/// `128^-1 mod q`, the scaling of the inverse NTT.
+const INV_128: u32 = 3303;
+const SYMBYTES: usize = 32;
+pub const SHARED_SECRET_BYTES: usize = 32;
+const MAX_K: usize = 4;
+const SHA3_PAD: u8 = 0x06;
+
+pub trait KemParams {
+    const K: usize;
+    const ETA1: usize;
+    const ETA2: usize = 2;
+    const DU: usize;
+    const DV: usize;
+
+    const POLYVEC_BYTES: usize = 384 * Self::K;
+    const PUBLIC_KEY_BYTES: usize = Self::POLYVEC_BYTES + SYMBYTES;
+    const SECRET_KEY_BYTES: usize = 2 * Self::POLYVEC_BYTES + 3 * SYMBYTES;
+    const CIPHERTEXT_BYTES: usize = 32 * (Self::DU * Self::K + Self::DV);
+}
+
+pub struct MlKem512;
+impl KemParams for MlKem512 {
+    const K: usize = 2;
+    const ETA1: usize = 3;
+    const DU: usize = 10;
+    const DV: usize = 4;
+}
+
+pub struct MlKem768;
+impl KemParams for MlKem768 {
+    const K: usize = 3;
+    const ETA1: usize = 2;
+    const DU: usize = 10;
+    const DV: usize = 4;
+}
+
+pub struct MlKem1024;
+impl KemParams for MlKem1024 {
+    const K: usize = 4;
+    const ETA1: usize = 2;
+    const DU: usize = 11;
+    const DV: usize = 5;
+}
+
+const fn pow17(mut e: u32) -> u32 {
+  let (mut r, mut b) = (1, 17);
+  while e > 0 {
+    if e & 1 == 1 {
+      r = r * b % Q;
+    }
+    b = b * b % Q;
+    e >>= 1;
+  }
+  r
+}
+
+const fn bitrev7(i: u32) -> u32 {
+  i.reverse_bits() >> 25
+}
+
+/// `17^BitRev7(i) mod q`, FIPS 203 appendix A.
+const ZETAS: [u32; 128] = {
+  let mut z = [0; 128];
+  let mut i = 0;
+  while i < 128 {
+    z[i] = pow17(bitrev7(i as u32));
+    i += 1;
+  }
+  z
+};
+
+/// `17^(2 BitRev7(i) + 1) mod q`, the moduli of the base-case products.
+const GAMMAS: [u32; 128] = {
+  let mut g = [0; 128];
+  let mut i = 0;
+  while i < 128 {
+    g[i] = pow17(2 * bitrev7(i as u32) + 1);
+    i += 1;
+  }
+  g
+};
+
+#[inline(always)]
+fn reduce(x: u32) -> u32 {
+  x - ((x as u64 * BARRETT) >> 36) as u32 * Q
+}
+
+/// `x - q` if `x >= q`, without a branch; `x < 2q`.
+#[inline(always)]
+fn csubq(x: u32) -> u32 {
+  let t = x.wrapping_sub(Q);
+  t.wrapping_add((t >> 31).wrapping_neg() & Q)
+}
+
+#[inline(always)]
+fn fqmul(a: u32, b: u32) -> u32 {
+  reduce(a * b)
+}
+
+#[derive(Clone, Copy)]
+struct Poly([u32; N]);
+
+impl Default for Poly {
+  fn default() -> Self {
+    Poly([0; N])
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Poly {
+  fn zeroize(&mut self) {
+    self.0.zeroize();
+  }
+}
+
+impl Poly {
+  fn add_assign(&mut self, b: &Poly) {
+    for (x, y) in self.0.iter_mut().zip(b.0.iter()) {
+      *x = csubq(*x + y);
+    }
+  }
+
+  fn sub_assign(&mut self, b: &Poly) {
+    for (x, y) in self.0.iter_mut().zip(b.0.iter()) {
+      *x = csubq(*x + Q - y);
+    }
+  }
+
+  /// Algorithm 9, `NTT`.
+  fn ntt(&mut self) {
+    let f = &mut self.0;
+    let mut i = 1;
+    let mut len = 128;
+    while len >= 2 {
+      for start in (0..N).step_by(2 * len) {
+        let zeta = ZETAS[i];
+        i += 1;
+        for j in start..start + len {
+          let t = fqmul(zeta, f[j + len]);
+          f[j + len] = csubq(f[j] + Q - t);
+          f[j] = csubq(f[j] + t);
+        }
+      }
+      len >>= 1;
+    }
+  }
+
+  /// Algorithm 10, `NTT^-1`.
+  fn inv_ntt(&mut self) {
+    let f = &mut self.0;
+    let mut i = 127;
+    let mut len = 2;
+    while len <= 128 {
+      for start in (0..N).step_by(2 * len) {
+        let zeta = ZETAS[i];
+        i -= 1;
+        for j in start..start + len {
+          let t = f[j];
+          f[j] = csubq(t + f[j + len]);
+          f[j + len] = fqmul(zeta, f[j + len] + Q - t);
+        }
+      }
+      len <<= 1;
+    }
+    for x in f.iter_mut() {
+      *x = fqmul(*x, INV_128);
+    }
+  }
+
+  /// Algorithms 11 and 12: adds the NTT-domain product `a * b` to `self`.
+  fn mul_acc(&mut self, a: &Poly, b: &Poly) {
+    for i in 0..N / 2 {
+      let (a0, a1, b0, b1) = (a.0[2 * i], a.0[2 * i + 1], b.0[2 * i], b.0[2 * i + 1]);
+      let c0 = reduce(a0 * b0 + fqmul(reduce(a1 * b1), GAMMAS[i]));
+      let c1 = reduce(a0 * b1 + a1 * b0);
+      self.0[2 * i] = csubq(self.0[2 * i] + c0);
+      self.0[2 * i + 1] = csubq(self.0[2 * i + 1] + c1);
+    }
+  }
+
+  /// Algorithm 5, `ByteEncode_d`, of the low `d` bits of every coefficient.
+  fn encode(&self, d: usize, out: &mut [u8]) {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut pos = 0;
+    for &x in self.0.iter() {
+      acc |= x << bits;
+      bits += d;
+      while bits >= 8 {
+        out[pos] = acc as u8;
+        pos += 1;
+        acc >>= 8;
+        bits -= 8;
+      }
+    }
+  }
+
+  /// Algorithm 6, `ByteDecode_d`, without the reduction mod `q` for `d = 12`.
+  fn decode(d: usize, bytes: &[u8]) -> Poly {
+    let mut p = Poly::default();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut pos = 0;
+    for x in p.0.iter_mut() {
+      while bits < d {
+        acc |= (bytes[pos] as u32) << bits;
+        pos += 1;
+        bits += 8;
+      }
+      *x = acc & ((1 << d) - 1);
+      acc >>= d;
+      bits -= d;
+    }
+    p
+  }
+
+  /// `Compress_d`: `round(2^d x / q) mod 2^d`.
+  fn compress(&mut self, d: usize) {
+    for x in self.0.iter_mut() {
+      let n = (*x << d) + (Q - 1) / 2;
+      *x = ((n as u64 * BARRETT) >> 36) as u32 & ((1 << d) - 1);
+    }
+  }
+
+  /// `Decompress_d`: `round(q y / 2^d)`.
+  fn decompress(&mut self, d: usize) {
+    for x in self.0.iter_mut() {
+      *x = (*x * Q + (1 << (d - 1))) >> d;
+    }
+  }
+}
+
+type PolyVec = [Poly; MAX_K];
+
+fn sha3(out: &mut [u8], rate: usize, parts: &[&[u8]]) {
+  let mut state = KeccakState::default();
+  for part in parts {
+    state.absorb(rate, part);
+  }
+  state.finalize(rate, SHA3_PAD).read(out);
+}
+
+/// `H`: SHA3-256.
+fn hash_h(out: &mut [u8; 32], input: &[u8]) {
+  sha3(out, SHA3_256_RATE, &[input]);
+}
+
+/// `G`: SHA3-512, split into two 32-byte halves.
+fn hash_g(out: &mut [u8; 64], parts: &[&[u8]]) {
+  sha3(out, SHA3_512_RATE, parts);
+}
+
+/// `J`: SHAKE256 with 32 bytes of output.
+fn hash_j(out: &mut [u8], z: &[u8], c: &[u8]) {
+  let mut state = KeccakState::default();
+  state.shake256_absorb(z);
+  state.shake256_absorb(c);
+  state.shake256_finalize().read(out);
+}
+
+/// Algorithm 7, `SampleNTT`, of `XOF(rho || j || i)`.
+fn sample_ntt(rho: &[u8], j: u8, i: u8) -> Poly {
+  let mut state = KeccakState::default();
+  state.shake128_absorb(rho);
+  state.shake128_absorb(&[j, i]);
+  let mut xof = state.shake128_finalize();
+  let mut p = Poly::default();
+  let mut buf = [0u8; 168];
+  let mut pos = buf.len();
+  let mut n = 0;
+  while n < N {
+    if pos == buf.len() {
+      xof.read(&mut buf);
+      pos = 0;
+    }
+    let (b0, b1, b2) = (buf[pos] as u32, buf[pos + 1] as u32, buf[pos + 2] as u32);
+    pos += 3;
+    let d1 = b0 | (b1 & 0x0F) << 8;
+    let d2 = b1 >> 4 | b2 << 4;
+    if d1 < Q {
+      p.0[n] = d1;
+      n += 1;
+    }
+    if d2 < Q && n < N {
+      p.0[n] = d2;
+      n += 1;
+    }
+  }
+  p
+}
+
+/// Algorithm 8, `SamplePolyCBD_eta`, of `PRF_eta(seed, nonce)`.
+fn sample_cbd(seed: &[u8], nonce: u8, eta: usize) -> Poly {
+  let mut buf = [0u8; 64 * 3];
+  let buf = &mut buf[..64 * eta];
+  let mut state = KeccakState::default();
+  state.shake256_absorb(seed);
+  state.shake256_absorb(&[nonce]);
+  state.shake256_finalize().read(buf);
+  let bit = |k: usize| (buf[k / 8] >> (k % 8)) as u32 & 1;
+  let mut p = Poly::default();
+  for (i, x) in p.0.iter_mut().enumerate() {
+    let (mut a, mut b) = (0, 0);
+    for j in 0..eta {
+      a += bit(2 * i * eta + j);
+      b += bit(2 * i * eta + eta + j);
+    }
+    *x = csubq(a + Q - b);
+  }
+  #[cfg(feature = "zeroize")]
+  buf.zeroize();
+  p
+}
+
+/// `A[i][j]`, or its transpose.
+fn matrix_entry(rho: &[u8], i: usize, j: usize, transposed: bool) -> Poly {
+  if transposed {
+    sample_ntt(rho, i as u8, j as u8)
+  } else {
+    sample_ntt(rho, j as u8, i as u8)
+  }
+}
+
+/// Algorithm 13, `K-PKE.KeyGen`, writing `ek_pke` and `dk_pke`.
+fn pke_keygen<P: KemParams>(ek: &mut [u8], dk: &mut [u8], d: &[u8]) {
+  let mut seeds = [0u8; 64];
+  hash_g(&mut seeds, &[d, &[P::K as u8]]);
+  let (rho, sigma) = seeds.split_at(SYMBYTES);
+
+  let mut s = PolyVec::default();
+  let mut e = PolyVec::default();
+  for i in 0..P::K {
+    s[i] = sample_cbd(sigma, i as u8, P::ETA1);
+    s[i].ntt();
+  }
+  for i in 0..P::K {
+    e[i] = sample_cbd(sigma, (P::K + i) as u8, P::ETA1);
+    e[i].ntt();
+  }
+  for i in 0..P::K {
+    let mut t = e[i];
+    for j in 0..P::K {
+      t.mul_acc(&matrix_entry(rho, i, j, false), &s[j]);
+    }
+    t.encode(12, &mut ek[384 * i..384 * (i + 1)]);
+    s[i].encode(12, &mut dk[384 * i..384 * (i + 1)]);
+  }
+  ek[P::POLYVEC_BYTES..].copy_from_slice(rho);
+  #[cfg(feature = "zeroize")]
+  {
+    seeds.zeroize();
+    s.zeroize();
+    e.zeroize();
+  }
+}
+
+/// Algorithm 14, `K-PKE.Encrypt`.
+fn pke_encrypt<P: KemParams>(c: &mut [u8], ek: &[u8], m: &[u8], r: &[u8]) {
+  let rho = &ek[P::POLYVEC_BYTES..];
+  let mut y = PolyVec::default();
+  for i in 0..P::K {
+    y[i] = sample_cbd(r, i as u8, P::ETA1);
+    y[i].ntt();
+  }
+
+  let (c1, c2) = c.split_at_mut(32 * P::DU * P::K);
+  for i in 0..P::K {
+    let mut u = Poly::default();
+    for j in 0..P::K {
+      u.mul_acc(&matrix_entry(rho, i, j, true), &y[j]);
+    }
+    u.inv_ntt();
+    u.add_assign(&sample_cbd(r, (P::K + i) as u8, P::ETA2));
+    u.compress(P::DU);
+    u.encode(P::DU, &mut c1[32 * P::DU * i..32 * P::DU * (i + 1)]);
+  }
+
+  let mut v = Poly::default();
+  for i in 0..P::K {
+    v.mul_acc(&decode12(&ek[384 * i..384 * (i + 1)]), &y[i]);
+  }
+  v.inv_ntt();
+  v.add_assign(&sample_cbd(r, (2 * P::K) as u8, P::ETA2));
+  let mut mu = Poly::decode(1, m);
+  mu.decompress(1);
+  v.add_assign(&mu);
+  v.compress(P::DV);
+  v.encode(P::DV, c2);
+  #[cfg(feature = "zeroize")]
+  {
+    y.zeroize();
+    v.zeroize();
+    mu.zeroize();
+  }
+}
+
+/// Algorithm 15, `K-PKE.Decrypt`.
+fn pke_decrypt<P: KemParams>(m: &mut [u8], dk: &[u8], c: &[u8]) {
+  let (c1, c2) = c.split_at(32 * P::DU * P::K);
+  let mut w = Poly::default();
+  for i in 0..P::K {
+    let mut u = Poly::decode(P::DU, &c1[32 * P::DU * i..]);
+    u.decompress(P::DU);
+    u.ntt();
+    w.mul_acc(&decode12(&dk[384 * i..384 * (i + 1)]), &u);
+  }
+  w.inv_ntt();
+  let mut v = Poly::decode(P::DV, c2);
+  v.decompress(P::DV);
+  v.sub_assign(&w);
+  v.compress(1);
+  v.encode(1, m);
+  #[cfg(feature = "zeroize")]
+  {
+    w.zeroize();
+    v.zeroize();
+  }
+}
+
+/// `ByteDecode_12` followed by the reduction mod `q`.
+fn decode12(bytes: &[u8]) -> Poly {
+  let mut p = Poly::decode(12, bytes);
+  for x in p.0.iter_mut() {
+    *x = csubq(*x);
+  }
+  p
+}
+
+/// Algorithm 16, `ML-KEM.KeyGen_internal`, from the 64-byte seed `d || z`.
+pub fn kem_keypair_from_seed<P: KemParams>(pk: &mut [u8], sk: &mut [u8], seed: &[u8]) {
+  let (d, z) = seed.split_at(SYMBYTES);
+  let (dk_pke, rest) = sk.split_at_mut(P::POLYVEC_BYTES);
+  pke_keygen::<P>(pk, dk_pke, d);
+  let (ek, rest) = rest.split_at_mut(P::PUBLIC_KEY_BYTES);
+  ek.copy_from_slice(pk);
+  let (h, z_out) = rest.split_at_mut(SYMBYTES);
+  hash_h(h.try_into().unwrap(), pk);
+  z_out.copy_from_slice(z);
+}
+
+/// Algorithm 19, `ML-KEM.KeyGen`.
+pub fn kem_keypair<P: KemParams, R: RngCore>(pk: &mut [u8], sk: &mut [u8], rng: &mut R) {
+  let mut seed = SecretBytes::<{ 2 * SYMBYTES }>::default();
+  randombytes(&mut seed, rng);
+  kem_keypair_from_seed::<P>(pk, sk, &seed);
+}
+
+/// The encapsulation key check of FIPS 203 section 7.2: every coefficient
+/// of `t` must already be reduced mod `q`.
+pub fn kem_check_public_key<P: KemParams>(pk: &[u8]) -> Result<(), &'static str> {
+  if pk.len() != P::PUBLIC_KEY_BYTES {
+    return Err("Invalid public key length");
+  }
+  for i in 0..P::K {
+    let t = Poly::decode(12, &pk[384 * i..384 * (i + 1)]);
+    if t.0.iter().any(|&x| x >= Q) {
+      return Err("Public key coefficient out of range");
+    }
+  }
+  Ok(())
+}
+
+/// The decapsulation key check of FIPS 203 section 7.3 (the stored
+/// `H(ek)` must match), plus the public key check on the embedded `ek`.
+pub fn kem_check_secret_key<P: KemParams>(sk: &[u8]) -> Result<(), &'static str> {
+  if sk.len() != P::SECRET_KEY_BYTES {
+    return Err("Invalid secret key length");
+  }
+  let ek = &sk[P::POLYVEC_BYTES..P::POLYVEC_BYTES + P::PUBLIC_KEY_BYTES];
+  kem_check_public_key::<P>(ek)?;
+  let mut h = [0u8; 32];
+  hash_h(&mut h, ek);
+  if h[..] != sk[P::POLYVEC_BYTES + P::PUBLIC_KEY_BYTES..][..SYMBYTES] {
+    return Err("Secret key hash check failed");
+  }
+  Ok(())
+}
+
+/// Algorithm 17, `ML-KEM.Encaps_internal`, with the message `m`.
+pub fn kem_encaps_from_seed<P: KemParams>(ct: &mut [u8], ss: &mut [u8], pk: &[u8], m: &[u8]) {
+  let mut h = [0u8; 32];
+  hash_h(&mut h, pk);
+  let mut kr = SecretBytes::<64>::default();
+  hash_g((&mut *kr).try_into().unwrap(), &[m, &h]);
+  pke_encrypt::<P>(ct, pk, m, &kr[SYMBYTES..]);
+  ss.copy_from_slice(&kr[..SYMBYTES]);
+}
+
+/// Algorithm 20, `ML-KEM.Encaps`, after the public key check.
+pub fn kem_encaps<P: KemParams, R: RngCore>(ct: &mut [u8], ss: &mut [u8], pk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+  kem_check_public_key::<P>(pk)?;
+  let mut m = SecretBytes::<SYMBYTES>::default();
+  randombytes(&mut m, rng);
+  kem_encaps_from_seed::<P>(ct, ss, pk, &m);
+  Ok(())
+}
+
+/// Algorithm 18, `ML-KEM.Decaps_internal`. A ciphertext that does not
+/// re-encrypt to itself yields the implicit-rejection key `J(z || c)`,
+/// chosen without a branch.
+pub fn kem_decaps<P: KemParams>(ss: &mut [u8], ct: &[u8], sk: &[u8]) {
+  let dk_pke = &sk[..P::POLYVEC_BYTES];
+  let ek = &sk[P::POLYVEC_BYTES..P::POLYVEC_BYTES + P::PUBLIC_KEY_BYTES];
+  let h = &sk[P::POLYVEC_BYTES + P::PUBLIC_KEY_BYTES..][..SYMBYTES];
+  let z = &sk[P::SECRET_KEY_BYTES - SYMBYTES..];
+
+  let mut m = SecretBytes::<SYMBYTES>::default();
+  pke_decrypt::<P>(&mut m, dk_pke, ct);
+  let mut kr = SecretBytes::<64>::default();
+  hash_g((&mut *kr).try_into().unwrap(), &[&m, h]);
+  let mut reject = SecretBytes::<SYMBYTES>::default();
+  hash_j(&mut reject, z, ct);
+
+  let mut max = [0u8; MlKem1024::CIPHERTEXT_BYTES];
+  let ct2 = &mut max[..P::CIPHERTEXT_BYTES];
+  pke_encrypt::<P>(ct2, ek, &m, &kr[SYMBYTES..]);
+  let same: Choice = ct.ct_eq(ct2);
+  for ((out, &k), &r) in ss.iter_mut().zip(kr.iter()).zip(reject.iter()) {
+    *out = u8::conditional_select(&r, &k, same);
+  }
+}
@@ -1,16 +1,100 @@
-#![cfg_attr(feature = "no_std", no_std)]
+//! ML-DSA (CRYSTALS-Dilithium) signatures for Mode2, Mode3 and Mode5, with
+//! optional SLH-DSA, ML-KEM, SHA-3 and X.509/CMS/JOSE/COSE/OpenSSH support.
+//!
+//! # FIPS 204 interoperability
+//!
+//! The signer implements round-3 Dilithium, which predates the final
+//! FIPS 204: it signs without a context string and with the round-3 hash
+//! sizes. Its signatures verify with `pure_dsa` but not with FIPS 204
+//! implementations. The same holds for every format built on it — X.509
+//! certificates, CMS, JOSE, COSE and OpenSSH objects carry the standard
+//! ML-DSA names and identifiers, but their signatures only check against
+//! this crate.
+//!
+//! Keys do not carry over either. JWK and COSE_Key `priv` parameters and the
+//! OpenSSH private key container hold the 32-byte seed, which this crate
+//! expands the round-3 way, as `SHAKE256(seed)`; FIPS 204 appends the
+//! one-byte encodings of `k` and `l` before hashing. A FIPS 204
+//! implementation that imports such a key derives a different key pair from
+//! it without any error, unless it checks the result against the stored
+//! public key. The reverse direction is caught: a seed that does not derive
+//! the stored public key is rejected on import. Exchange private keys only
+//! between `pure_dsa` users.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The arithmetic and packing routines mirror the reference implementation
+// coefficient by coefficient; keep that shape readable.
+#![allow(
+    clippy::identity_op,
+    clippy::erasing_op,
+    clippy::precedence,
+    clippy::needless_range_loop
+)]
+
+#[cfg(not(any(feature = "mode2", feature = "mode3", feature = "mode5")))]
+compile_error!("enable at least one of the `mode2`, `mode3` or `mode5` features");
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 mod rounding;
 mod reduce;
 mod randombytes;
 mod ntt;
 mod fips202;
+pub mod sha3;
 mod symmetric;
 mod poly;
 mod polyvec;
 mod sign;
+#[cfg(feature = "low_memory")]
+mod lowmem;
+#[cfg(feature = "hardened")]
+mod hardened;
+#[cfg(feature = "masked")]
+pub mod masked;
+#[cfg(feature = "slh_dsa")]
+mod slh;
+#[cfg(feature = "slh_dsa")]
+pub mod slh_dsa;
+#[cfg(feature = "ml_kem")]
+mod kem;
+#[cfg(feature = "ml_kem")]
+pub mod ml_kem;
+#[cfg(any(feature = "x509", feature = "jose", feature = "ssh"))]
+mod base64;
+#[cfg(feature = "x509")]
+mod der;
+#[cfg(feature = "x509")]
+pub mod x509;
+#[cfg(feature = "cms")]
+pub mod cms;
+#[cfg(feature = "jose")]
+mod json;
+#[cfg(feature = "jose")]
+pub mod jose;
+#[cfg(feature = "cose")]
+mod cbor;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(any(feature = "x509", feature = "ssh"))]
+mod sha2;
+#[cfg(feature = "ssh")]
+mod ssh_wire;
+#[cfg(feature = "ssh")]
+pub mod ssh;
 mod packing;
 mod params;
 mod objects;
+mod secret;
 
 pub use objects::*;
+pub use secret::SecretBytes;
+#[cfg(any(feature = "jose", feature = "cose", feature = "ssh"))]
+pub use secret::SeedKey;
+#[cfg(feature = "hardened")]
+pub use hardened::FAULT_DETECTED;
+#[cfg(feature = "fault_injection")]
+pub use hardened::Fault;
+#[cfg(feature = "parallel")]
+pub use sign::MAX_SIGN_THREADS;
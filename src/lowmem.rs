@@ -0,0 +1,351 @@
+//! Streaming keygen, signing and verification for the `low_memory` feature.
+//!
+//! These produce the same keys and signatures as the cached implementation in
+//! `sign.rs`, but never hold the whole matrix `A` or the expanded secret
+//! vectors. Matrix entries are regenerated from `rho` inside
+//! `polyvec_matrix_pointwise_montgomery`, `s1`, `s2` and `t0` are unpacked
+//! from the secret key one polynomial at a time when they are needed, and `y`
+//! is re-sampled instead of being kept next to `z`.
+//!
+//! Smallest thread stack that runs each `Algorithm`/`Keypair` entry point,
+//! measured on x86_64 Linux with a release build; the default build is shown
+//! in parentheses. Verification fits in the 16 KB glibc minimum.
+//!
+//! | mode  | keygen        | sign          | verify        |
+//! |-------|---------------|---------------|---------------|
+//! | Mode2 | 32 KB (58 KB) | 32 KB (72 KB) | 16 KB (41 KB) |
+//! | Mode3 | 35 KB (80 KB) | 37 KB (110 KB)| 16 KB (62 KB) |
+//! | Mode5 | 39 KB (170 KB)| 43 KB (229 KB)| 16 KB (151 KB)|
+//!
+//! The same `low_memory` entry points in an unoptimized (`dev` profile) build:
+//!
+//! | mode  | keygen | sign   | verify |
+//! |-------|--------|--------|--------|
+//! | Mode2 | 84 KB  | 92 KB  | 31 KB  |
+//! | Mode3 | 90 KB  | 107 KB | 33 KB  |
+//! | Mode5 | 98 KB  | 125 KB | 37 KB  |
+//!
+//! `tests/low_memory.rs` runs every mode on a 64 KB thread stack, but only
+//! when built with optimizations; a plain `cargo test` uses 160 KB. Check the
+//! 64 KB bound with
+//! `cargo test --release --features low_memory --test low_memory`.
+
+use rand_core::RngCore;
+use subtle::{Choice, ConstantTimeGreater};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+#[cfg(feature = "random")]
+use crate::randombytes::randombytes;
+use crate::{
+    secret::SecretBytes,
+    params::DilithiumParams,
+    polyvec::*,
+    fips202::*,
+    packing::*,
+    poly::*
+};
+
+const SEEDBYTES: usize = 32;
+const CRHBYTES: usize = 64;
+
+pub fn crypto_sign_keypair_from_seed<P: DilithiumParams>(pk: &mut [u8], sk: &mut [u8], seed: &[u8]) {
+  let mut seedbuf = SecretBytes::<{ 2 * SEEDBYTES + CRHBYTES }>::default();
+  let mut tr = [0u8; SEEDBYTES];
+  let mut mat = P::Mat::default();
+  let mut s1 = P::Polyvecl::default();
+  let mut t1 = P::Polyveck::default();
+  let mut t = Poly::default();
+
+  shake256(&mut seedbuf, seed);
+
+  let rho = &seedbuf[..SEEDBYTES];
+  let rhoprime = &seedbuf[SEEDBYTES..SEEDBYTES + CRHBYTES];
+  let key = &seedbuf[SEEDBYTES + CRHBYTES..];
+
+  polyvec_matrix_expand::<P>(&mut mat, rho);
+
+  polyvecl_uniform_eta::<P>(&mut s1, rhoprime, 0);
+  for (i, p) in s1.vec().iter().enumerate() {
+    polyeta_pack::<P>(&mut sk[sk_s1_offset::<P>(i)..], p);
+  }
+  polyvecl_ntt::<P>(&mut s1);
+
+  polyvec_matrix_pointwise_montgomery::<P>(&mut t1, &mat, &s1);
+  polyveck_reduce::<P>(&mut t1);
+  polyveck_invntt_tomont::<P>(&mut t1);
+
+  for (i, t1i) in t1.vec_mut().iter_mut().enumerate() {
+    poly_uniform_eta::<P>(&mut t, rhoprime, (P::L + i) as u16);
+    polyeta_pack::<P>(&mut sk[sk_s2_offset::<P>(i)..], &t);
+
+    poly_add_assign(t1i, &t);
+    poly_caddq::<P>(t1i);
+    poly_power2round::<P>(t1i, &mut t);
+    polyt0_pack(&mut sk[sk_t0_offset::<P>(i)..], &t);
+  }
+
+  pack_pk::<P>(pk, rho, &t1);
+  shake256(&mut tr, &pk[..P::crypto_publickeybytes()]);
+
+  sk[..SEEDBYTES].copy_from_slice(rho);
+  sk[SEEDBYTES..2 * SEEDBYTES].copy_from_slice(&key[..SEEDBYTES]);
+  sk[2 * SEEDBYTES..3 * SEEDBYTES].copy_from_slice(&tr);
+
+  #[cfg(feature = "zeroize")]
+  tr.zeroize();
+}
+
+/// Per-message signing state. Unlike the cached variant this keeps a borrow
+/// of the packed secret key instead of `s1`, `s2` and `t0` in NTT domain.
+pub(crate) struct SigningKey<'a, P: DilithiumParams> {
+  mat: P::Mat,
+  sk: &'a [u8],
+  keymu: SecretBytes<{ SEEDBYTES + CRHBYTES }>,
+  rhoprime: SecretBytes<CRHBYTES>,
+}
+
+impl<'a, P: DilithiumParams> SigningKey<'a, P> {
+  /// Zeroed key; `expand` fills it in place so the expanded secrets never
+  /// sit in a moved-from copy that nothing wipes.
+  #[inline(always)]
+  pub(crate) fn new() -> Self {
+    SigningKey::<P> {
+      mat: P::Mat::default(),
+      sk: &[],
+      keymu: SecretBytes::default(),
+      rhoprime: SecretBytes::default(),
+    }
+  }
+
+  pub(crate) fn expand<R: RngCore>(&mut self, m: &[u8], sk: &'a [u8], rng: &mut R) {
+    self.sk = sk;
+    let mut state = KeccakState::default();
+
+    self.keymu[..SEEDBYTES].copy_from_slice(&sk[SEEDBYTES..2 * SEEDBYTES]);
+
+    state.shake256_absorb(&sk[2 * SEEDBYTES..3 * SEEDBYTES]);
+    state.shake256_absorb(m);
+    state.shake256_finalize().read(&mut self.keymu[SEEDBYTES..]);
+
+    #[cfg(feature = "random")]
+    randombytes(&mut self.rhoprime, rng);
+
+    #[cfg(not(feature = "random"))]
+    {
+      let _ = rng;
+      shake256(&mut self.rhoprime, &self.keymu);
+    }
+
+    polyvec_matrix_expand::<P>(&mut self.mat, &sk[..SEEDBYTES]);
+  }
+
+  #[cfg(feature = "hardened")]
+  pub(crate) fn mu(&self) -> &[u8] {
+    &self.keymu[SEEDBYTES..]
+  }
+
+  /// SHAKE256 of the packed `s1` and `s2`, which this variant unpacks from
+  /// `sk` on every use.
+  #[cfg(feature = "hardened")]
+  pub(crate) fn secret_digest(&self) -> [u8; SEEDBYTES] {
+    let mut digest = [0u8; SEEDBYTES];
+    let s = &self.sk[sk_s1_offset::<P>(0)..sk_t0_offset::<P>(0)];
+    shake256(&mut digest, s);
+    digest
+  }
+
+  /// Moves the view of `sk` by one byte, as a glitched address would, so
+  /// every later unpack of `s1`/`s2` reads the wrong bytes.
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn inject_fault(&mut self) {
+    self.sk = &self.sk[1..];
+  }
+
+  /// `t = c * s` for a secret polynomial `s` packed at `offset`, back in normal domain.
+  fn mul_challenge(&self, t: &mut Poly, cp: &Poly, offset: usize, eta: bool) {
+    if eta {
+      polyeta_unpack::<P>(t, &self.sk[offset..]);
+    } else {
+      polyt0_unpack(t, &self.sk[offset..]);
+    }
+    poly_ntt::<P>(t);
+    poly_pointwise_montgomery_assign::<P>(t, cp);
+    poly_invntt_tomont::<P>(t);
+  }
+}
+
+/// Working vectors for one attempt. `z` first holds `NTT(y)`, then `y + c*s1`;
+/// `w0` ends up holding the hint vector.
+pub(crate) struct SignScratch<P: DilithiumParams> {
+  z: P::Polyvecl,
+  w1: P::Polyveck,
+  w0: P::Polyveck,
+  cp: Poly,
+  t: Poly,
+}
+
+impl<P: DilithiumParams> SignScratch<P> {
+  #[cfg(feature = "hardened")]
+  pub(crate) fn w1(&self) -> &P::Polyveck {
+    &self.w1
+  }
+
+  /// The challenge polynomial, in NTT domain.
+  #[cfg(feature = "hardened")]
+  pub(crate) fn challenge(&self) -> &Poly {
+    &self.cp
+  }
+
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn w1_mut(&mut self) -> &mut P::Polyveck {
+    &mut self.w1
+  }
+
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn challenge_mut(&mut self) -> &mut Poly {
+    &mut self.cp
+  }
+}
+
+impl<P: DilithiumParams> Default for SignScratch<P> {
+  #[inline(always)]
+  fn default() -> Self {
+    SignScratch {
+      z: P::Polyvecl::default(),
+      w1: P::Polyveck::default(),
+      w0: P::Polyveck::default(),
+      cp: Poly::default(),
+      t: Poly::default(),
+    }
+  }
+}
+
+/// Streaming counterpart of the cached attempt; the rejection checks are
+/// accumulated the same way, so no early exit reveals which bound failed.
+pub(crate) fn crypto_sign_attempt<P: DilithiumParams>(
+  key: &SigningKey<'_, P>,
+  ws: &mut SignScratch<P>,
+  sig: &mut [u8],
+  nonce: u16,
+) -> bool {
+  let mut state = KeccakState::default();
+
+  polyvecl_uniform_gamma1::<P>(&mut ws.z, &key.rhoprime, nonce);
+  polyvecl_ntt::<P>(&mut ws.z);
+  polyvec_matrix_pointwise_montgomery::<P>(&mut ws.w1, &key.mat, &ws.z);
+  polyveck_reduce::<P>(&mut ws.w1);
+  polyveck_invntt_tomont::<P>(&mut ws.w1);
+
+  polyveck_caddq::<P>(&mut ws.w1);
+  polyveck_decompose_in_place::<P>(&mut ws.w1, &mut ws.w0);
+  polyveck_pack_w1::<P>(sig, &ws.w1);
+
+  state.shake256_absorb(&key.keymu[SEEDBYTES..]);
+  state.shake256_absorb(&sig[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut sig[..SEEDBYTES]);
+
+  poly_challenge::<P>(&mut ws.cp, sig);
+  poly_ntt::<P>(&mut ws.cp);
+
+  let mut reject = Choice::from(0);
+  for (j, zj) in ws.z.vec_mut().iter_mut().enumerate() {
+    key.mul_challenge(&mut ws.t, &ws.cp, sk_s1_offset::<P>(j), true);
+    poly_uniform_gamma1::<P>(zj, &key.rhoprime, P::L as u16 * nonce + j as u16);
+    poly_add_assign(zj, &ws.t);
+    poly_reduce::<P>(zj);
+    reject |= Choice::from(poly_chknorm::<P>(zj, (P::GAMMA1 - P::BETA) as i32));
+  }
+
+  for (i, w0i) in ws.w0.vec_mut().iter_mut().enumerate() {
+    key.mul_challenge(&mut ws.t, &ws.cp, sk_s2_offset::<P>(i), true);
+    poly_sub_assign(w0i, &ws.t);
+    poly_reduce::<P>(w0i);
+    reject |= Choice::from(poly_chknorm::<P>(w0i, (P::GAMMA2 - P::BETA) as i32));
+  }
+
+  let mut n = 0;
+  for (i, (w0i, w1i)) in ws.w0.vec_mut().iter_mut().zip(ws.w1.vec()).enumerate() {
+    key.mul_challenge(&mut ws.t, &ws.cp, sk_t0_offset::<P>(i), false);
+    poly_reduce::<P>(&mut ws.t);
+    reject |= Choice::from(poly_chknorm::<P>(&ws.t, P::GAMMA2 as i32));
+    poly_add_assign(w0i, &ws.t);
+    n += poly_make_hint::<P>(&mut ws.t, w0i, w1i);
+    core::mem::swap(w0i, &mut ws.t);
+  }
+  reject |= (n as u32).ct_gt(&(P::OMEGA as u32));
+  if bool::from(reject) {
+    return false;
+  }
+
+  pack_sig::<P>(sig, None, &ws.z, &ws.w0);
+  true
+}
+
+pub fn crypto_sign_verify<P: DilithiumParams>(
+  sig: &[u8],
+  m: &[u8],
+  pk: &[u8],
+) -> Result<(), &'static str> {
+  let mut buf = [0u8; 8 * 128];
+  let mut mu = [0u8; CRHBYTES];
+  let mut c2 = [0u8; SEEDBYTES];
+  let mut cp = Poly::default();
+  let mut mat = P::Mat::default();
+  let mut z = P::Polyvecl::default();
+  let (mut w, mut t) = (Poly::default(), Poly::default());
+  let mut state = KeccakState::default();
+
+  if sig.len() != P::SIGNBYTES {
+    return Err("Signature length mismatch");
+  }
+  if pk.len() != P::PUBLIC_KEY_BYTES {
+    return Err("Public key length mismatch");
+  }
+
+  for (i, zi) in z.vec_mut().iter_mut().enumerate() {
+    polyz_unpack::<P>(zi, &sig[SEEDBYTES + i * P::POLYZ_PACKEDBYTES..]);
+  }
+  if polyvecl_chknorm::<P>(&z, (P::GAMMA1 - P::BETA) as i32) > 0 {
+    return Err("Invalid z");
+  }
+
+  shake256(&mut mu[..SEEDBYTES], &pk[..P::PUBLIC_KEY_BYTES]);
+  state.shake256_absorb(&mu[..SEEDBYTES]);
+  state.shake256_absorb(m);
+  state.shake256_finalize().read(&mut mu);
+
+  poly_challenge::<P>(&mut cp, &sig[..SEEDBYTES]);
+  poly_ntt::<P>(&mut cp);
+  polyvec_matrix_expand::<P>(&mut mat, &pk[..SEEDBYTES]);
+  polyvecl_ntt::<P>(&mut z);
+
+  for i in 0..P::K {
+    mat.row_pointwise_acc_montgomery::<P>(&mut w, i, z.vec());
+
+    polyt1_unpack(&mut t, &pk[SEEDBYTES + i * POLYT1_PACKEDBYTES..]);
+    poly_shiftl(&mut t);
+    poly_ntt::<P>(&mut t);
+    poly_pointwise_montgomery_assign::<P>(&mut t, &cp);
+    poly_sub_assign(&mut w, &t);
+    poly_reduce::<P>(&mut w);
+    poly_invntt_tomont::<P>(&mut w);
+    poly_caddq::<P>(&mut w);
+
+    unpack_sig_hint_row::<P>(&mut t, sig, i)?;
+    poly_use_hint_in_place::<P>(&mut w, &t);
+    polyw1_pack::<P>(&mut buf[i * P::POLYW1_PACKEDBYTES..], &w);
+  }
+  check_sig_hint_padding::<P>(sig)?;
+
+  let mut state = KeccakState::default();
+  state.shake256_absorb(&mu);
+  state.shake256_absorb(&buf[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut c2);
+
+  if sig[..SEEDBYTES] != c2 {
+    Err("Invalid signature")
+  } else {
+    Ok(())
+  }
+}
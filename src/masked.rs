@@ -0,0 +1,703 @@
+//! First-order masked signing for the `masked` feature.
+//!
+//! Constant-time code still leaks through power and EM: a trace taken while
+//! `c*s1` is added to `y` is correlated with `s1` itself. The signer here
+//! splits every secret into two shares, so that no single intermediate value
+//! depends on the secret, following the masked Dilithium designs of Migliore
+//! et al. (ACNS 2019) and Azouaoui et al. (TCHES 2023):
+//!
+//! - `s1`, `s2`, `y`, `z` and `w` are kept as two arithmetic shares modulo
+//!   `q`. NTT, pointwise products with the public challenge and additions are
+//!   linear, so the ordinary `poly`/`ntt` routines run on each share;
+//! - `rhoprime` (and `K`, without the `random` feature) are Boolean shares
+//!   and expanded with a masked Keccak-f[1600] whose chi uses DOM AND gates;
+//!   `y` is converted from the Boolean stream to arithmetic shares with a
+//!   masked adder;
+//! - decompose, the norm checks and hint generation convert shares to Boolean
+//!   form and evaluate the reference formulas as masked circuits.
+//!
+//! Only public values are ever recombined: `w1` (recomputable from any
+//! signature), the single accept/reject decision of an attempt, and `z` and
+//! `h` once the attempt is accepted. `t0` is not masked, as in the papers
+//! above; it is not needed to forge and follows from enough signatures.
+//!
+//! Mask randomness comes from SHAKE128 keyed with 32 bytes from the caller's
+//! RNG, drawn after `rhoprime`, so the signature is byte-identical to
+//! `crypto_sign_signature` for the same RNG. The packed secret key is split
+//! into shares when signing starts; that unpacking is the one step where the
+//! key is handled unshared. Masked signing is roughly two orders of
+//! magnitude slower than the unmasked path.
+
+use rand_core::RngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    randombytes::randombytes,
+    secret::SecretBytes,
+    params::DilithiumParams,
+    polyvec::*,
+    fips202::*,
+    packing::*,
+    poly::*
+};
+
+const QU: u32 = Q as u32;
+
+const KECCAK_RHO: [u32; 25] = [
+  0, 1, 62, 28, 27,
+  36, 44, 6, 55, 20,
+  3, 10, 43, 25, 39,
+  41, 45, 15, 21, 8,
+  18, 2, 61, 56, 14,
+];
+
+fn shake128_stream(seed: &[u8]) -> XofReader {
+  let mut state = KeccakState::default();
+  state.shake128_absorb(seed);
+  state.shake128_finalize()
+}
+
+/// Mask randomness: SHAKE128 keyed once from the caller's RNG, so masking a
+/// signature costs one RNG call rather than one per mask.
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+pub struct MaskRng {
+  stream: XofReader,
+  buf: [u8; SHAKE128_RATE],
+  pos: usize,
+}
+
+impl MaskRng {
+  pub fn new(seed: &[u8]) -> Self {
+    let mut masks = Self::unseeded();
+    masks.stream = shake128_stream(seed);
+    masks
+  }
+
+  pub fn from_rng<R: RngCore>(rng: &mut R) -> Self {
+    let mut masks = Self::unseeded();
+    masks.reseed(rng);
+    masks
+  }
+
+  #[inline(always)]
+  pub(crate) fn unseeded() -> Self {
+    MaskRng {
+      stream: shake128_stream(&[]),
+      buf: [0u8; SHAKE128_RATE],
+      pos: SHAKE128_RATE,
+    }
+  }
+
+  pub(crate) fn reseed<R: RngCore>(&mut self, rng: &mut R) {
+    let mut seed = SecretBytes::<SEEDBYTES>::default();
+    randombytes(&mut seed, rng);
+    self.stream = shake128_stream(&seed);
+    self.pos = SHAKE128_RATE;
+  }
+
+  pub fn next_u32(&mut self) -> u32 {
+    if self.pos == SHAKE128_RATE {
+      self.stream.read(&mut self.buf);
+      self.pos = 0;
+    }
+    let b = &self.buf[self.pos..self.pos + 4];
+    self.pos += 4;
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.next_u32() as u64 | (self.next_u32() as u64) << 32
+  }
+
+  pub fn fill_bytes(&mut self, out: &mut [u8]) {
+    for chunk in out.chunks_mut(4) {
+      chunk.copy_from_slice(&self.next_u32().to_le_bytes()[..chunk.len()]);
+    }
+  }
+
+  /// Uniform in `[0, q)`. The rejection depends only on mask randomness.
+  pub fn below_q(&mut self) -> u32 {
+    loop {
+      let r = self.next_u32() & 0x7F_FFFF;
+      if r < QU {
+        return r;
+      }
+    }
+  }
+}
+
+/// `a - b mod q` for `a`, `b` in `[0, q]`, without a branch on the operands.
+#[inline(always)]
+fn sub_q(a: u32, b: u32) -> u32 {
+  let d = a + QU - b;
+  let t = d.wrapping_sub(QU);
+  t.wrapping_add(((t as i32) >> 31) as u32 & QU)
+}
+
+/// Canonical representative in `[0, q)` of any coefficient the signer produces.
+#[inline(always)]
+fn freeze_q(a: i32) -> u32 {
+  let t = (a + (1 << 22)) >> 23;
+  let a = a - t * Q as i32;
+  (a + ((a >> 31) & Q as i32)) as u32
+}
+
+/// Splits `x` into two arithmetic shares modulo `q`, each in `[0, q)`.
+pub fn split_q(x: i32, rng: &mut MaskRng) -> [u32; 2] {
+  let r = rng.below_q();
+  [sub_q(freeze_q(x), r), r]
+}
+
+/// Recombines two arithmetic shares modulo `q` into `[0, q)`.
+pub fn recombine_q(a: [u32; 2]) -> u32 {
+  sub_q(a[0], QU - a[1])
+}
+
+/// Re-randomises Boolean shares with a fresh mask.
+#[inline(always)]
+pub fn refresh(x: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let r = rng.next_u32();
+  [x[0] ^ r, x[1] ^ r]
+}
+
+/// Masked AND of Boolean shares (domain-oriented masking, one fresh mask).
+#[inline(always)]
+pub fn sec_and(x: [u32; 2], y: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let r = rng.next_u32();
+  [
+    (x[0] & y[0]) ^ ((x[0] & y[1]) ^ r),
+    (x[1] & y[1]) ^ ((x[1] & y[0]) ^ r),
+  ]
+}
+
+#[inline(always)]
+fn sec_and64(x: [u64; 2], y: [u64; 2], rng: &mut MaskRng) -> [u64; 2] {
+  let r = rng.next_u64();
+  [
+    (x[0] & y[0]) ^ ((x[0] & y[1]) ^ r),
+    (x[1] & y[1]) ^ ((x[1] & y[0]) ^ r),
+  ]
+}
+
+/// Masked addition modulo `2^32` of Boolean shares: a Kogge-Stone adder
+/// built from `sec_and` (Coron, Großschädl, Tibouchi and Vadnan, FSE 2015).
+pub fn sec_add(x: [u32; 2], y: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let mut p = [x[0] ^ y[0], x[1] ^ y[1]];
+  let mut g = sec_and(x, refresh(y, rng), rng);
+  for shift in [1, 2, 4, 8, 16] {
+    let t = sec_and(p, refresh([g[0] << shift, g[1] << shift], rng), rng);
+    g = [g[0] ^ t[0], g[1] ^ t[1]];
+    if shift < 16 {
+      p = sec_and(p, refresh([p[0] << shift, p[1] << shift], rng), rng);
+    }
+  }
+  [x[0] ^ y[0] ^ (g[0] << 1), x[1] ^ y[1] ^ (g[1] << 1)]
+}
+
+/// Boolean shares to arithmetic shares modulo `2^32` (Goubin, CHES 2001).
+pub fn b2a(x: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let g = rng.next_u32();
+  let t = ((x[0] ^ g).wrapping_sub(g)) ^ x[0];
+  let g = g ^ x[1];
+  let a = ((x[0] ^ g).wrapping_sub(g)) ^ t;
+  [a, x[1]]
+}
+
+/// Arithmetic shares modulo `2^32` to Boolean shares.
+pub fn a2b(a: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let (m0, m1) = (rng.next_u32(), rng.next_u32());
+  sec_add([a[0] ^ m0, m0], [m1, a[1] ^ m1], rng)
+}
+
+/// Arithmetic shares modulo `q` to Boolean shares of the value in `[0, q)`.
+pub fn a2b_q(a: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  csubq(a2b(a, rng), rng)
+}
+
+/// Boolean shares of `x < q` to arithmetic shares modulo `q`: a random share
+/// `a` is subtracted under the mask and `x - a mod q`, which is uniform, is
+/// unmasked as the other share.
+pub fn b2a_q(x: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let a = rng.below_q();
+  let m = rng.next_u32();
+  let v = csubq(sec_add(x, [(QU - a) ^ m, m], rng), rng);
+  [v[0] ^ v[1], a]
+}
+
+/// Masked `x - q if x >= q`, for Boolean-shared `x < 2q`.
+fn csubq(x: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  let t = sec_add(x, [QU.wrapping_neg(), 0], rng);
+  let keep = sign_mask(t);
+  let d = sec_and(refresh([x[0] ^ t[0], x[1] ^ t[1]], rng), keep, rng);
+  [t[0] ^ d[0], t[1] ^ d[1]]
+}
+
+/// Broadcasts the shared sign bit to all 32 bits of each share.
+#[inline(always)]
+fn sign_mask(x: [u32; 2]) -> [u32; 2] {
+  [((x[0] as i32) >> 31) as u32, ((x[1] as i32) >> 31) as u32]
+}
+
+#[inline(always)]
+fn not(x: [u32; 2]) -> [u32; 2] {
+  [!x[0], x[1]]
+}
+
+#[inline(always)]
+fn or(x: [u32; 2], y: [u32; 2], rng: &mut MaskRng) -> [u32; 2] {
+  not(sec_and(not(x), refresh(not(y), rng), rng))
+}
+
+/// Mask of `x < c` for Boolean-shared `x < 2^31` and public `c <= 2^31`.
+#[inline(always)]
+fn lt(x: [u32; 2], c: u32, rng: &mut MaskRng) -> [u32; 2] {
+  sign_mask(sec_add(x, [c.wrapping_neg(), 0], rng))
+}
+
+/// Mask of `lo <= x < hi`.
+fn in_range(x: [u32; 2], lo: u32, hi: u32, rng: &mut MaskRng) -> [u32; 2] {
+  let below = lt(x, lo, rng);
+  let under = lt(x, hi, rng);
+  sec_and(not(below), refresh(under, rng), rng)
+}
+
+/// `HighBits` of a Boolean-shared `w` in `[0, q)`, following `decompose`
+/// step by step with the constant multiplications expanded into shifted
+/// masked additions. Only the result is unmasked.
+fn highbits<P: DilithiumParams>(w: [u32; 2], rng: &mut MaskRng) -> u32 {
+  let a = sec_add(w, [127, 0], rng);
+  let a = [a[0] >> 7, a[1] >> 7];
+
+  if P::GAMMA2 == (Q - 1) / 32 {
+    // a * 1025 = a + (a << 10)
+    let t = sec_add(a, [a[0] << 10, a[1] << 10], rng);
+    let t = sec_add(t, [1 << 21, 0], rng);
+    ((t[0] >> 22) ^ (t[1] >> 22)) & 15
+  } else {
+    // a * 11275 = a + (a << 1) + (a << 3) + (a << 10) + (a << 11) + (a << 13)
+    let mut t = a;
+    for shift in [1, 3, 10, 11, 13] {
+      t = sec_add(t, [a[0] << shift, a[1] << shift], rng);
+    }
+    let t = sec_add(t, [1 << 23, 0], rng);
+    let t = [t[0] >> 24, t[1] >> 24];
+    // a1 ^= ((43 - a1) >> 31) & a1, with 43 - a1 = !a1 + 44
+    let wrap = sign_mask(sec_add(not(t), [44, 0], rng));
+    let d = sec_and(refresh(t, rng), wrap, rng);
+    t[0] ^ d[0] ^ t[1] ^ d[1]
+  }
+}
+
+/// SHAKE256 on Boolean shares of the state. The linear steps of Keccak-f
+/// run on each share; chi uses one `sec_and` per lane.
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+#[derive(Default)]
+pub struct MaskedShake256 {
+  s: [[u64; 25]; 2],
+  pos: usize,
+}
+
+impl MaskedShake256 {
+  #[inline(always)]
+  fn xor_byte(&mut self, share: usize, i: usize, b: u8) {
+    self.s[share][i / 8] ^= (b as u64) << (8 * (i % 8));
+  }
+
+  #[inline(always)]
+  fn byte(&self, share: usize, i: usize) -> u8 {
+    (self.s[share][i / 8] >> (8 * (i % 8))) as u8
+  }
+
+  fn advance(&mut self, rng: &mut MaskRng) {
+    self.pos += 1;
+    if self.pos == SHAKE256_RATE {
+      masked_keccakf1600(&mut self.s, rng);
+      self.pos = 0;
+    }
+  }
+
+  /// Absorbs an input given as two Boolean shares of equal length.
+  pub fn absorb_shared(&mut self, a: &[u8], b: &[u8], rng: &mut MaskRng) {
+    assert_eq!(a.len(), b.len(), "share length mismatch");
+    for (x, y) in a.iter().zip(b) {
+      self.xor_byte(0, self.pos, *x);
+      self.xor_byte(1, self.pos, *y);
+      self.advance(rng);
+    }
+  }
+
+  /// Absorbs a public input into the first share.
+  pub fn absorb(&mut self, a: &[u8], rng: &mut MaskRng) {
+    for x in a {
+      self.xor_byte(0, self.pos, *x);
+      self.advance(rng);
+    }
+  }
+
+  pub fn finalize(&mut self) {
+    self.xor_byte(0, self.pos, 0x1F);
+    self.xor_byte(0, SHAKE256_RATE - 1, 0x80);
+    self.pos = SHAKE256_RATE;
+  }
+
+  /// Squeezes Boolean shares of the next `out0.len()` output bytes.
+  pub fn squeeze(&mut self, out0: &mut [u8], out1: &mut [u8], rng: &mut MaskRng) {
+    assert_eq!(out0.len(), out1.len(), "share length mismatch");
+    for (x, y) in out0.iter_mut().zip(out1.iter_mut()) {
+      if self.pos == SHAKE256_RATE {
+        masked_keccakf1600(&mut self.s, rng);
+        self.pos = 0;
+      }
+      *x = self.byte(0, self.pos);
+      *y = self.byte(1, self.pos);
+      self.pos += 1;
+    }
+  }
+}
+
+fn masked_keccakf1600(s: &mut [[u64; 25]; 2], rng: &mut MaskRng) {
+  let mut b = [[0u64; 25]; 2];
+  for rc in KECCAKF_ROUND_CONSTANTS {
+    for (a, b) in s.iter_mut().zip(b.iter_mut()) {
+      let mut c = [0u64; 5];
+      for x in 0..5 {
+        c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+      }
+      for x in 0..5 {
+        let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+        for y in 0..5 {
+          a[x + 5 * y] ^= d;
+        }
+      }
+      for x in 0..5 {
+        for y in 0..5 {
+          b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(KECCAK_RHO[x + 5 * y]);
+        }
+      }
+    }
+    for y in 0..5 {
+      for x in 0..5 {
+        let (i, i1, i2) = (x + 5 * y, (x + 1) % 5 + 5 * y, (x + 2) % 5 + 5 * y);
+        let t = sec_and64([!b[0][i1], b[1][i1]], [b[0][i2], b[1][i2]], rng);
+        s[0][i] = b[0][i] ^ t[0];
+        s[1][i] = b[1][i] ^ t[1];
+      }
+    }
+    s[0][0] ^= rc;
+  }
+  #[cfg(feature = "zeroize")]
+  b.zeroize();
+}
+
+fn polyvec_freeze<V: PolyVec>(v: &mut V) {
+  for p in v.vec_mut() {
+    for c in p.coeffs.iter_mut() {
+      *c = freeze_q(*c) as i32;
+    }
+  }
+}
+
+#[inline(always)]
+fn shares_of<V: PolyVec>(v: &[V; 2], i: usize, j: usize) -> [u32; 2] {
+  [v[0].vec()[i].coeffs[j] as u32, v[1].vec()[i].coeffs[j] as u32]
+}
+
+/// Mask of "some coefficient of the shared vector has `|x| >= bound`".
+fn norm_violation<V: PolyVec>(v: &[V; 2], bound: u32, rng: &mut MaskRng) -> [u32; 2] {
+  let mut acc = [0u32; 2];
+  for i in 0..v[0].vec().len() {
+    for j in 0..N {
+      let x = a2b_q(shares_of(v, i, j), rng);
+      acc = or(acc, in_range(x, bound, QU - bound + 1, rng), rng);
+    }
+  }
+  acc
+}
+
+fn poly_split(a: &Poly, r0: &mut Poly, r1: &mut Poly, rng: &mut MaskRng) {
+  for j in 0..N {
+    let s = split_q(a.coeffs[j], rng);
+    r0.coeffs[j] = s[0] as i32;
+    r1.coeffs[j] = s[1] as i32;
+  }
+}
+
+/// Masked counterpart of `poly_uniform_gamma1`: arithmetic shares of the
+/// same `y` coefficient stream, expanded from Boolean shares of `rhoprime`.
+fn poly_uniform_gamma1_masked<P: DilithiumParams>(
+  y0: &mut Poly,
+  y1: &mut Poly,
+  seed: &[SecretBytes<CRHBYTES>; 2],
+  nonce: u16,
+  rng: &mut MaskRng,
+) {
+  let mut buf0 = P::PolyUniformGamma1Buffer::default();
+  let mut buf1 = P::PolyUniformGamma1Buffer::default();
+  let mut state = MaskedShake256::default();
+
+  state.absorb_shared(&seed[0], &seed[1], rng);
+  state.absorb(&nonce.to_le_bytes(), rng);
+  state.finalize();
+  state.squeeze(buf0.as_mut(), buf1.as_mut(), rng);
+
+  // Unpacking only moves bits around, so it maps each share of the stream
+  // to the matching Boolean share of `GAMMA1 - y`.
+  polyz_unpack::<P>(y0, buf0.as_mut());
+  polyz_unpack::<P>(y1, buf1.as_mut());
+  let gamma1 = P::GAMMA1 as i32;
+  for j in 0..N {
+    let x = [(gamma1 - y0.coeffs[j]) as u32, (gamma1 - y1.coeffs[j]) as u32];
+    let a = b2a_q(x, rng);
+    y0.coeffs[j] = sub_q(P::GAMMA1 as u32, a[0]) as i32;
+    y1.coeffs[j] = sub_q(0, a[1]) as i32;
+  }
+  #[cfg(feature = "zeroize")]
+  {
+    buf0.as_mut().zeroize();
+    buf1.as_mut().zeroize();
+  }
+}
+
+/// Secret key in shares: `s1`, `s2` (NTT domain) and `rhoprime`, with the
+/// public `A`, `t0` and `mu` alongside, plus the mask generator.
+pub(crate) struct MaskedSigningKey<P: DilithiumParams> {
+  mat: P::Mat,
+  s1: [P::Polyvecl; 2],
+  s2: [P::Polyveck; 2],
+  t0: P::Polyveck,
+  mu: [u8; CRHBYTES],
+  rhoprime: [SecretBytes<CRHBYTES>; 2],
+  masks: MaskRng,
+}
+
+impl<P: DilithiumParams> MaskedSigningKey<P> {
+  #[inline(always)]
+  pub(crate) fn new() -> Self {
+    MaskedSigningKey::<P> {
+      mat: P::Mat::default(),
+      s1: Default::default(),
+      s2: Default::default(),
+      t0: P::Polyveck::default(),
+      mu: [0u8; CRHBYTES],
+      rhoprime: Default::default(),
+      masks: MaskRng::unseeded(),
+    }
+  }
+
+  pub(crate) fn expand<R: RngCore>(&mut self, m: &[u8], sk: &[u8], rng: &mut R) {
+    let mut state = KeccakState::default();
+    state.shake256_absorb(&sk[2 * SEEDBYTES..3 * SEEDBYTES]);
+    state.shake256_absorb(m);
+    state.shake256_finalize().read(&mut self.mu);
+
+    // `rhoprime` is drawn before the mask seed, exactly as the unmasked
+    // signer draws it, and shared straight away.
+    #[cfg(feature = "random")]
+    randombytes(&mut self.rhoprime[0], rng);
+    self.masks.reseed(rng);
+    let [r0, r1] = &mut self.rhoprime;
+
+    #[cfg(feature = "random")]
+    {
+      self.masks.fill_bytes(r1);
+      for (a, b) in r0.iter_mut().zip(r1.iter()) {
+        *a ^= *b;
+      }
+    }
+
+    #[cfg(not(feature = "random"))]
+    {
+      let mut key = [SecretBytes::<SEEDBYTES>::default(), SecretBytes::<SEEDBYTES>::default()];
+      let [k0, k1] = &mut key;
+      self.masks.fill_bytes(k1);
+      for ((a, b), s) in k0.iter_mut().zip(k1.iter()).zip(&sk[SEEDBYTES..2 * SEEDBYTES]) {
+        *a = *b ^ *s;
+      }
+      let mut state = MaskedShake256::default();
+      state.absorb_shared(k0, k1, &mut self.masks);
+      state.absorb(&self.mu, &mut self.masks);
+      state.finalize();
+      state.squeeze(r0, r1, &mut self.masks);
+    }
+
+    polyvec_matrix_expand::<P>(&mut self.mat, &sk[..SEEDBYTES]);
+
+    let mut t = Poly::default();
+    let [s10, s11] = &mut self.s1;
+    for (i, (a, b)) in s10.vec_mut().iter_mut().zip(s11.vec_mut()).enumerate() {
+      polyeta_unpack::<P>(&mut t, &sk[sk_s1_offset::<P>(i)..]);
+      poly_split(&t, a, b, &mut self.masks);
+    }
+    let [s20, s21] = &mut self.s2;
+    for (i, (a, b)) in s20.vec_mut().iter_mut().zip(s21.vec_mut()).enumerate() {
+      polyeta_unpack::<P>(&mut t, &sk[sk_s2_offset::<P>(i)..]);
+      poly_split(&t, a, b, &mut self.masks);
+    }
+    for (i, p) in self.t0.vec_mut().iter_mut().enumerate() {
+      polyt0_unpack(p, &sk[sk_t0_offset::<P>(i)..]);
+    }
+
+    for s in 0..2 {
+      polyvecl_ntt::<P>(&mut self.s1[s]);
+      polyvecl_reduce::<P>(&mut self.s1[s]);
+      polyveck_ntt::<P>(&mut self.s2[s]);
+      polyveck_reduce::<P>(&mut self.s2[s]);
+    }
+    polyveck_ntt::<P>(&mut self.t0);
+  }
+}
+
+/// Per-attempt vectors; `y`, `z` and `w` hold arithmetic shares, `h` the
+/// Boolean shares of the hint bits.
+pub(crate) struct MaskedScratch<P: DilithiumParams> {
+  y: [P::Polyvecl; 2],
+  z: [P::Polyvecl; 2],
+  w: [P::Polyveck; 2],
+  h: [P::Polyveck; 2],
+  w1: P::Polyveck,
+  ct0: P::Polyveck,
+  cp: Poly,
+}
+
+impl<P: DilithiumParams> Default for MaskedScratch<P> {
+  #[inline(always)]
+  fn default() -> Self {
+    MaskedScratch {
+      y: Default::default(),
+      z: Default::default(),
+      w: Default::default(),
+      h: Default::default(),
+      w1: P::Polyveck::default(),
+      ct0: P::Polyveck::default(),
+      cp: Poly::default(),
+    }
+  }
+}
+
+/// Masked counterpart of `crypto_sign_attempt`: same candidate, same
+/// decision, same packed output.
+pub(crate) fn crypto_sign_attempt_masked<P: DilithiumParams>(
+  key: &mut MaskedSigningKey<P>,
+  ws: &mut MaskedScratch<P>,
+  sig: &mut [u8],
+  nonce: u16,
+) -> bool {
+  let MaskedSigningKey { mat, s1, s2, t0, mu, rhoprime, masks } = key;
+  let mut state = KeccakState::default();
+  let q = QU;
+  let gamma2 = P::GAMMA2 as u32;
+
+  let [y0, y1] = &mut ws.y;
+  for (i, (a, b)) in y0.vec_mut().iter_mut().zip(y1.vec_mut()).enumerate() {
+    poly_uniform_gamma1_masked::<P>(a, b, rhoprime, P::L as u16 * nonce + i as u16, masks);
+  }
+
+  for s in 0..2 {
+    ws.z[s].vec_mut().clone_from_slice(ws.y[s].vec());
+    polyvecl_ntt::<P>(&mut ws.z[s]);
+    polyvec_matrix_pointwise_montgomery::<P>(&mut ws.w[s], mat, &ws.z[s]);
+    polyveck_reduce::<P>(&mut ws.w[s]);
+    polyveck_invntt_tomont::<P>(&mut ws.w[s]);
+    polyvec_freeze(&mut ws.w[s]);
+  }
+
+  // w1 is public; w0 = w - w1 * 2 * gamma2 stays in shares.
+  for i in 0..P::K {
+    for j in 0..N {
+      let a = shares_of(&ws.w, i, j);
+      let w1 = highbits::<P>(a2b_q(a, masks), masks);
+      ws.w1.vec_mut()[i].coeffs[j] = w1 as i32;
+      ws.w[0].vec_mut()[i].coeffs[j] = sub_q(a[0], w1 * 2 * gamma2) as i32;
+    }
+  }
+  polyveck_pack_w1::<P>(sig, &ws.w1);
+
+  state.shake256_absorb(&mu[..]);
+  state.shake256_absorb(&sig[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut sig[..SEEDBYTES]);
+
+  poly_challenge::<P>(&mut ws.cp, sig);
+  poly_ntt::<P>(&mut ws.cp);
+
+  for s in 0..2 {
+    polyvecl_pointwise_poly_montgomery::<P>(&mut ws.z[s], &ws.cp, &s1[s]);
+    polyvecl_invntt_tomont::<P>(&mut ws.z[s]);
+    polyvecl_add_assign(&mut ws.z[s], &ws.y[s]);
+    polyvec_freeze(&mut ws.z[s]);
+  }
+  let mut reject = norm_violation(&ws.z, (P::GAMMA1 - P::BETA) as u32, masks);
+
+  for s in 0..2 {
+    polyveck_pointwise_poly_montgomery::<P>(&mut ws.h[s], &ws.cp, &s2[s]);
+    polyveck_invntt_tomont::<P>(&mut ws.h[s]);
+    polyveck_sub_assign(&mut ws.w[s], &ws.h[s]);
+    polyvec_freeze(&mut ws.w[s]);
+  }
+  let r0 = norm_violation(&ws.w, gamma2 - P::BETA as u32, masks);
+  reject = or(reject, r0, masks);
+
+  // t0 is not masked, so neither is c*t0 or its norm check.
+  polyveck_pointwise_poly_montgomery::<P>(&mut ws.ct0, &ws.cp, t0);
+  polyveck_invntt_tomont::<P>(&mut ws.ct0);
+  polyveck_reduce::<P>(&mut ws.ct0);
+  let ct0_over = (polyveck_chknorm::<P>(&ws.ct0, P::GAMMA2 as i32) as u32).wrapping_neg();
+  reject = or(reject, [ct0_over, 0], masks);
+
+  // With the bounds above, w0 - c*s2 + c*t0 lies in (-2*gamma2, 2*gamma2),
+  // where make_hint is 1 exactly on [gamma2 + 1, q - gamma2 + [w1 != 0]).
+  polyveck_add_assign(&mut ws.w[0], &ws.ct0);
+  polyvec_freeze(&mut ws.w[0]);
+  let mut count = [0u32; 2];
+  for i in 0..P::K {
+    for j in 0..N {
+      let x = a2b_q(shares_of(&ws.w, i, j), masks);
+      let nonzero = (ws.w1.vec()[i].coeffs[j] != 0) as u32;
+      let hint = in_range(x, gamma2 + 1, q - gamma2 + nonzero, masks);
+      let bit = [hint[0] & 1, hint[1] & 1];
+      ws.h[0].vec_mut()[i].coeffs[j] = bit[0] as i32;
+      ws.h[1].vec_mut()[i].coeffs[j] = bit[1] as i32;
+      let a = b2a(bit, masks);
+      count = [count[0].wrapping_add(a[0]), count[1].wrapping_add(a[1])];
+    }
+  }
+  let too_many = not(lt(a2b(count, masks), P::OMEGA as u32 + 1, masks));
+  reject = or(reject, too_many, masks);
+
+  if reject[0] ^ reject[1] != 0 {
+    return false;
+  }
+
+  let (z0, z1) = ws.z.split_at_mut(1);
+  for (a, b) in z0[0].vec_mut().iter_mut().zip(z1[0].vec()) {
+    for (x, y) in a.coeffs.iter_mut().zip(b.coeffs.iter()) {
+      let z = recombine_q([*x as u32, *y as u32]) as i32;
+      *x = z - ((((Q as i32 - 1) / 2 - z) >> 31) & Q as i32);
+    }
+  }
+  let (h0, h1) = ws.h.split_at_mut(1);
+  for (a, b) in h0[0].vec_mut().iter_mut().zip(h1[0].vec()) {
+    for (x, y) in a.coeffs.iter_mut().zip(b.coeffs.iter()) {
+      *x ^= *y;
+    }
+  }
+
+  pack_sig::<P>(sig, None, &ws.z[0], &ws.h[0]);
+  true
+}
+
+pub(crate) fn crypto_sign_signature_masked<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+) {
+  let mut key = MaskedSigningKey::<P>::new();
+  key.expand(m, sk, rng);
+  let mut ws = MaskedScratch::<P>::default();
+  let mut nonce = 0u16;
+
+  while !crypto_sign_attempt_masked::<P>(&mut key, &mut ws, sig, nonce) {
+    nonce += 1;
+  }
+}
@@ -0,0 +1,358 @@
+//! ML-KEM (FIPS 203) key encapsulation, sharing the Keccak core with the
+//! signature schemes.
+//!
+//! The API follows the ML-DSA one at the crate root: an [`Algorithm`] picks
+//! the parameter set and a [`Keypair`] holds the encapsulation key (public)
+//! and the decapsulation key (secret). `encapsulate` returns a
+//! [`Ciphertext`] and the [`SharedSecret`] it carries; `decapsulate` recovers
+//! the secret. Decapsulation uses implicit rejection: a tampered ciphertext
+//! gives an unrelated pseudorandom secret rather than an error, so the
+//! failure only shows up when the keys are used.
+
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::kem::*;
+use crate::secret::SecretBytes;
+
+pub use crate::kem::{KemParams, MlKem1024, MlKem512, MlKem768, SHARED_SECRET_BYTES};
+
+/// The 32-byte shared key; wiped on drop with the `zeroize` feature.
+pub type SharedSecret = SecretBytes<SHARED_SECRET_BYTES>;
+
+/// Bytes of the key generation seed `d || z`.
+pub const SEED_BYTES: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    MlKem512,
+    MlKem768,
+    MlKem1024,
+}
+
+impl Algorithm {
+    /// Bytes of the encapsulation key.
+    pub const fn public_key_bytes(&self) -> usize {
+        match self {
+            Algorithm::MlKem512 => MlKem512::PUBLIC_KEY_BYTES,
+            Algorithm::MlKem768 => MlKem768::PUBLIC_KEY_BYTES,
+            Algorithm::MlKem1024 => MlKem1024::PUBLIC_KEY_BYTES,
+        }
+    }
+
+    /// Bytes of the decapsulation key.
+    pub const fn secret_key_bytes(&self) -> usize {
+        match self {
+            Algorithm::MlKem512 => MlKem512::SECRET_KEY_BYTES,
+            Algorithm::MlKem768 => MlKem768::SECRET_KEY_BYTES,
+            Algorithm::MlKem1024 => MlKem1024::SECRET_KEY_BYTES,
+        }
+    }
+
+    pub const fn ciphertext_bytes(&self) -> usize {
+        match self {
+            Algorithm::MlKem512 => MlKem512::CIPHERTEXT_BYTES,
+            Algorithm::MlKem768 => MlKem768::CIPHERTEXT_BYTES,
+            Algorithm::MlKem1024 => MlKem1024::CIPHERTEXT_BYTES,
+        }
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn generate(&self) -> Keypair {
+        self.generate_with_rng(&mut OsRng)
+    }
+
+    pub fn generate_with_rng<R: RngCore>(&self, rng: &mut R) -> Keypair {
+        let mut seed = SecretBytes::<SEED_BYTES>::default();
+        rng.fill_bytes(&mut seed);
+        self.generate_from_seed_unchecked(&seed)
+    }
+
+    /// Deterministic key generation from the 64-byte seed `d || z`
+    /// (`ML-KEM.KeyGen_internal`). The seed is the compact form of the
+    /// decapsulation key.
+    pub fn generate_from_seed(&self, seed: &[u8]) -> Result<Keypair, &'static str> {
+        if seed.len() != SEED_BYTES {
+            return Err("Invalid seed length");
+        }
+        Ok(self.generate_from_seed_unchecked(seed))
+    }
+
+    fn generate_from_seed_unchecked(&self, seed: &[u8]) -> Keypair {
+        match self {
+            Algorithm::MlKem512 => {
+                let mut pk = [0u8; MlKem512::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ MlKem512::SECRET_KEY_BYTES }>::default();
+                kem_keypair_from_seed::<MlKem512>(&mut pk, &mut sk, seed);
+                Keypair::MlKem512(pk, sk)
+            }
+            Algorithm::MlKem768 => {
+                let mut pk = [0u8; MlKem768::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ MlKem768::SECRET_KEY_BYTES }>::default();
+                kem_keypair_from_seed::<MlKem768>(&mut pk, &mut sk, seed);
+                Keypair::MlKem768(pk, sk)
+            }
+            Algorithm::MlKem1024 => {
+                let mut pk = [0u8; MlKem1024::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ MlKem1024::SECRET_KEY_BYTES }>::default();
+                kem_keypair_from_seed::<MlKem1024>(&mut pk, &mut sk, seed);
+                Keypair::MlKem1024(pk, sk)
+            }
+        }
+    }
+
+    /// Generates a keypair straight into caller-provided buffers of
+    /// `public_key_bytes()` and `secret_key_bytes()` bytes.
+    pub fn generate_into<R: RngCore>(&self, pk: &mut [u8], sk: &mut [u8], rng: &mut R) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key buffer lengths");
+        }
+        match self {
+            Algorithm::MlKem512 => kem_keypair::<MlKem512, R>(pk, sk, rng),
+            Algorithm::MlKem768 => kem_keypair::<MlKem768, R>(pk, sk, rng),
+            Algorithm::MlKem1024 => kem_keypair::<MlKem1024, R>(pk, sk, rng),
+        }
+        Ok(())
+    }
+
+    /// Builds a `Keypair` from its encoded halves after `check_keypair`.
+    pub fn load_from_bytes(&self, sk: &[u8], pk: &[u8]) -> Result<Keypair, &'static str> {
+        self.check_keypair(sk, pk)?;
+        Ok(match self {
+            Algorithm::MlKem512 => {
+                let mut pk_buf = [0u8; MlKem512::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ MlKem512::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::MlKem512(pk_buf, sk_buf)
+            }
+            Algorithm::MlKem768 => {
+                let mut pk_buf = [0u8; MlKem768::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ MlKem768::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::MlKem768(pk_buf, sk_buf)
+            }
+            Algorithm::MlKem1024 => {
+                let mut pk_buf = [0u8; MlKem1024::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ MlKem1024::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::MlKem1024(pk_buf, sk_buf)
+            }
+        })
+    }
+
+    /// The FIPS 203 input checks on both keys: lengths, coefficients of the
+    /// encapsulation key below `q`, the `H(ek)` stored in the decapsulation
+    /// key, and that the decapsulation key embeds `pk`.
+    pub fn check_keypair(&self, sk: &[u8], pk: &[u8]) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key lengths");
+        }
+        self.check_public_key(pk)?;
+        match self {
+            Algorithm::MlKem512 => kem_check_secret_key::<MlKem512>(sk)?,
+            Algorithm::MlKem768 => kem_check_secret_key::<MlKem768>(sk)?,
+            Algorithm::MlKem1024 => kem_check_secret_key::<MlKem1024>(sk)?,
+        }
+        let embedded = &sk[384 * self.rank()..][..pk.len()];
+        if embedded != pk {
+            return Err("Public key does not match the secret key");
+        }
+        Ok(())
+    }
+
+    /// The encapsulation key check of FIPS 203 section 7.2.
+    pub fn check_public_key(&self, pk: &[u8]) -> Result<(), &'static str> {
+        match self {
+            Algorithm::MlKem512 => kem_check_public_key::<MlKem512>(pk),
+            Algorithm::MlKem768 => kem_check_public_key::<MlKem768>(pk),
+            Algorithm::MlKem1024 => kem_check_public_key::<MlKem1024>(pk),
+        }
+    }
+
+    const fn rank(&self) -> usize {
+        match self {
+            Algorithm::MlKem512 => MlKem512::K,
+            Algorithm::MlKem768 => MlKem768::K,
+            Algorithm::MlKem1024 => MlKem1024::K,
+        }
+    }
+
+    /// Encapsulates a fresh shared secret to the encapsulation key `pk`.
+    #[cfg(feature = "getrandom")]
+    pub fn encapsulate(&self, pk: &[u8]) -> Result<(Ciphertext, SharedSecret), &'static str> {
+        self.encapsulate_with_rng(pk, &mut OsRng)
+    }
+
+    pub fn encapsulate_with_rng<R: RngCore>(&self, pk: &[u8], rng: &mut R) -> Result<(Ciphertext, SharedSecret), &'static str> {
+        let mut ss = SharedSecret::default();
+        let bytes = match self {
+            Algorithm::MlKem512 => {
+                let mut ct = [0u8; MlKem512::CIPHERTEXT_BYTES];
+                kem_encaps::<MlKem512, R>(&mut ct, &mut ss, pk, rng)?;
+                CiphertextType::MlKem512(ct)
+            }
+            Algorithm::MlKem768 => {
+                let mut ct = [0u8; MlKem768::CIPHERTEXT_BYTES];
+                kem_encaps::<MlKem768, R>(&mut ct, &mut ss, pk, rng)?;
+                CiphertextType::MlKem768(ct)
+            }
+            Algorithm::MlKem1024 => {
+                let mut ct = [0u8; MlKem1024::CIPHERTEXT_BYTES];
+                kem_encaps::<MlKem1024, R>(&mut ct, &mut ss, pk, rng)?;
+                CiphertextType::MlKem1024(ct)
+            }
+        };
+        Ok((Ciphertext { bytes }, ss))
+    }
+
+    /// Encapsulates into caller-provided buffers of `ciphertext_bytes()`
+    /// and `SHARED_SECRET_BYTES` bytes.
+    pub fn encapsulate_into<R: RngCore>(&self, ct: &mut [u8], ss: &mut [u8], pk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        if ct.len() != self.ciphertext_bytes() {
+            return Err("Invalid ciphertext buffer length");
+        }
+        if ss.len() != SHARED_SECRET_BYTES {
+            return Err("Invalid shared secret buffer length");
+        }
+        match self {
+            Algorithm::MlKem512 => kem_encaps::<MlKem512, R>(ct, ss, pk, rng),
+            Algorithm::MlKem768 => kem_encaps::<MlKem768, R>(ct, ss, pk, rng),
+            Algorithm::MlKem1024 => kem_encaps::<MlKem1024, R>(ct, ss, pk, rng),
+        }
+    }
+
+    /// `ML-KEM.Encaps_internal` with the caller's 32-byte message `m`, for
+    /// known-answer tests. Never reuse `m` outside of tests.
+    pub fn encapsulate_deterministic(&self, pk: &[u8], m: &[u8]) -> Result<(Ciphertext, SharedSecret), &'static str> {
+        if m.len() != 32 {
+            return Err("Invalid message length");
+        }
+        self.check_public_key(pk)?;
+        let mut ss = SharedSecret::default();
+        let bytes = match self {
+            Algorithm::MlKem512 => {
+                let mut ct = [0u8; MlKem512::CIPHERTEXT_BYTES];
+                kem_encaps_from_seed::<MlKem512>(&mut ct, &mut ss, pk, m);
+                CiphertextType::MlKem512(ct)
+            }
+            Algorithm::MlKem768 => {
+                let mut ct = [0u8; MlKem768::CIPHERTEXT_BYTES];
+                kem_encaps_from_seed::<MlKem768>(&mut ct, &mut ss, pk, m);
+                CiphertextType::MlKem768(ct)
+            }
+            Algorithm::MlKem1024 => {
+                let mut ct = [0u8; MlKem1024::CIPHERTEXT_BYTES];
+                kem_encaps_from_seed::<MlKem1024>(&mut ct, &mut ss, pk, m);
+                CiphertextType::MlKem1024(ct)
+            }
+        };
+        Ok((Ciphertext { bytes }, ss))
+    }
+
+    /// Recovers the shared secret from `ct` with the decapsulation key `sk`.
+    /// Only the lengths can fail; see the module docs on implicit rejection.
+    pub fn decapsulate(&self, ct: &[u8], sk: &[u8]) -> Result<SharedSecret, &'static str> {
+        let mut ss = SharedSecret::default();
+        self.decapsulate_into(&mut ss, ct, sk)?;
+        Ok(ss)
+    }
+
+    pub fn decapsulate_into(&self, ss: &mut [u8], ct: &[u8], sk: &[u8]) -> Result<(), &'static str> {
+        if ct.len() != self.ciphertext_bytes() {
+            return Err("Invalid ciphertext length");
+        }
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if ss.len() != SHARED_SECRET_BYTES {
+            return Err("Invalid shared secret buffer length");
+        }
+        match self {
+            Algorithm::MlKem512 => kem_decaps::<MlKem512>(ss, ct, sk),
+            Algorithm::MlKem768 => kem_decaps::<MlKem768>(ss, ct, sk),
+            Algorithm::MlKem1024 => kem_decaps::<MlKem1024>(ss, ct, sk),
+        }
+        Ok(())
+    }
+}
+
+/// An encapsulation key and its decapsulation key. The decapsulation key
+/// embeds the encapsulation key, so `Keypair::from_secret_key` restores both.
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Keypair {
+    MlKem512([u8; MlKem512::PUBLIC_KEY_BYTES], SecretBytes<{ MlKem512::SECRET_KEY_BYTES }>),
+    MlKem768([u8; MlKem768::PUBLIC_KEY_BYTES], SecretBytes<{ MlKem768::SECRET_KEY_BYTES }>),
+    MlKem1024([u8; MlKem1024::PUBLIC_KEY_BYTES], SecretBytes<{ MlKem1024::SECRET_KEY_BYTES }>),
+}
+
+impl Keypair {
+    /// Rebuilds a `Keypair` from the decapsulation key alone, after the
+    /// checks of `Algorithm::check_keypair`.
+    pub fn from_secret_key(alg: Algorithm, sk: &[u8]) -> Result<Keypair, &'static str> {
+        if sk.len() != alg.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        alg.load_from_bytes(sk, &sk[384 * alg.rank()..][..alg.public_key_bytes()])
+    }
+
+    pub fn decapsulate(&self, ct: &Ciphertext) -> Result<SharedSecret, &'static str> {
+        self.algorithm().decapsulate(ct.bytes(), self.secret())
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            Keypair::MlKem512(..) => Algorithm::MlKem512,
+            Keypair::MlKem768(..) => Algorithm::MlKem768,
+            Keypair::MlKem1024(..) => Algorithm::MlKem1024,
+        }
+    }
+
+    /// The encapsulation key.
+    pub fn public(&self) -> &[u8] {
+        match self {
+            Keypair::MlKem512(pk, _) => pk,
+            Keypair::MlKem768(pk, _) => pk,
+            Keypair::MlKem1024(pk, _) => pk,
+        }
+    }
+
+    /// The decapsulation key.
+    pub fn secret(&self) -> &[u8] {
+        match self {
+            Keypair::MlKem512(_, sk) => &sk[..],
+            Keypair::MlKem768(_, sk) => &sk[..],
+            Keypair::MlKem1024(_, sk) => &sk[..],
+        }
+    }
+}
+
+pub struct Ciphertext {
+    bytes: CiphertextType
+}
+
+impl Ciphertext {
+    #[inline(always)]
+    pub fn bytes(&self) -> &[u8] {
+        match &self.bytes {
+            CiphertextType::MlKem512(arr) => &arr[..],
+            CiphertextType::MlKem768(arr) => &arr[..],
+            CiphertextType::MlKem1024(arr) => &arr[..],
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum CiphertextType {
+    MlKem512([u8; MlKem512::CIPHERTEXT_BYTES]),
+    MlKem768([u8; MlKem768::CIPHERTEXT_BYTES]),
+    MlKem1024([u8; MlKem1024::CIPHERTEXT_BYTES]),
+}
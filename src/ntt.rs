@@ -51,9 +51,9 @@ pub fn ntt<P: DilithiumParams>(a: &mut [i32; N]) {
             for j in start..(start + len) {
                 let t = montgomery_reduce::<P>((zeta as i64) * (a[j + len] as i64));
                 a[j + len] = a[j] - t;
-                a[j] = a[j] + t;
+                a[j] += t;
             }
-            start = start + len * 2;
+            start += len * 2;
         }
         len >>= 1;
     }
@@ -1,40 +1,103 @@
 use crate::sign::*;
 use crate::params::*;
-use rand::{
-    rngs::OsRng,
-    RngCore
-};
+use crate::secret::SecretBytes;
+#[cfg(feature = "hardened")]
+use crate::hardened::*;
+#[cfg(feature = "masked")]
+use crate::masked::crypto_sign_signature_masked;
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+#[cfg(all(feature = "alloc", feature = "getrandom"))]
+use alloc::vec::Vec;
 
 #[cfg(feature = "zeroize")]
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Algorithm {
+    #[cfg(feature = "mode2")]
     Mode2,
+    #[cfg(feature = "mode3")]
     Mode3,
+    #[cfg(feature = "mode5")]
     Mode5,
 }
 
 impl Algorithm {
+    /// The enabled algorithm `f` picks out; the key and signature formats
+    /// map algorithms to their names and look names up through this.
+    #[cfg(any(feature = "x509", feature = "jose", feature = "cose", feature = "ssh"))]
+    pub(crate) fn find(f: impl Fn(Algorithm) -> bool) -> Option<Algorithm> {
+        [
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2,
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3,
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5,
+        ]
+        .into_iter()
+        .find(|&alg| f(alg))
+    }
+
+    pub const fn public_key_bytes(&self) -> usize {
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => Mode2::PUBLIC_KEY_BYTES,
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => Mode3::PUBLIC_KEY_BYTES,
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => Mode5::PUBLIC_KEY_BYTES,
+        }
+    }
+
+    pub const fn secret_key_bytes(&self) -> usize {
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => Mode2::SECRET_KEY_BYTES,
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => Mode3::SECRET_KEY_BYTES,
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => Mode5::SECRET_KEY_BYTES,
+        }
+    }
+
+    pub const fn signature_bytes(&self) -> usize {
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => Mode2::SIGNBYTES,
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => Mode3::SIGNBYTES,
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => Mode5::SIGNBYTES,
+        }
+    }
+
+    #[cfg(feature = "getrandom")]
     pub fn generate(&self) -> Keypair {
         match self {
+            #[cfg(feature = "mode2")]
             Algorithm::Mode2 => {
                 let mut pk = [0u8; Mode2::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode2::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode2::SECRET_KEY_BYTES }>::default();
                 let mut rng = OsRng;
                 crypto_sign_keypair::<Mode2, OsRng>(&mut pk, &mut sk, &mut rng);
                 Keypair::Mode2(pk, sk)
             }
+            #[cfg(feature = "mode3")]
             Algorithm::Mode3 => {
                 let mut pk = [0u8; Mode3::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode3::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode3::SECRET_KEY_BYTES }>::default();
                 let mut rng = OsRng;
                 crypto_sign_keypair::<Mode3, _>(&mut pk, &mut sk, &mut rng);
                 Keypair::Mode3(pk, sk)
             }
+            #[cfg(feature = "mode5")]
             Algorithm::Mode5 => {
                 let mut pk = [0u8; Mode5::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode5::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode5::SECRET_KEY_BYTES }>::default();
                 let mut rng = OsRng;
                 crypto_sign_keypair::<Mode5, _>(&mut pk, &mut sk, &mut rng);
                 Keypair::Mode5(pk, sk)
@@ -42,34 +105,42 @@ impl Algorithm {
         }
     }
 
-    pub fn load_from_bytes(&self, sk: &[u8], pk: &[u8]) -> Result<Keypair, String> {
+    /// Builds a `Keypair` from its encoded halves after the full consistency
+    /// check of `check_keypair`.
+    pub fn load_from_bytes(&self, sk: &[u8], pk: &[u8]) -> Result<Keypair, &'static str> {
         match self {
+            #[cfg(feature = "mode2")]
             Algorithm::Mode2 => {
                 if pk.len() != Mode2::PUBLIC_KEY_BYTES || sk.len() != Mode2::SECRET_KEY_BYTES {
-                    return Err("Invalid key lengths for Mode2".into());
+                    return Err("Invalid key lengths for Mode2");
                 }
+                crypto_sign_check_keypair::<Mode2>(pk, sk)?;
                 let mut pk_buf = [0u8; Mode2::PUBLIC_KEY_BYTES];
-                let mut sk_buf = [0u8; Mode2::SECRET_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Mode2::SECRET_KEY_BYTES }>::default();
                 pk_buf.copy_from_slice(pk);
                 sk_buf.copy_from_slice(sk);
                 Ok(Keypair::Mode2(pk_buf, sk_buf))
             }
+            #[cfg(feature = "mode3")]
             Algorithm::Mode3 => {
                 if pk.len() != Mode3::PUBLIC_KEY_BYTES || sk.len() != Mode3::SECRET_KEY_BYTES {
-                    return Err("Invalid key lengths for Mode3".into());
+                    return Err("Invalid key lengths for Mode3");
                 }
+                crypto_sign_check_keypair::<Mode3>(pk, sk)?;
                 let mut pk_buf = [0u8; Mode3::PUBLIC_KEY_BYTES];
-                let mut sk_buf = [0u8; Mode3::SECRET_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Mode3::SECRET_KEY_BYTES }>::default();
                 pk_buf.copy_from_slice(pk);
                 sk_buf.copy_from_slice(sk);
                 Ok(Keypair::Mode3(pk_buf, sk_buf))
             }
+            #[cfg(feature = "mode5")]
             Algorithm::Mode5 => {
                 if pk.len() != Mode5::PUBLIC_KEY_BYTES || sk.len() != Mode5::SECRET_KEY_BYTES {
-                    return Err("Invalid key lengths for Mode5".into());
+                    return Err("Invalid key lengths for Mode5");
                 }
+                crypto_sign_check_keypair::<Mode5>(pk, sk)?;
                 let mut pk_buf = [0u8; Mode5::PUBLIC_KEY_BYTES];
-                let mut sk_buf = [0u8; Mode5::SECRET_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Mode5::SECRET_KEY_BYTES }>::default();
                 pk_buf.copy_from_slice(pk);
                 sk_buf.copy_from_slice(sk);
                 Ok(Keypair::Mode5(pk_buf, sk_buf))
@@ -77,37 +148,191 @@ impl Algorithm {
         }
     }
 
+    /// Checks that `sk` and `pk` belong together and are well formed: the
+    /// lengths, `rho`, the range of `s1`/`s2`, `t1` and `t0` against
+    /// `A*s1 + s2`, and `tr` against `H(pk)`. The error says which check failed.
+    pub fn check_keypair(&self, sk: &[u8], pk: &[u8]) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key lengths");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_check_keypair::<Mode2>(pk, sk),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_check_keypair::<Mode3>(pk, sk),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_check_keypair::<Mode5>(pk, sk),
+        }
+    }
+
     pub fn generate_with_rng<R: RngCore>(&self, rng: &mut R) -> Keypair {
         match self {
+            #[cfg(feature = "mode2")]
             Algorithm::Mode2 => {
                 let mut pk = [0u8; Mode2::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode2::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode2::SECRET_KEY_BYTES }>::default();
                 crypto_sign_keypair::<Mode2, R>(&mut pk, &mut sk, rng);
                 Keypair::Mode2(pk, sk)
             }
+            #[cfg(feature = "mode3")]
             Algorithm::Mode3 => {
                 let mut pk = [0u8; Mode3::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode3::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode3::SECRET_KEY_BYTES }>::default();
                 crypto_sign_keypair::<Mode3, R>(&mut pk, &mut sk, rng);
                 Keypair::Mode3(pk, sk)
             }
+            #[cfg(feature = "mode5")]
             Algorithm::Mode5 => {
                 let mut pk = [0u8; Mode5::PUBLIC_KEY_BYTES];
-                let mut sk = [0u8; Mode5::SECRET_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode5::SECRET_KEY_BYTES }>::default();
                 crypto_sign_keypair::<Mode5, R>(&mut pk, &mut sk, rng);
                 Keypair::Mode5(pk, sk)
             }
         }
     }
 
+    /// Derives the keypair determined by a 32-byte seed, the form
+    /// `generate_with_rng` draws from its RNG. Storing the seed is enough to
+    /// recreate the whole keypair.
+    pub fn generate_from_seed(&self, seed: &[u8]) -> Result<Keypair, &'static str> {
+        if seed.len() != 32 {
+            return Err("Invalid seed length");
+        }
+        Ok(match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => {
+                let mut pk = [0u8; Mode2::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode2::SECRET_KEY_BYTES }>::default();
+                crypto_sign_keypair_from_seed::<Mode2>(&mut pk, &mut sk, seed);
+                Keypair::Mode2(pk, sk)
+            }
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => {
+                let mut pk = [0u8; Mode3::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode3::SECRET_KEY_BYTES }>::default();
+                crypto_sign_keypair_from_seed::<Mode3>(&mut pk, &mut sk, seed);
+                Keypair::Mode3(pk, sk)
+            }
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => {
+                let mut pk = [0u8; Mode5::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Mode5::SECRET_KEY_BYTES }>::default();
+                crypto_sign_keypair_from_seed::<Mode5>(&mut pk, &mut sk, seed);
+                Keypair::Mode5(pk, sk)
+            }
+        })
+    }
+
+    /// Generates a keypair straight into caller-provided buffers of
+    /// `public_key_bytes()` and `secret_key_bytes()` bytes.
+    pub fn generate_into<R: RngCore>(&self, pk: &mut [u8], sk: &mut [u8], rng: &mut R) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key buffer lengths");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_keypair::<Mode2, R>(pk, sk, rng),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_keypair::<Mode3, R>(pk, sk, rng),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_keypair::<Mode5, R>(pk, sk, rng),
+        };
+        Ok(())
+    }
+
+    /// Signs `msg` with a raw secret key into a caller-provided buffer of
+    /// `signature_bytes()` bytes.
+    pub fn sign_into<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if sig.len() != self.signature_bytes() {
+            return Err("Invalid signature buffer length");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_signature::<Mode2, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_signature::<Mode3, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_signature::<Mode5, R>(sig, msg, sk, rng),
+        }
+        Ok(())
+    }
+
+    /// Like `sign_into`, but with the redundant fault checks of the `hardened`
+    /// feature. Returns `FAULT_DETECTED`, with `sig` wiped, if any check fails.
+    #[cfg(feature = "hardened")]
+    pub fn sign_into_hardened<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if sig.len() != self.signature_bytes() {
+            return Err("Invalid signature buffer length");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_signature_hardened::<Mode2, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_signature_hardened::<Mode3, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_signature_hardened::<Mode5, R>(sig, msg, sk, rng),
+        }
+    }
+
+    /// `sign_into_hardened` with `fault` injected after the rejection loop.
+    /// Only for testing the fault checks; never enable `fault_injection` in a
+    /// production build.
+    #[cfg(feature = "fault_injection")]
+    pub fn sign_into_hardened_faulted<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], rng: &mut R, fault: Fault) -> Result<(), &'static str> {
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if sig.len() != self.signature_bytes() {
+            return Err("Invalid signature buffer length");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_signature_faulted::<Mode2, R>(sig, msg, sk, rng, fault),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_signature_faulted::<Mode3, R>(sig, msg, sk, rng, fault),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_signature_faulted::<Mode5, R>(sig, msg, sk, rng, fault),
+        }
+    }
+
+    /// Like `sign_into`, but with the first-order masked signer of the
+    /// `masked` feature. The output is identical for the same `rng`.
+    #[cfg(feature = "masked")]
+    pub fn sign_into_masked<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if sig.len() != self.signature_bytes() {
+            return Err("Invalid signature buffer length");
+        }
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_signature_masked::<Mode2, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_signature_masked::<Mode3, R>(sig, msg, sk, rng),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_signature_masked::<Mode5, R>(sig, msg, sk, rng),
+        }
+        Ok(())
+    }
+
     pub fn verify(&self, signature: &Signature, msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
         match self {
+            #[cfg(feature = "mode2")]
             Algorithm::Mode2 => {
                 crypto_sign_verify::<Mode2>(signature.bytes(), msg, public_key)
             },
+            #[cfg(feature = "mode3")]
             Algorithm::Mode3 => {
                 crypto_sign_verify::<Mode3>(signature.bytes(), msg, public_key)
             },
+            #[cfg(feature = "mode5")]
             Algorithm::Mode5 => {
                 crypto_sign_verify::<Mode5>(signature.bytes(), msg, public_key)
             },
@@ -116,60 +341,253 @@ impl Algorithm {
 
     pub fn verify_raw(&self, signature: &[u8], msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
         match self {
+            #[cfg(feature = "mode2")]
             Algorithm::Mode2 => crypto_sign_verify::<Mode2>(signature, msg, public_key),
+            #[cfg(feature = "mode3")]
             Algorithm::Mode3 => crypto_sign_verify::<Mode3>(signature, msg, public_key),
+            #[cfg(feature = "mode5")]
             Algorithm::Mode5 => crypto_sign_verify::<Mode5>(signature, msg, public_key),
         }
     }
+
+    /// Like `verify`, but also rejects any signature or public key that is
+    /// not the canonical encoding of what it decodes to. Two distinct byte
+    /// strings never both pass for the same message and key.
+    pub fn verify_strict(&self, signature: &Signature, msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
+        self.verify_raw_strict(signature.bytes(), msg, public_key)
+    }
+
+    pub fn verify_raw_strict(&self, signature: &[u8], msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
+        match self {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => crypto_sign_verify_strict::<Mode2>(signature, msg, public_key),
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => crypto_sign_verify_strict::<Mode3>(signature, msg, public_key),
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => crypto_sign_verify_strict::<Mode5>(signature, msg, public_key),
+        }
+    }
 }
 
+/// A public key and its secret key. The secret half is a [`SecretBytes`]:
+/// `Debug` redacts it and there is no `Clone`; use
+/// `Algorithm::load_from_bytes` to make a deliberate copy.
 #[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
-#[derive(Clone, Debug)]
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Keypair {
-    Mode2([u8; Mode2::PUBLIC_KEY_BYTES], [u8; Mode2::SECRET_KEY_BYTES]),
-    Mode3([u8; Mode3::PUBLIC_KEY_BYTES], [u8; Mode3::SECRET_KEY_BYTES]),
-    Mode5([u8; Mode5::PUBLIC_KEY_BYTES], [u8; Mode5::SECRET_KEY_BYTES]),
+    #[cfg(feature = "mode2")]
+    Mode2([u8; Mode2::PUBLIC_KEY_BYTES], SecretBytes<{ Mode2::SECRET_KEY_BYTES }>),
+    #[cfg(feature = "mode3")]
+    Mode3([u8; Mode3::PUBLIC_KEY_BYTES], SecretBytes<{ Mode3::SECRET_KEY_BYTES }>),
+    #[cfg(feature = "mode5")]
+    Mode5([u8; Mode5::PUBLIC_KEY_BYTES], SecretBytes<{ Mode5::SECRET_KEY_BYTES }>),
 }
 
 impl Keypair {
+    /// Rebuilds a `Keypair` from the secret key alone, recomputing the public
+    /// key from `rho`, `s1` and `s2`. Fails if the secret key is malformed or
+    /// its stored `t0` or `tr` disagree with the recomputed key.
+    pub fn from_secret_key(alg: Algorithm, sk: &[u8]) -> Result<Keypair, &'static str> {
+        if sk.len() != alg.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        match alg {
+            #[cfg(feature = "mode2")]
+            Algorithm::Mode2 => {
+                let mut pk = [0u8; Mode2::PUBLIC_KEY_BYTES];
+                crypto_sign_public_key::<Mode2>(&mut pk, sk)?;
+                Ok(Keypair::Mode2(pk, SecretBytes::from_slice(sk)?))
+            }
+            #[cfg(feature = "mode3")]
+            Algorithm::Mode3 => {
+                let mut pk = [0u8; Mode3::PUBLIC_KEY_BYTES];
+                crypto_sign_public_key::<Mode3>(&mut pk, sk)?;
+                Ok(Keypair::Mode3(pk, SecretBytes::from_slice(sk)?))
+            }
+            #[cfg(feature = "mode5")]
+            Algorithm::Mode5 => {
+                let mut pk = [0u8; Mode5::PUBLIC_KEY_BYTES];
+                crypto_sign_public_key::<Mode5>(&mut pk, sk)?;
+                Ok(Keypair::Mode5(pk, SecretBytes::from_slice(sk)?))
+            }
+        }
+    }
+
+    #[cfg(feature = "getrandom")]
     pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.sign_with_rng(msg, &mut OsRng)
+    }
+
+    pub fn sign_with_rng<R: RngCore>(&self, msg: &[u8], rng: &mut R) -> Signature {
         match self {
+            #[cfg(feature = "mode2")]
             Keypair::Mode2(_, sk) => {
                 let mut sig = [0u8; Mode2::SIGNBYTES];
-                crypto_sign_signature::<Mode2, _>(&mut sig, msg, sk, &mut OsRng);
+                crypto_sign_signature::<Mode2, R>(&mut sig, msg, sk, rng);
                 Signature {
                     bytes: SignType::SignMode2(sig)
                 }
             }
+            #[cfg(feature = "mode3")]
             Keypair::Mode3(_, sk) => {
                 let mut sig = [0u8; Mode3::SIGNBYTES];
-                crypto_sign_signature::<Mode3, _>(&mut sig, msg, sk, &mut OsRng);
+                crypto_sign_signature::<Mode3, R>(&mut sig, msg, sk, rng);
                 Signature {
                     bytes: SignType::SignMode3(sig)
                 }
             }
+            #[cfg(feature = "mode5")]
             Keypair::Mode5(_, sk) => {
                 let mut sig = [0u8; Mode5::SIGNBYTES];
-                crypto_sign_signature::<Mode5, _>(&mut sig, msg, sk, &mut OsRng);
+                crypto_sign_signature::<Mode5, R>(&mut sig, msg, sk, rng);
+                Signature {
+                    bytes: SignType::SignMode5(sig),
+                }
+            }
+        }
+    }
+
+    /// Signs `msg` with the redundant fault checks of the `hardened` feature.
+    /// Returns `FAULT_DETECTED` instead of a signature if any check fails.
+    #[cfg(all(feature = "hardened", feature = "getrandom"))]
+    pub fn sign_hardened(&self, msg: &[u8]) -> Result<Signature, &'static str> {
+        self.sign_hardened_with_rng(msg, &mut OsRng)
+    }
+
+    #[cfg(feature = "hardened")]
+    pub fn sign_hardened_with_rng<R: RngCore>(&self, msg: &[u8], rng: &mut R) -> Result<Signature, &'static str> {
+        match self {
+            #[cfg(feature = "mode2")]
+            Keypair::Mode2(_, sk) => {
+                let mut sig = [0u8; Mode2::SIGNBYTES];
+                crypto_sign_signature_hardened::<Mode2, R>(&mut sig, msg, sk, rng)?;
+                Ok(Signature {
+                    bytes: SignType::SignMode2(sig)
+                })
+            }
+            #[cfg(feature = "mode3")]
+            Keypair::Mode3(_, sk) => {
+                let mut sig = [0u8; Mode3::SIGNBYTES];
+                crypto_sign_signature_hardened::<Mode3, R>(&mut sig, msg, sk, rng)?;
+                Ok(Signature {
+                    bytes: SignType::SignMode3(sig)
+                })
+            }
+            #[cfg(feature = "mode5")]
+            Keypair::Mode5(_, sk) => {
+                let mut sig = [0u8; Mode5::SIGNBYTES];
+                crypto_sign_signature_hardened::<Mode5, R>(&mut sig, msg, sk, rng)?;
+                Ok(Signature {
+                    bytes: SignType::SignMode5(sig),
+                })
+            }
+        }
+    }
+
+    /// Signs `msg` with the first-order masked signer of the `masked` feature.
+    #[cfg(all(feature = "masked", feature = "getrandom"))]
+    pub fn sign_masked(&self, msg: &[u8]) -> Signature {
+        self.sign_masked_with_rng(msg, &mut OsRng)
+    }
+
+    /// Masked counterpart of `sign_with_rng`; byte-identical output for the
+    /// same `rng`.
+    #[cfg(feature = "masked")]
+    pub fn sign_masked_with_rng<R: RngCore>(&self, msg: &[u8], rng: &mut R) -> Signature {
+        match self {
+            #[cfg(feature = "mode2")]
+            Keypair::Mode2(_, sk) => {
+                let mut sig = [0u8; Mode2::SIGNBYTES];
+                crypto_sign_signature_masked::<Mode2, R>(&mut sig, msg, sk, rng);
+                Signature {
+                    bytes: SignType::SignMode2(sig)
+                }
+            }
+            #[cfg(feature = "mode3")]
+            Keypair::Mode3(_, sk) => {
+                let mut sig = [0u8; Mode3::SIGNBYTES];
+                crypto_sign_signature_masked::<Mode3, R>(&mut sig, msg, sk, rng);
+                Signature {
+                    bytes: SignType::SignMode3(sig)
+                }
+            }
+            #[cfg(feature = "mode5")]
+            Keypair::Mode5(_, sk) => {
+                let mut sig = [0u8; Mode5::SIGNBYTES];
+                crypto_sign_signature_masked::<Mode5, R>(&mut sig, msg, sk, rng);
                 Signature {
                     bytes: SignType::SignMode5(sig),
                 }
             }
         }
     }
-    #[cfg(not(feature = "no_std"))]
+
+    /// Signs `msg` into a caller-provided buffer of the mode's signature size.
+    pub fn sign_into<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        self.algorithm().sign_into(sig, msg, self.secret(), rng)
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            #[cfg(feature = "mode2")]
+            Keypair::Mode2(..) => Algorithm::Mode2,
+            #[cfg(feature = "mode3")]
+            Keypair::Mode3(..) => Algorithm::Mode3,
+            #[cfg(feature = "mode5")]
+            Keypair::Mode5(..) => Algorithm::Mode5,
+        }
+    }
+
+    /// Signs `msg` with `threads` workers racing through the rejection loop,
+    /// at most `MAX_SIGN_THREADS`. See `crypto_sign_signature_parallel` for
+    /// how the work is split.
+    #[cfg(feature = "parallel")]
+    pub fn sign_parallel(&self, msg: &[u8], threads: usize) -> Signature {
+        match self {
+            #[cfg(feature = "mode2")]
+            Keypair::Mode2(_, sk) => {
+                let mut sig = [0u8; Mode2::SIGNBYTES];
+                crypto_sign_signature_parallel::<Mode2, _>(&mut sig, msg, sk, &mut OsRng, threads);
+                Signature {
+                    bytes: SignType::SignMode2(sig)
+                }
+            }
+            #[cfg(feature = "mode3")]
+            Keypair::Mode3(_, sk) => {
+                let mut sig = [0u8; Mode3::SIGNBYTES];
+                crypto_sign_signature_parallel::<Mode3, _>(&mut sig, msg, sk, &mut OsRng, threads);
+                Signature {
+                    bytes: SignType::SignMode3(sig)
+                }
+            }
+            #[cfg(feature = "mode5")]
+            Keypair::Mode5(_, sk) => {
+                let mut sig = [0u8; Mode5::SIGNBYTES];
+                crypto_sign_signature_parallel::<Mode5, _>(&mut sig, msg, sk, &mut OsRng, threads);
+                Signature {
+                    bytes: SignType::SignMode5(sig),
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "alloc", feature = "getrandom"))]
     pub fn sign_to_slice(&self, msg: &[u8], sk: &[u8]) -> Vec<u8> {
         match self {
+            #[cfg(feature = "mode2")]
             Keypair::Mode2(_, _) => {
                 let mut sig = [0u8; Mode2::SIGNBYTES];
                 crypto_sign_signature::<Mode2, _>(&mut sig, msg, sk, &mut OsRng);
                 sig.to_vec()
             }
+            #[cfg(feature = "mode3")]
             Keypair::Mode3(_, _) => {
                 let mut sig = [0u8; Mode3::SIGNBYTES];
                 crypto_sign_signature::<Mode3, _>(&mut sig, msg, sk, &mut OsRng);
                 sig.to_vec()
             }
+            #[cfg(feature = "mode5")]
             Keypair::Mode5(_, _) => {
                 let mut sig = [0u8; Mode5::SIGNBYTES];
                 crypto_sign_signature::<Mode5, _>(&mut sig, msg, sk, &mut OsRng);
@@ -180,17 +598,23 @@ impl Keypair {
         
     pub fn public(&self) -> &[u8] {
         match self {
+            #[cfg(feature = "mode2")]
             Keypair::Mode2(pk, _) => pk,
+            #[cfg(feature = "mode3")]
             Keypair::Mode3(pk, _) => pk,
+            #[cfg(feature = "mode5")]
             Keypair::Mode5(pk, _) => pk,
         }
     }
 
     pub fn secret(&self) -> &[u8] {
         match self {
-            Keypair::Mode2(_, sk) => sk,
-            Keypair::Mode3(_, sk) => sk,
-            Keypair::Mode5(_, sk) => sk,
+            #[cfg(feature = "mode2")]
+            Keypair::Mode2(_, sk) => &sk[..],
+            #[cfg(feature = "mode3")]
+            Keypair::Mode3(_, sk) => &sk[..],
+            #[cfg(feature = "mode5")]
+            Keypair::Mode5(_, sk) => &sk[..],
         }
     }
 }
@@ -203,16 +627,23 @@ impl Signature {
     #[inline(always)]
     pub fn bytes(&self) -> &[u8] {
         match &self.bytes {
+            #[cfg(feature = "mode2")]
             SignType::SignMode2(arr) => &arr[..],
+            #[cfg(feature = "mode3")]
             SignType::SignMode3(arr) => &arr[..],
+            #[cfg(feature = "mode5")]
             SignType::SignMode5(arr) => &arr[..],
         }
     }
 }
 
 #[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+#[allow(clippy::large_enum_variant)]
 enum SignType {
+    #[cfg(feature = "mode2")]
     SignMode2([u8; 2420]),
+    #[cfg(feature = "mode3")]
     SignMode3([u8; 3293]),
+    #[cfg(feature = "mode5")]
     SignMode5([u8; 4595])
 }
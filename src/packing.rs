@@ -2,12 +2,12 @@ use crate::params::DilithiumParams;
 use crate::polyvec::*;
 use crate::poly::*;
 
-pub fn pack_pk<P: DilithiumParams>(pk: &mut [u8], rho: &[u8], t1: &Polyveck) {
+pub fn pack_pk<P: DilithiumParams>(pk: &mut [u8], rho: &[u8], t1: &P::Polyveck) {
     pk[..P::SEEDBYTES].copy_from_slice(rho);
 
     let mut offset = P::SEEDBYTES;
 
-    let t1_vec = vec_from_polyveck(t1);
+    let t1_vec = t1.vec();
     for i in 0..P::K {
         polyt1_pack(
             &mut pk[offset..offset + POLYT1_PACKEDBYTES],
@@ -16,20 +16,21 @@ pub fn pack_pk<P: DilithiumParams>(pk: &mut [u8], rho: &[u8], t1: &Polyveck) {
         offset += POLYT1_PACKEDBYTES;
     }
 }
+#[cfg(not(feature = "low_memory"))]
 pub fn pack_sk<P: DilithiumParams>(
   sk: &mut [u8],
   rho: &[u8],
   tr: &[u8],
   key: &[u8],
-  t0: &Polyveck,
-  s1: &Polyvecl,
-  s2: &Polyveck,
+  t0: &P::Polyveck,
+  s1: &P::Polyvecl,
+  s2: &P::Polyveck,
 ) {
   let mut idx = 0usize;
 
-    let s1_vec = vec_from_polyvecl(s1);
-    let s2_vec = vec_from_polyveck(s2);
-    let t0_vec = vec_from_polyveck(t0);
+    let s1_vec = s1.vec();
+    let s2_vec = s2.vec();
+    let t0_vec = t0.vec();
 
   sk[idx..SEEDBYTES].copy_from_slice(&rho[0..SEEDBYTES]);
   idx += SEEDBYTES;
@@ -56,19 +57,20 @@ pub fn pack_sk<P: DilithiumParams>(
   }
 }
 
+#[cfg(not(feature = "low_memory"))]
 pub fn unpack_sk<P: DilithiumParams>(
   rho: &mut [u8],
   tr: &mut [u8],
   key: &mut [u8],
-  t0: &mut Polyveck,
-  s1: &mut Polyvecl,
-  s2: &mut Polyveck,
+  t0: &mut P::Polyveck,
+  s1: &mut P::Polyvecl,
+  s2: &mut P::Polyveck,
   sk: &[u8],
 ) {
   let mut idx = 0usize;
-let s1_vec = vec_from_polyvecl_mut(s1);
-let s2_vec = vec_from_polyveck_mut(s2);
-let t0_vec = vec_from_polyveck_mut(t0);
+let s1_vec = s1.vec_mut();
+let s2_vec = s2.vec_mut();
+let t0_vec = t0.vec_mut();
   rho[..SEEDBYTES].copy_from_slice(&sk[..SEEDBYTES]);
   idx += SEEDBYTES;
 
@@ -96,9 +98,9 @@ for i in 0..P::L {
   }
 }
 
-pub fn pack_sig<P: DilithiumParams>(sig: &mut [u8], c: Option<&[u8]>, z: &Polyvecl, h: &Polyveck) {
-    let z_vec = vec_from_polyvecl(z);
-    let h_vec = vec_from_polyveck(h);
+pub fn pack_sig<P: DilithiumParams>(sig: &mut [u8], c: Option<&[u8]>, z: &P::Polyvecl, h: &P::Polyveck) {
+    let z_vec = z.vec();
+    let h_vec = h.vec();
   let mut idx = 0usize;
 
   if let Some(challenge) = c {
@@ -108,9 +110,9 @@ pub fn pack_sig<P: DilithiumParams>(sig: &mut [u8], c: Option<&[u8]>, z: &Polyve
   idx += SEEDBYTES;
 
   for i in 0..P::L {
-    polyz_pack::<P>(&mut sig[idx + i * P::polyz_packedbytes()..], &z_vec[i]);
+    polyz_pack::<P>(&mut sig[idx + i * P::POLYZ_PACKEDBYTES..], &z_vec[i]);
   }
-  idx += P::L * P::polyz_packedbytes();
+  idx += P::L * P::POLYZ_PACKEDBYTES;
   let max = [0u8; 7 * 75];
   sig[idx..idx + P::OMEGA + P::K].copy_from_slice(&max[.. P::OMEGA + P::K]);
 
@@ -126,22 +128,23 @@ pub fn pack_sig<P: DilithiumParams>(sig: &mut [u8], c: Option<&[u8]>, z: &Polyve
   }
 }
 
+#[cfg(not(feature = "low_memory"))]
 pub fn unpack_sig<P: DilithiumParams>(
   c: &mut [u8],
-  z: &mut Polyvecl,
-  h: &mut Polyveck,
+  z: &mut P::Polyvecl,
+  h: &mut P::Polyveck,
   sig: &[u8],
 ) -> Result<(), &'static str> {
   let mut idx = 0usize;
-    let z_vec = vec_from_polyvecl_mut(z);
-    let h_vec = vec_from_polyveck_mut(h);
+    let z_vec = z.vec_mut();
+    let h_vec = h.vec_mut();
   c[..SEEDBYTES].copy_from_slice(&sig[..SEEDBYTES]);
   idx += SEEDBYTES;
 
   for i in 0..P::L {
-    polyz_unpack::<P>(&mut z_vec[i], &sig[idx + i * P::polyz_packedbytes()..]);
+    polyz_unpack::<P>(&mut z_vec[i], &sig[idx + i * P::POLYZ_PACKEDBYTES..]);
   }
-  idx += P::L * P::polyz_packedbytes();
+  idx += P::L * P::POLYZ_PACKEDBYTES;
 
   let mut k = 0usize;
   for i in 0..P::K {
@@ -149,7 +152,7 @@ pub fn unpack_sig<P: DilithiumParams>(
       return Err("INVALID OMEGA");
     }
     for j in k..sig[idx + P::OMEGA + i] as usize {
-      if j > k && sig[idx + j as usize] <= sig[idx + j as usize - 1] {
+      if j > k && sig[idx + j] <= sig[idx + j - 1] {
         return Err("INVALID H");
       }
       h_vec[i].coeffs[sig[idx + j] as usize] = 1;
@@ -158,7 +161,7 @@ pub fn unpack_sig<P: DilithiumParams>(
   }
 
   for j in k..P::OMEGA {
-    if sig[idx + j as usize] > 0 {
+    if sig[idx + j] > 0 {
       return Err("INVALID H");
     }
   }
@@ -166,10 +169,114 @@ pub fn unpack_sig<P: DilithiumParams>(
   Ok(())
 }
 
-pub fn unpack_pk<P: DilithiumParams>(rho: &mut [u8], t1: &mut Polyveck, pk: &[u8]) {
+pub fn unpack_pk<P: DilithiumParams>(rho: &mut [u8], t1: &mut P::Polyveck, pk: &[u8]) {
   rho[..SEEDBYTES].copy_from_slice(&pk[..SEEDBYTES]);
-  let t1_vec = vec_from_polyveck_mut(t1);
+  let t1_vec = t1.vec_mut();
   for i in 0..P::K {
     polyt1_unpack(&mut t1_vec[i], &pk[SEEDBYTES + i * POLYT1_PACKEDBYTES..])
   }
-}
\ No newline at end of file
+}
+
+/// Offset of `s1[i]` inside a packed secret key.
+pub fn sk_s1_offset<P: DilithiumParams>(i: usize) -> usize {
+  3 * SEEDBYTES + i * P::POLYETA_PACKEDBYTES
+}
+
+/// Offset of `s2[i]` inside a packed secret key.
+pub fn sk_s2_offset<P: DilithiumParams>(i: usize) -> usize {
+  3 * SEEDBYTES + (P::L + i) * P::POLYETA_PACKEDBYTES
+}
+
+/// Offset of `t0[i]` inside a packed secret key.
+pub fn sk_t0_offset<P: DilithiumParams>(i: usize) -> usize {
+  3 * SEEDBYTES + (P::L + P::K) * P::POLYETA_PACKEDBYTES + i * POLYT0_PACKEDBYTES
+}
+
+/// Decodes row `i` of the hint vector from a packed signature, applying the
+/// same checks as `unpack_sig`. The trailing padding is checked separately by
+/// `check_sig_hint_padding` once all rows are read.
+pub fn unpack_sig_hint_row<P: DilithiumParams>(
+  h: &mut Poly,
+  sig: &[u8],
+  i: usize,
+) -> Result<(), &'static str> {
+  let idx = SEEDBYTES + P::L * P::POLYZ_PACKEDBYTES;
+  let k = if i == 0 { 0 } else { sig[idx + P::OMEGA + i - 1] as usize };
+  let end = sig[idx + P::OMEGA + i] as usize;
+
+  h.coeffs.fill(0);
+  if end < k || end > P::OMEGA {
+    return Err("INVALID OMEGA");
+  }
+  for j in k..end {
+    if j > k && sig[idx + j] <= sig[idx + j - 1] {
+      return Err("INVALID H");
+    }
+    h.coeffs[sig[idx + j] as usize] = 1;
+  }
+  Ok(())
+}
+
+#[cfg(feature = "low_memory")]
+pub fn check_sig_hint_padding<P: DilithiumParams>(sig: &[u8]) -> Result<(), &'static str> {
+  let idx = SEEDBYTES + P::L * P::POLYZ_PACKEDBYTES;
+  let k = sig[idx + P::OMEGA + P::K - 1] as usize;
+  if sig[idx + k..idx + P::OMEGA].iter().any(|&b| b > 0) {
+    return Err("INVALID H");
+  }
+  Ok(())
+}
+
+/// Rejects a signature that is not the unique encoding of the `c`, `z` and
+/// `h` it decodes to, by re-encoding `z` and the hint section and comparing
+/// bytes. The length must already have been checked.
+pub fn check_sig_canonical<P: DilithiumParams>(sig: &[u8]) -> Result<(), &'static str> {
+  let mut p = Poly::default();
+  // Large enough for POLYZ_PACKEDBYTES and OMEGA + K in every mode.
+  let mut buf = [0u8; 640];
+
+  for i in 0..P::L {
+    let packed = &sig[SEEDBYTES + i * P::POLYZ_PACKEDBYTES..][..P::POLYZ_PACKEDBYTES];
+    polyz_unpack::<P>(&mut p, packed);
+    polyz_pack::<P>(&mut buf, &p);
+    if buf[..P::POLYZ_PACKEDBYTES] != *packed {
+      return Err("Non-canonical z encoding");
+    }
+  }
+
+  let idx = SEEDBYTES + P::L * P::POLYZ_PACKEDBYTES;
+  let hint = &mut buf[..P::OMEGA + P::K];
+  hint.fill(0);
+  let mut k = 0;
+  for i in 0..P::K {
+    unpack_sig_hint_row::<P>(&mut p, sig, i)?;
+    for (j, c) in p.coeffs.iter().enumerate() {
+      if *c != 0 {
+        hint[k] = j as u8;
+        k += 1;
+      }
+    }
+    hint[P::OMEGA + i] = k as u8;
+  }
+  if *hint != sig[idx..idx + P::OMEGA + P::K] {
+    return Err("Non-canonical hint encoding");
+  }
+  Ok(())
+}
+
+/// Rejects a public key whose `t1` does not re-encode to the same bytes.
+/// The length must already have been checked.
+pub fn check_pk_canonical<P: DilithiumParams>(pk: &[u8]) -> Result<(), &'static str> {
+  let mut t1 = Poly::default();
+  let mut buf = [0u8; POLYT1_PACKEDBYTES];
+
+  for i in 0..P::K {
+    let packed = &pk[SEEDBYTES + i * POLYT1_PACKEDBYTES..][..POLYT1_PACKEDBYTES];
+    polyt1_unpack(&mut t1, packed);
+    polyt1_pack(&mut buf, &t1);
+    if buf != *packed {
+      return Err("Non-canonical t1 encoding");
+    }
+  }
+  Ok(())
+}
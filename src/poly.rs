@@ -1,24 +1,21 @@
-#![allow(dead_code)]
-
 use crate::{
   reduce::*, 
   params::DilithiumParams, 
+  secret::SecretBytes,
   ntt::*, 
   rounding::*,
   fips202::*
 };
 
+use subtle::{ConditionallySelectable, ConstantTimeLess};
 #[cfg(feature = "zeroize")]
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub const SEEDBYTES: usize = 32;
 pub const CRHBYTES: usize = 64;
-pub const TRBYTES: usize = 64;
-pub const RNDBYTES: usize = 32;
 pub const N: usize = 256;
 pub const Q: usize = 8380417;
 pub const D: usize = 13;
-pub const ROOT_OF_UNITY: usize = 1753;
 pub const POLYT1_PACKEDBYTES: usize =  320;
 pub const POLYT0_PACKEDBYTES: usize =  416;
 pub const STREAM128_BLOCKBYTES: usize = SHAKE128_RATE;
@@ -49,15 +46,15 @@ pub fn poly_caddq<P: DilithiumParams>(a: &mut Poly) {
   }
 }
 
-pub fn poly_add(c: &mut Poly, a: &Poly, b: &Poly) {
+pub fn poly_add_assign(a: &mut Poly, b: &Poly) {
   for i in 0..N {
-    c.coeffs[i] = a.coeffs[i] + b.coeffs[i];
+    a.coeffs[i] += b.coeffs[i];
   }
 }
 
-pub fn poly_sub(c: &mut Poly, a: &Poly, b: &Poly) {
+pub fn poly_sub_assign(a: &mut Poly, b: &Poly) {
   for i in 0..N {
-    c.coeffs[i] = a.coeffs[i] - b.coeffs[i];
+    a.coeffs[i] -= b.coeffs[i];
   }
 }
 
@@ -75,22 +72,31 @@ pub fn poly_invntt_tomont<P: DilithiumParams>(a: &mut Poly) {
   invntt_tomont::<P>(&mut a.coeffs);
 }
 
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
 pub fn poly_pointwise_montgomery<P: DilithiumParams>(c: &mut Poly, a: &Poly, b: &Poly) {
   for i in 0..N {
     c.coeffs[i] = montgomery_reduce::<P>((a.coeffs[i] as i64) * b.coeffs[i] as i64);
   }
 }
 
-pub fn poly_power2round<P: DilithiumParams>(a1: &mut Poly, a0: &mut Poly, _a: &Poly) {
+pub fn poly_pointwise_montgomery_assign<P: DilithiumParams>(a: &mut Poly, b: &Poly) {
+  for i in 0..N {
+    a.coeffs[i] = montgomery_reduce::<P>((a.coeffs[i] as i64) * b.coeffs[i] as i64);
+  }
+}
+
+/// Splits `a1` in place: on return `a1` holds the high bits and `a0` the low bits.
+pub fn poly_power2round<P: DilithiumParams>(a1: &mut Poly, a0: &mut Poly) {
   for i in 0..N {
     a1.coeffs[i] = power2round::<P>(&mut a0.coeffs[i], a1.coeffs[i]);
   }
 }
 
 
-pub fn poly_decompose<P: DilithiumParams>(a1: &mut Poly, a0: &mut Poly, a: &Poly) {
+/// Splits `a1` into high bits, left in `a1`, and low bits, written to `a0`.
+pub fn poly_decompose_in_place<P: DilithiumParams>(a1: &mut Poly, a0: &mut Poly) {
   for i in 0..N {
-    a1.coeffs[i] = decompose::<P>(&mut a0.coeffs[i], a.coeffs[i]);
+    a1.coeffs[i] = decompose::<P>(&mut a0.coeffs[i], a1.coeffs[i]);
   }
 }
 
@@ -103,14 +109,18 @@ pub fn poly_make_hint<P: DilithiumParams>(h: &mut Poly, a0: &Poly, a1: &Poly) ->
   s
 }
 
-pub fn poly_use_hint<P: DilithiumParams>(b: &mut Poly, a: &Poly, h: &Poly) {
+pub fn poly_use_hint_in_place<P: DilithiumParams>(a: &mut Poly, h: &Poly) {
   for i in 0..N {
-    b.coeffs[i] = use_hint::<P>(a.coeffs[i], h.coeffs[i] as u32);
+    a.coeffs[i] = use_hint::<P>(a.coeffs[i], h.coeffs[i] as u32);
   }
 }
 
+/// Returns 1 if any coefficient has `|a_i| >= b`. The bound is public, but
+/// every coefficient is scanned so neither the position nor the sign of an
+/// out-of-range value shows in the running time.
 pub fn poly_chknorm<P: DilithiumParams>(a: &Poly, b: i32) -> u8 {
   let mut t;
+  let mut over = 0i32;
 
   if b > ((P::Q - 1) / 8 ) as i32 {
     return 1;
@@ -119,11 +129,9 @@ pub fn poly_chknorm<P: DilithiumParams>(a: &Poly, b: i32) -> u8 {
     t = a.coeffs[i] >> 31;
     t = a.coeffs[i] - (t & 2 * a.coeffs[i]);
 
-    if t >= b {
-      return 1;
-    }
+    over |= (b - 1 - t) >> 31;
   }
-  return 0;
+  (over & 1) as u8
 }
 
 #[inline(always)]
@@ -148,20 +156,18 @@ pub fn rej_uniform(a: &mut [i32], len: u32, buf: &[u8], buflen: usize) -> u32 {
 }
 
 const POLY_UNIFORM_NBLOCKS: usize =
-  (768 + STREAM128_BLOCKBYTES - 1) / STREAM128_BLOCKBYTES;
+  768_usize.div_ceil(STREAM128_BLOCKBYTES);
 
 #[inline(always)]
 pub fn poly_uniform<P: DilithiumParams>(a: &mut Poly, seed: &[u8], nonce: u16) {
   
   let mut buflen = POLY_UNIFORM_NBLOCKS * STREAM128_BLOCKBYTES;
   let mut buf = [0u8; POLY_UNIFORM_NBLOCKS * STREAM128_BLOCKBYTES + 2];
-  let mut state = KeccakState::default();
-
-  state.dilithium_shake128_stream_init(seed, nonce);
+  let mut state = XofReader::dilithium_shake128_stream(seed, nonce);
 
-  state.shake128_squeezeblocks(&mut buf, POLY_UNIFORM_NBLOCKS);
+  state.read(&mut buf[..buflen]);
 
-  let mut ctr = rej_uniform(&mut a.coeffs, P::N as u32, &mut buf, buflen);
+  let mut ctr = rej_uniform(&mut a.coeffs, P::N as u32, &buf, buflen);
   let mut off;
   while ctr < P::N as u32 {
     off = buflen % 3;
@@ -169,78 +175,72 @@ pub fn poly_uniform<P: DilithiumParams>(a: &mut Poly, seed: &[u8], nonce: u16) {
       buf[i] = buf[buflen - off + i];
     }
     buflen = STREAM128_BLOCKBYTES + off;
-    state.shake128_squeezeblocks(&mut buf[off..], 1);
+    state.read(&mut buf[off..buflen]);
     ctr += rej_uniform(
       &mut a.coeffs[(ctr as usize)..],
       P::N as u32 - ctr,
-      &mut buf,
+      &buf,
       buflen,
     );
   }
 }
 
+/// Rejection-samples `eta`-bounded coefficients from the secret stream.
+///
+/// Whether a nibble is accepted does not depend on the value it yields, so
+/// `ctr` may advance observably; the acceptance test, the reduction mod 5 and
+/// the store itself are branch-free.
 pub fn rej_eta<P: DilithiumParams>(a: &mut [i32], len: usize, buf: &[u8], buflen: usize) -> u32 {
   let (mut ctr, mut pos) = (0usize, 0usize);
   let (mut t0, mut t1);
+  let bound = if P::ETA == 2 { 15u32 } else { 9u32 };
   while ctr < len && pos < buflen {
     t0 = (buf[pos] & 0x0F) as u32;
     t1 = (buf[pos] >> 4) as u32;
     pos += 1;
 
+    let ok0 = t0.ct_lt(&bound);
+    let ok1 = t1.ct_lt(&bound);
     if P::ETA == 2 {
-      if t0 < 15 {
-        t0 = t0 - (205 * t0 >> 10) * 5;
-        a[ctr] = 2 - t0 as i32;
-        ctr += 1;
-      }
-      if t1 < 15 && ctr < len {
-        t1 = t1 - (205 * t1 >> 10) * 5;
-        a[ctr] = 2 - t1 as i32;
-        ctr += 1;
-      }
-    } else if P::ETA == 4 {
-      if t0 < 9 {
-        a[ctr] = 4 - t0 as i32;
-        ctr += 1;
-      }
-      if t1 < 9 && ctr < len {
-        a[ctr] = 4 - t1 as i32;
-        ctr += 1;
-      }
+      t0 = t0 - (205 * t0 >> 10) * 5;
+      t1 = t1 - (205 * t1 >> 10) * 5;
+    }
+
+    a[ctr].conditional_assign(&(P::ETA as i32 - t0 as i32), ok0);
+    ctr += ok0.unwrap_u8() as usize;
+    if ctr < len {
+      a[ctr].conditional_assign(&(P::ETA as i32 - t1 as i32), ok1);
+      ctr += ok1.unwrap_u8() as usize;
     }
   }
   ctr as u32
 }
 pub const fn poly_uniform_eta_nblocks(eta: usize) -> usize {
     if eta == 2 {
-        (136 + STREAM256_BLOCKBYTES - 1) / STREAM256_BLOCKBYTES
+        136_usize.div_ceil(STREAM256_BLOCKBYTES)
     } else {
-        (227 + STREAM256_BLOCKBYTES - 1) / STREAM256_BLOCKBYTES
+        227_usize.div_ceil(STREAM256_BLOCKBYTES)
     }
 }
 
 #[allow(non_snake_case)]
 pub fn poly_uniform_eta<P: DilithiumParams>(a: &mut Poly, seed: &[u8], nonce: u16) {
   
-  let mut max = [0u8; 362];
+  let mut max = SecretBytes::<362>::default();
   
-  let mut buf = &mut max[..poly_uniform_eta_nblocks(P::ETA) * STREAM256_BLOCKBYTES];
+  let buf = &mut max[..poly_uniform_eta_nblocks(P::ETA) * STREAM256_BLOCKBYTES];
   let buf_len = buf.len();
-  let mut state = KeccakState::default();
-  state.dilithium_shake256_stream_init(seed, nonce);
-  state.shake256_squeezeblocks(
-    buf,
-    poly_uniform_eta_nblocks(P::ETA)
-  );
+  let mut state = XofReader::dilithium_shake256_stream(seed, nonce);
+  state.read(buf);
 
-  let mut ctr = rej_eta::<P>(&mut a.coeffs, N, &buf, buf_len);
+  let mut ctr = rej_eta::<P>(&mut a.coeffs, N, buf, buf_len);
 
   while ctr < P::N as u32 {
-    state.shake256_squeezeblocks(&mut buf, 1);
+    state.read(&mut buf[..STREAM256_BLOCKBYTES]);
     ctr += rej_eta::<P>(
       &mut a.coeffs[ctr as usize..],
       N - ctr as usize,
-      &buf,
+      buf,
       STREAM256_BLOCKBYTES,
     );
   }
@@ -248,18 +248,12 @@ pub fn poly_uniform_eta<P: DilithiumParams>(a: &mut Poly, seed: &[u8], nonce: u1
 
 
 pub fn poly_uniform_gamma1<P: DilithiumParams>(a: &mut Poly, seed: &[u8], nonce: u16) {
-  let mut poly_buf = P::poly_uniform_gamma1_buffer();
-  
-  let mut buf = poly_buf.buf();
-
-  let mut state = KeccakState::default();
+  let mut poly_buf = P::PolyUniformGamma1Buffer::default();
+  let buf = poly_buf.as_mut();
 
-  state.dilithium_shake256_stream_init(seed, nonce);
-  state.shake256_squeezeblocks(
-    &mut buf,
-    P::POLY_UNIFORM_GAMMA1_NBLOCKS
-  );
-  polyz_unpack::<P>(a, &mut buf);
+  let mut state = XofReader::dilithium_shake256_stream(seed, nonce);
+  state.read(buf);
+  polyz_unpack::<P>(a, buf);
 }
 
 pub fn poly_challenge<P: DilithiumParams>(c: &mut Poly, seed: &[u8]) {
@@ -267,9 +261,9 @@ pub fn poly_challenge<P: DilithiumParams>(c: &mut Poly, seed: &[u8]) {
   let mut buf = [0u8; SHAKE256_RATE];
   let mut state = KeccakState::default();
 
-  state.shake256_absorb(seed, SEEDBYTES);
-  state.shake256_finalize();
-  state.shake256_squeezeblocks(&mut buf, 1);
+  state.shake256_absorb(&seed[..SEEDBYTES]);
+  let mut state = state.shake256_finalize();
+  state.read(&mut buf);
 
   for i in 0..8 {
     _signs |= (buf[i] as u64) << 8 * i;
@@ -281,7 +275,7 @@ pub fn poly_challenge<P: DilithiumParams>(c: &mut Poly, seed: &[u8]) {
   for i in N - P::TAU..N {
     loop {
       if pos >= SHAKE256_RATE {
-        state.shake256_squeezeblocks(&mut buf, 1);
+        state.read(&mut buf);
         pos = 0;
       }
       b = buf[pos] as usize;
@@ -290,8 +284,8 @@ pub fn poly_challenge<P: DilithiumParams>(c: &mut Poly, seed: &[u8]) {
         break;
       }
     }
-    c.coeffs[i] = c.coeffs[b as usize];
-    c.coeffs[b as usize] = 1i32 - 2 * (_signs & 1) as i32;
+    c.coeffs[i] = c.coeffs[b];
+    c.coeffs[b] = 1i32 - 2 * (_signs & 1) as i32;
     _signs >>= 1;
   }
 }
@@ -336,14 +330,14 @@ pub fn polyeta_unpack<P: DilithiumParams>(r: &mut Poly, a: &[u8]) {
       r.coeffs[8 * i + 6] = ((a[3 * i + 2] >> 2) & 0x07) as i32;
       r.coeffs[8 * i + 7] = ((a[3 * i + 2] >> 5) & 0x07) as i32;
 
-      r.coeffs[8 * i + 0] = (P::ETA as i32 - r.coeffs[8 * i + 0]) as i32;
-      r.coeffs[8 * i + 1] = (P::ETA as i32 - r.coeffs[8 * i + 1]) as i32;
-      r.coeffs[8 * i + 2] = (P::ETA as i32 - r.coeffs[8 * i + 2]) as i32;
-      r.coeffs[8 * i + 3] = (P::ETA as i32 - r.coeffs[8 * i + 3]) as i32;
-      r.coeffs[8 * i + 4] = (P::ETA as i32 - r.coeffs[8 * i + 4]) as i32;
-      r.coeffs[8 * i + 5] = (P::ETA as i32 - r.coeffs[8 * i + 5]) as i32;
-      r.coeffs[8 * i + 6] = (P::ETA as i32 - r.coeffs[8 * i + 6]) as i32;
-      r.coeffs[8 * i + 7] = (P::ETA as i32 - r.coeffs[8 * i + 7]) as i32;
+      r.coeffs[8 * i + 0] = P::ETA as i32 - r.coeffs[8 * i + 0];
+      r.coeffs[8 * i + 1] = P::ETA as i32 - r.coeffs[8 * i + 1];
+      r.coeffs[8 * i + 2] = P::ETA as i32 - r.coeffs[8 * i + 2];
+      r.coeffs[8 * i + 3] = P::ETA as i32 - r.coeffs[8 * i + 3];
+      r.coeffs[8 * i + 4] = P::ETA as i32 - r.coeffs[8 * i + 4];
+      r.coeffs[8 * i + 5] = P::ETA as i32 - r.coeffs[8 * i + 5];
+      r.coeffs[8 * i + 6] = P::ETA as i32 - r.coeffs[8 * i + 6];
+      r.coeffs[8 * i + 7] = P::ETA as i32 - r.coeffs[8 * i + 7];
     }
   } else {
     for i in 0..N / 2 {
@@ -351,8 +345,8 @@ pub fn polyeta_unpack<P: DilithiumParams>(r: &mut Poly, a: &[u8]) {
       
       r.coeffs[2 * i + 1] = (a[i] >> 4) as i32;
       
-      r.coeffs[2 * i + 0] = (P::ETA as i32 - r.coeffs[2 * i + 0]) as i32;
-      r.coeffs[2 * i + 1] = (P::ETA as i32 - r.coeffs[2 * i + 1]) as i32;
+      r.coeffs[2 * i + 0] = P::ETA as i32 - r.coeffs[2 * i + 0];
+      r.coeffs[2 * i + 1] = P::ETA as i32 - r.coeffs[2 * i + 1];
     }
   }
 }
@@ -547,7 +541,7 @@ pub fn polyz_unpack<P: DilithiumParams>(r: &mut Poly, a: &[u8]) {
       r.coeffs[2 * i + 1] = (a[5 * i + 2] as i32) >> 4;
       r.coeffs[2 * i + 1] |= (a[5 * i + 3] as i32) << 4;
       r.coeffs[2 * i + 1] |= (a[5 * i + 4] as i32) << 12;
-      r.coeffs[2 * i + 0] &= 0xFFFFF; // TODO: Unnecessary mask?
+      r.coeffs[2 * i + 1] &= 0xFFFFF;
 
       r.coeffs[2 * i + 0] = P::GAMMA1 as i32 - r.coeffs[2 * i + 0];
       r.coeffs[2 * i + 1] = P::GAMMA1 as i32 - r.coeffs[2 * i + 1];
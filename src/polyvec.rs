@@ -5,318 +5,352 @@ use crate::{
 #[cfg(feature = "zeroize")]
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
-
-#[derive(Clone, Debug)]
-pub enum Polyveck {
-    Mode2(PolyveckStruct<4>),
-    Mode3(PolyveckStruct<6>),
-    Mode5(PolyveckStruct<8>)
+/// Fixed-length vector of polynomials. Implemented by `Polyveck<K>` and
+/// `Polyvecl<L>` so the parameter set picks the length at compile time.
+pub trait PolyVec: Default + Clone + Send + Sync {
+    fn vec(&self) -> &[Poly];
+    fn vec_mut(&mut self) -> &mut [Poly];
 }
+
 #[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
 #[derive(Clone, Debug)]
-pub struct PolyveckStruct<const K: usize> {
+pub struct Polyveck<const K: usize> {
     pub vec: [Poly; K],
 }
 
-impl<const K: usize> Default for PolyveckStruct<K> {
+impl<const K: usize> Default for Polyveck<K> {
+    #[inline(always)]
     fn default() -> Self {
-        PolyveckStruct {
-            vec: core::array::from_fn(|_| Poly::default()),
+        Polyveck {
+            vec: [const { Poly { coeffs: [0i32; N] } }; K],
         }
     }
 }
 
-
-#[derive(Clone, Debug)]
-pub enum Polyvecl {
-    Mode2(PolyveclStruct<4>),
-    Mode3(PolyveclStruct<5>),
-    Mode5(PolyveclStruct<7>)
+impl<const K: usize> PolyVec for Polyveck<K> {
+    #[inline(always)]
+    fn vec(&self) -> &[Poly] {
+        &self.vec
+    }
+    #[inline(always)]
+    fn vec_mut(&mut self) -> &mut [Poly] {
+        &mut self.vec
+    }
 }
 
 #[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
 #[derive(Clone, Debug)]
-pub struct PolyveclStruct<const K: usize> {
-    pub vec: [Poly; K],
+pub struct Polyvecl<const L: usize> {
+    pub vec: [Poly; L],
 }
 
-impl<const K: usize> Default for PolyveclStruct<K> {
+impl<const L: usize> Default for Polyvecl<L> {
+    #[inline(always)]
     fn default() -> Self {
-        PolyveclStruct {
-            vec: core::array::from_fn(|_| Poly::default()),
+        Polyvecl {
+            vec: [const { Poly { coeffs: [0i32; N] } }; L],
         }
     }
 }
 
-#[inline(always)]
-pub fn vec_from_polyveck_mut(v: &mut Polyveck) -> &mut [Poly] {
-    match v {
-        Polyveck::Mode2(v) => &mut v.vec,
-        Polyveck::Mode3(v) => &mut v.vec,
-        Polyveck::Mode5(v) => &mut v.vec,
+impl<const L: usize> PolyVec for Polyvecl<L> {
+    #[inline(always)]
+    fn vec(&self) -> &[Poly] {
+        &self.vec
+    }
+    #[inline(always)]
+    fn vec_mut(&mut self) -> &mut [Poly] {
+        &mut self.vec
     }
 }
-#[inline(always)]
-pub fn vec_from_polyveck(v: &Polyveck) -> &[Poly] {
-    match v {
-        Polyveck::Mode2(inner) => &inner.vec,
-        Polyveck::Mode3(inner) => &inner.vec,
-        Polyveck::Mode5(inner) => &inner.vec,
+
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+pub struct PolyUniformGamma1Buffer<const N: usize> {
+    pub buf: [u8; N],
+}
+
+impl<const N: usize> Default for PolyUniformGamma1Buffer<N> {
+    fn default() -> Self {
+        PolyUniformGamma1Buffer { buf: [0u8; N] }
     }
 }
-#[inline(always)]
-pub fn vec_from_polyvecl_mut(v: &mut Polyvecl) -> &mut [Poly] {
-    match v {
-        Polyvecl::Mode2(v) => &mut v.vec,
-        Polyvecl::Mode3(v) => &mut v.vec,
-        Polyvecl::Mode5(v) => &mut v.vec,
+
+impl<const N: usize> AsMut<[u8]> for PolyUniformGamma1Buffer<N> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.buf
     }
 }
-#[inline(always)]
-pub fn vec_from_polyvecl(v: &Polyvecl) -> &[Poly] {
-    match v {
-        Polyvecl::Mode2(inner) => &inner.vec,
-        Polyvecl::Mode3(inner) => &inner.vec,
-        Polyvecl::Mode5(inner) => &inner.vec,
+
+/// Operations on the public matrix `A`.
+///
+/// By default the whole `K x L` matrix is expanded once and cached. With the
+/// `low_memory` feature only `rho` is kept and each entry is regenerated from
+/// it while multiplying, trading `K * L` extra SHAKE128 expansions per product
+/// for the `K * L` KB the cached matrix would occupy.
+pub trait PolyMatrix: Default + Clone + Send + Sync {
+    fn expand<P: DilithiumParams>(&mut self, rho: &[u8]);
+    /// `w = sum_j A[i][j] * v[j]`, with `v` in NTT domain.
+    fn row_pointwise_acc_montgomery<P: DilithiumParams>(&self, w: &mut Poly, i: usize, v: &[Poly]);
+}
+
+#[cfg(not(feature = "low_memory"))]
+#[derive(Clone)]
+pub struct Mat<const K: usize, const L: usize> {
+    rows: [Polyvecl<L>; K],
+}
+
+#[cfg(not(feature = "low_memory"))]
+impl<const K: usize, const L: usize> Default for Mat<K, L> {
+    fn default() -> Self {
+        Mat {
+            rows: core::array::from_fn(|_| Polyvecl::default()),
+        }
     }
 }
 
-pub fn polyvec_matrix_expand<P: DilithiumParams>(mat: &mut [Polyvecl], rho: &[u8]) {
-    for (i, row) in mat.iter_mut().enumerate() {
-      let row_vec = vec_from_polyvecl_mut(row);
-      for (j, poly) in row_vec.iter_mut().enumerate() {
-          poly_uniform::<P>(poly, rho, ((i << 8) + j) as u16);
-      }
-  }
+#[cfg(not(feature = "low_memory"))]
+impl<const K: usize, const L: usize> PolyMatrix for Mat<K, L> {
+    fn expand<P: DilithiumParams>(&mut self, rho: &[u8]) {
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            for (j, poly) in row.vec.iter_mut().enumerate() {
+                poly_uniform::<P>(poly, rho, ((i << 8) + j) as u16);
+            }
+        }
+    }
+
+    fn row_pointwise_acc_montgomery<P: DilithiumParams>(&self, w: &mut Poly, i: usize, v: &[Poly]) {
+        let row = &self.rows[i].vec;
+        let mut t = Poly::default();
+        poly_pointwise_montgomery::<P>(w, &row[0], &v[0]);
+        for j in 1..L {
+            poly_pointwise_montgomery::<P>(&mut t, &row[j], &v[j]);
+            poly_add_assign(w, &t);
+        }
+    }
+}
+
+#[cfg(feature = "low_memory")]
+#[derive(Clone, Default)]
+pub struct Mat<const K: usize, const L: usize> {
+    rho: [u8; SEEDBYTES],
+}
+
+#[cfg(feature = "low_memory")]
+impl<const K: usize, const L: usize> PolyMatrix for Mat<K, L> {
+    fn expand<P: DilithiumParams>(&mut self, rho: &[u8]) {
+        self.rho.copy_from_slice(&rho[..SEEDBYTES]);
+    }
+
+    fn row_pointwise_acc_montgomery<P: DilithiumParams>(&self, w: &mut Poly, i: usize, v: &[Poly]) {
+        let mut a = Poly::default();
+        w.coeffs.fill(0);
+        for (j, vj) in v.iter().enumerate().take(L) {
+            poly_uniform::<P>(&mut a, &self.rho, ((i << 8) + j) as u16);
+            poly_pointwise_montgomery_assign::<P>(&mut a, vj);
+            poly_add_assign(w, &a);
+        }
+    }
+}
+
+pub fn polyvec_matrix_expand<P: DilithiumParams>(mat: &mut P::Mat, rho: &[u8]) {
+  mat.expand::<P>(rho);
 }
 
 pub fn polyvec_matrix_pointwise_montgomery<P: DilithiumParams>(
-    t: &mut Polyveck,
-    mat: &[Polyvecl],
-    v: &Polyvecl,
+  t: &mut P::Polyveck,
+  mat: &P::Mat,
+  v: &P::Polyvecl,
 ) {
-    let t_vec = vec_from_polyveck_mut(t);
-    for i in 0..t_vec.len() {
-        polyvecl_pointwise_acc_montgomery::<P>(&mut t_vec[i], &mat[i], v);
-    }
+  for (i, ti) in t.vec_mut().iter_mut().enumerate() {
+    mat.row_pointwise_acc_montgomery::<P>(ti, i, v.vec());
+  }
 }
 
 pub fn polyvecl_uniform_eta<P: DilithiumParams>(
-  v: &mut Polyvecl, 
+  v: &mut P::Polyvecl, 
   seed: &[u8], 
   mut nonce: u16
 ) {
-    let v_vec = vec_from_polyvecl_mut(v);
-    for i in 0..P::L {
-      poly_uniform_eta::<P>(&mut v_vec[i], seed, nonce);
+  for p in v.vec_mut() {
+    poly_uniform_eta::<P>(p, seed, nonce);
     nonce += 1;
   }
 }
 
-pub fn polyvecl_uniform_gamma1<P: DilithiumParams>(v: &mut Polyvecl, seed: &[u8], nonce: u16) {
-  let v_vec = vec_from_polyvecl_mut(v);
-  for i in 0..v_vec.len() {
-    poly_uniform_gamma1::<P>(&mut v_vec[i], seed, P::L as u16 * nonce + i as u16);
+pub fn polyvecl_uniform_gamma1<P: DilithiumParams>(v: &mut P::Polyvecl, seed: &[u8], nonce: u16) {
+  for (i, p) in v.vec_mut().iter_mut().enumerate() {
+    poly_uniform_gamma1::<P>(p, seed, P::L as u16 * nonce + i as u16);
   }
 }
-pub fn polyvecl_reduce<P: DilithiumParams>(v: &mut Polyvecl) {
-  let v_vec = vec_from_polyvecl_mut(v);
-  for i in 0..v_vec.len() {
-    poly_reduce::<P>(&mut v_vec[i]);
+
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyvecl_reduce<P: DilithiumParams>(v: &mut P::Polyvecl) {
+  for p in v.vec_mut() {
+    poly_reduce::<P>(p);
   }
 }
 
-pub fn polyvecl_add(w: &mut Polyvecl, u: &Polyvecl, v: &Polyvecl) {
-  let w_vec = vec_from_polyvecl_mut(w);
-  let u_vec = vec_from_polyvecl(u);
-  let v_vec = vec_from_polyvecl(v);
-  for i in 0..u_vec.len() {
-    poly_add(&mut w_vec[i], &u_vec[i],&v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyvecl_add_assign<V: PolyVec>(w: &mut V, v: &V) {
+  for (wi, vi) in w.vec_mut().iter_mut().zip(v.vec()) {
+    poly_add_assign(wi, vi);
   }
 }
 
-pub fn polyvecl_ntt<P: DilithiumParams>(v: &mut Polyvecl) {
-    let v_vec = vec_from_polyvecl_mut(v);
-    for i in 0..v_vec.len() {
-    poly_ntt::<P>(&mut v_vec[i]);
+pub fn polyvecl_ntt<P: DilithiumParams>(v: &mut P::Polyvecl) {
+  for p in v.vec_mut() {
+    poly_ntt::<P>(p);
   }
 }
 
-pub fn polyvecl_invntt_tomont<P: DilithiumParams>(v: &mut Polyvecl) {
-  let v_vec = vec_from_polyvecl_mut(v);
-  for i in 0..v_vec.len() {
-    poly_invntt_tomont::<P>(&mut v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyvecl_invntt_tomont<P: DilithiumParams>(v: &mut P::Polyvecl) {
+  for p in v.vec_mut() {
+    poly_invntt_tomont::<P>(p);
   }
 }
 
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
 pub fn polyvecl_pointwise_poly_montgomery<P: DilithiumParams>(
-  r: &mut Polyvecl,
+  r: &mut P::Polyvecl,
   a: &Poly,
-  v: &Polyvecl,
+  v: &P::Polyvecl,
 ) {
-  let r_vec = vec_from_polyvecl_mut(r);
-  let v_vec = vec_from_polyvecl(v);
-  for i in 0..r_vec.len() {
-    poly_pointwise_montgomery::<P>(&mut r_vec[i], a, &v_vec[i]);
+  for (ri, vi) in r.vec_mut().iter_mut().zip(v.vec()) {
+    poly_pointwise_montgomery::<P>(ri, a, vi);
   }
 }
 
-pub fn polyvecl_pointwise_acc_montgomery<P: DilithiumParams>(
-  w: &mut Poly,
-  u: &Polyvecl,
-  v: &Polyvecl,
-) {
-  let u_vec = vec_from_polyvecl(u);
-  let v_vec = vec_from_polyvecl(v);
-  let mut t = Poly::default();
-  poly_pointwise_montgomery::<P>(w, &u_vec[0], &v_vec[0]);
-  for i in 1..u_vec.len() {
-    poly_pointwise_montgomery::<P>(&mut t, &u_vec[i], &v_vec[i]);
-    let tmp = w.clone();
-    poly_add(w, &tmp,&t);
+pub fn polyvecl_chknorm<P: DilithiumParams>(v: &P::Polyvecl, bound: i32) -> u8 {
+  let mut over = 0;
+  for p in v.vec() {
+    over |= poly_chknorm::<P>(p, bound);
   }
+  over
 }
 
-pub fn polyvecl_chknorm<P: DilithiumParams>(v: &Polyvecl, bound: i32) -> u8 {
-  let v_vec = vec_from_polyvecl(v);
-  for i in 0..v_vec.len() {
-    if poly_chknorm::<P>(&v_vec[i], bound) > 0 {
-      return 1;
-    }
+#[cfg(not(feature = "low_memory"))]
+pub fn polyveck_uniform_eta<P: DilithiumParams>(v: &mut P::Polyveck, seed: &[u8], mut nonce: u16) {
+  for p in v.vec_mut() {
+    poly_uniform_eta::<P>(p, seed, nonce);
+    nonce += 1
   }
-  return 0;
 }
 
-pub fn polyveck_uniform_eta<P: DilithiumParams>(v: &mut Polyveck, seed: &[u8], mut nonce: u16) {
-  let v_vec = vec_from_polyveck_mut(v);
-  for i in 0..v_vec.len() {
-    poly_uniform_eta::<P>(&mut v_vec[i], seed, nonce);
-    nonce += 1
+pub fn polyveck_reduce<P: DilithiumParams>(v: &mut P::Polyveck) {
+  for p in v.vec_mut() {
+    poly_reduce::<P>(p);
   }
 }
 
-pub fn polyveck_reduce<P: DilithiumParams>(v: &mut Polyveck) {
-  let v_vec = vec_from_polyveck_mut(v);
-  for i in 0..v_vec.len() {
-    poly_reduce::<P>(&mut v_vec[i]);
+pub fn polyveck_caddq<P: DilithiumParams>(v: &mut P::Polyveck) {
+  for p in v.vec_mut() {
+    poly_caddq::<P>(p);
   }
 }
 
-pub fn polyveck_caddq<P: DilithiumParams>(v: &mut Polyveck) {
-  let v_vec = vec_from_polyveck_mut(v);
-  
-  for i in 0..P::K {
-    poly_caddq::<P>(&mut v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyveck_add_assign<V: PolyVec>(w: &mut V, v: &V) {
+  for (wi, vi) in w.vec_mut().iter_mut().zip(v.vec()) {
+    poly_add_assign(wi, vi);
   }
 }
 
-pub fn polyveck_add(w: &mut Polyveck, u: &Polyveck,v: &Polyveck) {
-  let w_vec = vec_from_polyveck_mut(w);
-  let v_vec = vec_from_polyveck(v);
-  let u_vec = vec_from_polyveck(u);
-  for i in 0..v_vec.len() {
-    poly_add(&mut w_vec[i], &u_vec[i], &v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyveck_sub_assign<V: PolyVec>(w: &mut V, v: &V) {
+  for (wi, vi) in w.vec_mut().iter_mut().zip(v.vec()) {
+    poly_sub_assign(wi, vi);
   }
 }
 
-pub fn polyveck_sub(w: &mut Polyveck, u: &Polyveck, v: &Polyveck) {
-  let w_vec = vec_from_polyveck_mut(w);
-  let v_vec = vec_from_polyveck(v);
-  let u_vec = vec_from_polyveck(u);
-
-  for i in 0..w_vec.len() {
-    poly_sub(&mut w_vec[i], &u_vec[i], &v_vec[i]);
+#[cfg(not(feature = "low_memory"))]
+pub fn polyveck_shiftl<V: PolyVec>(v: &mut V) {
+  for p in v.vec_mut() {
+    poly_shiftl(p);
   }
 }
 
-pub fn polyveck_shiftl(v: &mut Polyveck) {
-  let v_vec = vec_from_polyveck_mut(v);
-  for i in 0..v_vec.len() {
-    poly_shiftl(&mut v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyveck_ntt<P: DilithiumParams>(v: &mut P::Polyveck) {
+  for p in v.vec_mut() {
+    poly_ntt::<P>(p);
   }
 }
 
-pub fn polyveck_ntt<P: DilithiumParams>(v: &mut Polyveck) {
-  let v_vec = vec_from_polyveck_mut(v);
-  for i in 0..v_vec.len() {
-    poly_ntt::<P>(&mut v_vec[i]);
+pub fn polyveck_invntt_tomont<P: DilithiumParams>(v: &mut P::Polyveck) {
+  for p in v.vec_mut() {
+    poly_invntt_tomont::<P>(p);
   }
 }
 
-pub fn polyveck_invntt_tomont<P: DilithiumParams>(v: &mut Polyveck) {
-  let v_vec = vec_from_polyveck_mut(v);
-  for i in 0..v_vec.len() {
-    poly_invntt_tomont::<P>(&mut v_vec[i]);
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyveck_pointwise_poly_montgomery<P: DilithiumParams>(
+  r: &mut P::Polyveck,
+  a: &Poly,
+  v: &P::Polyveck,
+) {
+  for (ri, vi) in r.vec_mut().iter_mut().zip(v.vec()) {
+    poly_pointwise_montgomery::<P>(ri, a, vi);
   }
 }
 
-pub fn polyveck_pointwise_poly_montgomery<P: DilithiumParams>(
-  r: &mut Polyveck,
+#[cfg(not(feature = "low_memory"))]
+pub fn polyveck_pointwise_poly_montgomery_assign<P: DilithiumParams>(
+  v: &mut P::Polyveck,
   a: &Poly,
-  v: &Polyveck,
 ) {
-  let r_vec = vec_from_polyveck_mut(r);
-  let v_vec = vec_from_polyveck(v);
-  for i in 0..r_vec.len() {
-    poly_pointwise_montgomery::<P>(&mut r_vec[i], a, &v_vec[i]);
+  for vi in v.vec_mut() {
+    poly_pointwise_montgomery_assign::<P>(vi, a);
   }
 }
 
-pub fn polyveck_chknorm<P: DilithiumParams>(v: &Polyveck, bound: i32) -> u8 {
-  let v_vec = vec_from_polyveck(v);
-  for i in 0..v_vec.len() {
-    if poly_chknorm::<P>(&v_vec[i], bound) > 0 {
-      return 1;
-    }
+#[cfg(any(not(feature = "low_memory"), feature = "masked"))]
+pub fn polyveck_chknorm<P: DilithiumParams>(v: &P::Polyveck, bound: i32) -> u8 {
+  let mut over = 0;
+  for p in v.vec() {
+    over |= poly_chknorm::<P>(p, bound);
   }
-  return 0;
+  over
 }
 
-pub fn polyveck_power2round<P: DilithiumParams>(v1: &mut Polyveck, v0: &mut Polyveck, v: &Polyveck) {
-  let v1_vec = vec_from_polyveck_mut(v1);
-  let v0_vec = vec_from_polyveck_mut(v0);
-  let v_vec = vec_from_polyveck(v);
-  for i in 0.. v1_vec.len() {
-    poly_power2round::<P>(&mut v1_vec[i], &mut v0_vec[i], &v_vec[i]);
+#[cfg(not(feature = "low_memory"))]
+pub fn polyveck_power2round<P: DilithiumParams>(v1: &mut P::Polyveck, v0: &mut P::Polyveck) {
+  for (p1, p0) in v1.vec_mut().iter_mut().zip(v0.vec_mut()) {
+    poly_power2round::<P>(p1, p0);
   }
 }
 
-pub fn polyveck_decompose<P: DilithiumParams>(v1: &mut Polyveck, v0: &mut Polyveck, v: &Polyveck) {
-  let v1_vec = vec_from_polyveck_mut(v1);
-  let v0_vec = vec_from_polyveck_mut(v0);
-  let v_vec = vec_from_polyveck(v);
-  for i in 0..v1_vec.len() {
-    poly_decompose::<P>(&mut v1_vec[i], &mut v0_vec[i], &v_vec[i]);
+pub fn polyveck_decompose_in_place<P: DilithiumParams>(v1: &mut P::Polyveck, v0: &mut P::Polyveck) {
+  for (p1, p0) in v1.vec_mut().iter_mut().zip(v0.vec_mut()) {
+    poly_decompose_in_place::<P>(p1, p0);
   }
 }
 
+#[cfg(not(feature = "low_memory"))]
 pub fn polyveck_make_hint<P: DilithiumParams>(
-  h: &mut Polyveck,
-  v0: &Polyveck,
-  v1: &Polyveck,
+  h: &mut P::Polyveck,
+  v0: &P::Polyveck,
+  v1: &P::Polyveck,
 ) -> i32 {
-  let v0_vec = vec_from_polyveck(v0);
-  let v1_vec = vec_from_polyveck(v1);
-  let h_vec = vec_from_polyveck_mut(h);
+  let h_vec = h.vec_mut();
+  let v0_vec = v0.vec();
+  let v1_vec = v1.vec();
   let mut s = 0i32;
-  for i in 0.. h_vec.len() {
+  for i in 0..P::K {
     s += poly_make_hint::<P>(&mut h_vec[i], &v0_vec[i], &v1_vec[i]);
   }
   s
 }
 
-pub fn polyveck_use_hint<P: DilithiumParams>(w: &mut Polyveck, u: &Polyveck, h: &Polyveck) {
-  let w_vec = vec_from_polyveck_mut(w);
-  let u_vec = vec_from_polyveck(u);
-  let h_vec = vec_from_polyveck(h);
-  for i in 0..w_vec.len() {
-    poly_use_hint::<P>(&mut w_vec[i], &u_vec[i], &h_vec[i]);
+#[cfg(not(feature = "low_memory"))]
+pub fn polyveck_use_hint_in_place<P: DilithiumParams>(w: &mut P::Polyveck, h: &P::Polyveck) {
+  for (wi, hi) in w.vec_mut().iter_mut().zip(h.vec()) {
+    poly_use_hint_in_place::<P>(wi, hi);
   }
 }
 
-pub fn polyveck_pack_w1<P: DilithiumParams>(r: &mut [u8], w1: &Polyveck) {
-  let w1_vec = vec_from_polyveck(w1);
-  for i in 0..P::K {
-    polyw1_pack::<P>(&mut r[i * P::polyw1_packedbytes()..], &w1_vec[i]);
+pub fn polyveck_pack_w1<P: DilithiumParams>(r: &mut [u8], w1: &P::Polyveck) {
+  for (i, p) in w1.vec().iter().enumerate() {
+    polyw1_pack::<P>(&mut r[i * P::POLYW1_PACKEDBYTES..], p);
   }
 }
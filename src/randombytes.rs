@@ -1,4 +1,4 @@
-use rand::{
+use rand_core::{
     RngCore
 };
 
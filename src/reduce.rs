@@ -1,15 +1,12 @@
-#![allow(dead_code)]
-
 use crate::params::DilithiumParams;
 
-const MONT: i32 = -4186625;
 const QINV: i32 =  58728449;
 
 pub fn montgomery_reduce<P: DilithiumParams>(a: i64) -> i32 {
     let t: i32 = (a as i32).wrapping_mul(QINV);
     
-    let result = ((a - (t as i64) * (P::Q as i64)) >> 32) as i32;
-    result
+    
+    ((a - (t as i64) * (P::Q as i64)) >> 32) as i32
 }
 
 pub fn reduce32<P: DilithiumParams>(a: i32) -> i32 {
@@ -20,9 +17,3 @@ pub fn reduce32<P: DilithiumParams>(a: i32) -> i32 {
 pub fn caddq<P: DilithiumParams>(a: i32) -> i32 {
     a + ((a >> 31) & P::Q as i32)
 }
-
-
-pub fn freeze<P: DilithiumParams>(a: i32) -> i32 {
-    let a = reduce32::<P>(a);
-    caddq::<P>(a)
-}
\ No newline at end of file
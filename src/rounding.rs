@@ -16,7 +16,7 @@ pub fn decompose<P: DilithiumParams>(a0: &mut i32, a: i32) -> i32 {
     if P::GAMMA2 == (P::Q - 1) / 32 {
         a1 = (a1 * 1025 + (1 << 21)) >> 22;
         a1 &= 15;
-    } else if P::GAMMA2 == (P::Q - 1) / 88 {
+    } else {
         a1 = (a1 * 11275 + (1 << 23)) >> 24;
         a1 ^= ((43 - a1) >> 31) & a1;
     }
@@ -27,41 +27,39 @@ pub fn decompose<P: DilithiumParams>(a0: &mut i32, a: i32) -> i32 {
     a1
 }
 
+/// Returns 1 if `a0` lies outside `[-gamma2, gamma2]`, or equals `-gamma2`
+/// with `a1 != 0`. `a0` is derived from the secret `s2`/`t0` products, so the
+/// comparisons are folded into sign masks instead of branches.
 pub fn make_hint<P: DilithiumParams>(a0: i32, a1: i32) -> u32 {
     let gamma2 = P::GAMMA2 as i32;
 
-    if a0 > gamma2 || a0 < -gamma2 || (a0 == -gamma2 && a1 != 0) {
-        1
-    } else {
-        0
-    }
+    let above = (gamma2 - a0) >> 31;
+    let below = (a0 + gamma2) >> 31;
+    let at_low = !(((a0 + gamma2) | -(a0 + gamma2)) >> 31);
+    let a1_nonzero = (a1 | -a1) >> 31;
+
+    ((above | below | (at_low & a1_nonzero)) & 1) as u32
 }
 
+/// Branch-free counterpart of the reference `use_hint`: the correction is
+/// `+1` when `a0 > 0`, `-1` otherwise, applied only when `hint` is set and
+/// wrapped into `[0, (q - 1) / (2 * gamma2))`.
 pub fn use_hint<P: DilithiumParams>(a: i32, hint: u32) -> i32 {
     let q = P::Q as i32;
     let gamma2 = P::GAMMA2 as i32;
     let mut a0: i32 = 0;
-    let a1: i32;
 
-    a1 = decompose::<P>(&mut a0, a);
+    let a1: i32 = decompose::<P>(&mut a0, a);
 
-    if hint == 0 {
-        return a1;
-    }
+    let positive = (-a0) >> 31;
+    let delta = ((positive & 1) | !positive) & -((hint & 1) as i32);
 
     if gamma2 == (q - 1) / 32 {
-        if a0 > 0 {
-            (a1 + 1) & 15
-        } else {
-            (a1 - 1) & 15
-        }
-    } else if gamma2 == (q - 1) / 88 {
-        if a0 > 0 {
-            if a1 == 43 { 0 } else { a1 + 1 }
-        } else {
-            if a1 == 0 { 43 } else { a1 - 1 }
-        }
+        (a1 + delta) & 15
     } else {
-        panic!("Unsupported GAMMA2 value");
+        let mut r = a1 + delta;
+        r += (r >> 31) & 44;
+        r -= ((43 - r) >> 31) & 44;
+        r
     }
 }
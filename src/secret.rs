@@ -0,0 +1,187 @@
+use core::{
+  fmt,
+  ops::{Deref, DerefMut},
+};
+
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Fixed-size secret byte buffer.
+///
+/// Wiped on drop when the `zeroize` feature is enabled, never printed by
+/// `Debug`, and deliberately not `Clone`: copying key material has to go
+/// through an explicit `copy_from_slice`. Used for secret keys and for every
+/// seed or key-derived buffer inside the signer.
+pub struct SecretBytes<const N: usize>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+  pub const LEN: usize = N;
+
+  /// Copies `bytes` into a new buffer; fails if the length is not `N`.
+  pub fn from_slice(bytes: &[u8]) -> Result<Self, &'static str> {
+    if bytes.len() != N {
+      return Err("Invalid secret length");
+    }
+    let mut secret = Self::default();
+    secret.0.copy_from_slice(bytes);
+    Ok(secret)
+  }
+}
+
+impl<const N: usize> Default for SecretBytes<N> {
+  #[inline(always)]
+  fn default() -> Self {
+    SecretBytes([0u8; N])
+  }
+}
+
+impl<const N: usize> Deref for SecretBytes<N> {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+impl<const N: usize> DerefMut for SecretBytes<N> {
+  fn deref_mut(&mut self) -> &mut [u8] {
+    &mut self.0
+  }
+}
+
+impl<const N: usize> AsRef<[u8]> for SecretBytes<N> {
+  fn as_ref(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "SecretBytes<{N}>(<redacted>)")
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> Zeroize for SecretBytes<N> {
+  fn zeroize(&mut self) {
+    self.0.zeroize();
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> Drop for SecretBytes<N> {
+  fn drop(&mut self) {
+    self.0.zeroize();
+  }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> ZeroizeOnDrop for SecretBytes<N> {}
+
+/// Wipes and frees a heap copy of secret bytes, such as a decoded seed or an
+/// encoded private key, when the `zeroize` feature is enabled.
+#[cfg(any(feature = "x509", feature = "jose", feature = "cose", feature = "ssh"))]
+pub(crate) fn wipe(buf: alloc::vec::Vec<u8>) {
+  #[cfg(feature = "zeroize")]
+  {
+    let mut buf = buf;
+    buf.zeroize();
+  }
+  #[cfg(not(feature = "zeroize"))]
+  drop(buf);
+}
+
+/// An ML-DSA public key and, for private keys, the 32-byte seed it was
+/// derived from: the key that JWKs, COSE_Keys and OpenSSH private keys
+/// carry, whatever their encoding. The seed is expanded the round-3 way, so
+/// FIPS 204 implementations derive another key from it; see
+/// [FIPS 204 interoperability](crate#fips-204-interoperability).
+///
+/// Like [`SecretBytes`], which holds the seed, it is not `Clone`, so the
+/// seed is never copied implicitly; `SeedKey::from_seed` with `seed()`
+/// makes a deliberate copy.
+#[cfg(any(feature = "jose", feature = "cose", feature = "ssh"))]
+#[derive(Debug)]
+pub struct SeedKey {
+  algorithm: crate::Algorithm,
+  public_key: alloc::vec::Vec<u8>,
+  seed: Option<SecretBytes<32>>,
+}
+
+#[cfg(any(feature = "jose", feature = "cose", feature = "ssh"))]
+impl SeedKey {
+  /// A private key derived from a 32-byte seed.
+  pub fn from_seed(algorithm: crate::Algorithm, seed: &[u8]) -> Result<Self, &'static str> {
+    let keypair = algorithm.generate_from_seed(seed)?;
+    Ok(SeedKey { algorithm, public_key: keypair.public().to_vec(), seed: Some(SecretBytes::from_slice(seed)?) })
+  }
+
+  pub fn from_public_key(algorithm: crate::Algorithm, public_key: &[u8]) -> Result<Self, &'static str> {
+    if public_key.len() != algorithm.public_key_bytes() {
+      return Err("Invalid public key length");
+    }
+    Ok(SeedKey { algorithm, public_key: public_key.to_vec(), seed: None })
+  }
+
+  /// A private key from a seed drawn from the OS RNG.
+  #[cfg(feature = "getrandom")]
+  pub fn generate(algorithm: crate::Algorithm) -> Self {
+    Self::generate_with_rng(algorithm, &mut rand_core::OsRng)
+  }
+
+  pub fn generate_with_rng<R: rand_core::RngCore>(algorithm: crate::Algorithm, rng: &mut R) -> Self {
+    let mut seed = SecretBytes::<32>::default();
+    rng.fill_bytes(&mut seed);
+    Self::from_seed(algorithm, &seed).unwrap()
+  }
+
+  /// The key a parsed public key and optional seed describe; a seed has to
+  /// derive that public key.
+  pub(crate) fn read(
+    algorithm: crate::Algorithm,
+    public_key: &[u8],
+    seed: Option<&[u8]>,
+  ) -> Result<Self, &'static str> {
+    let Some(seed) = seed else {
+      return Self::from_public_key(algorithm, public_key);
+    };
+    let key = Self::from_seed(algorithm, seed)?;
+    if key.public_key != public_key {
+      return Err("Private key does not match the public key");
+    }
+    Ok(key)
+  }
+
+  pub fn algorithm(&self) -> crate::Algorithm {
+    self.algorithm
+  }
+
+  pub fn public_key(&self) -> &[u8] {
+    &self.public_key
+  }
+
+  /// The seed of a private key, which only `pure_dsa` expands into this
+  /// key pair.
+  pub fn seed(&self) -> Option<&[u8]> {
+    self.seed.as_deref()
+  }
+
+  pub fn is_private(&self) -> bool {
+    self.seed.is_some()
+  }
+
+  /// The same key without its seed.
+  pub fn to_public(&self) -> SeedKey {
+    SeedKey { algorithm: self.algorithm, public_key: self.public_key.clone(), seed: None }
+  }
+
+  /// The keypair of a private key, derived from its seed.
+  pub fn keypair(&self) -> Result<crate::Keypair, &'static str> {
+    self.algorithm.generate_from_seed(self.seed.as_ref().ok_or("Key has no seed")?)
+  }
+
+  /// Verifies a signature over `msg` with the public key.
+  pub fn verify(&self, signature: &[u8], msg: &[u8]) -> Result<(), &'static str> {
+    self.algorithm.verify_raw(signature, msg, &self.public_key)
+  }
+}
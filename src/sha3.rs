@@ -0,0 +1,756 @@
+//! SHA-3 (FIPS 202) and the SP 800-185 functions built on it.
+//!
+//! Everything here runs on the same Keccak-f[1600] the signer uses:
+//!
+//! - SHA3-224/256/384/512 return fixed-size arrays;
+//! - SHAKE128/256 and cSHAKE128/256 hand out an [`XofReader`], which may be
+//!   read in pieces of any size;
+//! - KMAC, TupleHash and ParallelHash take their output length from the
+//!   buffer passed to `finalize`, or return an [`XofReader`] for the XOF
+//!   variants;
+//! - TurboSHAKE128/256 and KangarooTwelve (RFC 9861) run the 12-round
+//!   Keccak-p[1600, 12]. KangarooTwelve hashes 8 KiB leaves independently,
+//!   and with the `parallel` feature `KangarooTwelve::update_parallel` spreads
+//!   them over threads, which suits pre-hashing large inputs.
+//!
+//! All hashers are incremental. With the `digest` feature they also
+//! implement the `digest` 0.10 traits, so they can stand in for the RustCrypto
+//! `sha3` crate. Sponge states are wiped on drop with the `zeroize` feature,
+//! which matters for KMAC keys.
+
+use subtle::{Choice, ConstantTimeEq};
+
+use crate::fips202::*;
+
+pub use crate::fips202::XofReader;
+#[cfg(feature = "digest")]
+pub use digest;
+
+// Domain separation and first padding bit, FIPS 202 and SP 800-185.
+const SHA3_PAD: u8 = 0x06;
+const SHAKE_PAD: u8 = 0x1F;
+const CSHAKE_PAD: u8 = 0x04;
+
+// KangarooTwelve, RFC 9861: leaves of 8192 bytes, 32-byte chaining values,
+// and the domain bytes of a single node, a leaf and the final node.
+const K12_CHUNK: usize = 8192;
+const K12_CV: usize = 32;
+const K12_SINGLE: u8 = 0x07;
+const K12_LEAF: u8 = 0x0B;
+const K12_FINAL: u8 = 0x06;
+
+#[derive(Clone, Debug)]
+struct Sponge {
+  state: KeccakState,
+  rate: usize,
+  pad: u8,
+}
+
+impl Sponge {
+  fn new(rate: usize, pad: u8) -> Self {
+    Sponge { state: KeccakState::default(), rate, pad }
+  }
+
+  fn turbo(rate: usize, pad: u8) -> Self {
+    Sponge { state: KeccakState::with_rounds(TURBOSHAKE_ROUNDS), rate, pad }
+  }
+
+  fn absorb(&mut self, data: &[u8]) {
+    self.state.absorb(self.rate, data);
+  }
+
+  fn finalize(mut self) -> XofReader {
+    self.state.finalize(self.rate, self.pad)
+  }
+
+  fn absorb_left_encoded(&mut self, x: u64) {
+    let mut buf = [0u8; 9];
+    self.absorb(left_encode(x, &mut buf));
+  }
+
+  fn absorb_right_encoded(&mut self, x: u64) {
+    let mut buf = [0u8; 9];
+    self.absorb(right_encode(x, &mut buf));
+  }
+
+  /// `encode_string`: the bit length of `s`, then `s`.
+  fn absorb_string(&mut self, s: &[u8]) {
+    self.absorb_left_encoded(bits(s.len()));
+    self.absorb(s);
+  }
+
+  /// Ends a `bytepad` by zero-filling the current block. Zeros do not change
+  /// the state, so this is a permutation unless the block is already empty.
+  fn pad_block(&mut self) {
+    if self.state.pos != 0 {
+      self.state.permute();
+      self.state.pos = 0;
+    }
+  }
+}
+
+fn bits(len: usize) -> u64 {
+  (len as u64) * 8
+}
+
+fn left_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+  let n = (8 - x.leading_zeros() as usize / 8).max(1);
+  buf[0] = n as u8;
+  buf[1..=n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+  &buf[..=n]
+}
+
+fn right_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+  let n = (8 - x.leading_zeros() as usize / 8).max(1);
+  buf[..n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+  buf[n] = n as u8;
+  &buf[..=n]
+}
+
+/// KangarooTwelve's `length_encode`: like `right_encode`, but zero is
+/// encoded with no bytes at all.
+fn length_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+  let n = 8 - x.leading_zeros() as usize / 8;
+  buf[..n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+  buf[n] = n as u8;
+  &buf[..=n]
+}
+
+/// cSHAKE prefix: `bytepad(encode_string(N) || encode_string(S), rate)`.
+/// With both strings empty cSHAKE is plain SHAKE.
+fn cshake(rate: usize, name: &[u8], customization: &[u8]) -> Sponge {
+  if name.is_empty() && customization.is_empty() {
+    return Sponge::new(rate, SHAKE_PAD);
+  }
+  let mut sponge = Sponge::new(rate, CSHAKE_PAD);
+  sponge.absorb_left_encoded(rate as u64);
+  sponge.absorb_string(name);
+  sponge.absorb_string(customization);
+  sponge.pad_block();
+  sponge
+}
+
+macro_rules! sha3_hash {
+  ($name:ident, $func:ident, $rate:expr, $len:literal, $size:ident, $block:ident, $title:literal) => {
+    #[doc = concat!($title, " hasher.")]
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl $name {
+      pub const OUTPUT_BYTES: usize = $len;
+
+      pub fn new() -> Self {
+        $name { sponge: Sponge::new($rate, SHA3_PAD) }
+      }
+
+      pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+      }
+
+      pub fn finalize(self) -> [u8; $len] {
+        let mut out = [0u8; $len];
+        self.sponge.finalize().read(&mut out);
+        out
+      }
+    }
+
+    impl Default for $name {
+      fn default() -> Self {
+        Self::new()
+      }
+    }
+
+    #[doc = concat!($title, " of `data`.")]
+    pub fn $func(data: &[u8]) -> [u8; $len] {
+      let mut hasher = $name::new();
+      hasher.update(data);
+      hasher.finalize()
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::HashMarker for $name {}
+
+    #[cfg(feature = "digest")]
+    impl digest::OutputSizeUser for $name {
+      type OutputSize = digest::consts::$size;
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::core_api::BlockSizeUser for $name {
+      type BlockSize = digest::consts::$block;
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::FixedOutput for $name {
+      fn finalize_into(self, out: &mut digest::Output<Self>) {
+        out.copy_from_slice(&$name::finalize(self));
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Reset for $name {
+      fn reset(&mut self) {
+        *self = Self::new();
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::FixedOutputReset for $name {
+      fn finalize_into_reset(&mut self, out: &mut digest::Output<Self>) {
+        out.copy_from_slice(&core::mem::take(self).finalize());
+      }
+    }
+  };
+}
+
+sha3_hash!(Sha3_224, sha3_224, SHA3_224_RATE, 28, U28, U144, "SHA3-224");
+sha3_hash!(Sha3_256, sha3_256, SHA3_256_RATE, 32, U32, U136, "SHA3-256");
+sha3_hash!(Sha3_384, sha3_384, SHA3_384_RATE, 48, U48, U104, "SHA3-384");
+sha3_hash!(Sha3_512, sha3_512, SHA3_512_RATE, 64, U64, U72, "SHA3-512");
+
+macro_rules! shake {
+  ($name:ident, $func:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, " XOF.")]
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl $name {
+      pub fn new() -> Self {
+        $name { sponge: Sponge::new($rate, SHAKE_PAD) }
+      }
+
+      pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+      }
+
+      pub fn finalize_xof(self) -> XofReader {
+        self.sponge.finalize()
+      }
+    }
+
+    impl Default for $name {
+      fn default() -> Self {
+        Self::new()
+      }
+    }
+
+    #[doc = concat!("Fills `out` with ", $title, " of `data`.")]
+    pub fn $func(data: &[u8], out: &mut [u8]) {
+      let mut xof = $name::new();
+      xof.update(data);
+      xof.finalize_xof().read(out);
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutput for $name {
+      type Reader = XofReader;
+
+      fn finalize_xof(self) -> XofReader {
+        $name::finalize_xof(self)
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Reset for $name {
+      fn reset(&mut self) {
+        *self = Self::new();
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutputReset for $name {
+      fn finalize_xof_reset(&mut self) -> XofReader {
+        core::mem::take(self).finalize_xof()
+      }
+    }
+  };
+}
+
+shake!(Shake128, shake128, SHAKE128_RATE, "SHAKE128");
+shake!(Shake256, shake256, SHAKE256_RATE, "SHAKE256");
+
+macro_rules! cshake {
+  ($name:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, " with a function name `N` and customization string `S`.")]
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl $name {
+      pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+        $name { sponge: cshake($rate, function_name, customization) }
+      }
+
+      pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+      }
+
+      pub fn finalize_xof(self) -> XofReader {
+        self.sponge.finalize()
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutput for $name {
+      type Reader = XofReader;
+
+      fn finalize_xof(self) -> XofReader {
+        $name::finalize_xof(self)
+      }
+    }
+  };
+}
+
+cshake!(CShake128, SHAKE128_RATE, "cSHAKE128");
+cshake!(CShake256, SHAKE256_RATE, "cSHAKE256");
+
+/// Shortest tag `Kmac128::verify` and `Kmac256::verify` accept.
+pub const MIN_TAG_BYTES: usize = 4;
+
+macro_rules! kmac {
+  ($name:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, ". The output length is part of the input, so a ")]
+    /// 32-byte tag is not a prefix of a 64-byte one; `finalize_xof` gives the
+    /// KMACXOF variant, whose output does not depend on how much is read.
+    ///
+    /// The state is keyed, so there is no `Clone` and `Debug` is redacted;
+    /// `duplicate` copies it explicitly, e.g. to MAC several messages after
+    /// absorbing the key once.
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl core::fmt::Debug for $name {
+      fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(concat!(stringify!($name), "(<redacted>)"))
+      }
+    }
+
+    impl $name {
+      pub fn new(key: &[u8], customization: &[u8]) -> Self {
+        let mut sponge = cshake($rate, b"KMAC", customization);
+        sponge.absorb_left_encoded($rate as u64);
+        sponge.absorb_string(key);
+        sponge.pad_block();
+        $name { sponge }
+      }
+
+      /// A second instance with the same key and absorbed input.
+      pub fn duplicate(&self) -> Self {
+        $name { sponge: self.sponge.clone() }
+      }
+
+      pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+      }
+
+      /// Writes a tag of `out.len()` bytes.
+      pub fn finalize(mut self, out: &mut [u8]) {
+        self.sponge.absorb_right_encoded(bits(out.len()));
+        self.sponge.finalize().read(out);
+      }
+
+      pub fn finalize_xof(mut self) -> XofReader {
+        self.sponge.absorb_right_encoded(0);
+        self.sponge.finalize()
+      }
+
+      /// Compares `tag` with the tag of the same length, in constant time.
+      /// Tags shorter than `MIN_TAG_BYTES` are refused: SP 800-185 requires
+      /// at least 32 bits, and an empty tag would verify for any message.
+      pub fn verify(mut self, tag: &[u8]) -> Result<(), &'static str> {
+        if tag.len() < MIN_TAG_BYTES {
+          return Err("MAC tag too short");
+        }
+        self.sponge.absorb_right_encoded(bits(tag.len()));
+        let mut reader = self.sponge.finalize();
+        let mut buf = [0u8; 64];
+        let mut ok = Choice::from(1);
+        for chunk in tag.chunks(buf.len()) {
+          reader.read(&mut buf[..chunk.len()]);
+          ok &= buf[..chunk.len()].ct_eq(chunk);
+        }
+        if bool::from(ok) { Ok(()) } else { Err("Invalid MAC") }
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutput for $name {
+      type Reader = XofReader;
+
+      fn finalize_xof(self) -> XofReader {
+        $name::finalize_xof(self)
+      }
+    }
+  };
+}
+
+kmac!(Kmac128, SHAKE128_RATE, "KMAC128");
+kmac!(Kmac256, SHAKE256_RATE, "KMAC256");
+
+macro_rules! tuple_hash {
+  ($name:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, ": hashes a sequence of byte strings so that their ")]
+    /// boundaries matter, e.g. `("ab", "c")` and `("a", "bc")` differ.
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl $name {
+      pub fn new(customization: &[u8]) -> Self {
+        $name { sponge: cshake($rate, b"TupleHash", customization) }
+      }
+
+      /// Appends one whole element to the tuple.
+      pub fn push(&mut self, element: &[u8]) {
+        self.sponge.absorb_string(element);
+      }
+
+      /// Writes `out.len()` bytes of output.
+      pub fn finalize(mut self, out: &mut [u8]) {
+        self.sponge.absorb_right_encoded(bits(out.len()));
+        self.sponge.finalize().read(out);
+      }
+
+      pub fn finalize_xof(mut self) -> XofReader {
+        self.sponge.absorb_right_encoded(0);
+        self.sponge.finalize()
+      }
+    }
+  };
+}
+
+tuple_hash!(TupleHash128, SHAKE128_RATE, "TupleHash128");
+tuple_hash!(TupleHash256, SHAKE256_RATE, "TupleHash256");
+
+macro_rules! parallel_hash {
+  ($name:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, ": the input is cut into blocks of `block_size` bytes ")]
+    /// that are hashed independently, then the block digests are hashed.
+    /// Blocks are processed in order as they fill up.
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+      leaf: Sponge,
+      block_size: usize,
+      filled: usize,
+      blocks: u64,
+    }
+
+    impl $name {
+      // Each block is hashed to twice the security level, i.e. the capacity.
+      const LEAF_BYTES: usize = 200 - $rate;
+
+      /// # Panics
+      ///
+      /// If `block_size` is zero.
+      pub fn new(block_size: usize, customization: &[u8]) -> Self {
+        assert!(block_size > 0, "ParallelHash block size must be nonzero");
+        let mut sponge = cshake($rate, b"ParallelHash", customization);
+        sponge.absorb_left_encoded(block_size as u64);
+        $name {
+          sponge,
+          leaf: Sponge::new($rate, SHAKE_PAD),
+          block_size,
+          filled: 0,
+          blocks: 0,
+        }
+      }
+
+      pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+          let take = (self.block_size - self.filled).min(data.len());
+          self.leaf.absorb(&data[..take]);
+          self.filled += take;
+          data = &data[take..];
+          if self.filled == self.block_size {
+            self.finish_block();
+          }
+        }
+      }
+
+      /// Writes `out.len()` bytes of output.
+      pub fn finalize(mut self, out: &mut [u8]) {
+        self.finish(bits(out.len()));
+        self.sponge.finalize().read(out);
+      }
+
+      pub fn finalize_xof(mut self) -> XofReader {
+        self.finish(0);
+        self.sponge.finalize()
+      }
+
+      fn finish_block(&mut self) {
+        let leaf = core::mem::replace(&mut self.leaf, Sponge::new($rate, SHAKE_PAD));
+        let mut digest = [0u8; Self::LEAF_BYTES];
+        leaf.finalize().read(&mut digest);
+        self.sponge.absorb(&digest);
+        self.filled = 0;
+        self.blocks += 1;
+      }
+
+      fn finish(&mut self, out_bits: u64) {
+        if self.filled > 0 {
+          self.finish_block();
+        }
+        self.sponge.absorb_right_encoded(self.blocks);
+        self.sponge.absorb_right_encoded(out_bits);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutput for $name {
+      type Reader = XofReader;
+
+      fn finalize_xof(self) -> XofReader {
+        $name::finalize_xof(self)
+      }
+    }
+  };
+}
+
+parallel_hash!(ParallelHash128, SHAKE128_RATE, "ParallelHash128");
+parallel_hash!(ParallelHash256, SHAKE256_RATE, "ParallelHash256");
+
+macro_rules! turbo_shake {
+  ($name:ident, $func:ident, $rate:expr, $title:literal) => {
+    #[doc = concat!($title, ": SHAKE with the 12-round Keccak-p permutation and a ")]
+    /// caller-chosen domain separation byte, as specified in RFC 9861.
+    #[derive(Clone, Debug)]
+    pub struct $name {
+      sponge: Sponge,
+    }
+
+    impl $name {
+      /// # Panics
+      ///
+      /// If `domain` is not in `0x01..=0x7F`.
+      pub fn new(domain: u8) -> Self {
+        assert!((0x01..=0x7F).contains(&domain), "TurboSHAKE domain byte must be in 0x01..=0x7F");
+        $name { sponge: Sponge::turbo($rate, domain) }
+      }
+
+      pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb(data);
+      }
+
+      pub fn finalize_xof(self) -> XofReader {
+        self.sponge.finalize()
+      }
+    }
+
+    #[doc = concat!("Fills `out` with ", $title, " of `data` under `domain`.")]
+    pub fn $func(data: &[u8], domain: u8, out: &mut [u8]) {
+      let mut xof = $name::new(domain);
+      xof.update(data);
+      xof.finalize_xof().read(out);
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::Update for $name {
+      fn update(&mut self, data: &[u8]) {
+        $name::update(self, data);
+      }
+    }
+
+    #[cfg(feature = "digest")]
+    impl digest::ExtendableOutput for $name {
+      type Reader = XofReader;
+
+      fn finalize_xof(self) -> XofReader {
+        $name::finalize_xof(self)
+      }
+    }
+  };
+}
+
+turbo_shake!(TurboShake128, turbo_shake128, SHAKE128_RATE, "TurboSHAKE128");
+turbo_shake!(TurboShake256, turbo_shake256, SHAKE256_RATE, "TurboSHAKE256");
+
+/// KangarooTwelve (KT128, RFC 9861), a tree hash over TurboSHAKE128.
+///
+/// The input `S = M || C || length_encode(|C|)` is cut into 8192-byte
+/// chunks. Up to one chunk, the output is TurboSHAKE128 of `S`. Beyond that
+/// every chunk after the first is a leaf, hashed independently to a 32-byte
+/// chaining value, and the first chunk and the chaining values make up the
+/// final node. Leaves are hashed as they fill up; `update_parallel` spreads
+/// them over several threads.
+#[derive(Clone, Debug)]
+pub struct KangarooTwelve<'a> {
+  customization: &'a [u8],
+  node: Sponge,
+  leaf: Sponge,
+  // Index of the chunk being filled and how much of it is there. A full
+  // chunk is only closed once more input arrives, since whether `S` ends
+  // there decides between the single-node and the tree encoding.
+  chunk: u64,
+  filled: usize,
+}
+
+impl<'a> KangarooTwelve<'a> {
+  pub fn new(customization: &'a [u8]) -> Self {
+    KangarooTwelve {
+      customization,
+      node: Sponge::turbo(SHAKE128_RATE, K12_SINGLE),
+      leaf: Sponge::turbo(SHAKE128_RATE, K12_LEAF),
+      chunk: 0,
+      filled: 0,
+    }
+  }
+
+  pub fn update(&mut self, mut data: &[u8]) {
+    while !data.is_empty() {
+      if self.filled == K12_CHUNK {
+        self.next_chunk();
+      }
+      let take = (K12_CHUNK - self.filled).min(data.len());
+      if self.chunk == 0 {
+        self.node.absorb(&data[..take]);
+      } else {
+        self.leaf.absorb(&data[..take]);
+      }
+      self.filled += take;
+      data = &data[take..];
+    }
+  }
+
+  /// Like `update`, but whole leaves in `data` are hashed on `threads`
+  /// threads. The output does not depend on `threads`.
+  #[cfg(feature = "parallel")]
+  pub fn update_parallel(&mut self, mut data: &[u8], threads: usize) {
+    let head = (K12_CHUNK - self.filled).min(data.len());
+    self.update(&data[..head]);
+    data = &data[head..];
+    if data.len() <= K12_CHUNK {
+      return self.update(data);
+    }
+
+    // At least one byte is kept back, so every chunk hashed here is
+    // followed by more input and is certainly a leaf.
+    self.next_chunk();
+    let leaves = (data.len() - 1) / K12_CHUNK;
+    let threads = threads.max(1);
+    let mut cvs = std::vec![[0u8; K12_CV]; leaves.min(64 * threads)];
+    for batch in data[..leaves * K12_CHUNK].chunks(cvs.len() * K12_CHUNK) {
+      let cvs = &mut cvs[..batch.len() / K12_CHUNK];
+      let per_thread = cvs.len().div_ceil(threads);
+      std::thread::scope(|scope| {
+        for (src, dst) in batch.chunks(per_thread * K12_CHUNK).zip(cvs.chunks_mut(per_thread)) {
+          scope.spawn(move || {
+            for (leaf, cv) in src.chunks(K12_CHUNK).zip(dst) {
+              *cv = leaf_cv(leaf);
+            }
+          });
+        }
+      });
+      for cv in cvs.iter() {
+        self.node.absorb(cv);
+      }
+    }
+    self.chunk += leaves as u64;
+    self.update(&data[leaves * K12_CHUNK..]);
+  }
+
+  pub fn finalize_xof(mut self) -> XofReader {
+    let mut buf = [0u8; 9];
+    self.update(self.customization);
+    self.update(length_encode(self.customization.len() as u64, &mut buf));
+    if self.chunk > 0 {
+      self.finish_leaf();
+      self.node.absorb(length_encode(self.chunk, &mut buf));
+      self.node.absorb(&[0xFF, 0xFF]);
+      self.node.pad = K12_FINAL;
+    }
+    self.node.finalize()
+  }
+
+  fn next_chunk(&mut self) {
+    if self.chunk == 0 {
+      self.node.absorb(&[0x03, 0, 0, 0, 0, 0, 0, 0]);
+    } else {
+      self.finish_leaf();
+    }
+    self.chunk += 1;
+    self.filled = 0;
+  }
+
+  fn finish_leaf(&mut self) {
+    let leaf = core::mem::replace(&mut self.leaf, Sponge::turbo(SHAKE128_RATE, K12_LEAF));
+    let mut cv = [0u8; K12_CV];
+    leaf.finalize().read(&mut cv);
+    self.node.absorb(&cv);
+  }
+}
+
+#[cfg(feature = "parallel")]
+fn leaf_cv(leaf: &[u8]) -> [u8; K12_CV] {
+  let mut sponge = Sponge::turbo(SHAKE128_RATE, K12_LEAF);
+  let mut cv = [0u8; K12_CV];
+  sponge.absorb(leaf);
+  sponge.finalize().read(&mut cv);
+  cv
+}
+
+/// Fills `out` with KangarooTwelve of `data` under `customization`.
+pub fn kangaroo_twelve(data: &[u8], customization: &[u8], out: &mut [u8]) {
+  let mut xof = KangarooTwelve::new(customization);
+  xof.update(data);
+  xof.finalize_xof().read(out);
+}
+
+#[cfg(feature = "digest")]
+impl digest::Update for KangarooTwelve<'_> {
+  fn update(&mut self, data: &[u8]) {
+    KangarooTwelve::update(self, data);
+  }
+}
+
+#[cfg(feature = "digest")]
+impl digest::ExtendableOutput for KangarooTwelve<'_> {
+  type Reader = XofReader;
+
+  fn finalize_xof(self) -> XofReader {
+    KangarooTwelve::finalize_xof(self)
+  }
+}
@@ -1,62 +1,72 @@
-#![allow(dead_code)]
-
-use rand::RngCore;
+// With `low_memory` the cached keygen/verify below are compiled out.
+#![cfg_attr(feature = "low_memory", allow(unused_imports))]
+
+#[cfg(not(feature = "low_memory"))]
+use core::marker::PhantomData;
+use rand_core::RngCore;
+use subtle::{Choice, ConstantTimeEq, ConstantTimeGreater};
+#[cfg(feature = "zeroize")]
 use zeroize::Zeroize;
 
 use crate::{
-    randombytes::randombytes, 
-    params::{
-      DilithiumParams,
-      Mat
-    },
+    randombytes::randombytes,
+    secret::SecretBytes,
+    params::DilithiumParams,
     polyvec::*,
     fips202::*,
     packing::*,
     poly::*
 };
 
-fn mat_from(v: &mut Mat) -> &mut [Polyvecl] {
-    match v {
-        Mat::Mode2(v) => &mut v[..],
-        Mat::Mode3(v) => &mut v[..],
-        Mat::Mode5(v) => &mut v[..],
-    }
-}
+#[cfg(feature = "low_memory")]
+pub use crate::lowmem::{
+    crypto_sign_keypair_from_seed,
+    crypto_sign_verify
+};
+#[cfg(feature = "low_memory")]
+pub(crate) use crate::lowmem::{
+    SigningKey,
+    SignScratch,
+    crypto_sign_attempt
+};
+
 const SEEDBYTES: usize = 32;
+#[cfg(feature = "parallel")]
+use crate::params::MAX_SIGNBYTES;
+#[cfg(not(feature = "low_memory"))]
 const CRHBYTES: usize = 64;
-const RNDBYTES: usize = 32;
-const N: usize = 256;
-const Q: usize = 8380417;
-const D: usize = 13;
-const ROOT_OF_UNITY: usize = 1753;
 
 pub fn crypto_sign_keypair<P: DilithiumParams, R: RngCore>(
   pk: &mut [u8],
   sk: &mut [u8],
   rng: &mut R,
 ) -> u8 {
-    let mut seedbuf = [0u8; 2 * SEEDBYTES + CRHBYTES];
+  let mut seed = SecretBytes::<SEEDBYTES>::default();
+  randombytes(&mut seed, rng);
+  crypto_sign_keypair_from_seed::<P>(pk, sk, &seed);
+  0
+}
+
+/// Deterministic key generation from the `SEEDBYTES` seed that
+/// `crypto_sign_keypair` draws. The seed determines the whole key pair, so
+/// it is a compact way to store the secret key.
+#[cfg(not(feature = "low_memory"))]
+pub fn crypto_sign_keypair_from_seed<P: DilithiumParams>(pk: &mut [u8], sk: &mut [u8], seed: &[u8]) {
+    let mut seedbuf = SecretBytes::<{ 2 * SEEDBYTES + CRHBYTES }>::default();
     let mut tr = [0u8; SEEDBYTES];
-    let mut mat_data = P::mat();
-   
-    let mut seed: [u8; SEEDBYTES] = [0; SEEDBYTES];
-    
-    randombytes(&mut seed, rng);
+    let mut mat = P::Mat::default();
 
-    shake256(&mut seedbuf, &seed, 2 * SEEDBYTES + CRHBYTES, SEEDBYTES);
+    shake256(&mut seedbuf, seed);
 
-    seed.zeroize();
 
     let rho_slice = &seedbuf[..SEEDBYTES];
     let rhoprime_slice = &seedbuf[SEEDBYTES..SEEDBYTES + CRHBYTES];
     let key_slice = &seedbuf[SEEDBYTES + CRHBYTES..];
 
-    let mut mat = mat_from(&mut mat_data);
-
     polyvec_matrix_expand::<P>(&mut mat, rho_slice);
 
-  let mut s1 = P::polyveclnew();
-  let mut s2 = P::polyvecknew();
+  let mut s1 = P::Polyvecl::default();
+  let mut s2 = P::Polyveck::default();
   
   polyvecl_uniform_eta::<P>(&mut s1, rhoprime_slice, 0);
   
@@ -66,182 +76,352 @@ pub fn crypto_sign_keypair<P: DilithiumParams, R: RngCore>(
   
   
 
-  let mut t1 = P::polyvecknew();
+  let mut t1 = P::Polyveck::default();
   polyvec_matrix_pointwise_montgomery::<P>(&mut t1, &mat, &s1hat);
   
   polyveck_reduce::<P>(&mut t1);
   polyveck_invntt_tomont::<P>(&mut t1);
 
-  let t1_clone = t1.clone();
-  polyveck_add(&mut t1, &t1_clone, &s2);
+  polyveck_add_assign(&mut t1, &s2);
 
   polyveck_caddq::<P>(&mut t1);
   
-  let mut t0 = P::polyvecknew();
-  let t1_clone = t1.clone();
-  polyveck_power2round::<P>(&mut t1, &mut t0, &t1_clone);
+  let mut t0 = P::Polyveck::default();
+  polyveck_power2round::<P>(&mut t1, &mut t0);
 
   pack_pk::<P>(pk, rho_slice, &t1);
   
 
-  shake256(&mut tr, &pk[..P::crypto_publickeybytes()], SEEDBYTES, P::PUBLIC_KEY_BYTES);
+  shake256(&mut tr, &pk[..P::crypto_publickeybytes()]);
 
   pack_sk::<P>(sk, rho_slice, &tr, key_slice, &t0, &s1, &s2);
+  #[cfg(feature = "zeroize")]
   tr.zeroize();
-  seedbuf.zeroize();
+}
 
-  return 0;
+/// Secret key material expanded once per message and shared by every
+/// rejection-loop attempt: `A`, `s1`, `s2` and `t0` in NTT domain, plus
+/// `key || mu` and `rhoprime`.
+#[cfg(not(feature = "low_memory"))]
+pub(crate) struct SigningKey<'a, P: DilithiumParams> {
+  mat: P::Mat,
+  s1: P::Polyvecl,
+  s2: P::Polyveck,
+  t0: P::Polyveck,
+  keymu: SecretBytes<{ SEEDBYTES + CRHBYTES }>,
+  rhoprime: SecretBytes<CRHBYTES>,
+  _sk: PhantomData<&'a [u8]>,
 }
 
-pub fn crypto_sign_signature<P: DilithiumParams, R: RngCore>(sig: &mut [u8], m: &[u8], sk: &[u8], rng: &mut R) {
+#[cfg(not(feature = "low_memory"))]
+impl<'a, P: DilithiumParams> SigningKey<'a, P> {
+  /// Zeroed key; `expand` fills it in place so the expanded secrets never
+  /// sit in a moved-from copy that nothing wipes.
+  #[inline(always)]
+  pub(crate) fn new() -> Self {
+    SigningKey::<P> {
+      mat: P::Mat::default(),
+      s1: P::Polyvecl::default(),
+      s2: P::Polyveck::default(),
+      t0: P::Polyveck::default(),
+      keymu: SecretBytes::default(),
+      rhoprime: SecretBytes::default(),
+      _sk: PhantomData,
+    }
+  }
 
-  let mut keymu = [0u8; SEEDBYTES + CRHBYTES];
+  pub(crate) fn expand<R: RngCore>(&mut self, m: &[u8], sk: &'a [u8], rng: &mut R) {
+    let mut state = KeccakState::default();
+    let mut rho = [0u8; SEEDBYTES];
+    let mut tr = [0u8; SEEDBYTES];
 
-  let mut nonce = 0u16;
-  let mut mat_data = P::mat();
-   let mut mat = mat_from(&mut mat_data);
-  let (mut s1, mut y) = (P::polyveclnew(), P::polyveclnew());
-  let (mut s2, mut t0) = (P::polyvecknew(), P::polyvecknew());
-  let (mut w1, mut w0) = (P::polyvecknew(), P::polyvecknew());
-  let mut h = P::polyvecknew();
-  let mut cp = Poly::default();
-  let mut state = KeccakState::default();
-  let mut rho = [0u8; SEEDBYTES];
-  let mut tr = [0u8; SEEDBYTES];
+    unpack_sk::<P>(
+      &mut rho,
+      &mut tr,
+      &mut self.keymu[..SEEDBYTES],
+      &mut self.t0,
+      &mut self.s1,
+      &mut self.s2,
+      sk,
+    );
+
+    state.shake256_absorb(&tr);
+    state.shake256_absorb(m);
+    state.shake256_finalize().read(&mut self.keymu[SEEDBYTES..]);
+
+    #[cfg(feature = "random")]
+    randombytes(&mut self.rhoprime, rng);
+
+    #[cfg(not(feature = "random"))]
+    {
+      let _ = rng;
+      shake256(&mut self.rhoprime, &self.keymu);
+    }
 
-  let mut rhoprime = [0u8; CRHBYTES];
+    polyvec_matrix_expand::<P>(&mut self.mat, &rho);
+    polyvecl_ntt::<P>(&mut self.s1);
+    polyveck_ntt::<P>(&mut self.s2);
+    polyveck_ntt::<P>(&mut self.t0);
 
-  unpack_sk::<P>(
-    &mut rho,
-    &mut tr,
-    &mut keymu[..SEEDBYTES],
-    &mut t0,
-    &mut s1,
-    &mut s2,
-    &sk,
-  );
-  
-  
-  state.shake256_absorb(&tr, SEEDBYTES);
-  state.shake256_absorb(m, m.len());
-  state.shake256_finalize();
-  state.shake256_squeeze(&mut keymu[SEEDBYTES..], CRHBYTES);
-  
-  #[cfg(feature = "random")]
-  randombytes(&mut rhoprime, rng);
+    #[cfg(feature = "zeroize")]
+    rho.zeroize();
+    #[cfg(feature = "zeroize")]
+    tr.zeroize();
+  }
 
-  #[cfg(not(feature = "random"))]
-  shake256(&mut rhoprime, &keymu, CRHBYTES, SEEDBYTES + CRHBYTES);
+  /// `mu`, the message representative the challenge is derived from.
+  #[cfg(feature = "hardened")]
+  pub(crate) fn mu(&self) -> &[u8] {
+    &self.keymu[SEEDBYTES..]
+  }
 
-  polyvec_matrix_expand::<P>(&mut mat, &rho);
-  polyvecl_ntt::<P>(&mut s1);
-  polyveck_ntt::<P>(&mut s2);
-  polyveck_ntt::<P>(&mut t0);
+  /// SHAKE256 of `s1` and `s2` as held for signing, to catch corruption
+  /// between expansion and release.
+  #[cfg(feature = "hardened")]
+  pub(crate) fn secret_digest(&self) -> [u8; SEEDBYTES] {
+    let mut state = KeccakState::default();
+    let mut digest = [0u8; SEEDBYTES];
+    for p in self.s1.vec().iter().chain(self.s2.vec()) {
+      for c in p.coeffs.iter() {
+        state.shake256_absorb(&c.to_le_bytes());
+      }
+    }
+    state.shake256_finalize().read(&mut digest);
+    digest
+  }
 
-  loop {
-    polyvecl_uniform_gamma1::<P>(&mut y, &rhoprime, nonce);
-    nonce += 1;
+  /// Flips a bit of `s1`, as a glitch in the held secret would.
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn inject_fault(&mut self) {
+    self.s1.vec_mut()[0].coeffs[0] ^= 1;
+  }
+}
 
-    let mut z = y.clone();
-    polyvecl_ntt::<P>(&mut z);
-    polyvec_matrix_pointwise_montgomery::<P>(&mut w1, &mat, &z);
-    polyveck_reduce::<P>(&mut w1);
-    polyveck_invntt_tomont::<P>(&mut w1);
-
-    polyveck_caddq::<P>(&mut w1);
-    let w1_clone = w1.clone();
-    polyveck_decompose::<P>(&mut w1, &mut w0, &w1_clone);
-    polyveck_pack_w1::<P>(sig, &w1);
-
-    state.init();
-    state.shake256_absorb(&keymu[SEEDBYTES..], CRHBYTES);
-    state.shake256_absorb(&sig, P::K * P::polyw1_packedbytes());
-    state.shake256_finalize();
-    state.shake256_squeeze(sig, SEEDBYTES);
-
-    poly_challenge::<P>(&mut cp, sig);
-
-    poly_ntt::<P>(&mut cp);
-
-    polyvecl_pointwise_poly_montgomery::<P>(&mut z, &cp, &s1);
-    polyvecl_invntt_tomont::<P>(&mut z);
-    let z_clone = z.clone();
-    polyvecl_add(&mut z, &z_clone, &y);
-    polyvecl_reduce::<P>(&mut z);
-    if polyvecl_chknorm::<P>(&z, (P::GAMMA1 - P::BETA) as i32) > 0 {
-      continue;
-    }
+/// Per-attempt working vectors, reused across iterations of the rejection loop.
+#[cfg(not(feature = "low_memory"))]
+pub(crate) struct SignScratch<P: DilithiumParams> {
+  y: P::Polyvecl,
+  z: P::Polyvecl,
+  w1: P::Polyveck,
+  w0: P::Polyveck,
+  h: P::Polyveck,
+  cp: Poly,
+}
 
-    polyveck_pointwise_poly_montgomery::<P>(&mut h, &cp, &s2);
-    polyveck_invntt_tomont::<P>(&mut h);
-    let w0_clone = w0.clone();
-    polyveck_sub(&mut w0, &w0_clone, &h);
-    polyveck_reduce::<P>(&mut w0);
-    if polyveck_chknorm::<P>(&w0, (P::GAMMA2 - P::BETA) as i32) > 0 {
-      continue;
-    }
+#[cfg(not(feature = "low_memory"))]
+impl<P: DilithiumParams> SignScratch<P> {
+  #[cfg(feature = "hardened")]
+  pub(crate) fn w1(&self) -> &P::Polyveck {
+    &self.w1
+  }
 
-    polyveck_pointwise_poly_montgomery::<P>(&mut h, &cp, &t0);
-    polyveck_invntt_tomont::<P>(&mut h);
-    polyveck_reduce::<P>(&mut h);
-    if polyveck_chknorm::<P>(&h, P::GAMMA2 as i32) > 0 {
-      continue;
-    }
-    let w0_clone = w0.clone();
-    polyveck_add(&mut w0, &w0_clone, &h);
-    let n = polyveck_make_hint::<P>(&mut h, &w0, &w1);
-    if n > P::OMEGA as i32 {
-      continue;
+  /// The challenge polynomial, in NTT domain.
+  #[cfg(feature = "hardened")]
+  pub(crate) fn challenge(&self) -> &Poly {
+    &self.cp
+  }
+
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn w1_mut(&mut self) -> &mut P::Polyveck {
+    &mut self.w1
+  }
+
+  #[cfg(feature = "fault_injection")]
+  pub(crate) fn challenge_mut(&mut self) -> &mut Poly {
+    &mut self.cp
+  }
+}
+
+#[cfg(not(feature = "low_memory"))]
+impl<P: DilithiumParams> Default for SignScratch<P> {
+  #[inline(always)]
+  fn default() -> Self {
+    SignScratch {
+      y: P::Polyvecl::default(),
+      z: P::Polyvecl::default(),
+      w1: P::Polyveck::default(),
+      w0: P::Polyveck::default(),
+      h: P::Polyveck::default(),
+      cp: Poly::default(),
     }
+  }
+}
 
-    pack_sig::<P>(sig, None, &z, &h);
-    rho.zeroize();
-    keymu.zeroize();
-    tr.zeroize();
-    return;
+/// Runs one iteration of the rejection loop with the given `nonce`.
+/// Returns `true` and leaves a packed signature in `sig` if the candidate is accepted.
+/// Every rejection check runs and they are combined before the only branch,
+/// so timing shows whether an attempt was rejected but not which bound failed.
+#[cfg(not(feature = "low_memory"))]
+pub(crate) fn crypto_sign_attempt<P: DilithiumParams>(
+  key: &SigningKey<'_, P>,
+  ws: &mut SignScratch<P>,
+  sig: &mut [u8],
+  nonce: u16,
+) -> bool {
+  let mut state = KeccakState::default();
+
+  polyvecl_uniform_gamma1::<P>(&mut ws.y, &key.rhoprime, nonce);
+
+  ws.z.vec_mut().clone_from_slice(ws.y.vec());
+  polyvecl_ntt::<P>(&mut ws.z);
+  polyvec_matrix_pointwise_montgomery::<P>(&mut ws.w1, &key.mat, &ws.z);
+  polyveck_reduce::<P>(&mut ws.w1);
+  polyveck_invntt_tomont::<P>(&mut ws.w1);
+
+  polyveck_caddq::<P>(&mut ws.w1);
+  polyveck_decompose_in_place::<P>(&mut ws.w1, &mut ws.w0);
+  polyveck_pack_w1::<P>(sig, &ws.w1);
+
+  state.shake256_absorb(&key.keymu[SEEDBYTES..]);
+  state.shake256_absorb(&sig[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut sig[..SEEDBYTES]);
+
+  poly_challenge::<P>(&mut ws.cp, sig);
+
+  poly_ntt::<P>(&mut ws.cp);
+
+  polyvecl_pointwise_poly_montgomery::<P>(&mut ws.z, &ws.cp, &key.s1);
+  polyvecl_invntt_tomont::<P>(&mut ws.z);
+  polyvecl_add_assign(&mut ws.z, &ws.y);
+  polyvecl_reduce::<P>(&mut ws.z);
+  let mut reject = Choice::from(polyvecl_chknorm::<P>(&ws.z, (P::GAMMA1 - P::BETA) as i32));
+
+  polyveck_pointwise_poly_montgomery::<P>(&mut ws.h, &ws.cp, &key.s2);
+  polyveck_invntt_tomont::<P>(&mut ws.h);
+  polyveck_sub_assign(&mut ws.w0, &ws.h);
+  polyveck_reduce::<P>(&mut ws.w0);
+  reject |= Choice::from(polyveck_chknorm::<P>(&ws.w0, (P::GAMMA2 - P::BETA) as i32));
+
+  polyveck_pointwise_poly_montgomery::<P>(&mut ws.h, &ws.cp, &key.t0);
+  polyveck_invntt_tomont::<P>(&mut ws.h);
+  polyveck_reduce::<P>(&mut ws.h);
+  reject |= Choice::from(polyveck_chknorm::<P>(&ws.h, P::GAMMA2 as i32));
+
+  polyveck_add_assign(&mut ws.w0, &ws.h);
+  let n = polyveck_make_hint::<P>(&mut ws.h, &ws.w0, &ws.w1);
+  reject |= (n as u32).ct_gt(&(P::OMEGA as u32));
+  if bool::from(reject) {
+    return false;
+  }
+
+  pack_sig::<P>(sig, None, &ws.z, &ws.h);
+  true
+}
+
+pub fn crypto_sign_signature<P: DilithiumParams, R: RngCore>(sig: &mut [u8], m: &[u8], sk: &[u8], rng: &mut R) {
+  let mut key = SigningKey::<P>::new();
+  key.expand(m, sk, rng);
+  let mut ws = SignScratch::<P>::default();
+  let mut nonce = 0u16;
+
+  while !crypto_sign_attempt::<P>(&key, &mut ws, sig, nonce) {
+    nonce += 1;
+  }
+}
+
+/// Most workers `crypto_sign_signature_parallel` starts; larger requests are
+/// clamped. Few attempts are rejected, so more threads only add start-up cost.
+#[cfg(feature = "parallel")]
+pub const MAX_SIGN_THREADS: usize = 64;
+
+/// Multi-threaded variant of `crypto_sign_signature`.
+///
+/// Worker `i` of `threads` tries nonces `i, i + threads, i + 2 * threads, ...`
+/// against a shared expanded key; the first accepted candidate is copied to
+/// `sig` and the other workers stop at their next iteration boundary. Every
+/// candidate is a valid signature, but which one wins depends on scheduling,
+/// so the output is not reproducible even without the `random` feature.
+/// `threads` is clamped to `1..=MAX_SIGN_THREADS`.
+#[cfg(feature = "parallel")]
+pub fn crypto_sign_signature_parallel<P: DilithiumParams, R: RngCore>(
+  sig: &mut [u8],
+  m: &[u8],
+  sk: &[u8],
+  rng: &mut R,
+  threads: usize,
+) {
+  use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, PoisonError,
+  };
+
+  let threads = threads.clamp(1, MAX_SIGN_THREADS);
+  if threads == 1 {
+    return crypto_sign_signature::<P, R>(sig, m, sk, rng);
   }
+
+  let mut key = SigningKey::<P>::new();
+  key.expand(m, sk, rng);
+  let done = AtomicBool::new(false);
+  let out = Mutex::new(&mut *sig);
+
+  std::thread::scope(|scope| {
+    for worker in 0..threads {
+      let (key, done, out) = (&key, &done, &out);
+      scope.spawn(move || {
+        let mut ws = SignScratch::<P>::default();
+        let mut candidate = [0u8; MAX_SIGNBYTES];
+        let candidate = &mut candidate[..P::SIGNBYTES];
+        let mut nonce = worker as u16;
+
+        while !done.load(Ordering::Relaxed) {
+          if crypto_sign_attempt::<P>(key, &mut ws, candidate, nonce) {
+            if !done.swap(true, Ordering::AcqRel) {
+              // Only the first finisher writes, so a poisoned lock can only
+              // come from a worker that panicked before touching `sig`.
+              out.lock().unwrap_or_else(PoisonError::into_inner).copy_from_slice(candidate);
+            }
+            break;
+          }
+          nonce = nonce.wrapping_add(threads as u16);
+        }
+        #[cfg(feature = "zeroize")]
+        candidate.zeroize();
+      });
+    }
+  });
 }
 
+#[cfg(not(feature = "low_memory"))]
 pub fn crypto_sign_verify<P: DilithiumParams>(
   sig: &[u8],
   m: &[u8],
   pk: &[u8],
 ) -> Result<(), &'static str> {
   let mut max = [0u8; 8 * 128];
-  let needed = P::K * P::polyw1_packedbytes();
-  let mut buf = &mut max[..needed];
+  let needed = P::K * P::POLYW1_PACKEDBYTES;
+  let buf = &mut max[..needed];
   let mut rho = [0u8; SEEDBYTES];
   let mut mu = [0u8; CRHBYTES];
   let mut c = [0u8; SEEDBYTES];
   let mut c2 = [0u8; SEEDBYTES];
   let mut cp = Poly::default();
-  let mut mat_data = P::mat();
-  let mut mat = mat_from(&mut mat_data);
-  let mut z = P::polyveclnew();
+  let mut mat = P::Mat::default();
+  let mut z = P::Polyvecl::default();
   let (mut t1, mut w1, mut h) = (
-    P::polyvecknew(),
-    P::polyvecknew(),
-    P::polyvecknew()
+    P::Polyveck::default(),
+    P::Polyveck::default(),
+    P::Polyveck::default()
   );
   let mut state = KeccakState::default(); // shake256_init()
 
   if sig.len() != P::SIGNBYTES {
     return Err("Signature length mismatch");
   }
+  if pk.len() != P::PUBLIC_KEY_BYTES {
+    return Err("Public key length mismatch");
+  }
 
   unpack_pk::<P>(&mut rho, &mut t1, pk);
-  if let Err(e) = unpack_sig::<P>(&mut c, &mut z, &mut h, sig) {
-    return Err(e);
-  }
+  unpack_sig::<P>(&mut c, &mut z, &mut h, sig)?;
   if polyvecl_chknorm::<P>(&z, (P::GAMMA1 - P::BETA) as i32) > 0 {
     return Err("Invalid z");
   }
 
-  shake256(&mut mu, pk, SEEDBYTES, P::PUBLIC_KEY_BYTES);
-  state.shake256_absorb(&mu, SEEDBYTES);
-  state.shake256_absorb(m, m.len());
-  state.shake256_finalize();
-  state.shake256_squeeze(&mut mu, CRHBYTES);
+  shake256(&mut mu[..SEEDBYTES], &pk[..P::PUBLIC_KEY_BYTES]);
+  state.shake256_absorb(&mu[..SEEDBYTES]);
+  state.shake256_absorb(m);
+  state.shake256_finalize().read(&mut mu);
 
   poly_challenge::<P>(&mut cp, &c);
   polyvec_matrix_expand::<P>(&mut mat, &rho);
@@ -252,23 +432,19 @@ pub fn crypto_sign_verify<P: DilithiumParams>(
   poly_ntt::<P>(&mut cp);
   polyveck_shiftl(&mut t1);
   polyveck_ntt::<P>(&mut t1);
-  let t1_2 = t1.clone();
-  polyveck_pointwise_poly_montgomery::<P>(&mut t1, &cp, &t1_2);
-  let w1_clone = w1.clone();
-  polyveck_sub(&mut w1, &w1_clone, &t1);
+  polyveck_pointwise_poly_montgomery_assign::<P>(&mut t1, &cp);
+  polyveck_sub_assign(&mut w1, &t1);
   polyveck_reduce::<P>(&mut w1);
   polyveck_invntt_tomont::<P>(&mut w1);
 
   polyveck_caddq::<P>(&mut w1);
-  let w1_clone = w1.clone();
-  polyveck_use_hint::<P>(&mut w1, &w1_clone, &h);
-  polyveck_pack_w1::<P>(&mut buf, &w1);
-
-  state.init();
-  state.shake256_absorb(&mu, CRHBYTES);
-  state.shake256_absorb(&buf, P::K * P::polyw1_packedbytes());
-  state.shake256_finalize();
-  state.shake256_squeeze(&mut c2, SEEDBYTES);
+  polyveck_use_hint_in_place::<P>(&mut w1, &h);
+  polyveck_pack_w1::<P>(buf, &w1);
+
+  let mut state = KeccakState::default();
+  state.shake256_absorb(&mu);
+  state.shake256_absorb(&buf[..P::K * P::POLYW1_PACKEDBYTES]);
+  state.shake256_finalize().read(&mut c2);
   
   if c != c2 {
     Err("Invalid signature")
@@ -277,3 +453,109 @@ pub fn crypto_sign_verify<P: DilithiumParams>(
     Ok(())
   }
 }
+
+/// `crypto_sign_verify` that also refuses any signature or public key that
+/// is not the unique encoding of the values it decodes to, so a signature
+/// that verifies has exactly one byte representation.
+pub fn crypto_sign_verify_strict<P: DilithiumParams>(
+  sig: &[u8],
+  m: &[u8],
+  pk: &[u8],
+) -> Result<(), &'static str> {
+  if sig.len() != P::SIGNBYTES {
+    return Err("Signature length mismatch");
+  }
+  if pk.len() != P::PUBLIC_KEY_BYTES {
+    return Err("Public key length mismatch");
+  }
+  check_pk_canonical::<P>(pk)?;
+  check_sig_canonical::<P>(sig)?;
+  crypto_sign_verify::<P>(sig, m, pk)
+}
+
+/// Recomputes `t1` from the `rho`, `s1` and `s2` stored in `sk`, rejecting
+/// `s1`/`s2` coefficients outside `[-ETA, ETA]`. The returned `Choice` says
+/// whether the `t0` stored in `sk` matches the recomputed one.
+fn derive_t1<P: DilithiumParams>(t1: &mut P::Polyveck, sk: &[u8]) -> Result<Choice, &'static str> {
+  let mut mat = P::Mat::default();
+  let mut s1 = P::Polyvecl::default();
+  let (mut t, mut t0) = (Poly::default(), Poly::default());
+  let mut t0_ok = Choice::from(1);
+
+  for (i, p) in s1.vec_mut().iter_mut().enumerate() {
+    polyeta_unpack::<P>(p, &sk[sk_s1_offset::<P>(i)..]);
+    if poly_chknorm::<P>(p, P::ETA as i32 + 1) != 0 {
+      return Err("Secret key s1 coefficient out of range");
+    }
+  }
+  polyvec_matrix_expand::<P>(&mut mat, &sk[..SEEDBYTES]);
+  polyvecl_ntt::<P>(&mut s1);
+  polyvec_matrix_pointwise_montgomery::<P>(t1, &mat, &s1);
+  polyveck_reduce::<P>(t1);
+  polyveck_invntt_tomont::<P>(t1);
+
+  for (i, t1i) in t1.vec_mut().iter_mut().enumerate() {
+    polyeta_unpack::<P>(&mut t, &sk[sk_s2_offset::<P>(i)..]);
+    if poly_chknorm::<P>(&t, P::ETA as i32 + 1) != 0 {
+      return Err("Secret key s2 coefficient out of range");
+    }
+    poly_add_assign(t1i, &t);
+    poly_caddq::<P>(t1i);
+    poly_power2round::<P>(t1i, &mut t);
+
+    polyt0_unpack(&mut t0, &sk[sk_t0_offset::<P>(i)..]);
+    for (a, b) in t.coeffs.iter().zip(t0.coeffs.iter()) {
+      t0_ok &= a.ct_eq(b);
+    }
+  }
+  Ok(t0_ok)
+}
+
+/// Rebuilds the public key from a secret key alone: `rho`, `s1` and `s2` give
+/// `t1` as in keygen. The stored `t0` and `tr` are checked against the result,
+/// so a corrupted secret key is reported rather than yielding a wrong `pk`.
+pub fn crypto_sign_public_key<P: DilithiumParams>(pk: &mut [u8], sk: &[u8]) -> Result<(), &'static str> {
+  let mut t1 = P::Polyveck::default();
+  let mut tr = [0u8; SEEDBYTES];
+
+  if !bool::from(derive_t1::<P>(&mut t1, sk)?) {
+    return Err("Secret key t0 does not match s1 and s2");
+  }
+  pack_pk::<P>(pk, &sk[..SEEDBYTES], &t1);
+
+  shake256(&mut tr, &pk[..P::PUBLIC_KEY_BYTES]);
+  if !bool::from(tr.ct_eq(&sk[2 * SEEDBYTES..3 * SEEDBYTES])) {
+    return Err("Secret key tr does not match public key");
+  }
+  Ok(())
+}
+
+/// Checks that `sk` and `pk` are a consistent key pair, as keygen would have
+/// produced them: `s1`/`s2` within `[-ETA, ETA]`, `t = A*s1 + s2` matching
+/// the `t1` in `pk` and the `t0` in `sk`, and the stored `tr` equal to
+/// `H(pk)`. The error names the first check that failed. Lengths are the
+/// caller's responsibility.
+pub fn crypto_sign_check_keypair<P: DilithiumParams>(pk: &[u8], sk: &[u8]) -> Result<(), &'static str> {
+  let (mut t1, mut pk_t1) = (P::Polyveck::default(), P::Polyveck::default());
+  let mut rho = [0u8; SEEDBYTES];
+  let mut tr = [0u8; SEEDBYTES];
+
+  if sk[..SEEDBYTES] != pk[..SEEDBYTES] {
+    return Err("Secret key rho does not match public key");
+  }
+  let t0_ok = derive_t1::<P>(&mut t1, sk)?;
+
+  unpack_pk::<P>(&mut rho, &mut pk_t1, pk);
+  if t1.vec().iter().zip(pk_t1.vec()).any(|(a, b)| a.coeffs != b.coeffs) {
+    return Err("Public key t1 does not match secret key");
+  }
+  if !bool::from(t0_ok) {
+    return Err("Secret key t0 does not match s1 and s2");
+  }
+
+  shake256(&mut tr, &pk[..P::PUBLIC_KEY_BYTES]);
+  if !bool::from(tr.ct_eq(&sk[2 * SEEDBYTES..3 * SEEDBYTES])) {
+    return Err("Secret key tr does not match public key");
+  }
+  Ok(())
+}
@@ -0,0 +1,539 @@
+//! SLH-DSA (FIPS 205) with the SHAKE instantiation of its hash functions.
+//!
+//! The code follows the algorithms of the standard one for one; the comments
+//! give their numbers. Every tweakable hash is a single SHAKE256 call on
+//! `PK.seed || ADRS || input`, so the whole scheme runs on `KeccakState`.
+//! Nodes are fixed `[u8; MAX_N]` buffers of which the first `P::N` bytes are
+//! used, which keeps the code allocation-free for all six parameter sets.
+
+use rand_core::RngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::{fips202::KeccakState, randombytes::randombytes, secret::SecretBytes};
+
+const MAX_N: usize = 32;
+const MAX_LEN: usize = 2 * MAX_N + 3;
+const MAX_K: usize = 35;
+const MAX_M: usize = 49;
+
+const LG_W: usize = 4;
+const W: u32 = 1 << LG_W;
+const LEN2: usize = 3;
+
+// Address types, FIPS 205 section 4.2.
+const WOTS_HASH: u32 = 0;
+const WOTS_PK: u32 = 1;
+const TREE: u32 = 2;
+const FORS_TREE: u32 = 3;
+const FORS_ROOTS: u32 = 4;
+const WOTS_PRF: u32 = 5;
+const FORS_PRF: u32 = 6;
+
+pub trait SlhParams {
+    /// Security parameter: bytes per hash value.
+    const N: usize;
+    /// Total height of the hypertree.
+    const H: usize;
+    /// Number of hypertree layers.
+    const D: usize;
+    /// Height of each XMSS tree, `H / D`.
+    const HP: usize;
+    /// Height of each FORS tree.
+    const A: usize;
+    /// Number of FORS trees.
+    const K: usize;
+    /// Bytes of `H_msg` output.
+    const M: usize;
+
+    const LEN1: usize = 2 * Self::N;
+    const LEN: usize = Self::LEN1 + LEN2;
+    const PUBLIC_KEY_BYTES: usize = 2 * Self::N;
+    const SECRET_KEY_BYTES: usize = 4 * Self::N;
+    const FORS_BYTES: usize = Self::K * (1 + Self::A) * Self::N;
+    const XMSS_BYTES: usize = (Self::LEN + Self::HP) * Self::N;
+    const SIGNBYTES: usize = Self::N + Self::FORS_BYTES + Self::D * Self::XMSS_BYTES;
+}
+
+macro_rules! slh_params {
+    ($name:ident, $n:expr, $h:expr, $d:expr, $hp:expr, $a:expr, $k:expr, $m:expr) => {
+        pub struct $name;
+        impl SlhParams for $name {
+            const N: usize = $n;
+            const H: usize = $h;
+            const D: usize = $d;
+            const HP: usize = $hp;
+            const A: usize = $a;
+            const K: usize = $k;
+            const M: usize = $m;
+        }
+    };
+}
+
+// FIPS 205 table 2.
+slh_params!(Shake128s, 16, 63, 7, 9, 12, 14, 30);
+slh_params!(Shake128f, 16, 66, 22, 3, 6, 33, 34);
+slh_params!(Shake192s, 24, 63, 7, 9, 14, 17, 39);
+slh_params!(Shake192f, 24, 66, 22, 3, 8, 33, 42);
+slh_params!(Shake256s, 32, 64, 8, 8, 14, 22, 47);
+slh_params!(Shake256f, 32, 68, 17, 4, 9, 35, 49);
+
+type Node = [u8; MAX_N];
+
+/// The 32-byte hash address `ADRS`, FIPS 205 section 4.2.
+#[derive(Clone, Copy, Default)]
+struct Adrs([u8; 32]);
+
+impl Adrs {
+  fn set_word(&mut self, at: usize, v: u32) {
+    self.0[at..at + 4].copy_from_slice(&v.to_be_bytes());
+  }
+
+  fn word(&self, at: usize) -> u32 {
+    u32::from_be_bytes([self.0[at], self.0[at + 1], self.0[at + 2], self.0[at + 3]])
+  }
+
+  fn set_layer(&mut self, layer: u32) {
+    self.set_word(0, layer);
+  }
+
+  fn set_tree(&mut self, tree: u64) {
+    self.0[4..8].fill(0);
+    self.0[8..16].copy_from_slice(&tree.to_be_bytes());
+  }
+
+  fn set_type_and_clear(&mut self, ty: u32) {
+    self.set_word(16, ty);
+    self.0[20..].fill(0);
+  }
+
+  fn set_key_pair(&mut self, i: u32) {
+    self.set_word(20, i);
+  }
+
+  fn key_pair(&self) -> u32 {
+    self.word(20)
+  }
+
+  fn set_chain(&mut self, i: u32) {
+    self.set_word(24, i);
+  }
+
+  fn set_tree_height(&mut self, z: u32) {
+    self.set_word(24, z);
+  }
+
+  fn set_hash(&mut self, i: u32) {
+    self.set_word(28, i);
+  }
+
+  fn set_tree_index(&mut self, i: u32) {
+    self.set_word(28, i);
+  }
+
+  fn tree_index(&self) -> u32 {
+    self.word(28)
+  }
+}
+
+/// The seeds every hash call needs.
+struct Seeds<'a> {
+  pk_seed: &'a [u8],
+  sk_seed: &'a [u8],
+}
+
+fn shake256_parts(out: &mut [u8], parts: &[&[u8]]) {
+  let mut state = KeccakState::default();
+  for part in parts {
+    state.shake256_absorb(part);
+  }
+  state.shake256_finalize().read(out);
+}
+
+/// `F`, `H` and `T_l`: SHAKE256(PK.seed || ADRS || M_1 || ... || M_l).
+fn thash<P: SlhParams>(out: &mut Node, pk_seed: &[u8], adrs: &Adrs, blocks: &[Node]) {
+  let mut state = KeccakState::default();
+  state.shake256_absorb(pk_seed);
+  state.shake256_absorb(&adrs.0);
+  for block in blocks {
+    state.shake256_absorb(&block[..P::N]);
+  }
+  state.shake256_finalize().read(&mut out[..P::N]);
+}
+
+/// `PRF`: SHAKE256(PK.seed || ADRS || SK.seed).
+fn prf<P: SlhParams>(out: &mut Node, seeds: &Seeds, adrs: &Adrs) {
+  shake256_parts(&mut out[..P::N], &[seeds.pk_seed, &adrs.0, seeds.sk_seed]);
+}
+
+/// Algorithm 4, `base_2b`: `out.len()` big-endian `b`-bit digits of `x`.
+fn base_2b(x: &[u8], b: usize, out: &mut [u32]) {
+  let mut pos = 0;
+  let mut bits = 0;
+  let mut total = 0u64;
+  for digit in out.iter_mut() {
+    while bits < b {
+      total = (total << 8) | x[pos] as u64;
+      pos += 1;
+      bits += 8;
+    }
+    bits -= b;
+    *digit = ((total >> bits) & ((1 << b) - 1)) as u32;
+  }
+}
+
+/// The `len` base-`w` digits WOTS+ signs: the message digits and checksum.
+fn wots_digits<P: SlhParams>(msg: &[u8], digits: &mut [u32; MAX_LEN]) {
+  base_2b(msg, LG_W, &mut digits[..P::LEN1]);
+  let csum: u32 = digits[..P::LEN1].iter().map(|&d| W - 1 - d).sum();
+  // len2 * lg_w = 12 bits, left-aligned in two bytes.
+  let csum = ((csum as u16) << 4).to_be_bytes();
+  base_2b(&csum, LG_W, &mut digits[P::LEN1..P::LEN]);
+}
+
+/// Algorithm 5, `chain`: `steps` applications of `F` from position `start`.
+fn chain<P: SlhParams>(x: &mut Node, start: u32, steps: u32, pk_seed: &[u8], adrs: &mut Adrs) {
+  for j in start..start + steps {
+    adrs.set_hash(j);
+    let tmp = *x;
+    thash::<P>(x, pk_seed, adrs, &[tmp]);
+  }
+}
+
+/// The WOTS+ secret value of chain `i`, for the key pair in `adrs`.
+fn wots_sk<P: SlhParams>(sk: &mut Node, seeds: &Seeds, adrs: &Adrs, i: u32) {
+  let mut sk_adrs = *adrs;
+  sk_adrs.set_type_and_clear(WOTS_PRF);
+  sk_adrs.set_key_pair(adrs.key_pair());
+  sk_adrs.set_chain(i);
+  prf::<P>(sk, seeds, &sk_adrs);
+}
+
+/// Compresses the chain ends into a WOTS+ public key.
+fn wots_compress<P: SlhParams>(pk: &mut Node, pk_seed: &[u8], adrs: &Adrs, ends: &[Node]) {
+  let mut pk_adrs = *adrs;
+  pk_adrs.set_type_and_clear(WOTS_PK);
+  pk_adrs.set_key_pair(adrs.key_pair());
+  thash::<P>(pk, pk_seed, &pk_adrs, ends);
+}
+
+/// Algorithm 6, `wots_pkGen`.
+fn wots_pk_gen<P: SlhParams>(pk: &mut Node, seeds: &Seeds, adrs: &mut Adrs) {
+  let mut ends = [[0u8; MAX_N]; MAX_LEN];
+  for i in 0..P::LEN {
+    wots_sk::<P>(&mut ends[i], seeds, adrs, i as u32);
+    adrs.set_chain(i as u32);
+    chain::<P>(&mut ends[i], 0, W - 1, seeds.pk_seed, adrs);
+  }
+  wots_compress::<P>(pk, seeds.pk_seed, adrs, &ends[..P::LEN]);
+}
+
+/// Algorithm 7, `wots_sign`: writes `len * n` bytes to `sig`.
+fn wots_sign<P: SlhParams>(sig: &mut [u8], msg: &[u8], seeds: &Seeds, adrs: &mut Adrs) {
+  let mut digits = [0u32; MAX_LEN];
+  wots_digits::<P>(msg, &mut digits);
+  let mut x = [0u8; MAX_N];
+  for i in 0..P::LEN {
+    wots_sk::<P>(&mut x, seeds, adrs, i as u32);
+    adrs.set_chain(i as u32);
+    chain::<P>(&mut x, 0, digits[i], seeds.pk_seed, adrs);
+    sig[i * P::N..(i + 1) * P::N].copy_from_slice(&x[..P::N]);
+  }
+  #[cfg(feature = "zeroize")]
+  x.zeroize();
+}
+
+/// Algorithm 8, `wots_pkFromSig`.
+fn wots_pk_from_sig<P: SlhParams>(pk: &mut Node, sig: &[u8], msg: &[u8], pk_seed: &[u8], adrs: &mut Adrs) {
+  let mut digits = [0u32; MAX_LEN];
+  wots_digits::<P>(msg, &mut digits);
+  let mut ends = [[0u8; MAX_N]; MAX_LEN];
+  for i in 0..P::LEN {
+    ends[i][..P::N].copy_from_slice(&sig[i * P::N..(i + 1) * P::N]);
+    adrs.set_chain(i as u32);
+    chain::<P>(&mut ends[i], digits[i], W - 1 - digits[i], pk_seed, adrs);
+  }
+  wots_compress::<P>(pk, pk_seed, adrs, &ends[..P::LEN]);
+}
+
+/// Algorithm 9, `xmss_node`: the node at height `z`, index `i`.
+fn xmss_node<P: SlhParams>(node: &mut Node, seeds: &Seeds, i: u32, z: usize, adrs: &mut Adrs) {
+  if z == 0 {
+    adrs.set_type_and_clear(WOTS_HASH);
+    adrs.set_key_pair(i);
+    wots_pk_gen::<P>(node, seeds, adrs);
+    return;
+  }
+  let mut children = [[0u8; MAX_N]; 2];
+  xmss_node::<P>(&mut children[0], seeds, 2 * i, z - 1, adrs);
+  xmss_node::<P>(&mut children[1], seeds, 2 * i + 1, z - 1, adrs);
+  adrs.set_type_and_clear(TREE);
+  adrs.set_tree_height(z as u32);
+  adrs.set_tree_index(i);
+  thash::<P>(node, seeds.pk_seed, adrs, &children);
+}
+
+/// Algorithm 10, `xmss_sign`: a WOTS+ signature and the authentication path.
+fn xmss_sign<P: SlhParams>(sig: &mut [u8], msg: &[u8], seeds: &Seeds, idx: u32, adrs: &mut Adrs) {
+  let (wots, auth) = sig.split_at_mut(P::LEN * P::N);
+  let mut node = [0u8; MAX_N];
+  for j in 0..P::HP {
+    xmss_node::<P>(&mut node, seeds, (idx >> j) ^ 1, j, adrs);
+    auth[j * P::N..(j + 1) * P::N].copy_from_slice(&node[..P::N]);
+  }
+  adrs.set_type_and_clear(WOTS_HASH);
+  adrs.set_key_pair(idx);
+  wots_sign::<P>(wots, msg, seeds, adrs);
+}
+
+/// Climbs from `node`, the leaf at `idx`, to the root along `auth`. The tree
+/// index in `adrs` must already hold `idx`; heights start at `height + 1`.
+fn climb<P: SlhParams>(node: &mut Node, idx: u32, auth: &[u8], pk_seed: &[u8], adrs: &mut Adrs) {
+  let mut pair = [[0u8; MAX_N]; 2];
+  for (k, sibling) in auth.chunks_exact(P::N).enumerate() {
+    adrs.set_tree_height(k as u32 + 1);
+    let right = (idx >> k) & 1;
+    adrs.set_tree_index(adrs.tree_index() >> 1);
+    pair[right as usize][..P::N].copy_from_slice(&node[..P::N]);
+    pair[1 - right as usize][..P::N].copy_from_slice(sibling);
+    thash::<P>(node, pk_seed, adrs, &pair);
+  }
+}
+
+/// Algorithm 11, `xmss_pkFromSig`.
+fn xmss_pk_from_sig<P: SlhParams>(root: &mut Node, idx: u32, sig: &[u8], msg: &[u8], pk_seed: &[u8], adrs: &mut Adrs) {
+  let (wots, auth) = sig.split_at(P::LEN * P::N);
+  adrs.set_type_and_clear(WOTS_HASH);
+  adrs.set_key_pair(idx);
+  wots_pk_from_sig::<P>(root, wots, msg, pk_seed, adrs);
+  adrs.set_type_and_clear(TREE);
+  adrs.set_tree_index(idx);
+  climb::<P>(root, idx, auth, pk_seed, adrs);
+}
+
+fn leaf_mask<P: SlhParams>() -> u64 {
+  (1 << P::HP) - 1
+}
+
+/// Algorithm 12, `ht_sign`.
+fn ht_sign<P: SlhParams>(sig: &mut [u8], msg: &Node, seeds: &Seeds, mut tree: u64, mut leaf: u32) {
+  let mut adrs = Adrs::default();
+  let mut root = *msg;
+  for (j, layer) in sig.chunks_exact_mut(P::XMSS_BYTES).enumerate() {
+    if j > 0 {
+      leaf = (tree & leaf_mask::<P>()) as u32;
+      tree >>= P::HP;
+    }
+    adrs.set_layer(j as u32);
+    adrs.set_tree(tree);
+    xmss_sign::<P>(layer, &root[..P::N], seeds, leaf, &mut adrs);
+    if j + 1 < P::D {
+      let msg = root;
+      xmss_pk_from_sig::<P>(&mut root, leaf, layer, &msg[..P::N], seeds.pk_seed, &mut adrs);
+    }
+  }
+}
+
+/// Algorithm 13, `ht_verify`, returning the root the signature leads to.
+fn ht_root<P: SlhParams>(root: &mut Node, sig: &[u8], msg: &Node, pk_seed: &[u8], mut tree: u64, mut leaf: u32) {
+  let mut adrs = Adrs::default();
+  *root = *msg;
+  for (j, layer) in sig.chunks_exact(P::XMSS_BYTES).enumerate() {
+    if j > 0 {
+      leaf = (tree & leaf_mask::<P>()) as u32;
+      tree >>= P::HP;
+    }
+    adrs.set_layer(j as u32);
+    adrs.set_tree(tree);
+    let msg = *root;
+    xmss_pk_from_sig::<P>(root, leaf, layer, &msg[..P::N], pk_seed, &mut adrs);
+  }
+}
+
+/// Algorithm 14, `fors_skGen`.
+fn fors_sk<P: SlhParams>(sk: &mut Node, seeds: &Seeds, adrs: &Adrs, idx: u32) {
+  let mut sk_adrs = *adrs;
+  sk_adrs.set_type_and_clear(FORS_PRF);
+  sk_adrs.set_key_pair(adrs.key_pair());
+  sk_adrs.set_tree_index(idx);
+  prf::<P>(sk, seeds, &sk_adrs);
+}
+
+/// Algorithm 15, `fors_node`.
+fn fors_node<P: SlhParams>(node: &mut Node, seeds: &Seeds, i: u32, z: usize, adrs: &mut Adrs) {
+  if z == 0 {
+    let mut sk = [0u8; MAX_N];
+    fors_sk::<P>(&mut sk, seeds, adrs, i);
+    adrs.set_tree_height(0);
+    adrs.set_tree_index(i);
+    thash::<P>(node, seeds.pk_seed, adrs, &[sk]);
+    #[cfg(feature = "zeroize")]
+    sk.zeroize();
+    return;
+  }
+  let mut children = [[0u8; MAX_N]; 2];
+  fors_node::<P>(&mut children[0], seeds, 2 * i, z - 1, adrs);
+  fors_node::<P>(&mut children[1], seeds, 2 * i + 1, z - 1, adrs);
+  adrs.set_tree_height(z as u32);
+  adrs.set_tree_index(i);
+  thash::<P>(node, seeds.pk_seed, adrs, &children);
+}
+
+/// Algorithm 16, `fors_sign`.
+fn fors_sign<P: SlhParams>(sig: &mut [u8], md: &[u8], seeds: &Seeds, adrs: &mut Adrs) {
+  let mut indices = [0u32; MAX_K];
+  base_2b(md, P::A, &mut indices[..P::K]);
+  let mut node = [0u8; MAX_N];
+  for (i, tree) in sig.chunks_exact_mut((1 + P::A) * P::N).enumerate() {
+    let (sk, auth) = tree.split_at_mut(P::N);
+    let base = (i as u32) << P::A;
+    fors_sk::<P>(&mut node, seeds, adrs, base + indices[i]);
+    sk.copy_from_slice(&node[..P::N]);
+    for j in 0..P::A {
+      let sibling = (indices[i] >> j) ^ 1;
+      fors_node::<P>(&mut node, seeds, (base >> j) + sibling, j, adrs);
+      auth[j * P::N..(j + 1) * P::N].copy_from_slice(&node[..P::N]);
+    }
+  }
+  #[cfg(feature = "zeroize")]
+  node.zeroize();
+}
+
+/// Algorithm 17, `fors_pkFromSig`.
+fn fors_pk_from_sig<P: SlhParams>(pk: &mut Node, sig: &[u8], md: &[u8], pk_seed: &[u8], adrs: &mut Adrs) {
+  let mut indices = [0u32; MAX_K];
+  base_2b(md, P::A, &mut indices[..P::K]);
+  let mut roots = [[0u8; MAX_N]; MAX_K];
+  for (i, tree) in sig.chunks_exact((1 + P::A) * P::N).enumerate() {
+    let (sk, auth) = tree.split_at(P::N);
+    let idx = ((i as u32) << P::A) + indices[i];
+    let mut leaf = [0u8; MAX_N];
+    leaf[..P::N].copy_from_slice(sk);
+    adrs.set_tree_height(0);
+    adrs.set_tree_index(idx);
+    thash::<P>(&mut roots[i], pk_seed, adrs, &[leaf]);
+    climb::<P>(&mut roots[i], idx, auth, pk_seed, adrs);
+  }
+  let mut pk_adrs = *adrs;
+  pk_adrs.set_type_and_clear(FORS_ROOTS);
+  pk_adrs.set_key_pair(adrs.key_pair());
+  thash::<P>(pk, pk_seed, &pk_adrs, &roots[..P::K]);
+}
+
+/// Splits `H_msg` output into the FORS message digest and the hypertree
+/// tree and leaf indices (algorithm 19, lines 7-11).
+fn split_digest<P: SlhParams>(digest: &[u8]) -> (&[u8], u64, u32) {
+  let md_len = (P::K * P::A).div_ceil(8);
+  let tree_bits = P::H - P::HP;
+  let tree_len = tree_bits.div_ceil(8);
+  let (md, rest) = digest.split_at(md_len);
+  let to_int = |bytes: &[u8]| bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+  let tree = to_int(&rest[..tree_len]) & (u64::MAX >> (64 - tree_bits));
+  let leaf = to_int(&rest[tree_len..]) & leaf_mask::<P>();
+  (md, tree, leaf as u32)
+}
+
+/// Domain separator and context prefix of the pure signing interface,
+/// `M' = 0 || |ctx| || ctx || M` with an empty context.
+const PURE_PREFIX: [u8; 2] = [0, 0];
+
+/// `H_msg(R, PK.seed, PK.root, M')`.
+fn h_msg<P: SlhParams>(digest: &mut [u8], r: &[u8], pk: &[u8], msg: &[u8]) {
+  shake256_parts(&mut digest[..P::M], &[r, pk, &PURE_PREFIX, msg]);
+}
+
+/// Algorithm 18, `slh_keygen_internal`, from the `3n`-byte seed
+/// `SK.seed || SK.prf || PK.seed`.
+pub fn slh_keypair_from_seed<P: SlhParams>(pk: &mut [u8], sk: &mut [u8], seed: &[u8]) {
+  let n = P::N;
+  sk[..3 * n].copy_from_slice(&seed[..3 * n]);
+  let seeds = Seeds { pk_seed: &seed[2 * n..3 * n], sk_seed: &seed[..n] };
+  let mut adrs = Adrs::default();
+  adrs.set_layer(P::D as u32 - 1);
+  let mut root = [0u8; MAX_N];
+  xmss_node::<P>(&mut root, &seeds, 0, P::HP, &mut adrs);
+  sk[3 * n..].copy_from_slice(&root[..n]);
+  pk.copy_from_slice(&sk[2 * n..]);
+}
+
+/// Algorithm 21, `slh_keygen`.
+pub fn slh_keypair<P: SlhParams, R: RngCore>(pk: &mut [u8], sk: &mut [u8], rng: &mut R) {
+  let mut seed = SecretBytes::<{ 3 * MAX_N }>::default();
+  randombytes(&mut seed[..3 * P::N], rng);
+  slh_keypair_from_seed::<P>(pk, sk, &seed);
+}
+
+/// Algorithms 19 and 22, `slh_sign` with an empty context. `addrnd` is the
+/// `n`-byte hedging randomness; `None` gives the deterministic variant.
+pub fn slh_sign<P: SlhParams>(sig: &mut [u8], msg: &[u8], sk: &[u8], addrnd: Option<&[u8]>) {
+  let n = P::N;
+  let (sk_seed, rest) = sk.split_at(n);
+  let (sk_prf, pk) = rest.split_at(n);
+  let seeds = Seeds { pk_seed: &pk[..n], sk_seed };
+
+  let (r, rest) = sig.split_at_mut(n);
+  let opt_rand = addrnd.unwrap_or(&pk[..n]);
+  shake256_parts(r, &[sk_prf, opt_rand, &PURE_PREFIX, msg]);
+
+  let mut digest = [0u8; MAX_M];
+  h_msg::<P>(&mut digest, r, pk, msg);
+  let (md, tree, leaf) = split_digest::<P>(&digest[..P::M]);
+
+  let (fors, ht) = rest.split_at_mut(P::FORS_BYTES);
+  let mut adrs = Adrs::default();
+  adrs.set_tree(tree);
+  adrs.set_type_and_clear(FORS_TREE);
+  adrs.set_key_pair(leaf);
+  fors_sign::<P>(fors, md, &seeds, &mut adrs);
+  let mut fors_pk = [0u8; MAX_N];
+  fors_pk_from_sig::<P>(&mut fors_pk, fors, md, seeds.pk_seed, &mut adrs);
+  ht_sign::<P>(ht, &fors_pk, &seeds, tree, leaf);
+}
+
+/// Algorithms 20 and 24, `slh_verify` with an empty context.
+pub fn slh_verify<P: SlhParams>(sig: &[u8], msg: &[u8], pk: &[u8]) -> Result<(), &'static str> {
+  if pk.len() != P::PUBLIC_KEY_BYTES {
+    return Err("Invalid public key length");
+  }
+  if sig.len() != P::SIGNBYTES {
+    return Err("Invalid signature length");
+  }
+  let n = P::N;
+  let (r, rest) = sig.split_at(n);
+  let (fors, ht) = rest.split_at(P::FORS_BYTES);
+
+  let mut digest = [0u8; MAX_M];
+  h_msg::<P>(&mut digest, r, pk, msg);
+  let (md, tree, leaf) = split_digest::<P>(&digest[..P::M]);
+
+  let mut adrs = Adrs::default();
+  adrs.set_tree(tree);
+  adrs.set_type_and_clear(FORS_TREE);
+  adrs.set_key_pair(leaf);
+  let mut fors_pk = [0u8; MAX_N];
+  fors_pk_from_sig::<P>(&mut fors_pk, fors, md, &pk[..n], &mut adrs);
+  let mut root = [0u8; MAX_N];
+  ht_root::<P>(&mut root, ht, &fors_pk, &pk[..n], tree, leaf);
+  if root[..n] != pk[n..] {
+    return Err("Signature verification failed");
+  }
+  Ok(())
+}
+
+/// Checks that `pk` is the public half stored in `sk` and that `PK.root`
+/// is the root of the top tree grown from `SK.seed` and `PK.seed`.
+pub fn slh_check_keypair<P: SlhParams>(pk: &[u8], sk: &[u8]) -> Result<(), &'static str> {
+  let n = P::N;
+  if *pk != sk[2 * n..] {
+    return Err("Public key does not match the secret key");
+  }
+  let seeds = Seeds { pk_seed: &sk[2 * n..3 * n], sk_seed: &sk[..n] };
+  let mut adrs = Adrs::default();
+  adrs.set_layer(P::D as u32 - 1);
+  let mut root = [0u8; MAX_N];
+  xmss_node::<P>(&mut root, &seeds, 0, P::HP, &mut adrs);
+  if root[..n] != sk[3 * n..] {
+    return Err("PK.root does not match SK.seed");
+  }
+  Ok(())
+}
@@ -0,0 +1,437 @@
+//! SLH-DSA (FIPS 205), the stateless hash-based signature scheme, with the
+//! six SHAKE parameter sets.
+//!
+//! The API mirrors the ML-DSA one at the crate root: an [`Algorithm`] picks
+//! the parameter set, a [`Keypair`] holds both halves and signs, and a
+//! [`Signature`] carries the bytes. Signing uses the pure interface with an
+//! empty context string, so signatures interoperate with other FIPS 205
+//! implementations. The `s` sets have small signatures and slow signing,
+//! the `f` sets the reverse; see the size methods on `Algorithm`.
+
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::secret::SecretBytes;
+use crate::slh::*;
+
+pub use crate::slh::SlhParams;
+pub use crate::slh::{Shake128f, Shake128s, Shake192f, Shake192s, Shake256f, Shake256s};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Shake128s,
+    Shake128f,
+    Shake192s,
+    Shake192f,
+    Shake256s,
+    Shake256f,
+}
+
+impl Algorithm {
+    pub const fn public_key_bytes(&self) -> usize {
+        match self {
+            Algorithm::Shake128s => Shake128s::PUBLIC_KEY_BYTES,
+            Algorithm::Shake128f => Shake128f::PUBLIC_KEY_BYTES,
+            Algorithm::Shake192s => Shake192s::PUBLIC_KEY_BYTES,
+            Algorithm::Shake192f => Shake192f::PUBLIC_KEY_BYTES,
+            Algorithm::Shake256s => Shake256s::PUBLIC_KEY_BYTES,
+            Algorithm::Shake256f => Shake256f::PUBLIC_KEY_BYTES,
+        }
+    }
+
+    pub const fn secret_key_bytes(&self) -> usize {
+        match self {
+            Algorithm::Shake128s => Shake128s::SECRET_KEY_BYTES,
+            Algorithm::Shake128f => Shake128f::SECRET_KEY_BYTES,
+            Algorithm::Shake192s => Shake192s::SECRET_KEY_BYTES,
+            Algorithm::Shake192f => Shake192f::SECRET_KEY_BYTES,
+            Algorithm::Shake256s => Shake256s::SECRET_KEY_BYTES,
+            Algorithm::Shake256f => Shake256f::SECRET_KEY_BYTES,
+        }
+    }
+
+    pub const fn signature_bytes(&self) -> usize {
+        match self {
+            Algorithm::Shake128s => Shake128s::SIGNBYTES,
+            Algorithm::Shake128f => Shake128f::SIGNBYTES,
+            Algorithm::Shake192s => Shake192s::SIGNBYTES,
+            Algorithm::Shake192f => Shake192f::SIGNBYTES,
+            Algorithm::Shake256s => Shake256s::SIGNBYTES,
+            Algorithm::Shake256f => Shake256f::SIGNBYTES,
+        }
+    }
+
+    /// Bytes of `SK.seed || SK.prf || PK.seed`, the input of `generate_from_seed`.
+    pub const fn seed_bytes(&self) -> usize {
+        3 * self.public_key_bytes() / 2
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn generate(&self) -> Keypair {
+        self.generate_with_rng(&mut OsRng)
+    }
+
+    pub fn generate_with_rng<R: RngCore>(&self, rng: &mut R) -> Keypair {
+        match self {
+            Algorithm::Shake128s => {
+                let mut pk = [0u8; Shake128s::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake128s::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake128s, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake128s(pk, sk)
+            }
+            Algorithm::Shake128f => {
+                let mut pk = [0u8; Shake128f::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake128f::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake128f, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake128f(pk, sk)
+            }
+            Algorithm::Shake192s => {
+                let mut pk = [0u8; Shake192s::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake192s::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake192s, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake192s(pk, sk)
+            }
+            Algorithm::Shake192f => {
+                let mut pk = [0u8; Shake192f::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake192f::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake192f, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake192f(pk, sk)
+            }
+            Algorithm::Shake256s => {
+                let mut pk = [0u8; Shake256s::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake256s::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake256s, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake256s(pk, sk)
+            }
+            Algorithm::Shake256f => {
+                let mut pk = [0u8; Shake256f::PUBLIC_KEY_BYTES];
+                let mut sk = SecretBytes::<{ Shake256f::SECRET_KEY_BYTES }>::default();
+                slh_keypair::<Shake256f, R>(&mut pk, &mut sk, rng);
+                Keypair::Shake256f(pk, sk)
+            }
+        }
+    }
+
+    /// Deterministic key generation from `seed_bytes()` bytes of
+    /// `SK.seed || SK.prf || PK.seed` (`slh_keygen_internal`).
+    pub fn generate_from_seed(&self, seed: &[u8]) -> Result<Keypair, &'static str> {
+        if seed.len() != self.seed_bytes() {
+            return Err("Invalid seed length");
+        }
+        let mut sk = [0u8; 4 * 32];
+        let mut pk = [0u8; 2 * 32];
+        let (sk, pk) = (&mut sk[..self.secret_key_bytes()], &mut pk[..self.public_key_bytes()]);
+        self.keypair_from_seed(pk, sk, seed);
+        let keypair = self.load_unchecked(sk, pk);
+        #[cfg(feature = "zeroize")]
+        sk.zeroize();
+        Ok(keypair)
+    }
+
+    /// Generates a keypair straight into caller-provided buffers of
+    /// `public_key_bytes()` and `secret_key_bytes()` bytes.
+    pub fn generate_into<R: RngCore>(&self, pk: &mut [u8], sk: &mut [u8], rng: &mut R) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key buffer lengths");
+        }
+        match self {
+            Algorithm::Shake128s => slh_keypair::<Shake128s, R>(pk, sk, rng),
+            Algorithm::Shake128f => slh_keypair::<Shake128f, R>(pk, sk, rng),
+            Algorithm::Shake192s => slh_keypair::<Shake192s, R>(pk, sk, rng),
+            Algorithm::Shake192f => slh_keypair::<Shake192f, R>(pk, sk, rng),
+            Algorithm::Shake256s => slh_keypair::<Shake256s, R>(pk, sk, rng),
+            Algorithm::Shake256f => slh_keypair::<Shake256f, R>(pk, sk, rng),
+        }
+        Ok(())
+    }
+
+    fn keypair_from_seed(&self, pk: &mut [u8], sk: &mut [u8], seed: &[u8]) {
+        match self {
+            Algorithm::Shake128s => slh_keypair_from_seed::<Shake128s>(pk, sk, seed),
+            Algorithm::Shake128f => slh_keypair_from_seed::<Shake128f>(pk, sk, seed),
+            Algorithm::Shake192s => slh_keypair_from_seed::<Shake192s>(pk, sk, seed),
+            Algorithm::Shake192f => slh_keypair_from_seed::<Shake192f>(pk, sk, seed),
+            Algorithm::Shake256s => slh_keypair_from_seed::<Shake256s>(pk, sk, seed),
+            Algorithm::Shake256f => slh_keypair_from_seed::<Shake256f>(pk, sk, seed),
+        }
+    }
+
+    /// Builds a `Keypair` from its encoded halves after the consistency
+    /// check of `check_keypair`.
+    pub fn load_from_bytes(&self, sk: &[u8], pk: &[u8]) -> Result<Keypair, &'static str> {
+        self.check_keypair(sk, pk)?;
+        Ok(self.load_unchecked(sk, pk))
+    }
+
+    fn load_unchecked(&self, sk: &[u8], pk: &[u8]) -> Keypair {
+        match self {
+            Algorithm::Shake128s => {
+                let mut pk_buf = [0u8; Shake128s::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake128s::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake128s(pk_buf, sk_buf)
+            }
+            Algorithm::Shake128f => {
+                let mut pk_buf = [0u8; Shake128f::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake128f::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake128f(pk_buf, sk_buf)
+            }
+            Algorithm::Shake192s => {
+                let mut pk_buf = [0u8; Shake192s::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake192s::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake192s(pk_buf, sk_buf)
+            }
+            Algorithm::Shake192f => {
+                let mut pk_buf = [0u8; Shake192f::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake192f::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake192f(pk_buf, sk_buf)
+            }
+            Algorithm::Shake256s => {
+                let mut pk_buf = [0u8; Shake256s::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake256s::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake256s(pk_buf, sk_buf)
+            }
+            Algorithm::Shake256f => {
+                let mut pk_buf = [0u8; Shake256f::PUBLIC_KEY_BYTES];
+                let mut sk_buf = SecretBytes::<{ Shake256f::SECRET_KEY_BYTES }>::default();
+                pk_buf.copy_from_slice(pk);
+                sk_buf.copy_from_slice(sk);
+                Keypair::Shake256f(pk_buf, sk_buf)
+            }
+        }
+    }
+
+    /// Checks the lengths, that `pk` is the copy stored in `sk`, and that
+    /// `PK.root` is the root grown from `SK.seed` and `PK.seed`.
+    pub fn check_keypair(&self, sk: &[u8], pk: &[u8]) -> Result<(), &'static str> {
+        if pk.len() != self.public_key_bytes() || sk.len() != self.secret_key_bytes() {
+            return Err("Invalid key lengths");
+        }
+        match self {
+            Algorithm::Shake128s => slh_check_keypair::<Shake128s>(pk, sk),
+            Algorithm::Shake128f => slh_check_keypair::<Shake128f>(pk, sk),
+            Algorithm::Shake192s => slh_check_keypair::<Shake192s>(pk, sk),
+            Algorithm::Shake192f => slh_check_keypair::<Shake192f>(pk, sk),
+            Algorithm::Shake256s => slh_check_keypair::<Shake256s>(pk, sk),
+            Algorithm::Shake256f => slh_check_keypair::<Shake256f>(pk, sk),
+        }
+    }
+
+    /// Signs `msg` with a raw secret key into a caller-provided buffer of
+    /// `signature_bytes()` bytes. `rng` supplies the hedging randomness.
+    pub fn sign_into<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        let mut addrnd = [0u8; 32];
+        let addrnd = &mut addrnd[..self.public_key_bytes() / 2];
+        rng.fill_bytes(addrnd);
+        self.sign_into_internal(sig, msg, sk, Some(addrnd))
+    }
+
+    /// The deterministic variant of `sign_into`: the same message and key
+    /// always give the same signature.
+    pub fn sign_into_deterministic(&self, sig: &mut [u8], msg: &[u8], sk: &[u8]) -> Result<(), &'static str> {
+        self.sign_into_internal(sig, msg, sk, None)
+    }
+
+    fn sign_into_internal(&self, sig: &mut [u8], msg: &[u8], sk: &[u8], addrnd: Option<&[u8]>) -> Result<(), &'static str> {
+        if sk.len() != self.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        if sig.len() != self.signature_bytes() {
+            return Err("Invalid signature buffer length");
+        }
+        match self {
+            Algorithm::Shake128s => slh_sign::<Shake128s>(sig, msg, sk, addrnd),
+            Algorithm::Shake128f => slh_sign::<Shake128f>(sig, msg, sk, addrnd),
+            Algorithm::Shake192s => slh_sign::<Shake192s>(sig, msg, sk, addrnd),
+            Algorithm::Shake192f => slh_sign::<Shake192f>(sig, msg, sk, addrnd),
+            Algorithm::Shake256s => slh_sign::<Shake256s>(sig, msg, sk, addrnd),
+            Algorithm::Shake256f => slh_sign::<Shake256f>(sig, msg, sk, addrnd),
+        }
+        Ok(())
+    }
+
+    pub fn verify(&self, signature: &Signature, msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
+        self.verify_raw(signature.bytes(), msg, public_key)
+    }
+
+    pub fn verify_raw(&self, signature: &[u8], msg: &[u8], public_key: &[u8]) -> Result<(), &'static str> {
+        match self {
+            Algorithm::Shake128s => slh_verify::<Shake128s>(signature, msg, public_key),
+            Algorithm::Shake128f => slh_verify::<Shake128f>(signature, msg, public_key),
+            Algorithm::Shake192s => slh_verify::<Shake192s>(signature, msg, public_key),
+            Algorithm::Shake192f => slh_verify::<Shake192f>(signature, msg, public_key),
+            Algorithm::Shake256s => slh_verify::<Shake256s>(signature, msg, public_key),
+            Algorithm::Shake256f => slh_verify::<Shake256f>(signature, msg, public_key),
+        }
+    }
+}
+
+/// An SLH-DSA public key and its secret key. The secret key embeds the
+/// public key, so `Keypair::from_secret_key` restores both from one blob.
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+#[derive(Debug)]
+pub enum Keypair {
+    Shake128s([u8; Shake128s::PUBLIC_KEY_BYTES], SecretBytes<{ Shake128s::SECRET_KEY_BYTES }>),
+    Shake128f([u8; Shake128f::PUBLIC_KEY_BYTES], SecretBytes<{ Shake128f::SECRET_KEY_BYTES }>),
+    Shake192s([u8; Shake192s::PUBLIC_KEY_BYTES], SecretBytes<{ Shake192s::SECRET_KEY_BYTES }>),
+    Shake192f([u8; Shake192f::PUBLIC_KEY_BYTES], SecretBytes<{ Shake192f::SECRET_KEY_BYTES }>),
+    Shake256s([u8; Shake256s::PUBLIC_KEY_BYTES], SecretBytes<{ Shake256s::SECRET_KEY_BYTES }>),
+    Shake256f([u8; Shake256f::PUBLIC_KEY_BYTES], SecretBytes<{ Shake256f::SECRET_KEY_BYTES }>),
+}
+
+impl Keypair {
+    /// Rebuilds a `Keypair` from the secret key alone, after checking that
+    /// its `PK.root` matches the tree grown from its seeds.
+    pub fn from_secret_key(alg: Algorithm, sk: &[u8]) -> Result<Keypair, &'static str> {
+        if sk.len() != alg.secret_key_bytes() {
+            return Err("Invalid secret key length");
+        }
+        alg.load_from_bytes(sk, &sk[alg.secret_key_bytes() / 2..])
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.sign_with_rng(msg, &mut OsRng)
+    }
+
+    pub fn sign_with_rng<R: RngCore>(&self, msg: &[u8], rng: &mut R) -> Signature {
+        let mut addrnd = [0u8; 32];
+        let addrnd = &mut addrnd[..self.algorithm().public_key_bytes() / 2];
+        rng.fill_bytes(addrnd);
+        self.sign_internal(msg, Some(addrnd))
+    }
+
+    /// Signs `msg` with the deterministic variant: no randomness, and the
+    /// same message always gives the same signature.
+    pub fn sign_deterministic(&self, msg: &[u8]) -> Signature {
+        self.sign_internal(msg, None)
+    }
+
+    fn sign_internal(&self, msg: &[u8], addrnd: Option<&[u8]>) -> Signature {
+        match self {
+            Keypair::Shake128s(_, sk) => {
+                let mut sig = [0u8; Shake128s::SIGNBYTES];
+                slh_sign::<Shake128s>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake128s(sig)
+                }
+            }
+            Keypair::Shake128f(_, sk) => {
+                let mut sig = [0u8; Shake128f::SIGNBYTES];
+                slh_sign::<Shake128f>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake128f(sig)
+                }
+            }
+            Keypair::Shake192s(_, sk) => {
+                let mut sig = [0u8; Shake192s::SIGNBYTES];
+                slh_sign::<Shake192s>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake192s(sig)
+                }
+            }
+            Keypair::Shake192f(_, sk) => {
+                let mut sig = [0u8; Shake192f::SIGNBYTES];
+                slh_sign::<Shake192f>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake192f(sig)
+                }
+            }
+            Keypair::Shake256s(_, sk) => {
+                let mut sig = [0u8; Shake256s::SIGNBYTES];
+                slh_sign::<Shake256s>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake256s(sig)
+                }
+            }
+            Keypair::Shake256f(_, sk) => {
+                let mut sig = [0u8; Shake256f::SIGNBYTES];
+                slh_sign::<Shake256f>(&mut sig, msg, sk, addrnd);
+                Signature {
+                    bytes: SignType::Shake256f(sig)
+                }
+            }
+        }
+    }
+
+    /// Signs `msg` into a caller-provided buffer of the set's signature size.
+    pub fn sign_into<R: RngCore>(&self, sig: &mut [u8], msg: &[u8], rng: &mut R) -> Result<(), &'static str> {
+        self.algorithm().sign_into(sig, msg, self.secret(), rng)
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            Keypair::Shake128s(..) => Algorithm::Shake128s,
+            Keypair::Shake128f(..) => Algorithm::Shake128f,
+            Keypair::Shake192s(..) => Algorithm::Shake192s,
+            Keypair::Shake192f(..) => Algorithm::Shake192f,
+            Keypair::Shake256s(..) => Algorithm::Shake256s,
+            Keypair::Shake256f(..) => Algorithm::Shake256f,
+        }
+    }
+
+    pub fn public(&self) -> &[u8] {
+        match self {
+            Keypair::Shake128s(pk, _) => pk,
+            Keypair::Shake128f(pk, _) => pk,
+            Keypair::Shake192s(pk, _) => pk,
+            Keypair::Shake192f(pk, _) => pk,
+            Keypair::Shake256s(pk, _) => pk,
+            Keypair::Shake256f(pk, _) => pk,
+        }
+    }
+
+    pub fn secret(&self) -> &[u8] {
+        match self {
+            Keypair::Shake128s(_, sk) => &sk[..],
+            Keypair::Shake128f(_, sk) => &sk[..],
+            Keypair::Shake192s(_, sk) => &sk[..],
+            Keypair::Shake192f(_, sk) => &sk[..],
+            Keypair::Shake256s(_, sk) => &sk[..],
+            Keypair::Shake256f(_, sk) => &sk[..],
+        }
+    }
+}
+
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+pub struct Signature {
+    bytes: SignType
+}
+
+impl Signature {
+    #[inline(always)]
+    pub fn bytes(&self) -> &[u8] {
+        match &self.bytes {
+            SignType::Shake128s(arr) => &arr[..],
+            SignType::Shake128f(arr) => &arr[..],
+            SignType::Shake192s(arr) => &arr[..],
+            SignType::Shake192f(arr) => &arr[..],
+            SignType::Shake256s(arr) => &arr[..],
+            SignType::Shake256f(arr) => &arr[..],
+        }
+    }
+}
+
+#[cfg_attr(feature = "zeroize", derive(Zeroize, ZeroizeOnDrop))]
+#[allow(clippy::large_enum_variant)]
+enum SignType {
+    Shake128s([u8; Shake128s::SIGNBYTES]),
+    Shake128f([u8; Shake128f::SIGNBYTES]),
+    Shake192s([u8; Shake192s::SIGNBYTES]),
+    Shake192f([u8; Shake192f::SIGNBYTES]),
+    Shake256s([u8; Shake256s::SIGNBYTES]),
+    Shake256f([u8; Shake256f::SIGNBYTES]),
+}
@@ -0,0 +1,1094 @@
+//! OpenSSH formats with ML-DSA keys: public keys and `authorized_keys`
+//! lines, the `openssh-key-v1` private key container, and SSHSIG
+//! signatures (the `ssh-keygen -Y sign` format).
+//!
+//! Keys are named `ssh-mldsa-44`, `ssh-mldsa-65` and `ssh-mldsa-87`, as in
+//! the IETF draft for ML-DSA in SSH; a public key blob is the name followed
+//! by the encoded public key, and a signature blob the name followed by the
+//! raw signature. [`PrivateKey`] keeps the 32-byte seed, which the
+//! unencrypted container stores in place of the expanded secret key.
+//! [`SshSig`] hashes the message with SHA-256 or SHA-512 and signs it under
+//! a namespace, so that a signature made for one purpose (`git`, `file`)
+//! is not accepted for another.
+//!
+//! [`CertificateParams`] issues user and host certificates
+//! (`ssh-mldsa-65-cert-v01@openssh.com` and so on) signed by an ML-DSA CA
+//! key, and [`Certificate`] checks them against a CA directly or through
+//! the `cert-authority` lines of `authorized_keys` and the
+//! `@cert-authority` lines of `known_hosts` ([`KnownHost`]).
+//!
+//! See [FIPS 204 interoperability](crate#fips-204-interoperability) for which
+//! verifiers accept these signatures, and why a FIPS 204 implementation
+//! would derive another key from the seed in the private key container.
+
+use alloc::{string::String, vec::Vec};
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+use crate::base64;
+use crate::sha2::{sha256, sha512};
+use crate::secret::wipe;
+use crate::ssh_wire::{Reader, put_string, put_u32, put_u64};
+use crate::{Algorithm, Keypair, SeedKey};
+
+const PRIVATE_KEY_MAGIC: &[u8] = b"openssh-key-v1\0";
+const PRIVATE_KEY_LABEL: &str = "OPENSSH PRIVATE KEY";
+/// The unencrypted private section is padded to this block size.
+const PRIVATE_KEY_BLOCK: usize = 8;
+
+const SSHSIG_MAGIC: &[u8] = b"SSHSIG";
+const SSHSIG_VERSION: u32 = 1;
+const SSHSIG_LABEL: &str = "SSH SIGNATURE";
+
+/// OpenSSH wraps its armored keys and signatures at 70 columns.
+const ARMOR_WIDTH: usize = 70;
+
+fn algorithm_name(alg: Algorithm) -> &'static str {
+    match alg {
+        #[cfg(feature = "mode2")]
+        Algorithm::Mode2 => "ssh-mldsa-44",
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3 => "ssh-mldsa-65",
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5 => "ssh-mldsa-87",
+    }
+}
+
+fn algorithm_from_name(name: &[u8]) -> Result<Algorithm, &'static str> {
+    Algorithm::find(|alg| algorithm_name(alg).as_bytes() == name).ok_or("Unsupported key type")
+}
+
+/// The signature blob for `msg`: the key type name and the raw signature.
+fn sign_blob<R: RngCore>(keypair: &Keypair, msg: &[u8], rng: &mut R) -> Vec<u8> {
+    let signature = keypair.sign_with_rng(msg, rng);
+    let mut blob = Vec::new();
+    put_string(&mut blob, algorithm_name(keypair.algorithm()).as_bytes());
+    put_string(&mut blob, signature.bytes());
+    blob
+}
+
+/// Splits off the first whitespace-separated token of `s`, treating
+/// double-quoted runs as part of the token.
+fn split_token(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ' ' | '\t' if !quoted => return (&s[..i], s[i..].trim_start()),
+            _ => {}
+        }
+    }
+    (s, "")
+}
+
+/// An ML-DSA public key in SSH form, with the comment of its text line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    algorithm: Algorithm,
+    public_key: Vec<u8>,
+    comment: String,
+}
+
+impl PublicKey {
+    pub fn new(algorithm: Algorithm, public_key: &[u8]) -> Result<Self, &'static str> {
+        if public_key.len() != algorithm.public_key_bytes() {
+            return Err("Invalid public key length");
+        }
+        Ok(PublicKey { algorithm, public_key: public_key.to_vec(), comment: String::new() })
+    }
+
+    pub fn from_keypair(keypair: &Keypair) -> Self {
+        PublicKey { algorithm: keypair.algorithm(), public_key: keypair.public().to_vec(), comment: String::new() }
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Whether both hold the same key, whatever their comments.
+    pub fn same_key(&self, other: &PublicKey) -> bool {
+        self.algorithm == other.algorithm && self.public_key == other.public_key
+    }
+
+    /// The public key blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut blob = Vec::new();
+        put_string(&mut blob, algorithm_name(self.algorithm).as_bytes());
+        put_string(&mut blob, &self.public_key);
+        blob
+    }
+
+    pub fn from_bytes(blob: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(blob);
+        let key = Self::read(&mut r)?;
+        r.finish()?;
+        Ok(key)
+    }
+
+    fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let algorithm = algorithm_from_name(r.read_string()?)?;
+        Self::new(algorithm, r.read_string()?)
+    }
+
+    /// The `authorized_keys` form: type, base64 blob and comment.
+    pub fn to_openssh(&self) -> String {
+        let mut line = String::from(algorithm_name(self.algorithm));
+        line.push(' ');
+        line.push_str(&base64::encode(&self.to_bytes()));
+        if !self.comment.is_empty() {
+            line.push(' ');
+            line.push_str(&self.comment);
+        }
+        line
+    }
+
+    /// Parses `type base64 [comment]`; the type must match the blob's.
+    pub fn from_openssh(line: &str) -> Result<Self, &'static str> {
+        let (name, rest) = split_token(line.trim());
+        let algorithm = algorithm_from_name(name.as_bytes())?;
+        let (blob, comment) = split_token(rest);
+        let key = Self::from_bytes(&base64::decode(blob)?)?;
+        if algorithm != key.algorithm {
+            return Err("Key type mismatch");
+        }
+        Ok(key.with_comment(comment))
+    }
+
+    /// The `SHA256:` fingerprint `ssh-keygen -l` prints.
+    pub fn fingerprint(&self) -> String {
+        let mut fp = String::from("SHA256:");
+        fp.push_str(base64::encode(&sha256(&self.to_bytes())).trim_end_matches('='));
+        fp
+    }
+
+    /// Verifies a signature blob over `msg`.
+    pub fn verify(&self, signature: &[u8], msg: &[u8]) -> Result<(), &'static str> {
+        let mut r = Reader::new(signature);
+        if algorithm_from_name(r.read_string()?)? != self.algorithm {
+            return Err("Signature algorithm does not match the key");
+        }
+        let raw = r.read_string()?;
+        r.finish()?;
+        self.algorithm.verify_raw(raw, msg, &self.public_key)
+    }
+}
+
+/// One `authorized_keys` line: its options and key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorizedKey {
+    options: Vec<(String, Option<String>)>,
+    key: PublicKey,
+}
+
+impl AuthorizedKey {
+    pub fn new(key: PublicKey) -> Self {
+        AuthorizedKey { options: Vec::new(), key }
+    }
+
+    /// Adds an option such as `no-pty` or, with a value, `from="10.0.0.0/8"`.
+    pub fn with_option(mut self, name: &str, value: Option<&str>) -> Self {
+        self.options.push((name.into(), value.map(Into::into)));
+        self
+    }
+
+    pub fn key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    /// Whether the option is present; names are matched case-insensitively.
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    /// The unquoted value of an option.
+    pub fn option_value(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).and_then(|(_, v)| v.as_deref())
+    }
+
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let line = line.trim();
+        let (first, rest) = split_token(line);
+        if algorithm_from_name(first.as_bytes()).is_ok() {
+            return Ok(Self::new(PublicKey::from_openssh(line)?));
+        }
+        Ok(AuthorizedKey { options: parse_options(first)?, key: PublicKey::from_openssh(rest)? })
+    }
+
+    /// The line, options first.
+    pub fn to_line(&self) -> String {
+        let mut line = String::new();
+        for (name, value) in &self.options {
+            if !line.is_empty() {
+                line.push(',');
+            }
+            line.push_str(name);
+            if let Some(value) = value {
+                line.push_str("=\"");
+                line.push_str(&value.replace('"', "\\\""));
+                line.push('"');
+            }
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&self.key.to_openssh());
+        line
+    }
+}
+
+/// Splits `name,name="value",...`; a `\"` inside a value stands for `"`.
+fn parse_options(s: &str) -> Result<Vec<(String, Option<String>)>, &'static str> {
+    const MALFORMED: &str = "Malformed options";
+    let mut options = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && c != ',') {
+            name.push(c);
+        }
+        if name.is_empty() {
+            return Err(MALFORMED);
+        }
+        let mut value = None;
+        if chars.next_if_eq(&'=').is_some() {
+            if chars.next() != Some('"') {
+                return Err(MALFORMED);
+            }
+            let mut v = String::new();
+            loop {
+                match chars.next().ok_or(MALFORMED)? {
+                    '"' => break,
+                    '\\' if chars.peek() == Some(&'"') => v.push(chars.next().unwrap()),
+                    c => v.push(c),
+                }
+            }
+            value = Some(v);
+        }
+        options.push((name, value));
+        match chars.next() {
+            None => return Ok(options),
+            Some(',') => {}
+            Some(_) => return Err(MALFORMED),
+        }
+    }
+}
+
+/// The ML-DSA keys of an `authorized_keys` file. Comments, blank lines and
+/// lines for other key types are skipped.
+pub fn parse_authorized_keys(text: &str) -> Vec<AuthorizedKey> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| AuthorizedKey::parse(line).ok())
+        .collect()
+}
+
+/// An ML-DSA private key as OpenSSH stores it: a [`SeedKey`] that has its
+/// seed, and the public key with its comment.
+#[derive(Debug)]
+pub struct PrivateKey {
+    key: SeedKey,
+    public_key: PublicKey,
+}
+
+impl PrivateKey {
+    /// Fails for a public-only `key`.
+    pub fn new(key: SeedKey) -> Result<Self, &'static str> {
+        if !key.is_private() {
+            return Err("Key has no seed");
+        }
+        let public_key = PublicKey::new(key.algorithm(), key.public_key())?;
+        Ok(PrivateKey { key, public_key })
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.public_key.comment = comment.into();
+        self
+    }
+
+    pub fn key(&self) -> &SeedKey {
+        &self.key
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// The keypair; unlike `SeedKey::keypair` this cannot fail, since the
+    /// seed is always there.
+    pub fn keypair(&self) -> Keypair {
+        self.key.keypair().unwrap()
+    }
+
+    /// The unencrypted `openssh-key-v1` container, armored.
+    #[cfg(feature = "getrandom")]
+    pub fn to_openssh(&self) -> String {
+        self.to_openssh_with_rng(&mut OsRng)
+    }
+
+    /// Like `to_openssh`, drawing the check value from `rng`.
+    ///
+    /// Every intermediate buffer that holds the seed, raw or base64, is
+    /// sized up front so it never reallocates, and is wiped once the armor
+    /// is built; only the returned string still contains the key.
+    pub fn to_openssh_with_rng<R: RngCore>(&self, rng: &mut R) -> String {
+        let public = self.public_key.to_bytes();
+        let check = rng.next_u32();
+        let name = algorithm_name(self.public_key.algorithm);
+        let seed = self.key.seed().unwrap();
+        let len = 8 + 4 * 4 + name.len() + self.public_key.public_key.len() + seed.len() + self.public_key.comment.len();
+        let mut private = Vec::with_capacity(len + PRIVATE_KEY_BLOCK);
+        put_u32(&mut private, check);
+        put_u32(&mut private, check);
+        put_string(&mut private, name.as_bytes());
+        put_string(&mut private, &self.public_key.public_key);
+        put_string(&mut private, seed);
+        put_string(&mut private, self.public_key.comment.as_bytes());
+        let mut pad = 1;
+        while !private.len().is_multiple_of(PRIVATE_KEY_BLOCK) {
+            private.push(pad);
+            pad += 1;
+        }
+
+        let mut out = Vec::with_capacity(PRIVATE_KEY_MAGIC.len() + 4 * 6 + 8 + public.len() + private.len());
+        out.extend_from_slice(PRIVATE_KEY_MAGIC);
+        put_string(&mut out, b"none");
+        put_string(&mut out, b"none");
+        put_string(&mut out, b"");
+        put_u32(&mut out, 1);
+        put_string(&mut out, &public);
+        put_string(&mut out, &private);
+        wipe(private);
+        let pem = base64::pem_encode_width(PRIVATE_KEY_LABEL, &out, ARMOR_WIDTH);
+        wipe(out);
+        pem
+    }
+
+    pub fn from_openssh(pem: &str) -> Result<Self, &'static str> {
+        let data = base64::pem_decode(PRIVATE_KEY_LABEL, pem)?;
+        let key = Self::read_container(&data);
+        wipe(data);
+        key
+    }
+
+    /// Parses a decoded `openssh-key-v1` container.
+    fn read_container(data: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(data.strip_prefix(PRIVATE_KEY_MAGIC).ok_or("Not an OpenSSH private key")?);
+        let (cipher, kdf, _) = (r.read_string()?, r.read_string()?, r.read_string()?);
+        if cipher != b"none" || kdf != b"none" {
+            return Err("Encrypted OpenSSH keys are not supported");
+        }
+        if r.read_u32()? != 1 {
+            return Err("Unsupported key count");
+        }
+        let public = PublicKey::from_bytes(r.read_string()?)?;
+        let private = r.read_string()?;
+        r.finish()?;
+
+        if !private.len().is_multiple_of(PRIVATE_KEY_BLOCK) {
+            return Err("Invalid private key padding");
+        }
+        let mut r = Reader::new(private);
+        if r.read_u32()? != r.read_u32()? {
+            return Err("Invalid private key check value");
+        }
+        let algorithm = algorithm_from_name(r.read_string()?)?;
+        let public_key = r.read_string()?;
+        let key = Self::new(SeedKey::read(algorithm, public_key, Some(r.read_string()?))?)?;
+        if !key.public_key.same_key(&public) {
+            return Err("Private key does not match the public key");
+        }
+        let comment = r.read_str()?;
+        let padding = r.rest();
+        if padding.iter().enumerate().any(|(i, &b)| b as usize != i + 1) {
+            return Err("Invalid private key padding");
+        }
+        Ok(key.with_comment(comment))
+    }
+}
+
+/// The message hash an SSHSIG signs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Result<Self, &'static str> {
+        match name {
+            b"sha256" => Ok(HashAlgorithm::Sha256),
+            b"sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err("Unsupported hash algorithm"),
+        }
+    }
+
+    /// The hash of `data`, as SSHSIG signs it.
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data).to_vec(),
+            HashAlgorithm::Sha512 => sha512(data).to_vec(),
+        }
+    }
+}
+
+/// An SSHSIG signature (OpenSSH `PROTOCOL.sshsig`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SshSig {
+    public_key: PublicKey,
+    namespace: String,
+    reserved: Vec<u8>,
+    hash_algorithm: HashAlgorithm,
+    signature: Vec<u8>,
+}
+
+impl SshSig {
+    #[cfg(feature = "getrandom")]
+    pub fn sign(
+        keypair: &Keypair,
+        namespace: &str,
+        hash_algorithm: HashAlgorithm,
+        message: &[u8],
+    ) -> Result<Self, &'static str> {
+        Self::sign_with_rng(keypair, namespace, hash_algorithm, message, &mut OsRng)
+    }
+
+    /// Signs the hash of `message` under `namespace`, which must not be
+    /// empty.
+    pub fn sign_with_rng<R: RngCore>(
+        keypair: &Keypair,
+        namespace: &str,
+        hash_algorithm: HashAlgorithm,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, &'static str> {
+        if namespace.is_empty() {
+            return Err("Empty namespace");
+        }
+        let mut sig = SshSig {
+            public_key: PublicKey::from_keypair(keypair),
+            namespace: namespace.into(),
+            reserved: Vec::new(),
+            hash_algorithm,
+            signature: Vec::new(),
+        };
+        sig.signature = sign_blob(keypair, &sig.signed_data(message), rng);
+        Ok(sig)
+    }
+
+    /// The blob the signature covers.
+    fn signed_data(&self, message: &[u8]) -> Vec<u8> {
+        let mut data = SSHSIG_MAGIC.to_vec();
+        put_string(&mut data, self.namespace.as_bytes());
+        put_string(&mut data, &self.reserved);
+        put_string(&mut data, self.hash_algorithm.name().as_bytes());
+        put_string(&mut data, &self.hash_algorithm.digest(message));
+        data
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = SSHSIG_MAGIC.to_vec();
+        put_u32(&mut out, SSHSIG_VERSION);
+        put_string(&mut out, &self.public_key.to_bytes());
+        put_string(&mut out, self.namespace.as_bytes());
+        put_string(&mut out, &self.reserved);
+        put_string(&mut out, self.hash_algorithm.name().as_bytes());
+        put_string(&mut out, &self.signature);
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(data.strip_prefix(SSHSIG_MAGIC).ok_or("Not an SSH signature")?);
+        if r.read_u32()? != SSHSIG_VERSION {
+            return Err("Unsupported SSHSIG version");
+        }
+        let public_key = PublicKey::from_bytes(r.read_string()?)?;
+        let namespace = r.read_str()?.into();
+        let reserved = r.read_string()?.to_vec();
+        let hash_algorithm = HashAlgorithm::from_name(r.read_string()?)?;
+        let signature = r.read_string()?.to_vec();
+        r.finish()?;
+        Ok(SshSig { public_key, namespace, reserved, hash_algorithm, signature })
+    }
+
+    /// The `-----BEGIN SSH SIGNATURE-----` form `ssh-keygen -Y sign` writes.
+    pub fn to_armored(&self) -> String {
+        base64::pem_encode_width(SSHSIG_LABEL, &self.to_bytes(), ARMOR_WIDTH)
+    }
+
+    pub fn from_armored(armored: &str) -> Result<Self, &'static str> {
+        Self::from_bytes(&base64::pem_decode(SSHSIG_LABEL, armored)?)
+    }
+
+    /// The signer's key, which is not trusted until checked against the
+    /// allowed signers.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Checks the signature over `message` in `namespace` and returns the
+    /// signer's key.
+    pub fn verify(&self, namespace: &str, message: &[u8]) -> Result<&PublicKey, &'static str> {
+        if namespace != self.namespace {
+            return Err("Namespace mismatch");
+        }
+        self.public_key.verify(&self.signature, &self.signed_data(message))?;
+        Ok(&self.public_key)
+    }
+}
+
+/// Whom a certificate speaks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CertType {
+    User,
+    Host,
+}
+
+impl CertType {
+    fn code(self) -> u32 {
+        match self {
+            CertType::User => 1,
+            CertType::Host => 2,
+        }
+    }
+
+    fn from_code(code: u32) -> Result<Self, &'static str> {
+        match code {
+            1 => Ok(CertType::User),
+            2 => Ok(CertType::Host),
+            _ => Err("Unsupported certificate type"),
+        }
+    }
+}
+
+fn cert_name(alg: Algorithm) -> &'static str {
+    match alg {
+        #[cfg(feature = "mode2")]
+        Algorithm::Mode2 => "ssh-mldsa-44-cert-v01@openssh.com",
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3 => "ssh-mldsa-65-cert-v01@openssh.com",
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5 => "ssh-mldsa-87-cert-v01@openssh.com",
+    }
+}
+
+fn cert_algorithm_from_name(name: &[u8]) -> Result<Algorithm, &'static str> {
+    Algorithm::find(|alg| cert_name(alg).as_bytes() == name).ok_or("Unsupported key type")
+}
+
+/// The critical options OpenSSH defines, all for user certificates. A
+/// certificate with any other critical option is refused.
+const USER_CRITICAL_OPTIONS: &[&str] = &["force-command", "source-address", "verify-required"];
+
+/// Writes an option list: each name is followed by data that is empty for
+/// a flag and holds the value as a string otherwise.
+fn put_options(out: &mut Vec<u8>, options: &[(String, Option<String>)]) {
+    let mut list = Vec::new();
+    for (name, value) in options {
+        put_string(&mut list, name.as_bytes());
+        let mut data = Vec::new();
+        if let Some(value) = value {
+            put_string(&mut data, value.as_bytes());
+        }
+        put_string(&mut list, &data);
+    }
+    put_string(out, &list);
+}
+
+fn read_options(r: &mut Reader) -> Result<Vec<(String, Option<String>)>, &'static str> {
+    let mut list = Reader::new(r.read_string()?);
+    let mut options: Vec<(String, Option<String>)> = Vec::new();
+    while !list.is_empty() {
+        let name = list.read_str()?;
+        let data = list.read_string()?;
+        let value = if data.is_empty() {
+            None
+        } else {
+            let mut data = Reader::new(data);
+            let value = data.read_str()?;
+            data.finish()?;
+            Some(value.into())
+        };
+        if options.iter().any(|(n, _)| n == name) {
+            return Err("Duplicate certificate option");
+        }
+        options.push((name.into(), value));
+    }
+    Ok(options)
+}
+
+/// The contents of an OpenSSH certificate to be issued
+/// (`PROTOCOL.certkeys`). The certified key and the CA key are given when
+/// signing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateParams {
+    pub cert_type: CertType,
+    pub serial: u64,
+    /// Free-form text that the server logs on use.
+    pub key_id: String,
+    /// User or host names; an empty list is valid for any principal.
+    pub principals: Vec<String>,
+    /// Seconds after the Unix epoch. The certificate is valid from
+    /// `valid_after` up to, but not including, `valid_before`.
+    pub valid_after: u64,
+    pub valid_before: u64,
+    /// Options such as `force-command`, which a verifier must understand.
+    pub critical_options: Vec<(String, Option<String>)>,
+    /// Options such as `permit-pty`, which a verifier may ignore.
+    pub extensions: Vec<(String, Option<String>)>,
+}
+
+impl CertificateParams {
+    pub fn new(cert_type: CertType, key_id: &str, valid_after: u64, valid_before: u64) -> Self {
+        CertificateParams {
+            cert_type,
+            serial: 0,
+            key_id: key_id.into(),
+            principals: Vec::new(),
+            valid_after,
+            valid_before,
+            critical_options: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Certifies `key` with the CA key `ca`.
+    #[cfg(feature = "getrandom")]
+    pub fn sign(&self, key: &PublicKey, ca: &Keypair) -> Result<Certificate, &'static str> {
+        self.sign_with_rng(key, ca, &mut OsRng)
+    }
+
+    /// Like `sign`, drawing the nonce and the signature randomness from
+    /// `rng`.
+    pub fn sign_with_rng<R: RngCore>(
+        &self,
+        key: &PublicKey,
+        ca: &Keypair,
+        rng: &mut R,
+    ) -> Result<Certificate, &'static str> {
+        if self.valid_after >= self.valid_before {
+            return Err("Invalid validity period");
+        }
+        // Options go out sorted by name, as OpenSSH writes them.
+        let mut critical_options = self.critical_options.clone();
+        let mut extensions = self.extensions.clone();
+        for options in [&mut critical_options, &mut extensions] {
+            options.sort_by(|a, b| a.0.cmp(&b.0));
+            if options.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err("Duplicate certificate option");
+            }
+        }
+        let mut nonce = [0u8; 32];
+        rng.fill_bytes(&mut nonce);
+
+        let mut blob = Vec::new();
+        put_string(&mut blob, cert_name(key.algorithm).as_bytes());
+        put_string(&mut blob, &nonce);
+        put_string(&mut blob, &key.public_key);
+        put_u64(&mut blob, self.serial);
+        put_u32(&mut blob, self.cert_type.code());
+        put_string(&mut blob, self.key_id.as_bytes());
+        let mut principals = Vec::new();
+        for principal in &self.principals {
+            put_string(&mut principals, principal.as_bytes());
+        }
+        put_string(&mut blob, &principals);
+        put_u64(&mut blob, self.valid_after);
+        put_u64(&mut blob, self.valid_before);
+        put_options(&mut blob, &critical_options);
+        put_options(&mut blob, &extensions);
+        put_string(&mut blob, b"");
+        put_string(&mut blob, &PublicKey::from_keypair(ca).to_bytes());
+        let signature = sign_blob(ca, &blob, rng);
+        put_string(&mut blob, &signature);
+        Certificate::from_bytes(&blob)
+    }
+}
+
+/// A parsed OpenSSH certificate for an ML-DSA key, signed by an ML-DSA CA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    blob: Vec<u8>,
+    /// The length of the prefix of `blob` the CA signed.
+    signed_len: usize,
+    key: PublicKey,
+    serial: u64,
+    cert_type: CertType,
+    key_id: String,
+    principals: Vec<String>,
+    valid_after: u64,
+    valid_before: u64,
+    critical_options: Vec<(String, Option<String>)>,
+    extensions: Vec<(String, Option<String>)>,
+    signature_key: PublicKey,
+    signature: Vec<u8>,
+    comment: String,
+}
+
+impl Certificate {
+    /// Parses a certificate blob. The CA signature is not checked.
+    pub fn from_bytes(blob: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(blob);
+        let algorithm = cert_algorithm_from_name(r.read_string()?)?;
+        let _nonce = r.read_string()?;
+        let key = PublicKey::new(algorithm, r.read_string()?)?;
+        let serial = r.read_u64()?;
+        let cert_type = CertType::from_code(r.read_u32()?)?;
+        let key_id = r.read_str()?.into();
+        let mut list = Reader::new(r.read_string()?);
+        let mut principals = Vec::new();
+        while !list.is_empty() {
+            principals.push(list.read_str()?.into());
+        }
+        let valid_after = r.read_u64()?;
+        let valid_before = r.read_u64()?;
+        let critical_options = read_options(&mut r)?;
+        let extensions = read_options(&mut r)?;
+        let _reserved = r.read_string()?;
+        let signature_key = PublicKey::from_bytes(r.read_string()?)?;
+        let signature = r.read_string()?.to_vec();
+        r.finish()?;
+        Ok(Certificate {
+            blob: blob.to_vec(),
+            signed_len: blob.len() - 4 - signature.len(),
+            key,
+            serial,
+            cert_type,
+            key_id,
+            principals,
+            valid_after,
+            valid_before,
+            critical_options,
+            extensions,
+            signature_key,
+            signature,
+            comment: String::new(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.blob
+    }
+
+    /// The `-cert.pub` form: type, base64 blob and comment.
+    pub fn to_openssh(&self) -> String {
+        let mut line = String::from(cert_name(self.key.algorithm));
+        line.push(' ');
+        line.push_str(&base64::encode(&self.blob));
+        if !self.comment.is_empty() {
+            line.push(' ');
+            line.push_str(&self.comment);
+        }
+        line
+    }
+
+    /// Parses `type base64 [comment]`; the type must match the blob's.
+    pub fn from_openssh(line: &str) -> Result<Self, &'static str> {
+        let (name, rest) = split_token(line.trim());
+        let algorithm = cert_algorithm_from_name(name.as_bytes())?;
+        let (blob, comment) = split_token(rest);
+        let cert = Self::from_bytes(&base64::decode(blob)?)?;
+        if algorithm != cert.key.algorithm {
+            return Err("Key type mismatch");
+        }
+        Ok(cert.with_comment(comment))
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// The certified key.
+    pub fn key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn cert_type(&self) -> CertType {
+        self.cert_type
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn principals(&self) -> &[String] {
+        &self.principals
+    }
+
+    pub fn valid_after(&self) -> u64 {
+        self.valid_after
+    }
+
+    pub fn valid_before(&self) -> u64 {
+        self.valid_before
+    }
+
+    pub fn critical_options(&self) -> &[(String, Option<String>)] {
+        &self.critical_options
+    }
+
+    pub fn extensions(&self) -> &[(String, Option<String>)] {
+        &self.extensions
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|(n, _)| n == name)
+    }
+
+    /// The CA key that signed the certificate.
+    pub fn signature_key(&self) -> &PublicKey {
+        &self.signature_key
+    }
+
+    /// Checks the CA signature against the embedded CA key only; whether
+    /// that CA is trusted is up to the caller.
+    pub fn verify_signature(&self) -> Result<(), &'static str> {
+        self.signature_key.verify(&self.signature, &self.blob[..self.signed_len])
+    }
+
+    /// Whether the certificate names `principal`. A certificate without
+    /// principals names them all.
+    pub fn allows_principal(&self, principal: &str) -> bool {
+        self.principals.is_empty() || self.principals.iter().any(|p| p == principal)
+    }
+
+    /// Checks that the certificate was signed by `ca`, has type
+    /// `cert_type`, is valid at `now` (seconds after the Unix epoch), names
+    /// `principal` and carries no critical option outside those OpenSSH
+    /// defines. Enforcing `force-command` and `source-address` is left to
+    /// the caller.
+    pub fn validate(
+        &self,
+        ca: &PublicKey,
+        cert_type: CertType,
+        principal: &str,
+        now: u64,
+    ) -> Result<(), &'static str> {
+        self.check(ca, cert_type, now)?;
+        if !self.allows_principal(principal) {
+            return Err("Principal not listed in the certificate");
+        }
+        Ok(())
+    }
+
+    fn check(&self, ca: &PublicKey, cert_type: CertType, now: u64) -> Result<(), &'static str> {
+        if !self.signature_key.same_key(ca) {
+            return Err("Certificate not signed by this CA");
+        }
+        self.verify_signature()?;
+        if self.cert_type != cert_type {
+            return Err("Wrong certificate type");
+        }
+        if now < self.valid_after {
+            return Err("Certificate not yet valid");
+        }
+        if now >= self.valid_before {
+            return Err("Certificate expired");
+        }
+        let known = match cert_type {
+            CertType::User => USER_CRITICAL_OPTIONS,
+            CertType::Host => &[],
+        };
+        if self.critical_options.iter().any(|(name, _)| !known.contains(&name.as_str())) {
+            return Err("Unsupported critical option");
+        }
+        Ok(())
+    }
+
+    /// Checks a user certificate for login as `user` against the
+    /// `cert-authority` lines of an `authorized_keys` file, and returns the
+    /// first line that accepts it so that its other options can be
+    /// applied. A `principals="..."` option on a line replaces `user` with
+    /// the names it lists, one of which the certificate must carry; the CA
+    /// key may appear on several lines with different lists.
+    pub fn verify_authorized_keys<'a>(
+        &self,
+        keys: &'a [AuthorizedKey],
+        user: &str,
+        now: u64,
+    ) -> Result<&'a AuthorizedKey, &'static str> {
+        let mut lines =
+            keys.iter().filter(|k| k.has_option("cert-authority") && k.key.same_key(&self.signature_key)).peekable();
+        let first = lines.peek().ok_or("Certificate authority not trusted")?;
+        self.check(&first.key, CertType::User, now)?;
+        lines
+            .find(|entry| match entry.option_value("principals") {
+                Some(list) => list.split(',').any(|name| self.principals.iter().any(|p| p == name)),
+                None => self.allows_principal(user),
+            })
+            .ok_or("Principal not listed in the certificate")
+    }
+
+    /// Checks a host certificate presented by `host` on `port` against the
+    /// `@cert-authority` lines of a `known_hosts` file, and returns the line
+    /// that accepted it. `@revoked` lines for the host are honoured for
+    /// both the host key and the CA key.
+    pub fn verify_known_hosts<'a>(
+        &self,
+        entries: &'a [KnownHost],
+        host: &str,
+        port: u16,
+        now: u64,
+    ) -> Result<&'a KnownHost, &'static str> {
+        let mut matching = entries.iter().filter(|e| e.matches_host(host, port));
+        if matching.clone().any(|e| {
+            e.marker == Some(Marker::Revoked) && (e.key.same_key(&self.key) || e.key.same_key(&self.signature_key))
+        }) {
+            return Err("Key revoked");
+        }
+        let entry = matching
+            .find(|e| e.marker == Some(Marker::CertAuthority) && e.key.same_key(&self.signature_key))
+            .ok_or("Certificate authority not trusted")?;
+        self.validate(&entry.key, CertType::Host, host, now)?;
+        Ok(entry)
+    }
+}
+
+/// The marker that starts some `known_hosts` lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    /// `@cert-authority`: the key is a CA for host certificates.
+    CertAuthority,
+    /// `@revoked`: the key must not be accepted.
+    Revoked,
+}
+
+/// One `known_hosts` line: an optional marker, host patterns and a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnownHost {
+    marker: Option<Marker>,
+    hosts: String,
+    key: PublicKey,
+}
+
+impl KnownHost {
+    /// An entry for the comma-separated patterns `hosts`, such as
+    /// `*.example.org,!bastion.example.org`.
+    pub fn new(hosts: &str, key: PublicKey) -> Self {
+        KnownHost { marker: None, hosts: hosts.into(), key }
+    }
+
+    pub fn with_marker(mut self, marker: Marker) -> Self {
+        self.marker = Some(marker);
+        self
+    }
+
+    pub fn marker(&self) -> Option<Marker> {
+        self.marker
+    }
+
+    pub fn hosts(&self) -> &str {
+        &self.hosts
+    }
+
+    pub fn key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    /// Whether the patterns match `host`, written `[host]:port` in them
+    /// unless `port` is 22. Patterns may use `*` and `?` and are negated by
+    /// a leading `!`. Hashed host names never match.
+    pub fn matches_host(&self, host: &str, port: u16) -> bool {
+        let name = if port == 22 { host.into() } else { alloc::format!("[{host}]:{port}") };
+        let mut matched = false;
+        for pattern in self.hosts.split(',') {
+            match pattern.strip_prefix('!') {
+                Some(negated) if glob_match(negated.as_bytes(), name.as_bytes()) => return false,
+                Some(_) => {}
+                None => matched |= glob_match(pattern.as_bytes(), name.as_bytes()),
+            }
+        }
+        matched
+    }
+
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let (mut first, mut rest) = split_token(line.trim());
+        let mut marker = None;
+        if first.starts_with('@') {
+            marker = Some(match first {
+                "@cert-authority" => Marker::CertAuthority,
+                "@revoked" => Marker::Revoked,
+                _ => return Err("Unsupported known_hosts marker"),
+            });
+            (first, rest) = split_token(rest);
+        }
+        Ok(KnownHost { marker, hosts: first.into(), key: PublicKey::from_openssh(rest)? })
+    }
+
+    /// The line, marker first.
+    pub fn to_line(&self) -> String {
+        let mut line = String::from(match self.marker {
+            Some(Marker::CertAuthority) => "@cert-authority ",
+            Some(Marker::Revoked) => "@revoked ",
+            None => "",
+        });
+        line.push_str(&self.hosts);
+        line.push(' ');
+        line.push_str(&self.key.to_openssh());
+        line
+    }
+}
+
+/// Matches `name` against a pattern with `*` and `?`, ignoring ASCII case.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+        Some((&p, rest)) => match name.split_first() {
+            Some((&c, tail)) => (p == b'?' || p.eq_ignore_ascii_case(&c)) && glob_match(rest, tail),
+            None => false,
+        },
+    }
+}
+
+/// The ML-DSA keys of a `known_hosts` file. Comments, blank lines and lines
+/// for other key types are skipped.
+pub fn parse_known_hosts(text: &str) -> Vec<KnownHost> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| KnownHost::parse(line).ok())
+        .collect()
+}
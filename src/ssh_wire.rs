@@ -0,0 +1,71 @@
+//! The SSH wire encoding (RFC 4251, section 5) the OpenSSH key, signature
+//! and certificate formats are built from: big-endian integers and
+//! length-prefixed strings.
+
+use alloc::vec::Vec;
+
+const MALFORMED: &str = "Malformed SSH data";
+
+pub fn put_u32(out: &mut Vec<u8>, n: u32) {
+  out.extend_from_slice(&n.to_be_bytes());
+}
+
+pub fn put_u64(out: &mut Vec<u8>, n: u64) {
+  out.extend_from_slice(&n.to_be_bytes());
+}
+
+pub fn put_string(out: &mut Vec<u8>, s: &[u8]) {
+  put_u32(out, s.len() as u32);
+  out.extend_from_slice(s);
+}
+
+/// Reads SSH wire values from a byte slice.
+pub struct Reader<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  pub fn new(data: &'a [u8]) -> Self {
+    Reader { data }
+  }
+
+  pub fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+    if self.data.len() < n {
+      return Err(MALFORMED);
+    }
+    let (head, rest) = self.data.split_at(n);
+    self.data = rest;
+    Ok(head)
+  }
+
+  pub fn read_u32(&mut self) -> Result<u32, &'static str> {
+    Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+  }
+
+  pub fn read_u64(&mut self) -> Result<u64, &'static str> {
+    Ok(u64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap()))
+  }
+
+  pub fn read_string(&mut self) -> Result<&'a [u8], &'static str> {
+    let len = self.read_u32()? as usize;
+    self.read_bytes(len)
+  }
+
+  /// A string that must be UTF-8 text.
+  pub fn read_str(&mut self) -> Result<&'a str, &'static str> {
+    core::str::from_utf8(self.read_string()?).map_err(|_| MALFORMED)
+  }
+
+  /// Everything not read yet.
+  pub fn rest(&mut self) -> &'a [u8] {
+    core::mem::take(&mut self.data)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn finish(&self) -> Result<(), &'static str> {
+    if self.data.is_empty() { Ok(()) } else { Err("Trailing SSH data") }
+  }
+}
@@ -1,28 +1,25 @@
-use crate::fips202::KeccakState;
+use crate::fips202::{KeccakState, XofReader};
+use crate::poly::CRHBYTES;
 
 
-impl KeccakState {
-  pub fn dilithium_shake256_stream_init(
-    &mut self,
+impl XofReader {
+  pub fn dilithium_shake256_stream(
     seed: &[u8],
     nonce: u16,
-  ) {
-    let t = [nonce as u8, (nonce >> 8) as u8];
-    self.init();
-    self.shake256_absorb(seed, 64);
-    self.shake256_absorb( &t, 2);
-    self.shake256_finalize();
+  ) -> XofReader {
+    let mut state = KeccakState::default();
+    state.shake256_absorb(&seed[..CRHBYTES]);
+    state.shake256_absorb(&nonce.to_le_bytes());
+    state.shake256_finalize()
   }
-  pub fn dilithium_shake128_stream_init(
-    &mut self,
+  pub fn dilithium_shake128_stream(
     seed: &[u8],
     nonce: u16,
-  ) {
-    let t = [nonce as u8, (nonce >> 8) as u8];
-    self.init();
-    self.shake128_absorb(seed);
-    self.shake128_absorb( &t);
-    self.shake128_finalize();
+  ) -> XofReader {
+    let mut state = KeccakState::default();
+    state.shake128_absorb(seed);
+    state.shake128_absorb(&nonce.to_le_bytes());
+    state.shake128_finalize()
   }
   
-}
\ No newline at end of file
+}
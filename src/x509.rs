@@ -0,0 +1,1022 @@
+//! X.509 v3 certificates and PKCS#10 certificate requests with ML-DSA keys.
+//!
+//! [`CertificateParams`] describes a certificate and signs it with an
+//! issuer [`Keypair`], either as a self-signed root or under an issuer
+//! [`Certificate`]; [`CertificateRequest`] is the PKCS#10 request a subject
+//! sends to its CA. Parsed certificates expose the basic extensions
+//! (basicConstraints, keyUsage, subject and authority key identifiers and
+//! subjectAltName) and verify against an issuer's
+//! [`SubjectPublicKeyInfo`]; [`validate_path`] checks a whole chain up to a
+//! trusted root.
+//!
+//! Keys and signatures carry the ML-DSA algorithm identifiers of RFC 9881
+//! (id-ml-dsa-44/65/87, parameters absent). See [FIPS 204 interoperability](crate#fips-204-interoperability)
+//! for which verifiers accept them.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+use core::ops::BitOr;
+use rand_core::RngCore;
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+
+use crate::base64;
+use crate::der::{self, Reader, OID, SEQUENCE};
+use crate::sha2::sha256;
+use crate::{Algorithm, Keypair};
+
+#[cfg(feature = "mode2")]
+const ID_ML_DSA_44: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x03, 0x11];
+#[cfg(feature = "mode3")]
+const ID_ML_DSA_65: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x03, 0x12];
+#[cfg(feature = "mode5")]
+const ID_ML_DSA_87: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x03, 0x13];
+
+const ID_AT_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const ID_AT_COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+const ID_AT_LOCALITY: &[u8] = &[0x55, 0x04, 0x07];
+const ID_AT_STATE: &[u8] = &[0x55, 0x04, 0x08];
+const ID_AT_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+const ID_AT_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+
+const ID_CE_SUBJECT_KEY_ID: &[u8] = &[0x55, 0x1d, 0x0e];
+const ID_CE_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+const ID_CE_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const ID_CE_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+const ID_CE_AUTHORITY_KEY_ID: &[u8] = &[0x55, 0x1d, 0x23];
+
+const PKCS9_EXTENSION_REQUEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x0e];
+
+/// Serial numbers are at most 20 octets (RFC 5280, section 4.1.2.2).
+const MAX_SERIAL_BYTES: usize = 20;
+
+pub(crate) fn algorithm_oid(alg: Algorithm) -> &'static [u8] {
+    match alg {
+        #[cfg(feature = "mode2")]
+        Algorithm::Mode2 => ID_ML_DSA_44,
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3 => ID_ML_DSA_65,
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5 => ID_ML_DSA_87,
+    }
+}
+
+pub(crate) fn algorithm_from_oid(oid: &[u8]) -> Result<Algorithm, &'static str> {
+    Algorithm::find(|alg| algorithm_oid(alg) == oid).ok_or("Unsupported algorithm")
+}
+
+/// The AlgorithmIdentifier for `alg`; RFC 9881 requires absent parameters.
+pub(crate) fn algorithm_identifier(alg: Algorithm) -> Vec<u8> {
+    der::seq(&[&der::oid(algorithm_oid(alg))])
+}
+
+pub(crate) fn read_algorithm(r: &mut Reader) -> Result<Algorithm, &'static str> {
+    let mut alg = Reader::new(r.read(SEQUENCE)?);
+    let oid = alg.read(OID)?;
+    if !alg.is_empty() {
+        return Err("Unexpected algorithm parameters");
+    }
+    algorithm_from_oid(oid)
+}
+
+/// The dotted-decimal form of an encoded OBJECT IDENTIFIER.
+fn oid_string(oid: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut n = 0u64;
+    for &b in oid {
+        n = n << 7 | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            arcs.push(n);
+            n = 0;
+        }
+    }
+    let mut out = String::new();
+    if let Some(&first) = arcs.first() {
+        let (a, b) = if first < 80 { (first / 40, first % 40) } else { (2, first - 80) };
+        out = alloc::format!("{a}.{b}");
+    }
+    for arc in arcs.iter().skip(1) {
+        out.push_str(&alloc::format!(".{arc}"));
+    }
+    out
+}
+
+/// An ML-DSA public key with its algorithm, as encoded in certificates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubjectPublicKeyInfo {
+    algorithm: Algorithm,
+    public_key: Vec<u8>,
+}
+
+impl SubjectPublicKeyInfo {
+    pub fn new(algorithm: Algorithm, public_key: &[u8]) -> Result<Self, &'static str> {
+        if public_key.len() != algorithm.public_key_bytes() {
+            return Err("Invalid public key length");
+        }
+        Ok(SubjectPublicKeyInfo { algorithm, public_key: public_key.to_vec() })
+    }
+
+    pub fn from_keypair(keypair: &Keypair) -> Self {
+        SubjectPublicKeyInfo { algorithm: keypair.algorithm(), public_key: keypair.public().to_vec() }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// The key identifier used for the subject and authority key identifier
+    /// extensions: the leftmost 160 bits of SHA-256 over the public key,
+    /// RFC 7093's first method.
+    pub fn key_identifier(&self) -> [u8; 20] {
+        let mut id = [0u8; 20];
+        id.copy_from_slice(&sha256(&self.public_key)[..20]);
+        id
+    }
+
+    /// Verifies a raw signature over `msg` with this key.
+    pub fn verify(&self, signature: &[u8], msg: &[u8]) -> Result<(), &'static str> {
+        self.algorithm.verify_raw(signature, msg, &self.public_key)
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        der::seq(&[&algorithm_identifier(self.algorithm), &der::bit_string(&self.public_key)])
+    }
+
+    pub fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(der);
+        let spki = Self::read(&mut r)?;
+        r.finish()?;
+        Ok(spki)
+    }
+
+    pub fn to_pem(&self) -> String {
+        base64::pem_encode("PUBLIC KEY", &self.to_der())
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, &'static str> {
+        Self::from_der(&base64::pem_decode("PUBLIC KEY", pem)?)
+    }
+
+    pub(crate) fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let mut spki = Reader::new(r.read(SEQUENCE)?);
+        let algorithm = read_algorithm(&mut spki)?;
+        let public_key = spki.read_bit_string()?;
+        spki.finish()?;
+        Self::new(algorithm, public_key)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Attribute {
+    oid: Vec<u8>,
+    tag: u8,
+    value: String,
+}
+
+/// A distinguished name: a sequence of relative distinguished names, each a
+/// set of attribute values. Names compare equal when their encodings do.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Name {
+    rdns: Vec<Vec<Attribute>>,
+}
+
+impl Name {
+    pub fn new() -> Self {
+        Name::default()
+    }
+
+    fn with(mut self, oid: &[u8], tag: u8, value: &str) -> Self {
+        self.rdns.push(vec![Attribute { oid: oid.to_vec(), tag, value: value.into() }]);
+        self
+    }
+
+    pub fn with_common_name(self, value: &str) -> Self {
+        self.with(ID_AT_COMMON_NAME, der::UTF8_STRING, value)
+    }
+
+    /// Adds a two-letter ISO 3166 country code.
+    pub fn with_country(self, value: &str) -> Self {
+        self.with(ID_AT_COUNTRY, der::PRINTABLE_STRING, value)
+    }
+
+    pub fn with_locality(self, value: &str) -> Self {
+        self.with(ID_AT_LOCALITY, der::UTF8_STRING, value)
+    }
+
+    pub fn with_state(self, value: &str) -> Self {
+        self.with(ID_AT_STATE, der::UTF8_STRING, value)
+    }
+
+    pub fn with_organization(self, value: &str) -> Self {
+        self.with(ID_AT_ORGANIZATION, der::UTF8_STRING, value)
+    }
+
+    pub fn with_organizational_unit(self, value: &str) -> Self {
+        self.with(ID_AT_ORGANIZATIONAL_UNIT, der::UTF8_STRING, value)
+    }
+
+    fn get(&self, oid: &[u8]) -> Option<&str> {
+        self.rdns.iter().flatten().find(|a| a.oid == oid).map(|a| &a.value[..])
+    }
+
+    pub fn common_name(&self) -> Option<&str> {
+        self.get(ID_AT_COMMON_NAME)
+    }
+
+    pub fn country(&self) -> Option<&str> {
+        self.get(ID_AT_COUNTRY)
+    }
+
+    pub fn organization(&self) -> Option<&str> {
+        self.get(ID_AT_ORGANIZATION)
+    }
+
+    pub fn organizational_unit(&self) -> Option<&str> {
+        self.get(ID_AT_ORGANIZATIONAL_UNIT)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rdns.is_empty()
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        let rdns: Vec<Vec<u8>> = self
+            .rdns
+            .iter()
+            .map(|rdn| {
+                der::set_of(
+                    rdn.iter()
+                        .map(|a| der::seq(&[&der::oid(&a.oid), &der::tlv(a.tag, a.value.as_bytes())]))
+                        .collect(),
+                )
+            })
+            .collect();
+        let parts: Vec<&[u8]> = rdns.iter().map(|r| &r[..]).collect();
+        der::seq(&parts)
+    }
+
+    pub fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        let mut r = Reader::new(der);
+        let name = Self::read(&mut r)?;
+        r.finish()?;
+        Ok(name)
+    }
+
+    pub(crate) fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let mut rdns = Reader::new(r.read(SEQUENCE)?);
+        let mut name = Name::new();
+        while !rdns.is_empty() {
+            let mut set = Reader::new(rdns.read(der::SET)?);
+            let mut rdn = Vec::new();
+            while !set.is_empty() {
+                let mut atv = Reader::new(set.read(SEQUENCE)?);
+                let oid = atv.read(OID)?.to_vec();
+                let (tag, value, _) = atv.read_any()?;
+                atv.finish()?;
+                let ascii_only = match tag {
+                    der::UTF8_STRING => false,
+                    der::PRINTABLE_STRING | der::IA5_STRING => true,
+                    _ => return Err("Unsupported name string type"),
+                };
+                let value = core::str::from_utf8(value).map_err(|_| "Invalid name string")?;
+                if ascii_only && !value.is_ascii() {
+                    return Err("Invalid name string");
+                }
+                rdn.push(Attribute { oid, tag, value: value.into() });
+            }
+            if rdn.is_empty() {
+                return Err("Empty relative distinguished name");
+            }
+            name.rdns.push(rdn);
+        }
+        Ok(name)
+    }
+}
+
+/// `C=US, O=Example, CN=Root` style, in encoding order.
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, a) in self.rdns.iter().flatten().enumerate() {
+            let key = match &a.oid[..] {
+                ID_AT_COMMON_NAME => "CN".into(),
+                ID_AT_COUNTRY => "C".into(),
+                ID_AT_LOCALITY => "L".into(),
+                ID_AT_STATE => "ST".into(),
+                ID_AT_ORGANIZATION => "O".into(),
+                ID_AT_ORGANIZATIONAL_UNIT => "OU".into(),
+                oid => oid_string(oid),
+            };
+            write!(f, "{}{}={}", if i > 0 { ", " } else { "" }, key, a.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The basicConstraints extension: whether the subject is a CA and how many
+/// intermediate CAs may follow it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    pub path_len: Option<u32>,
+}
+
+/// The keyUsage bits; combine with `|`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyUsage(pub u16);
+
+impl KeyUsage {
+    pub const DIGITAL_SIGNATURE: KeyUsage = KeyUsage(1 << 0);
+    /// Called contentCommitment in later editions of X.509.
+    pub const NON_REPUDIATION: KeyUsage = KeyUsage(1 << 1);
+    pub const KEY_ENCIPHERMENT: KeyUsage = KeyUsage(1 << 2);
+    pub const DATA_ENCIPHERMENT: KeyUsage = KeyUsage(1 << 3);
+    pub const KEY_AGREEMENT: KeyUsage = KeyUsage(1 << 4);
+    pub const KEY_CERT_SIGN: KeyUsage = KeyUsage(1 << 5);
+    pub const CRL_SIGN: KeyUsage = KeyUsage(1 << 6);
+    pub const ENCIPHER_ONLY: KeyUsage = KeyUsage(1 << 7);
+    pub const DECIPHER_ONLY: KeyUsage = KeyUsage(1 << 8);
+
+    pub const fn contains(self, other: KeyUsage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The named-bit BIT STRING, with trailing zero bits dropped as DER requires.
+    fn to_der(self) -> Result<Vec<u8>, &'static str> {
+        let mut bits = vec![(self.0 as u8).reverse_bits(), ((self.0 >> 8) as u8 & 1) << 7];
+        while bits.last() == Some(&0) {
+            bits.pop();
+        }
+        let last = *bits.last().ok_or("Empty key usage")?;
+        let mut content = vec![last.trailing_zeros() as u8];
+        content.extend_from_slice(&bits);
+        Ok(der::tlv(der::BIT_STRING, &content))
+    }
+
+    fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        match der::parse(der, der::BIT_STRING)? {
+            [unused, bits @ ..] if *unused < 8 && !bits.is_empty() => {
+                if bits[bits.len() - 1] & ((1u8 << unused) - 1) != 0 {
+                    return Err("Malformed DER");
+                }
+                let low = bits[0].reverse_bits() as u16;
+                let high = bits.get(1).map_or(0, |b| (b >> 7) as u16);
+                Ok(KeyUsage(low | high << 8))
+            }
+            _ => Err("Malformed DER"),
+        }
+    }
+}
+
+impl BitOr for KeyUsage {
+    type Output = KeyUsage;
+
+    fn bitor(self, rhs: KeyUsage) -> KeyUsage {
+        KeyUsage(self.0 | rhs.0)
+    }
+}
+
+/// A subjectAltName entry. Forms other than these are kept as `Other` with
+/// their context tag and raw content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GeneralName {
+    Email(String),
+    Dns(String),
+    Uri(String),
+    /// A 4-byte IPv4 or 16-byte IPv6 address.
+    Ip(Vec<u8>),
+    Other(u8, Vec<u8>),
+}
+
+impl GeneralName {
+    fn to_der(&self) -> Result<Vec<u8>, &'static str> {
+        let (n, value) = match self {
+            GeneralName::Email(s) => (1, s.as_bytes()),
+            GeneralName::Dns(s) => (2, s.as_bytes()),
+            GeneralName::Uri(s) => (6, s.as_bytes()),
+            GeneralName::Ip(ip) if ip.len() == 4 || ip.len() == 16 => {
+                return Ok(der::tlv(der::context_primitive(7), ip));
+            }
+            GeneralName::Ip(_) => return Err("Invalid IP address length"),
+            GeneralName::Other(tag, value) => return Ok(der::tlv(*tag, value)),
+        };
+        if !value.is_ascii() {
+            return Err("Non-ASCII subjectAltName");
+        }
+        Ok(der::tlv(der::context_primitive(n), value))
+    }
+
+    fn read(r: &mut Reader) -> Result<Self, &'static str> {
+        let (tag, value, _) = r.read_any()?;
+        let ascii = || match core::str::from_utf8(value) {
+            Ok(s) if s.is_ascii() => Ok(String::from(s)),
+            _ => Err("Non-ASCII subjectAltName"),
+        };
+        Ok(match tag {
+            0x81 => GeneralName::Email(ascii()?),
+            0x82 => GeneralName::Dns(ascii()?),
+            0x86 => GeneralName::Uri(ascii()?),
+            0x87 if value.len() == 4 || value.len() == 16 => GeneralName::Ip(value.to_vec()),
+            0x87 => return Err("Invalid IP address length"),
+            _ => GeneralName::Other(tag, value.to_vec()),
+        })
+    }
+}
+
+/// The extensions this module understands, shared by certificates and the
+/// extension request of a CSR.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Extensions {
+    pub basic_constraints: Option<BasicConstraints>,
+    pub key_usage: Option<KeyUsage>,
+    pub subject_key_id: Option<Vec<u8>>,
+    pub authority_key_id: Option<Vec<u8>>,
+    pub subject_alt_names: Vec<GeneralName>,
+    /// Set when a critical extension outside the ones above is present.
+    pub unknown_critical: bool,
+}
+
+fn extension(oid: &[u8], critical: bool, value: &[u8]) -> Vec<u8> {
+    let critical = if critical { der::boolean(true) } else { Vec::new() };
+    der::seq(&[&der::oid(oid), &critical, &der::octet_string(value)])
+}
+
+impl Extensions {
+    /// The `Extensions` SEQUENCE. subjectAltName is critical when the
+    /// subject name is empty (RFC 5280, section 4.2.1.6).
+    fn to_der(&self, empty_subject: bool) -> Result<Vec<u8>, &'static str> {
+        let mut exts = Vec::new();
+        if let Some(bc) = self.basic_constraints {
+            let ca = if bc.ca { der::boolean(true) } else { Vec::new() };
+            let path_len = bc.path_len.map_or(Vec::new(), |n| der::small_uint(n as u64));
+            exts.push(extension(ID_CE_BASIC_CONSTRAINTS, true, &der::seq(&[&ca, &path_len])));
+        }
+        if let Some(ku) = self.key_usage {
+            exts.push(extension(ID_CE_KEY_USAGE, true, &ku.to_der()?));
+        }
+        if let Some(id) = &self.subject_key_id {
+            exts.push(extension(ID_CE_SUBJECT_KEY_ID, false, &der::octet_string(id)));
+        }
+        if let Some(id) = &self.authority_key_id {
+            let key_id = der::tlv(der::context_primitive(0), id);
+            exts.push(extension(ID_CE_AUTHORITY_KEY_ID, false, &der::seq(&[&key_id])));
+        }
+        if !self.subject_alt_names.is_empty() {
+            let names = self.subject_alt_names.iter().map(GeneralName::to_der).collect::<Result<Vec<_>, _>>()?;
+            let parts: Vec<&[u8]> = names.iter().map(|n| &n[..]).collect();
+            exts.push(extension(ID_CE_SUBJECT_ALT_NAME, empty_subject, &der::seq(&parts)));
+        }
+        let parts: Vec<&[u8]> = exts.iter().map(|e| &e[..]).collect();
+        Ok(der::seq(&parts))
+    }
+
+    /// Parses the content of an `Extensions` SEQUENCE.
+    fn parse(content: &[u8]) -> Result<Self, &'static str> {
+        let mut exts = Extensions::default();
+        let mut seen: Vec<&[u8]> = Vec::new();
+        let mut r = Reader::new(content);
+        if r.is_empty() {
+            return Err("Empty extensions");
+        }
+        while !r.is_empty() {
+            let mut ext = Reader::new(r.read(SEQUENCE)?);
+            let oid = ext.read(OID)?;
+            let critical = if ext.peek_tag() == Some(der::BOOLEAN) { ext.read_bool()? } else { false };
+            let value = ext.read(der::OCTET_STRING)?;
+            ext.finish()?;
+            if seen.contains(&oid) {
+                return Err("Duplicate extension");
+            }
+            seen.push(oid);
+            match oid {
+                ID_CE_BASIC_CONSTRAINTS => {
+                    let mut bc = Reader::new(der::parse(value, SEQUENCE)?);
+                    let ca = if bc.peek_tag() == Some(der::BOOLEAN) { bc.read_bool()? } else { false };
+                    let path_len = if bc.is_empty() {
+                        None
+                    } else {
+                        Some(u32::try_from(bc.read_small_uint()?).map_err(|_| "INTEGER out of range")?)
+                    };
+                    bc.finish()?;
+                    exts.basic_constraints = Some(BasicConstraints { ca, path_len });
+                }
+                ID_CE_KEY_USAGE => exts.key_usage = Some(KeyUsage::from_der(value)?),
+                ID_CE_SUBJECT_KEY_ID => exts.subject_key_id = Some(der::parse(value, der::OCTET_STRING)?.to_vec()),
+                ID_CE_AUTHORITY_KEY_ID => {
+                    let mut aki = Reader::new(der::parse(value, SEQUENCE)?);
+                    exts.authority_key_id = aki.read_optional(der::context_primitive(0))?.map(|id| id.to_vec());
+                    // authorityCertIssuer and authorityCertSerialNumber are not used.
+                    while !aki.is_empty() {
+                        aki.read_any()?;
+                    }
+                }
+                ID_CE_SUBJECT_ALT_NAME => {
+                    let mut names = Reader::new(der::parse(value, SEQUENCE)?);
+                    if names.is_empty() {
+                        return Err("Empty subjectAltName");
+                    }
+                    while !names.is_empty() {
+                        exts.subject_alt_names.push(GeneralName::read(&mut names)?);
+                    }
+                }
+                _ => exts.unknown_critical |= critical,
+            }
+        }
+        Ok(exts)
+    }
+}
+
+/// The contents of a certificate to be issued. The issuer name, signature
+/// algorithm and key identifiers come from the issuer when signing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateParams {
+    /// Unsigned big-endian, at most 20 octets and not zero.
+    pub serial_number: Vec<u8>,
+    pub subject: Name,
+    /// Seconds after the Unix epoch.
+    pub not_before: u64,
+    pub not_after: u64,
+    pub basic_constraints: Option<BasicConstraints>,
+    pub key_usage: Option<KeyUsage>,
+    pub subject_alt_names: Vec<GeneralName>,
+}
+
+impl CertificateParams {
+    pub fn new(serial_number: &[u8], subject: Name, not_before: u64, not_after: u64) -> Self {
+        CertificateParams {
+            serial_number: serial_number.to_vec(),
+            subject,
+            not_before,
+            not_after,
+            basic_constraints: None,
+            key_usage: None,
+            subject_alt_names: Vec::new(),
+        }
+    }
+
+    /// Signs the certificate with its own key, making a self-signed root.
+    #[cfg(feature = "getrandom")]
+    pub fn self_signed(&self, keypair: &Keypair) -> Result<Certificate, &'static str> {
+        self.self_signed_with_rng(keypair, &mut OsRng)
+    }
+
+    pub fn self_signed_with_rng<R: RngCore>(&self, keypair: &Keypair, rng: &mut R) -> Result<Certificate, &'static str> {
+        let spki = SubjectPublicKeyInfo::from_keypair(keypair);
+        let key_id = spki.key_identifier().to_vec();
+        self.sign(&spki, &self.subject, Some(key_id), keypair, rng)
+    }
+
+    /// Issues a certificate for `subject_key` under `issuer`, whose secret
+    /// key is `issuer_key`.
+    #[cfg(feature = "getrandom")]
+    pub fn signed_by(
+        &self,
+        subject_key: &SubjectPublicKeyInfo,
+        issuer: &Certificate,
+        issuer_key: &Keypair,
+    ) -> Result<Certificate, &'static str> {
+        self.signed_by_with_rng(subject_key, issuer, issuer_key, &mut OsRng)
+    }
+
+    pub fn signed_by_with_rng<R: RngCore>(
+        &self,
+        subject_key: &SubjectPublicKeyInfo,
+        issuer: &Certificate,
+        issuer_key: &Keypair,
+        rng: &mut R,
+    ) -> Result<Certificate, &'static str> {
+        if *issuer.public_key() != SubjectPublicKeyInfo::from_keypair(issuer_key) {
+            return Err("Issuer key does not match the issuer certificate");
+        }
+        let key_id = issuer.subject_key_id().map(|id| id.to_vec());
+        self.sign(subject_key, issuer.subject(), key_id, issuer_key, rng)
+    }
+
+    fn sign<R: RngCore>(
+        &self,
+        subject_key: &SubjectPublicKeyInfo,
+        issuer: &Name,
+        authority_key_id: Option<Vec<u8>>,
+        issuer_key: &Keypair,
+        rng: &mut R,
+    ) -> Result<Certificate, &'static str> {
+        let skip = self.serial_number.iter().take_while(|&&b| b == 0).count();
+        let serial = &self.serial_number[skip..];
+        if serial.is_empty() || serial.len() > MAX_SERIAL_BYTES {
+            return Err("Invalid serial number");
+        }
+        if self.not_before > self.not_after {
+            return Err("Invalid validity period");
+        }
+        let extensions = Extensions {
+            basic_constraints: self.basic_constraints,
+            key_usage: self.key_usage,
+            subject_key_id: Some(subject_key.key_identifier().to_vec()),
+            authority_key_id,
+            subject_alt_names: self.subject_alt_names.clone(),
+            unknown_critical: false,
+        };
+        let alg_id = algorithm_identifier(issuer_key.algorithm());
+        let validity = der::seq(&[&der::time(self.not_before)?, &der::time(self.not_after)?]);
+        let tbs = der::seq(&[
+            &der::tlv(der::context(0), &der::small_uint(2)),
+            &der::uint(serial),
+            &alg_id,
+            &issuer.to_der(),
+            &validity,
+            &self.subject.to_der(),
+            &subject_key.to_der(),
+            &der::tlv(der::context(3), &extensions.to_der(self.subject.is_empty())?),
+        ]);
+        let signature = issuer_key.sign_with_rng(&tbs, rng);
+        Certificate::from_der(&der::seq(&[&tbs, &alg_id, &der::bit_string(signature.bytes())]))
+    }
+}
+
+/// A parsed X.509 v3 certificate with an ML-DSA key and signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    tbs: Vec<u8>,
+    signature: Vec<u8>,
+    signature_algorithm: Algorithm,
+    serial_number: Vec<u8>,
+    issuer: Name,
+    subject: Name,
+    not_before: u64,
+    not_after: u64,
+    public_key: SubjectPublicKeyInfo,
+    pub(crate) extensions: Extensions,
+}
+
+impl Certificate {
+    pub fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        let mut cert = Reader::new(der::parse(der, SEQUENCE)?);
+        let tbs = cert.read_raw(SEQUENCE)?;
+        let signature_algorithm = read_algorithm(&mut cert)?;
+        let signature = cert.read_bit_string()?;
+        cert.finish()?;
+
+        let mut r = Reader::new(der::parse(tbs, SEQUENCE)?);
+        if r.read(der::context(0))? != der::small_uint(2) {
+            return Err("Unsupported certificate version");
+        }
+        let serial_number = r.read_uint()?.to_vec();
+        if read_algorithm(&mut r)? != signature_algorithm {
+            return Err("Mismatched signature algorithms");
+        }
+        let issuer = Name::read(&mut r)?;
+        let mut validity = Reader::new(r.read(SEQUENCE)?);
+        let not_before = validity.read_time()?;
+        let not_after = validity.read_time()?;
+        validity.finish()?;
+        let subject = Name::read(&mut r)?;
+        let public_key = SubjectPublicKeyInfo::read(&mut r)?;
+        // issuerUniqueID and subjectUniqueID are obsolete and ignored.
+        r.read_optional(der::context_primitive(1))?;
+        r.read_optional(der::context_primitive(2))?;
+        let extensions = match r.read_optional(der::context(3))? {
+            Some(exts) => Extensions::parse(der::parse(exts, SEQUENCE)?)?,
+            None => Extensions::default(),
+        };
+        r.finish()?;
+
+        Ok(Certificate {
+            der: der.to_vec(),
+            tbs: tbs.to_vec(),
+            signature: signature.to_vec(),
+            signature_algorithm,
+            serial_number,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            public_key,
+            extensions,
+        })
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, &'static str> {
+        Self::from_der(&base64::pem_decode("CERTIFICATE", pem)?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        base64::pem_encode("CERTIFICATE", &self.der)
+    }
+
+    /// The signed `TBSCertificate` encoding.
+    pub fn tbs_certificate(&self) -> &[u8] {
+        &self.tbs
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    pub fn signature_algorithm(&self) -> Algorithm {
+        self.signature_algorithm
+    }
+
+    /// Unsigned big-endian.
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    pub fn issuer(&self) -> &Name {
+        &self.issuer
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    /// Seconds after the Unix epoch.
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    pub fn public_key(&self) -> &SubjectPublicKeyInfo {
+        &self.public_key
+    }
+
+    pub fn basic_constraints(&self) -> Option<BasicConstraints> {
+        self.extensions.basic_constraints
+    }
+
+    pub fn key_usage(&self) -> Option<KeyUsage> {
+        self.extensions.key_usage
+    }
+
+    pub fn subject_key_id(&self) -> Option<&[u8]> {
+        self.extensions.subject_key_id.as_deref()
+    }
+
+    pub fn authority_key_id(&self) -> Option<&[u8]> {
+        self.extensions.authority_key_id.as_deref()
+    }
+
+    pub fn subject_alt_names(&self) -> &[GeneralName] {
+        &self.extensions.subject_alt_names
+    }
+
+    /// Whether the issuer and subject names are the same.
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer == self.subject
+    }
+
+    /// Verifies the certificate's signature with the issuer's public key.
+    /// Validity, extensions and names are not checked.
+    pub fn verify_signature(&self, issuer_key: &SubjectPublicKeyInfo) -> Result<(), &'static str> {
+        if issuer_key.algorithm() != self.signature_algorithm {
+            return Err("Signature algorithm does not match the issuer key");
+        }
+        issuer_key.verify(&self.signature, &self.tbs)
+    }
+}
+
+/// A parsed PKCS#10 certificate request. The only attribute understood is
+/// the extension request, of which the subjectAltName is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateRequest {
+    der: Vec<u8>,
+    info: Vec<u8>,
+    signature: Vec<u8>,
+    signature_algorithm: Algorithm,
+    subject: Name,
+    public_key: SubjectPublicKeyInfo,
+    subject_alt_names: Vec<GeneralName>,
+}
+
+impl CertificateRequest {
+    /// Creates a request for `subject` and `keypair`'s public key, asking for
+    /// `subject_alt_names` if not empty.
+    #[cfg(feature = "getrandom")]
+    pub fn new(subject: Name, subject_alt_names: &[GeneralName], keypair: &Keypair) -> Result<Self, &'static str> {
+        Self::new_with_rng(subject, subject_alt_names, keypair, &mut OsRng)
+    }
+
+    pub fn new_with_rng<R: RngCore>(
+        subject: Name,
+        subject_alt_names: &[GeneralName],
+        keypair: &Keypair,
+        rng: &mut R,
+    ) -> Result<Self, &'static str> {
+        let attributes = if subject_alt_names.is_empty() {
+            Vec::new()
+        } else {
+            let extensions = Extensions { subject_alt_names: subject_alt_names.to_vec(), ..Extensions::default() };
+            let values = der::set_of(vec![extensions.to_der(subject.is_empty())?]);
+            der::seq(&[&der::oid(PKCS9_EXTENSION_REQUEST), &values])
+        };
+        let info = der::seq(&[
+            &der::small_uint(0),
+            &subject.to_der(),
+            &SubjectPublicKeyInfo::from_keypair(keypair).to_der(),
+            &der::tlv(der::context(0), &attributes),
+        ]);
+        let signature = keypair.sign_with_rng(&info, rng);
+        let alg_id = algorithm_identifier(keypair.algorithm());
+        Self::from_der(&der::seq(&[&info, &alg_id, &der::bit_string(signature.bytes())]))
+    }
+
+    pub fn from_der(der: &[u8]) -> Result<Self, &'static str> {
+        let mut req = Reader::new(der::parse(der, SEQUENCE)?);
+        let info = req.read_raw(SEQUENCE)?;
+        let signature_algorithm = read_algorithm(&mut req)?;
+        let signature = req.read_bit_string()?;
+        req.finish()?;
+
+        let mut r = Reader::new(der::parse(info, SEQUENCE)?);
+        if r.read_small_uint()? != 0 {
+            return Err("Unsupported request version");
+        }
+        let subject = Name::read(&mut r)?;
+        let public_key = SubjectPublicKeyInfo::read(&mut r)?;
+        let mut attributes = Reader::new(r.read(der::context(0))?);
+        r.finish()?;
+        let mut subject_alt_names = Vec::new();
+        while !attributes.is_empty() {
+            let mut attribute = Reader::new(attributes.read(SEQUENCE)?);
+            let oid = attribute.read(OID)?;
+            let mut values = Reader::new(attribute.read(der::SET)?);
+            attribute.finish()?;
+            if oid == PKCS9_EXTENSION_REQUEST {
+                subject_alt_names = Extensions::parse(values.read(SEQUENCE)?)?.subject_alt_names;
+                values.finish()?;
+            }
+        }
+
+        Ok(CertificateRequest {
+            der: der.to_vec(),
+            info: info.to_vec(),
+            signature: signature.to_vec(),
+            signature_algorithm,
+            subject,
+            public_key,
+            subject_alt_names,
+        })
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, &'static str> {
+        Self::from_der(&base64::pem_decode("CERTIFICATE REQUEST", pem)?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        base64::pem_encode("CERTIFICATE REQUEST", &self.der)
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    pub fn public_key(&self) -> &SubjectPublicKeyInfo {
+        &self.public_key
+    }
+
+    pub fn subject_alt_names(&self) -> &[GeneralName] {
+        &self.subject_alt_names
+    }
+
+    /// Verifies the request's self-signature, the proof that the requester
+    /// holds the secret key.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        if self.public_key.algorithm() != self.signature_algorithm {
+            return Err("Signature algorithm does not match the public key");
+        }
+        self.public_key.verify(&self.signature, &self.info)
+    }
+}
+
+/// Why [`validate_path`] rejected a certificate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// The chain is empty.
+    EmptyPath,
+    /// The signature does not verify with the issuer's key, or uses a
+    /// different algorithm than that key.
+    InvalidSignature,
+    NotYetValid,
+    Expired,
+    /// The issuer name is not the subject of the certificate above it.
+    IssuerMismatch,
+    /// An issuing certificate lacks basicConstraints with cA set.
+    NotCa,
+    /// More intermediate CAs than a pathLenConstraint above them allows.
+    PathLenExceeded,
+    /// An issuing certificate has keyUsage without keyCertSign.
+    KeyUsage,
+    /// A critical extension this module does not process.
+    UnsupportedCriticalExtension,
+}
+
+impl PathErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathErrorKind::EmptyPath => "Empty certificate path",
+            PathErrorKind::InvalidSignature => "Invalid certificate signature",
+            PathErrorKind::NotYetValid => "Certificate not yet valid",
+            PathErrorKind::Expired => "Certificate expired",
+            PathErrorKind::IssuerMismatch => "Issuer does not match the issuing certificate",
+            PathErrorKind::NotCa => "Issuing certificate is not a CA",
+            PathErrorKind::PathLenExceeded => "Path length constraint exceeded",
+            PathErrorKind::KeyUsage => "Issuing certificate lacks keyCertSign",
+            PathErrorKind::UnsupportedCriticalExtension => "Unsupported critical extension",
+        }
+    }
+}
+
+/// A failed path validation: which certificate of the chain failed (0 is
+/// the end-entity certificate) and why.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathError {
+    pub index: usize,
+    pub kind: PathErrorKind,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "certificate {}: {}", self.index, self.kind.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+/// Validates `chain`, ordered from the end-entity certificate up, against
+/// the trust anchor `root` at `now` (seconds after the Unix epoch).
+///
+/// This is the RFC 5280 section 6.1 algorithm restricted to signatures,
+/// validity periods, name chaining, basicConstraints with pathLenConstraint
+/// and the keyCertSign key usage; policies, name constraints and revocation
+/// are out of scope. Only the subject, key and pathLenConstraint of `root`
+/// are used, so its own validity is not checked. `chain` may end with
+/// `root` itself, which is then skipped. The end-entity certificate's key
+/// usage is left to the caller.
+pub fn validate_path(chain: &[Certificate], root: &Certificate, now: u64) -> Result<(), PathError> {
+    let chain = match chain.split_last() {
+        Some((last, rest)) if last == root => rest,
+        _ => chain,
+    };
+    if chain.is_empty() {
+        return Err(PathError { index: 0, kind: PathErrorKind::EmptyPath });
+    }
+    let mut issuer_key = root.public_key();
+    let mut issuer_name = root.subject();
+    let mut max_path_len = root.basic_constraints().and_then(|bc| bc.path_len).map_or(chain.len(), |n| n as usize);
+
+    for (index, cert) in chain.iter().enumerate().rev() {
+        let fail = |kind| Err(PathError { index, kind });
+        if cert.verify_signature(issuer_key).is_err() {
+            return fail(PathErrorKind::InvalidSignature);
+        }
+        if now < cert.not_before() {
+            return fail(PathErrorKind::NotYetValid);
+        }
+        if now > cert.not_after() {
+            return fail(PathErrorKind::Expired);
+        }
+        if cert.issuer() != issuer_name {
+            return fail(PathErrorKind::IssuerMismatch);
+        }
+        if cert.extensions.unknown_critical {
+            return fail(PathErrorKind::UnsupportedCriticalExtension);
+        }
+        if index == 0 {
+            break;
+        }
+        // Preparation for the next certificate (RFC 5280, 6.1.4 (k)-(n)).
+        if !cert.basic_constraints().is_some_and(|bc| bc.ca) {
+            return fail(PathErrorKind::NotCa);
+        }
+        if !cert.is_self_issued() {
+            if max_path_len == 0 {
+                return fail(PathErrorKind::PathLenExceeded);
+            }
+            max_path_len -= 1;
+        }
+        if let Some(n) = cert.basic_constraints().and_then(|bc| bc.path_len) {
+            max_path_len = max_path_len.min(n as usize);
+        }
+        if cert.key_usage().is_some_and(|ku| !ku.contains(KeyUsage::KEY_CERT_SIGN)) {
+            return fail(PathErrorKind::KeyUsage);
+        }
+        issuer_key = cert.public_key();
+        issuer_name = cert.subject();
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! The buffer-based API is all a `no_std`, allocation-free caller gets, so
+//! exercise it without touching `OsRng` or `alloc`.
+
+use pure_dsa::Algorithm;
+use rand::{SeedableRng, rngs::StdRng};
+
+const MAX_PK: usize = 2592;
+const MAX_SK: usize = 4896;
+const MAX_SIG: usize = 4595;
+
+fn roundtrip(alg: Algorithm) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut pk = [0u8; MAX_PK];
+    let mut sk = [0u8; MAX_SK];
+    let mut sig = [0u8; MAX_SIG];
+    let pk = &mut pk[..alg.public_key_bytes()];
+    let sk = &mut sk[..alg.secret_key_bytes()];
+    let sig = &mut sig[..alg.signature_bytes()];
+
+    alg.generate_into(pk, sk, &mut rng).unwrap();
+    alg.sign_into(sig, b"buffers", sk, &mut rng).unwrap();
+    assert!(alg.verify_raw(sig, b"buffers", pk).is_ok());
+    assert!(alg.verify_raw(sig, b"bufferz", pk).is_err());
+
+    let keypair = alg.load_from_bytes(sk, pk).unwrap();
+    let mut again = [0u8; MAX_SIG];
+    keypair.sign_into(&mut again[..alg.signature_bytes()], b"buffers", &mut rng).unwrap();
+    assert!(alg.verify_raw(&again[..alg.signature_bytes()], b"buffers", pk).is_ok());
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn mode2_caller_buffers() {
+    roundtrip(Algorithm::Mode2);
+}
+
+#[cfg(feature = "mode3")]
+#[test]
+fn mode3_caller_buffers() {
+    roundtrip(Algorithm::Mode3);
+}
+
+#[cfg(feature = "mode5")]
+#[test]
+fn mode5_caller_buffers() {
+    roundtrip(Algorithm::Mode5);
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn rejects_wrong_buffer_lengths() {
+    let alg = Algorithm::Mode2;
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut pk = [0u8; MAX_PK];
+    let mut sk = [0u8; MAX_SK];
+    assert!(alg.generate_into(&mut pk, &mut sk, &mut rng).is_err());
+
+    let (pk, sk) = (&mut pk[..alg.public_key_bytes()], &mut sk[..alg.secret_key_bytes()]);
+    alg.generate_into(pk, sk, &mut rng).unwrap();
+    let mut sig = [0u8; MAX_SIG];
+    assert!(alg.sign_into(&mut sig, b"m", sk, &mut rng).is_err());
+    assert!(alg.sign_into(&mut sig[..alg.signature_bytes()], b"m", &sk[1..], &mut rng).is_err());
+}
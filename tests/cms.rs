@@ -0,0 +1,158 @@
+//! CMS SignedData: pure signers and signers with signed attributes,
+//! encapsulated and detached content, several signers, and the ways
+//! verification fails.
+#![cfg(all(feature = "cms", feature = "mode2", feature = "mode3"))]
+
+use pure_dsa::cms::*;
+use pure_dsa::x509::*;
+use pure_dsa::{Algorithm, Keypair};
+use rand::{SeedableRng, rngs::StdRng};
+
+/// 2025-01-01T00:00:00Z.
+const T0: u64 = 1735689600;
+const FIRMWARE: &[u8] = b"\x7fELF firmware image v1.2.3";
+
+fn signer(rng: &mut StdRng, alg: Algorithm, cn: &str) -> (Keypair, Certificate) {
+    let key = alg.generate_with_rng(rng);
+    let mut params = CertificateParams::new(&[1], Name::new().with_common_name(cn), T0, T0 + 86400 * 365);
+    params.key_usage = Some(KeyUsage::DIGITAL_SIGNATURE);
+    let cert = params.self_signed_with_rng(&key, rng).unwrap();
+    (key, cert)
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+/// Every digest of "abc"; SHAKE256 is taken to 512 bits. SHA-2 is covered
+/// in depth by `tests/sha2.rs`.
+#[test]
+fn digests() {
+    let vectors = [
+        (DigestAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (
+            DigestAlgorithm::Sha512,
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        ),
+        (DigestAlgorithm::Sha3_256, "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"),
+        (
+            DigestAlgorithm::Sha3_384,
+            "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b2\
+             98d88cea927ac7f539f1edf228376d25",
+        ),
+        (
+            DigestAlgorithm::Sha3_512,
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+             10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        ),
+        (
+            DigestAlgorithm::Shake256,
+            "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739\
+             d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+        ),
+    ];
+    for (digest, expected) in vectors {
+        assert_eq!(digest.digest(b"abc"), hex(expected), "{digest:?}");
+    }
+}
+
+#[test]
+fn encapsulated_signed_attributes() {
+    let mut rng = StdRng::seed_from_u64(5652);
+    let (key, cert) = signer(&mut rng, Algorithm::Mode3, "release signing");
+    let mode = SignatureMode::SignedAttributes { digest: DigestAlgorithm::Sha512, signing_time: Some(T0 + 60) };
+    let sd = SignedDataBuilder::new(FIRMWARE).add_signer(&cert, &key, mode).build_with_rng(&mut rng).unwrap();
+
+    let sd = SignedData::from_pem(&sd.to_pem()).unwrap();
+    assert!(!sd.is_detached());
+    assert_eq!(sd.content(), Some(FIRMWARE));
+    assert_eq!(sd.certificates(), std::slice::from_ref(&cert));
+    let info = &sd.signers()[0];
+    assert_eq!(info.mode(), mode);
+    assert_eq!(info.signing_time(), Some(T0 + 60));
+    assert_eq!(info.signature_algorithm(), Algorithm::Mode3);
+    assert_eq!(info.sid(), &SignerIdentifier::IssuerAndSerialNumber(cert.issuer().clone(), vec![1]));
+    assert_eq!(sd.verify().unwrap(), [&cert]);
+    assert_eq!(sd.verify_detached(FIRMWARE), Err("Content is encapsulated"));
+
+    // The content sits in the clear; changing it breaks the message digest.
+    let mut der = sd.to_der().to_vec();
+    let at = der.windows(FIRMWARE.len()).position(|w| w == FIRMWARE).unwrap();
+    der[at + 5] ^= 1;
+    assert_eq!(SignedData::from_der(&der).unwrap().verify(), Err("Message digest mismatch"));
+}
+
+#[test]
+fn detached_pure() {
+    let mut rng = StdRng::seed_from_u64(9882);
+    let (key, cert) = signer(&mut rng, Algorithm::Mode2, "build bot");
+    let sd = SignedDataBuilder::new(FIRMWARE)
+        .detached()
+        .add_signer(&cert, &key, SignatureMode::Pure)
+        .build_with_rng(&mut rng)
+        .unwrap();
+    let sd = SignedData::from_der(sd.to_der()).unwrap();
+    assert!(sd.is_detached());
+    assert_eq!(sd.content(), None);
+    assert_eq!(sd.signers()[0].mode(), SignatureMode::Pure);
+    assert_eq!(sd.signers()[0].digest_algorithm(), DigestAlgorithm::Sha512);
+    assert_eq!(sd.signers()[0].signing_time(), None);
+    assert_eq!(sd.verify_detached(FIRMWARE).unwrap(), [&cert]);
+    assert_eq!(sd.verify(), Err("Detached content required"));
+    assert_eq!(sd.verify_detached(b"other firmware"), Err("Invalid signature"));
+}
+
+#[test]
+fn several_signers_and_a_chain() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let root_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+    let mut params = CertificateParams::new(&[1], Name::new().with_common_name("Root"), T0, T0 + 86400 * 3650);
+    params.basic_constraints = Some(BasicConstraints { ca: true, path_len: None });
+    let root = params.self_signed_with_rng(&root_key, &mut rng).unwrap();
+    let ca_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+    params.subject = Name::new().with_common_name("Code signing CA");
+    params.serial_number = vec![2];
+    let ca = params.signed_by_with_rng(&SubjectPublicKeyInfo::from_keypair(&ca_key), &root, &root_key, &mut rng).unwrap();
+    let dev_key = Algorithm::Mode2.generate_with_rng(&mut rng);
+    let dev_params = CertificateParams::new(&[3], Name::new().with_common_name("developer"), T0, T0 + 86400 * 365);
+    let dev = dev_params.signed_by_with_rng(&SubjectPublicKeyInfo::from_keypair(&dev_key), &ca, &ca_key, &mut rng).unwrap();
+    let (bot_key, bot) = signer(&mut rng, Algorithm::Mode3, "build bot");
+
+    let sd = SignedDataBuilder::new(FIRMWARE)
+        .detached()
+        .add_certificate(&ca)
+        .add_signer(&dev, &dev_key, SignatureMode::SignedAttributes { digest: DigestAlgorithm::Shake256, signing_time: None })
+        .add_signer(&bot, &bot_key, SignatureMode::Pure)
+        .build_with_rng(&mut rng)
+        .unwrap();
+    let sd = SignedData::from_der(sd.to_der()).unwrap();
+    assert_eq!(sd.certificates().len(), 3);
+    let signers = sd.verify_detached(FIRMWARE).unwrap();
+    assert_eq!(signers, [&dev, &bot]);
+    assert_eq!(validate_path(&[signers[0].clone(), ca.clone()], &root, T0 + 1), Ok(()));
+
+    let mut der = sd.to_der().to_vec();
+    let sig = sd.signers()[1].signature();
+    let at = der.windows(sig.len()).position(|w| w == sig).unwrap();
+    der[at + 100] ^= 1;
+    assert_eq!(SignedData::from_der(&der).unwrap().verify_detached(FIRMWARE), Err("Invalid signature"));
+}
+
+#[test]
+fn builder_errors() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let (key, cert) = signer(&mut rng, Algorithm::Mode2, "a");
+    let (other_key, _) = signer(&mut rng, Algorithm::Mode2, "b");
+    assert_eq!(SignedDataBuilder::new(FIRMWARE).build_with_rng(&mut rng).err(), Some("No signers"));
+    assert_eq!(
+        SignedDataBuilder::new(FIRMWARE)
+            .add_signer(&cert, &other_key, SignatureMode::Pure)
+            .build_with_rng(&mut rng)
+            .err(),
+        Some("Signer key does not match its certificate")
+    );
+    let sd = SignedDataBuilder::new(FIRMWARE).add_signer(&cert, &key, SignatureMode::Pure).build_with_rng(&mut rng).unwrap();
+    assert_eq!(SignedData::from_der(&sd.to_der()[..sd.to_der().len() - 1]).err(), Some("Malformed DER"));
+    assert_eq!(SignedData::from_der(cert.to_der()).err(), Some("Unexpected DER tag"));
+}
@@ -0,0 +1,131 @@
+//! COSE: AKP keys through CBOR, COSE_Sign1 with external data, tagged and
+//! CWT-wrapped messages, and the encodings the decoder must refuse.
+#![cfg(all(feature = "cose", feature = "mode2", feature = "mode3"))]
+
+use pure_dsa::{Algorithm, SeedKey};
+use pure_dsa::cose::*;
+use rand::{SeedableRng, rngs::StdRng};
+
+/// A CWT claims set: {1: "iss", 4: 1767225600}.
+const CLAIMS: &[u8] = b"\xa2\x01\x63iss\x04\x1a\x69\x55\xb9\x00";
+
+fn generate(algorithm: Algorithm, rng: &mut StdRng) -> CoseKey {
+    SeedKey::generate_with_rng(algorithm, rng).into()
+}
+
+#[test]
+fn cose_key_round_trip() {
+    let mut rng = StdRng::seed_from_u64(9052);
+    let key = generate(Algorithm::Mode3, &mut rng).with_kid(b"k1");
+    let encoded = key.to_cbor();
+    // {1: 7, 2: h'6b31', 3: -49, -1: h'...', -2: h'...'}, keys in deterministic order.
+    assert!(encoded.starts_with(b"\xa5\x01\x07\x02\x42k1\x03\x38\x30\x20\x59\x07\xa0"));
+    assert!(encoded.ends_with(&[&b"\x21\x58\x20"[..], key.key().seed().unwrap()].concat()));
+
+    let parsed = CoseKey::from_cbor(&encoded).unwrap();
+    assert!(parsed.key().is_private());
+    assert_eq!(parsed.kid(), Some(&b"k1"[..]));
+    assert_eq!(parsed.key().algorithm(), Algorithm::Mode3);
+    assert_eq!(parsed.key().public_key(), key.key().public_key());
+    assert_eq!(parsed.key().keypair().unwrap().public(), key.key().public_key());
+
+    let public = key.to_public().to_cbor();
+    assert!(public.starts_with(b"\xa4\x01\x07"));
+    let public = CoseKey::from_cbor(&public).unwrap();
+    assert!(!public.key().is_private());
+    assert_eq!(public.key().keypair().err(), Some("Key has no seed"));
+
+    // Swap in another key's seed.
+    let other = generate(Algorithm::Mode3, &mut rng);
+    let mut spliced = encoded.clone();
+    let n = spliced.len();
+    spliced[n - 32..].copy_from_slice(other.key().seed().unwrap());
+    assert_eq!(CoseKey::from_cbor(&spliced).err(), Some("Private key does not match the public key"));
+}
+
+#[test]
+fn cose_key_errors() {
+    let key = generate(Algorithm::Mode2, &mut StdRng::seed_from_u64(1)).to_public().to_cbor();
+    let with = |at: usize, byte: u8| {
+        let mut k = key.clone();
+        k[at] = byte;
+        CoseKey::from_cbor(&k).err()
+    };
+    assert_eq!(with(2, 0x02), Some("Unsupported key type"));
+    assert_eq!(with(5, 0x32), Some("Unsupported algorithm"));
+    assert_eq!(with(5, 0x30), Some("Invalid public key length"));
+    assert_eq!(with(3, 0x01), Some("Duplicate CBOR map key"));
+    assert_eq!(CoseKey::from_cbor(&key[..key.len() - 1]).err(), Some("Malformed CBOR"));
+    assert_eq!(CoseKey::from_cbor(&[&key[..], &[0]].concat()).err(), Some("Trailing CBOR data"));
+    assert_eq!(CoseKey::from_cbor(b"\x80").err(), Some("Expected a CBOR map"));
+    assert_eq!(CoseKey::from_cbor(b"\xbf\xff").err(), Some("Unsupported indefinite-length CBOR"));
+    // A 1 written with a one-byte argument is not the shortest form.
+    assert_eq!(CoseKey::from_cbor(b"\xa1\x18\x01\x07").err(), Some("Malformed CBOR"));
+    assert_eq!(CoseKey::from_cbor(b"\xa2\x01\x07\x03\x38\x2f").err(), Some("Missing COSE parameter"));
+    assert_eq!(SeedKey::from_seed(Algorithm::Mode2, &[0; 33]).err(), Some("Invalid seed length"));
+}
+
+#[test]
+fn sign1() {
+    let mut rng = StdRng::seed_from_u64(9053);
+    let key = generate(Algorithm::Mode2, &mut rng);
+    let keypair = key.key().keypair().unwrap();
+    let header = Header::new(Algorithm::Mode2).with_kid(b"d1");
+    let msg = CoseSign1::sign_with_rng(&header, CLAIMS, b"device-42", &keypair, &mut rng).unwrap();
+
+    // 18([h'a101382f', {4: h'6431'}, payload, signature]).
+    let encoded = msg.to_cbor();
+    let prefix = [&b"\xd2\x84\x44\xa1\x01\x38\x2f\xa1\x04\x42d1\x4c"[..], CLAIMS, b"\x59\x09\x74"].concat();
+    assert!(encoded.starts_with(&prefix));
+    assert_eq!(encoded.len(), prefix.len() + Algorithm::Mode2.signature_bytes());
+
+    let parsed = CoseSign1::from_cbor(encoded).unwrap();
+    assert_eq!(parsed, msg);
+    assert_eq!(parsed.header(), &header);
+    assert_eq!(parsed.payload(), CLAIMS);
+    assert_eq!(parsed.verify(&key.to_public(), b"device-42"), Ok(CLAIMS));
+    assert_eq!(parsed.verify(&key, b"device-43"), Err("Invalid signature"));
+    assert_eq!(parsed.verify(&generate(Algorithm::Mode2, &mut rng), b"device-42"), Err("Invalid signature"));
+    assert_eq!(
+        parsed.verify(&generate(Algorithm::Mode3, &mut rng), b"device-42"),
+        Err("Key algorithm does not match the header")
+    );
+
+    // Untagged and CWT-wrapped forms carry the same signature.
+    let untagged = CoseSign1::from_cbor(&encoded[1..]).unwrap();
+    assert_eq!(untagged.verify(&key, b"device-42"), Ok(CLAIMS));
+    let cwt = CoseSign1::from_cbor(&[&b"\xd8\x3d"[..], encoded].concat()).unwrap();
+    assert_eq!(cwt.verify(&key, b"device-42"), Ok(CLAIMS));
+    assert_eq!(cwt.to_cbor()[..2], *b"\xd8\x3d");
+
+    let mut tampered = encoded.to_vec();
+    tampered[prefix.len() - 4] ^= 1;
+    let tampered = CoseSign1::from_cbor(&tampered).unwrap();
+    assert_ne!(tampered.payload(), CLAIMS);
+    assert_eq!(tampered.verify(&key, b"device-42"), Err("Invalid signature"));
+
+    assert_eq!(
+        CoseSign1::sign_with_rng(&Header::new(Algorithm::Mode3), CLAIMS, b"", &keypair, &mut rng).err(),
+        Some("Header alg does not match the key")
+    );
+}
+
+#[test]
+fn sign1_headers() {
+    let parse = |bytes: &[u8]| CoseSign1::from_cbor(bytes).err();
+    assert_eq!(parse(b"\x84\x40\xa1\x01\x38\x2f\x43abc\x40"), Some("Unprotected alg header parameter"));
+    assert_eq!(parse(b"\x84\x40\xa0\x43abc\x40"), Some("Missing alg header parameter"));
+    assert_eq!(parse(b"\x84\x47\xa2\x01\x38\x2f\x02\x81\x04\xa0\x43abc\x40"), Some("Unsupported critical header parameter"));
+    assert_eq!(parse(b"\x84\x44\xa1\x01\x38\x2f\xa1\x01\x38\x2f\x43abc\x40"), Some("Duplicate header parameter"));
+    assert_eq!(parse(b"\x84\x44\xa1\x01\x38\x2f\xa0\xf6\x40"), Some("Detached payload not supported"));
+    assert_eq!(parse(b"\x84\x44\xa1\x01\x38\x2f\xa0\x58\x01\x00\x40"), Some("Malformed CBOR"));
+    assert_eq!(parse(b"\x84\x44\xa1\x01\x38\x2f\xa0\x43abc"), Some("Malformed CBOR"));
+    assert_eq!(parse(b"\x83\x44\xa1\x01\x38\x2f\xa0\x43abc"), Some("Expected a COSE_Sign1"));
+    assert_eq!(parse(b"\xd8\x3d\xa0"), Some("Expected a COSE_Sign1"));
+    assert_eq!(parse(b"\x84\x44\xa1\x01\x38\x2f\xa0\x63abc\x40"), Some("Invalid COSE_Sign1 field"));
+
+    // Unknown parameters are skipped, whatever their type.
+    let msg = CoseSign1::from_cbor(b"\x84\x44\xa1\x01\x38\x2f\xa2\x61x\xf9\x3e\x00\x20\x82\xf5\xf7\x43abc\x40").unwrap();
+    assert_eq!(msg.header(), &Header::new(Algorithm::Mode2));
+    assert_eq!(msg.payload(), b"abc");
+}
@@ -0,0 +1,86 @@
+//! The hardened signer must agree with the plain one on good keys and refuse
+//! to release anything when the stored key is inconsistent or the signing
+//! state is corrupted. The latter needs the `fault_injection` feature.
+#![cfg(feature = "hardened")]
+
+#[cfg(feature = "fault_injection")]
+use pure_dsa::Fault;
+use pure_dsa::{Algorithm, FAULT_DETECTED, SecretBytes};
+use rand::{SeedableRng, rngs::StdRng};
+
+const MAX_SIG: usize = 4595;
+
+fn check(alg: Algorithm) {
+    let mut rng = StdRng::seed_from_u64(34);
+    let keypair = alg.generate_with_rng(&mut rng);
+
+    let sig = keypair.sign_hardened_with_rng(b"hardened", &mut rng).unwrap();
+    assert!(alg.verify(&sig, b"hardened", keypair.public()).is_ok());
+
+    // A single flipped bit in s1, as a fault in key storage would leave it.
+    let mut sk = SecretBytes::<4896>::default();
+    let sk = &mut sk[..alg.secret_key_bytes()];
+    sk.copy_from_slice(keypair.secret());
+    sk[3 * 32 + 5] ^= 0x01;
+
+    let mut out = [0xAAu8; MAX_SIG];
+    let out = &mut out[..alg.signature_bytes()];
+    assert_eq!(alg.sign_into_hardened(out, b"hardened", sk, &mut rng), Err(FAULT_DETECTED));
+    assert!(out.iter().all(|&b| b == 0));
+}
+
+/// Each fault lands after a valid signature has been produced, so only the
+/// digest and challenge checks stand between it and the caller.
+#[cfg(feature = "fault_injection")]
+fn check_faults(alg: Algorithm) {
+    let mut rng = StdRng::seed_from_u64(35);
+    let keypair = alg.generate_with_rng(&mut rng);
+    let mut out = [0u8; MAX_SIG];
+    let out = &mut out[..alg.signature_bytes()];
+
+    alg.sign_into_hardened_faulted(out, b"hardened", keypair.secret(), &mut rng, Fault::None).unwrap();
+    assert!(alg.verify_raw(out, b"hardened", keypair.public()).is_ok());
+
+    for fault in [Fault::Secret, Fault::W1, Fault::Challenge] {
+        out.fill(0xAA);
+        let result = alg.sign_into_hardened_faulted(out, b"hardened", keypair.secret(), &mut rng, fault);
+        assert_eq!(result, Err(FAULT_DETECTED), "{fault:?}");
+        assert!(out.iter().all(|&b| b == 0), "{fault:?}");
+    }
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn mode2_hardened() {
+    check(Algorithm::Mode2);
+}
+
+#[cfg(all(feature = "mode2", feature = "fault_injection"))]
+#[test]
+fn mode2_faults() {
+    check_faults(Algorithm::Mode2);
+}
+
+#[cfg(feature = "mode3")]
+#[test]
+fn mode3_hardened() {
+    check(Algorithm::Mode3);
+}
+
+#[cfg(all(feature = "mode3", feature = "fault_injection"))]
+#[test]
+fn mode3_faults() {
+    check_faults(Algorithm::Mode3);
+}
+
+#[cfg(feature = "mode5")]
+#[test]
+fn mode5_hardened() {
+    check(Algorithm::Mode5);
+}
+
+#[cfg(all(feature = "mode5", feature = "fault_injection"))]
+#[test]
+fn mode5_faults() {
+    check_faults(Algorithm::Mode5);
+}
@@ -0,0 +1,148 @@
+//! JOSE: AKP keys through JSON, compact JWS tokens, the general and
+//! flattened JSON serializations, and the inputs they must refuse.
+#![cfg(all(feature = "jose", feature = "mode2", feature = "mode3"))]
+
+use pure_dsa::{Algorithm, SeedKey};
+use pure_dsa::jose::*;
+use rand::{SeedableRng, rngs::StdRng};
+
+const CLAIMS: &[u8] = br#"{"iss":"https://auth.example.org","sub":"user-17","exp":1767225600}"#;
+
+fn generate(algorithm: Algorithm, rng: &mut StdRng) -> Jwk {
+    SeedKey::generate_with_rng(algorithm, rng).into()
+}
+
+#[test]
+fn jwk_round_trip() {
+    let mut rng = StdRng::seed_from_u64(7517);
+    let jwk = generate(Algorithm::Mode3, &mut rng).with_kid("2025-signing");
+    let json = jwk.to_json();
+    assert!(json.starts_with(r#"{"kty":"AKP","kid":"2025-signing","alg":"ML-DSA-65","pub":""#));
+    assert!(json.contains(r#""priv":""#));
+
+    let parsed = Jwk::from_json(&json).unwrap();
+    assert!(parsed.key().is_private());
+    assert_eq!(parsed.kid(), Some("2025-signing"));
+    assert_eq!(parsed.key().algorithm(), Algorithm::Mode3);
+    assert_eq!(parsed.key().public_key(), jwk.key().public_key());
+    assert_eq!(parsed.key().seed(), jwk.key().seed());
+    assert_eq!(parsed.key().keypair().unwrap().public(), jwk.key().public_key());
+    assert_eq!(SeedKey::from_seed(Algorithm::Mode3, jwk.key().seed().unwrap()).unwrap().public_key(), jwk.key().public_key());
+
+    let public = Jwk::from_json(&jwk.to_public().to_json()).unwrap();
+    assert!(!public.key().is_private());
+    assert!(!jwk.to_public().to_json().contains("priv"));
+    assert_eq!(public.key().public_key(), jwk.key().public_key());
+    assert_eq!(public.key().keypair().err(), Some("Key has no seed"));
+
+    // Unknown members are ignored; a seed that derives another key is not.
+    let other = generate(Algorithm::Mode3, &mut rng).to_json();
+    let with_use = other.replacen('{', r#"{"use":"sig","key_ops":["sign"],"#, 1);
+    assert!(Jwk::from_json(&with_use).is_ok());
+    let at = other.find(r#","priv""#).unwrap();
+    let spliced = [&other[..at], &json[json.find(r#","priv""#).unwrap()..]].concat();
+    assert_eq!(Jwk::from_json(&spliced).err(), Some("Private key does not match the public key"));
+}
+
+#[test]
+fn jwk_errors() {
+    let jwk = generate(Algorithm::Mode2, &mut StdRng::seed_from_u64(1)).to_public().to_json();
+    assert_eq!(Jwk::from_json(&jwk.replace("AKP", "OKP")).err(), Some("Unsupported key type"));
+    assert_eq!(Jwk::from_json(&jwk.replace("ML-DSA-44", "ML-DSA-66")).err(), Some("Unsupported algorithm"));
+    assert_eq!(Jwk::from_json(&jwk.replace("ML-DSA-44", "ML-DSA-65")).err(), Some("Invalid public key length"));
+    assert_eq!(Jwk::from_json(&jwk.replace(r#""pub""#, r#""pubkey""#)).err(), Some("Missing JSON member"));
+    assert_eq!(Jwk::from_json(&jwk[..jwk.len() - 1]).err(), Some("Malformed JSON"));
+    assert_eq!(Jwk::from_json(&jwk.replace('{', r#"{"kty":"AKP","#)).err(), Some("Duplicate JSON member"));
+    assert_eq!(Jwk::from_json("[]").err(), Some("Expected a JSON object"));
+    let with_seed = jwk.replacen('}', r#","priv":"AAAA"}"#, 1);
+    assert_eq!(Jwk::from_json(&with_seed).err(), Some("Invalid seed length"));
+    assert_eq!(SeedKey::from_seed(Algorithm::Mode2, &[0; 31]).err(), Some("Invalid seed length"));
+}
+
+#[test]
+fn compact() {
+    let mut rng = StdRng::seed_from_u64(7515);
+    let jwk = generate(Algorithm::Mode2, &mut rng).with_kid("k1");
+    let keypair = jwk.key().keypair().unwrap();
+    let header = Header::new(Algorithm::Mode2).with_kid("k1").with_typ("JWT");
+    let jws = CompactJws::sign_with_rng(&header, CLAIMS, &keypair, &mut rng).unwrap();
+    assert_eq!(jws.as_str().matches('.').count(), 2);
+    assert!(!jws.as_str().contains(['=', '+', '/']));
+
+    let parsed = CompactJws::parse(jws.as_str()).unwrap();
+    assert_eq!(parsed, jws);
+    assert_eq!(parsed.header(), &header);
+    assert_eq!(parsed.signature().len(), Algorithm::Mode2.signature_bytes());
+    assert_eq!(parsed.verify(&jwk.to_public()), Ok(CLAIMS));
+
+    let other = generate(Algorithm::Mode2, &mut rng);
+    assert_eq!(parsed.verify(&other), Err("Invalid signature"));
+    let mode3 = generate(Algorithm::Mode3, &mut rng);
+    assert_eq!(parsed.verify(&mode3), Err("Key algorithm does not match the header"));
+    assert_eq!(
+        CompactJws::sign_with_rng(&Header::new(Algorithm::Mode3), CLAIMS, &keypair, &mut rng).err(),
+        Some("Header alg does not match the key")
+    );
+
+    // Swap in another token's payload: the signature no longer covers it.
+    let forged_payload = CompactJws::sign_with_rng(&header, b"{\"sub\":\"admin\"}", &keypair, &mut rng).unwrap();
+    let parts: Vec<&str> = jws.as_str().split('.').collect();
+    let payload = forged_payload.as_str().split('.').nth(1).unwrap();
+    let forged = CompactJws::parse(&[parts[0], payload, parts[2]].join(".")).unwrap();
+    assert_eq!(forged.payload(), b"{\"sub\":\"admin\"}");
+    assert_eq!(forged.verify(&jwk), Err("Invalid signature"));
+
+    assert_eq!(CompactJws::parse(&parts[..2].join(".")).err(), Some("Malformed JWS"));
+    assert_eq!(CompactJws::parse(&[jws.as_str(), "x"].join(".")).err(), Some("Malformed JWS"));
+    assert_eq!(CompactJws::parse(&[parts[0], "a+b", parts[2]].join(".")).err(), Some("Invalid base64 character"));
+    assert_eq!(CompactJws::parse(&[parts[1], parts[1], parts[2]].join(".")).err(), Some("Missing alg header parameter"));
+}
+
+#[test]
+fn json_general_and_flattened() {
+    let mut rng = StdRng::seed_from_u64(7516);
+    let a = generate(Algorithm::Mode2, &mut rng);
+    let b = generate(Algorithm::Mode3, &mut rng);
+    let (ka, kb) = (a.key().keypair().unwrap(), b.key().keypair().unwrap());
+    let jws = JwsBuilder::new(CLAIMS)
+        .add_signer(Header::new(Algorithm::Mode2).with_kid("a"), &ka)
+        .add_signer(Header::new(Algorithm::Mode3).with_kid("b").with_cty("claims"), &kb)
+        .build_with_rng(&mut rng)
+        .unwrap();
+    let json = jws.to_json();
+    assert!(json.starts_with(r#"{"payload":""#));
+    let parsed = JsonJws::from_json(&json).unwrap();
+    assert_eq!(parsed, jws);
+    assert_eq!(parsed.payload(), CLAIMS);
+    assert_eq!(parsed.signatures().len(), 2);
+    assert_eq!(parsed.signatures()[1].header().cty.as_deref(), Some("claims"));
+    assert_eq!(parsed.verify(&a), Ok(CLAIMS));
+    assert_eq!(parsed.verify(&b.to_public()), Ok(CLAIMS));
+    assert_eq!(parsed.verify(&generate(Algorithm::Mode3, &mut rng)), Err("Invalid signature"));
+    assert_eq!(
+        jws.to_flattened_json().err(),
+        Some("Flattened serialization needs exactly one signature")
+    );
+    assert_eq!(JwsBuilder::new(CLAIMS).build_with_rng(&mut rng).err(), Some("No signers"));
+
+    let single = JwsBuilder::new(CLAIMS).add_signer(Header::new(Algorithm::Mode2), &ka).build_with_rng(&mut rng).unwrap();
+    let flat = single.to_flattened_json().unwrap();
+    assert!(!flat.contains("signatures"));
+    let parsed = JsonJws::from_json(&flat).unwrap();
+    assert_eq!(parsed, single);
+    assert_eq!(parsed.verify(&a), Ok(CLAIMS));
+
+    // An unprotected header is merged into the signature's header and kept
+    // on re-serialization, but may not repeat a protected parameter.
+    let with_kid = flat.replacen(r#","signature""#, "  ,\"header\" : { \"kid\" : \"a\\u00e9\\\"\" } ,\"signature\"", 1);
+    let parsed = JsonJws::from_json(&with_kid).unwrap();
+    assert_eq!(parsed.signatures()[0].header().kid.as_deref(), Some("a\u{e9}\""));
+    assert_eq!(parsed.verify(&a), Ok(CLAIMS));
+    assert_eq!(JsonJws::from_json(&parsed.to_json()).unwrap(), parsed);
+    let dup = flat.replacen(r#","signature""#, r#","header":{"alg":"ML-DSA-44"},"signature""#, 1);
+    assert_eq!(JsonJws::from_json(&dup).err(), Some("Duplicate header parameter"));
+    let crit = flat.replacen(r#","signature""#, r#","header":{"crit":["exp"]},"signature""#, 1);
+    assert_eq!(JsonJws::from_json(&crit).err(), Some("Unsupported critical header parameter"));
+    assert_eq!(JsonJws::from_json(&json.replace(r#""payload""#, r#""signature":"","payload""#)).err(), Some("Malformed JWS"));
+    assert_eq!(JsonJws::from_json(r#"{"payload":"","signatures":[]}"#).err(), Some("No signatures"));
+}
@@ -0,0 +1,140 @@
+//! `load_from_bytes` and `Keypair::from_secret_key` must refuse keys that
+//! keygen could not have produced, and say which part is wrong.
+#![cfg(feature = "mode2")]
+
+use pure_dsa::{Algorithm, Keypair};
+use rand::{SeedableRng, rngs::StdRng};
+
+// Mode2 layout: sk = rho | key | tr | s1 (4 x 96) | s2 (4 x 96) | t0; pk = rho | t1.
+const S1: usize = 3 * 32;
+const S2: usize = S1 + 4 * 96;
+const T0: usize = S2 + 4 * 96;
+
+fn keypair(seed: u64) -> (Vec<u8>, Vec<u8>) {
+    let keypair: Keypair = Algorithm::Mode2.generate_with_rng(&mut StdRng::seed_from_u64(seed));
+    (keypair.secret().to_vec(), keypair.public().to_vec())
+}
+
+fn load_err(sk: &[u8], pk: &[u8]) -> &'static str {
+    let alg = Algorithm::Mode2;
+    let err = alg.check_keypair(sk, pk).unwrap_err();
+    assert_eq!(alg.load_from_bytes(sk, pk).unwrap_err(), err);
+    err
+}
+
+#[test]
+fn accepts_generated_keys() {
+    for seed in 0..4 {
+        let (sk, pk) = keypair(seed);
+        assert!(Algorithm::Mode2.load_from_bytes(&sk, &pk).is_ok());
+    }
+}
+
+#[test]
+fn rejects_wrong_lengths() {
+    let (sk, pk) = keypair(1);
+    assert!(Algorithm::Mode2.load_from_bytes(&sk[1..], &pk).is_err());
+    assert_eq!(Algorithm::Mode2.check_keypair(&sk, &pk[1..]), Err("Invalid key lengths"));
+}
+
+#[test]
+fn rejects_mismatched_halves() {
+    let (sk, _) = keypair(1);
+    let (_, pk) = keypair(2);
+    assert_eq!(load_err(&sk, &pk), "Secret key rho does not match public key");
+}
+
+#[test]
+fn rejects_out_of_range_eta() {
+    // With ETA = 2 the 3-bit fields hold ETA - s; 7 decodes to -5.
+    let (mut sk, pk) = keypair(3);
+    sk[S1] |= 0x07;
+    assert_eq!(load_err(&sk, &pk), "Secret key s1 coefficient out of range");
+
+    let (mut sk, pk) = keypair(3);
+    sk[S2 + 95] |= 0xE0;
+    assert_eq!(load_err(&sk, &pk), "Secret key s2 coefficient out of range");
+}
+
+#[test]
+fn rejects_inconsistent_t() {
+    let (sk, mut pk) = keypair(4);
+    pk[32 + 100] ^= 0x01;
+    assert_eq!(load_err(&sk, &pk), "Public key t1 does not match secret key");
+
+    let (mut sk, pk) = keypair(4);
+    sk[T0 + 7] ^= 0x10;
+    assert_eq!(load_err(&sk, &pk), "Secret key t0 does not match s1 and s2");
+
+    // An in-range change to s1 moves t away from both stored halves.
+    let (mut sk, pk) = keypair(4);
+    let s = sk[S1 + 10] & 0x07;
+    sk[S1 + 10] = (sk[S1 + 10] & !0x07) | if s == 0 { 1 } else { s - 1 };
+    assert_eq!(load_err(&sk, &pk), "Public key t1 does not match secret key");
+}
+
+#[test]
+fn rejects_stale_tr() {
+    let (mut sk, pk) = keypair(5);
+    sk[2 * 32] ^= 0x80;
+    assert_eq!(load_err(&sk, &pk), "Secret key tr does not match public key");
+}
+
+#[test]
+fn public_key_rebuilt_from_secret_key() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let algs = [
+        Algorithm::Mode2,
+        #[cfg(feature = "mode3")]
+        Algorithm::Mode3,
+        #[cfg(feature = "mode5")]
+        Algorithm::Mode5,
+    ];
+    for alg in algs {
+        let original = alg.generate_with_rng(&mut rng);
+        let rebuilt = Keypair::from_secret_key(alg, original.secret()).unwrap();
+        assert_eq!(rebuilt.public(), original.public());
+        assert_eq!(rebuilt.secret(), original.secret());
+    }
+}
+
+#[test]
+fn corrupted_secret_key_not_rebuilt() {
+    let (sk, _) = keypair(7);
+    assert_eq!(
+        Keypair::from_secret_key(Algorithm::Mode2, &sk[1..]).unwrap_err(),
+        "Invalid secret key length"
+    );
+
+    let mut bad = sk.clone();
+    bad[S1] |= 0x07;
+    assert_eq!(
+        Keypair::from_secret_key(Algorithm::Mode2, &bad).unwrap_err(),
+        "Secret key s1 coefficient out of range"
+    );
+
+    let mut bad = sk.clone();
+    bad[T0] ^= 0x01;
+    assert_eq!(
+        Keypair::from_secret_key(Algorithm::Mode2, &bad).unwrap_err(),
+        "Secret key t0 does not match s1 and s2"
+    );
+
+    let mut bad = sk;
+    bad[2 * 32 + 31] ^= 0x01;
+    assert_eq!(
+        Keypair::from_secret_key(Algorithm::Mode2, &bad).unwrap_err(),
+        "Secret key tr does not match public key"
+    );
+}
+
+#[test]
+fn seed_determines_the_keypair() {
+    use rand::RngCore;
+    let mut seed = [0u8; 32];
+    StdRng::seed_from_u64(3).fill_bytes(&mut seed);
+    let from_seed = Algorithm::Mode2.generate_from_seed(&seed).unwrap();
+    let (sk, pk) = keypair(3);
+    assert_eq!((from_seed.secret(), from_seed.public()), (&sk[..], &pk[..]));
+    assert_eq!(Algorithm::Mode2.generate_from_seed(&seed[1..]).err(), Some("Invalid seed length"));
+}
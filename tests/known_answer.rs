@@ -0,0 +1,47 @@
+//! Fixed-seed known-answer values for key generation, signing and
+//! verification, so that refactors of the arithmetic can be checked
+//! against the outputs of the code they replace.
+//!
+//! Signing continues on the RNG that generated the key. With the `random`
+//! feature the signer draws its `rhoprime` from it, so the signatures
+//! differ from the deterministic build's; both sets are listed.
+#![cfg(all(feature = "mode2", feature = "mode3", feature = "mode5"))]
+
+use pure_dsa::Algorithm;
+use rand::{SeedableRng, rngs::StdRng};
+
+const MESSAGE: &[u8] = b"hello";
+
+/// 64-bit FNV-1a, enough to tell outputs apart.
+fn fnv(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Per parameter set: the RNG seed and the FNV-1a of the public key, the
+/// secret key, and the signature of `MESSAGE` without and with `random`.
+const VECTORS: [(&str, Algorithm, u64, u64, u64, u64, u64); 3] = [
+    ("Mode2", Algorithm::Mode2, 42, 0xdf711633f88dafd1, 0x81a822e7a1b36910, 0x3a503ac040027f27, 0xa132ba8a06ff1065),
+    ("Mode3", Algorithm::Mode3, 43, 0xbe268ca127bcad83, 0x35c463af1d9f1287, 0x587f361649219027, 0x2b4199048b3b46a7),
+    ("Mode5", Algorithm::Mode5, 44, 0x711f2740ea97fe84, 0x1a9aa8bbdbd98c0f, 0x5f51e4eb30486ea6, 0x208ce1aa56034cb9),
+];
+
+#[test]
+fn keypairs() {
+    for (name, alg, seed, pk, sk, ..) in VECTORS {
+        let keypair = alg.generate_with_rng(&mut StdRng::seed_from_u64(seed));
+        assert_eq!((fnv(keypair.public()), fnv(keypair.secret())), (pk, sk), "{name}");
+    }
+}
+
+#[test]
+fn signatures() {
+    for (name, alg, seed, _, _, deterministic, randomized) in VECTORS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let keypair = alg.generate_with_rng(&mut rng);
+        let sig = keypair.sign_with_rng(MESSAGE, &mut rng);
+        let expected = if cfg!(feature = "random") { randomized } else { deterministic };
+        assert_eq!(fnv(sig.bytes()), expected, "{name}");
+        assert!(alg.verify(&sig, MESSAGE, keypair.public()).is_ok());
+        assert!(alg.verify(&sig, b"hellp", keypair.public()).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+//! Runs keygen, signing and verification on a fixed-size thread stack.
+//! An overflow aborts the test binary rather than failing a single test.
+#![cfg(all(feature = "low_memory", feature = "getrandom"))]
+
+use pure_dsa::Algorithm;
+use std::thread;
+
+/// Target for embedded gateways. Debug builds keep every temporary on the
+/// stack, so they get a looser bound (see the figures in `src/lowmem.rs`);
+/// the 64 KB bound is only checked by
+/// `cargo test --release --features low_memory --test low_memory`.
+const STACK_BYTES: usize = if cfg!(debug_assertions) { 160 * 1024 } else { 64 * 1024 };
+
+fn on_small_stack<F: FnOnce() + Send + 'static>(f: F) {
+    thread::Builder::new()
+        .stack_size(STACK_BYTES)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn roundtrip(alg: fn() -> Algorithm) {
+    on_small_stack(move || {
+        let keypair = alg().generate();
+        let sig = keypair.sign(b"low memory");
+        assert!(alg().verify(&sig, b"low memory", keypair.public()).is_ok());
+        assert!(alg().verify(&sig, b"low memorz", keypair.public()).is_err());
+    });
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn mode2_fits_stack_budget() {
+    roundtrip(|| Algorithm::Mode2);
+}
+
+#[cfg(feature = "mode3")]
+#[test]
+fn mode3_fits_stack_budget() {
+    roundtrip(|| Algorithm::Mode3);
+}
+
+#[cfg(feature = "mode5")]
+#[test]
+fn mode5_fits_stack_budget() {
+    roundtrip(|| Algorithm::Mode5);
+}
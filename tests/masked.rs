@@ -0,0 +1,146 @@
+//! Share recombination for the masking gadgets, and byte-identical output of
+//! the masked signer.
+#![cfg(feature = "masked")]
+
+use pure_dsa::Algorithm;
+use pure_dsa::masked::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const Q: u32 = 8380417;
+const ROUNDS: usize = 2000;
+
+fn masks(seed: u64) -> MaskRng {
+    MaskRng::from_rng(&mut StdRng::seed_from_u64(seed))
+}
+
+fn bool_shares(x: u32, rng: &mut MaskRng) -> [u32; 2] {
+    let m = rng.next_u32();
+    [x ^ m, m]
+}
+
+#[test]
+fn arithmetic_shares_recombine() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut m = masks(2);
+    for x in [0, 1, -1, Q as i32 - 1, -(Q as i32 - 1) / 2, 1 << 19] {
+        assert_eq!(recombine_q(split_q(x, &mut m)), x.rem_euclid(Q as i32) as u32);
+    }
+    for _ in 0..ROUNDS {
+        let x = rng.gen_range(-(Q as i32)..Q as i32);
+        let s = split_q(x, &mut m);
+        assert!(s[0] < Q && s[1] < Q);
+        assert_eq!(recombine_q(s), x.rem_euclid(Q as i32) as u32);
+    }
+}
+
+#[test]
+fn boolean_gadgets_recombine() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut m = masks(4);
+    for _ in 0..ROUNDS {
+        let (x, y): (u32, u32) = (rng.r#gen(), rng.r#gen());
+        let (xs, ys) = (bool_shares(x, &mut m), bool_shares(y, &mut m));
+
+        let r = refresh(xs, &mut m);
+        assert_eq!(r[0] ^ r[1], x);
+        let r = sec_and(xs, ys, &mut m);
+        assert_eq!(r[0] ^ r[1], x & y);
+        let r = sec_add(xs, ys, &mut m);
+        assert_eq!(r[0] ^ r[1], x.wrapping_add(y));
+    }
+}
+
+#[test]
+fn conversions_recombine() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut m = masks(6);
+    for _ in 0..ROUNDS {
+        let x: u32 = rng.r#gen();
+        let a = b2a(bool_shares(x, &mut m), &mut m);
+        assert_eq!(a[0].wrapping_add(a[1]), x);
+        let b = a2b(a, &mut m);
+        assert_eq!(b[0] ^ b[1], x);
+
+        let x = rng.gen_range(0..Q);
+        let a = b2a_q(bool_shares(x, &mut m), &mut m);
+        assert!(a[0] < Q && a[1] < Q);
+        assert_eq!(recombine_q(a), x);
+        let b = a2b_q(a, &mut m);
+        assert_eq!(b[0] ^ b[1], x);
+    }
+    for x in [0, 1, Q - 1] {
+        let b = a2b_q(split_q(x as i32, &mut m), &mut m);
+        assert_eq!(b[0] ^ b[1], x);
+    }
+}
+
+#[test]
+fn masked_shake256_matches_reference() {
+    let mut m = masks(7);
+    let mut out = [[0u8; 32]; 2];
+
+    let mut state = MaskedShake256::default();
+    state.finalize();
+    let [o0, o1] = &mut out;
+    state.squeeze(o0, o1, &mut m);
+    let digest: Vec<u8> = out[0].iter().zip(&out[1]).map(|(a, b)| a ^ b).collect();
+    assert_eq!(
+        digest,
+        [
+            0x46, 0xb9, 0xdd, 0x2b, 0x0b, 0xa8, 0x8d, 0x13, 0x23, 0x3b, 0x3f, 0xeb, 0x74, 0x3e, 0xeb, 0x24,
+            0x3f, 0xcd, 0x52, 0xea, 0x62, 0xb8, 0x1b, 0x82, 0xb5, 0x0c, 0x27, 0x64, 0x6e, 0xd5, 0x76, 0x2f,
+        ]
+    );
+
+    // Splitting the input differently must not change the output.
+    let msg = [0xa3u8; 200];
+    let mut first = [0u8; 300];
+    for (i, share) in [[0u8; 200], [0x5cu8; 200]].iter().enumerate() {
+        let other: Vec<u8> = msg.iter().zip(share).map(|(a, b)| a ^ b).collect();
+        let mut state = MaskedShake256::default();
+        state.absorb_shared(&other, share, &mut m);
+        state.finalize();
+        let (mut o0, mut o1) = ([0u8; 300], [0u8; 300]);
+        state.squeeze(&mut o0, &mut o1, &mut m);
+        let out: Vec<u8> = o0.iter().zip(&o1).map(|(a, b)| a ^ b).collect();
+        if i == 0 {
+            first.copy_from_slice(&out);
+        } else {
+            assert_eq!(out, first);
+        }
+    }
+}
+
+fn check_identical(alg: Algorithm) {
+    let mut rng = StdRng::seed_from_u64(8);
+    let keypair = alg.generate_with_rng(&mut rng);
+    for i in 0..3u64 {
+        let msg = [i as u8; 33];
+        let sig = keypair.sign_with_rng(&msg, &mut StdRng::seed_from_u64(100 + i));
+        let masked = keypair.sign_masked_with_rng(&msg, &mut StdRng::seed_from_u64(100 + i));
+        assert_eq!(sig.bytes(), masked.bytes());
+        assert!(alg.verify(&masked, &msg, keypair.public()).is_ok());
+    }
+
+    let mut buf = vec![0u8; alg.signature_bytes()];
+    alg.sign_into_masked(&mut buf, b"buffer", keypair.secret(), &mut StdRng::seed_from_u64(9)).unwrap();
+    assert!(alg.verify_raw(&buf, b"buffer", keypair.public()).is_ok());
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn masked_signature_identical_mode2() {
+    check_identical(Algorithm::Mode2);
+}
+
+#[cfg(feature = "mode3")]
+#[test]
+fn masked_signature_identical_mode3() {
+    check_identical(Algorithm::Mode3);
+}
+
+#[cfg(feature = "mode5")]
+#[test]
+fn masked_signature_identical_mode5() {
+    check_identical(Algorithm::Mode5);
+}
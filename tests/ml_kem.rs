@@ -0,0 +1,130 @@
+//! ML-KEM against keys, ciphertexts and shared secrets from OpenSSL 3.5,
+//! plus the FIPS 203 input checks and implicit rejection.
+#![cfg(feature = "ml_kem")]
+
+use pure_dsa::ml_kem::{Algorithm, Keypair, SEED_BYTES};
+use pure_dsa::sha3::sha3_256;
+use rand::{SeedableRng, rngs::StdRng};
+
+const ALL: [Algorithm; 3] = [Algorithm::MlKem512, Algorithm::MlKem768, Algorithm::MlKem1024];
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+fn seed() -> Vec<u8> {
+    (0..SEED_BYTES).map(|i| (i * 5 + 3) as u8).collect()
+}
+
+fn message() -> Vec<u8> {
+    (100..132).collect()
+}
+
+/// For the key from `seed()` and encapsulation randomness `message()`:
+/// SHA3-256 of the encapsulation key and of the ciphertext, the shared
+/// secret, and the secret decapsulating the ciphertext with its first bit
+/// flipped yields.
+fn expected(alg: Algorithm) -> [&'static str; 4] {
+    match alg {
+        Algorithm::MlKem512 => [
+            "84ea2f4deecaaf1986b1c60a10ac924333ad4fbc55018e0e3a551f9c5c54e9a7",
+            "c01eb84da298f48a521197dd6984270944fb897ce8a7ffa396f4088e0b34c6e7",
+            "e0d7f604f47db2d0da194b47868186355ddc62801313324e6f0754df67d0bdc2",
+            "4700e32dc8b61676d8658d7033c5204988577ac71f2ee26a7970bea463c51f49",
+        ],
+        Algorithm::MlKem768 => [
+            "ccdef313a06776f8e2856efd00f68603864b6c57948555fa1e8bf12fe22cd1ab",
+            "c6622e94a6de60b09b1e5c9a22788f1ab3131b7c3435c260eee22432570638a5",
+            "a7ce78ffd292b372fa61abd08d5b551147c44a65b63568f19870b75ec16e668e",
+            "9ec427cdd3eda19ec4d9c5112d38176dae6a617eec61b7fa3489daef40501dd2",
+        ],
+        Algorithm::MlKem1024 => [
+            "36ac54e23c046f6d3e1761de6fbd2a47042547aac7bf69ef42386a65d931c188",
+            "5e0e09509c41c2f58ecbccbd9909fb3f910a781f32791357f15aa8053d9f937d",
+            "7bfbba8fec0e4bb12be848e9c19032c8775e9b7e94c0bd7647d2c44366abd33d",
+            "c9bccbe1a35a76dcebe6a79ba209331aa3d5d2b4414630d0d185cd641dbba9d7",
+        ],
+    }
+}
+
+#[test]
+fn sizes() {
+    let sizes: Vec<_> = ALL
+        .iter()
+        .map(|a| (a.public_key_bytes(), a.secret_key_bytes(), a.ciphertext_bytes()))
+        .collect();
+    assert_eq!(sizes, [(800, 1632, 768), (1184, 2400, 1088), (1568, 3168, 1568)]);
+}
+
+#[test]
+fn matches_openssl() {
+    for alg in ALL {
+        let [pk_hash, ct_hash, ss, reject] = expected(alg);
+        let keypair = alg.generate_from_seed(&seed()).unwrap();
+        assert_eq!(sha3_256(keypair.public()).to_vec(), hex(pk_hash), "{alg:?}");
+
+        let (ct, shared) = alg.encapsulate_deterministic(keypair.public(), &message()).unwrap();
+        assert_eq!(sha3_256(ct.bytes()).to_vec(), hex(ct_hash), "{alg:?}");
+        assert_eq!(shared[..], hex(ss)[..], "{alg:?}");
+        assert_eq!(keypair.decapsulate(&ct).unwrap()[..], hex(ss)[..], "{alg:?}");
+
+        let mut bad = ct.bytes().to_vec();
+        bad[0] ^= 1;
+        let rejected = alg.decapsulate(&bad, keypair.secret()).unwrap();
+        assert_eq!(rejected[..], hex(reject)[..], "{alg:?}");
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut rng = StdRng::seed_from_u64(203);
+    for alg in ALL {
+        let keypair: Keypair = alg.generate_with_rng(&mut rng);
+        for _ in 0..8 {
+            let (ct, shared) = alg.encapsulate_with_rng(keypair.public(), &mut rng).unwrap();
+            assert_eq!(keypair.decapsulate(&ct).unwrap()[..], shared[..]);
+        }
+
+        let mut pk = vec![0u8; alg.public_key_bytes()];
+        let mut sk = vec![0u8; alg.secret_key_bytes()];
+        alg.generate_into(&mut pk, &mut sk, &mut rng).unwrap();
+        let mut ct = vec![0u8; alg.ciphertext_bytes()];
+        let mut ss = [0u8; 32];
+        alg.encapsulate_into(&mut ct, &mut ss, &pk, &mut rng).unwrap();
+        let mut ss2 = [0u8; 32];
+        alg.decapsulate_into(&mut ss2, &ct, &sk).unwrap();
+        assert_eq!(ss, ss2);
+        assert!(alg.decapsulate(&ct[1..], &sk).is_err());
+        assert!(alg.encapsulate_into(&mut ct[1..], &mut ss, &pk, &mut rng).is_err());
+    }
+}
+
+#[test]
+fn input_checks() {
+    let alg = Algorithm::MlKem768;
+    let keypair = alg.generate_with_rng(&mut StdRng::seed_from_u64(1));
+    let (sk, pk) = (keypair.secret().to_vec(), keypair.public().to_vec());
+    assert!(alg.load_from_bytes(&sk, &pk).is_ok());
+    assert_eq!(Keypair::from_secret_key(alg, &sk).unwrap().public(), &pk[..]);
+
+    // A 12-bit coefficient of 0xFFF is not reduced mod q.
+    let mut bad_pk = pk.clone();
+    bad_pk[0] = 0xFF;
+    bad_pk[1] |= 0x0F;
+    let mut rng = StdRng::seed_from_u64(2);
+    assert_eq!(alg.check_public_key(&bad_pk), Err("Public key coefficient out of range"));
+    assert_eq!(
+        alg.encapsulate_with_rng(&bad_pk, &mut rng).err(),
+        Some("Public key coefficient out of range")
+    );
+    assert_eq!(alg.check_public_key(&pk[1..]), Err("Invalid public key length"));
+
+    let mut bad_sk = sk.clone();
+    bad_sk[384 * 3 + 1184] ^= 1;
+    assert_eq!(alg.check_keypair(&bad_sk, &pk), Err("Secret key hash check failed"));
+    let other = alg.generate_with_rng(&mut rng);
+    assert_eq!(alg.check_keypair(&sk, other.public()), Err("Public key does not match the secret key"));
+    assert_eq!(alg.check_keypair(&sk[1..], &pk), Err("Invalid key lengths"));
+    assert!(alg.generate_from_seed(&[0u8; 32]).is_err());
+    assert!(alg.encapsulate_deterministic(&pk, &[0u8; 31]).is_err());
+}
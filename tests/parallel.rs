@@ -0,0 +1,41 @@
+//! The parallel signer must release valid signatures in every compiled-in
+//! mode, whether it runs one worker, several, or is asked for far more
+//! than `MAX_SIGN_THREADS`.
+#![cfg(feature = "parallel")]
+
+use pure_dsa::{Algorithm, MAX_SIGN_THREADS};
+use rand::{SeedableRng, rngs::StdRng};
+
+fn check(alg: Algorithm) {
+    let keypair = alg.generate_with_rng(&mut StdRng::seed_from_u64(28));
+    for threads in [0, 1, 2, 7, MAX_SIGN_THREADS, usize::MAX] {
+        let msg = format!("{alg:?} with {threads} threads");
+        let sig = keypair.sign_parallel(msg.as_bytes(), threads);
+        assert_eq!(sig.bytes().len(), alg.signature_bytes());
+        assert_eq!(alg.verify(&sig, msg.as_bytes(), keypair.public()), Ok(()), "{msg}");
+        assert!(alg.verify(&sig, b"another message", keypair.public()).is_err(), "{msg}");
+    }
+    // Many short races, so that workers finishing together are exercised.
+    for i in 0..20u8 {
+        let sig = keypair.sign_parallel(&[i], 4);
+        assert_eq!(alg.verify(&sig, &[i], keypair.public()), Ok(()));
+    }
+}
+
+#[cfg(feature = "mode2")]
+#[test]
+fn mode2() {
+    check(Algorithm::Mode2);
+}
+
+#[cfg(feature = "mode3")]
+#[test]
+fn mode3() {
+    check(Algorithm::Mode3);
+}
+
+#[cfg(feature = "mode5")]
+#[test]
+fn mode5() {
+    check(Algorithm::Mode5);
+}
@@ -0,0 +1,104 @@
+//! SHA-256 and SHA-512 against the FIPS 180-4 examples, and over every
+//! length up to 256 bytes so the padding lands on each side of the block
+//! boundaries (55/56/64 bytes for SHA-256, 111/112/128 for SHA-512).
+//! Each is checked through every enabled API that offers it: SSHSIG's
+//! `HashAlgorithm` and CMS's `DigestAlgorithm`.
+#![cfg(any(feature = "ssh", feature = "cms"))]
+
+#[cfg(feature = "cms")]
+use pure_dsa::cms::DigestAlgorithm;
+#[cfg(feature = "ssh")]
+use pure_dsa::ssh::HashAlgorithm;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+const ABC_448: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+const ABC_896: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+type Hash = fn(&[u8]) -> Vec<u8>;
+
+fn sha256() -> Vec<Hash> {
+    vec![
+        #[cfg(feature = "ssh")]
+        |m| HashAlgorithm::Sha256.digest(m),
+        #[cfg(feature = "cms")]
+        |m| DigestAlgorithm::Sha256.digest(m),
+    ]
+}
+
+fn sha512() -> Vec<Hash> {
+    vec![
+        #[cfg(feature = "ssh")]
+        |m| HashAlgorithm::Sha512.digest(m),
+        #[cfg(feature = "cms")]
+        |m| DigestAlgorithm::Sha512.digest(m),
+    ]
+}
+
+fn check(hashes: Vec<Hash>, vectors: [(&[u8], &str); 4], million_a: &str, chained_digest: &str) {
+    for hash in hashes {
+        for (msg, digest) in vectors {
+            assert_eq!(hash(msg), hex(digest), "{} bytes", msg.len());
+        }
+        assert_eq!(hash(&vec![b'a'; 1_000_000]), hex(million_a));
+        assert_eq!(chained(hash), hex(chained_digest));
+    }
+}
+
+/// The hash of the concatenated digests of every prefix, 0 to 256 bytes
+/// long, of a message whose byte `i` is `i % 251`.
+fn chained(hash: Hash) -> Vec<u8> {
+    let msg: Vec<u8> = (0..256).map(|i| (i % 251) as u8).collect();
+    let digests: Vec<u8> = (0..=msg.len()).flat_map(|n| hash(&msg[..n])).collect();
+    hash(&digests)
+}
+
+#[test]
+fn sha256_vectors() {
+    check(
+        sha256(),
+        [
+            (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (ABC_448, "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+            (ABC_896, "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1"),
+        ],
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+        "4c0384cbd3c2f144d0f3201210c48cf88c1e45e9a8f1f1aae791983835b2ba44",
+    );
+}
+
+#[test]
+fn sha512_vectors() {
+    check(
+        sha512(),
+        [
+            (
+                b"",
+                "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+                 47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+            ),
+            (
+                b"abc",
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                ABC_448,
+                "204a8fc6dda82f0a0ced7beb8e08a41657c16ef468b228a8279be331a703c335\
+                 96fd15c13b1b07f9aa1d3bea57789ca031ad85c7a71dd70354ec631238ca3445",
+            ),
+            (
+                ABC_896,
+                "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+                 501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+            ),
+        ],
+        "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+         de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b",
+        "93c443c9b2f7a64048b60b7a4bc3fe2b8ab13a94e4d1563ca8e2b0ae74d923fa\
+         9f4d248b7d733e86932104bc791a02367e55ba2f53bc76aa74d0eea162491890",
+    );
+}
@@ -0,0 +1,259 @@
+//! `pure_dsa::sha3` against the FIPS 202 and SP 800-185 sample values.
+
+use pure_dsa::sha3::*;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+fn seq(start: u8, len: usize) -> Vec<u8> {
+    (0..len).map(|i| start.wrapping_add(i as u8)).collect()
+}
+
+const KMAC_KEY: &str = "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f";
+const PARALLEL_INPUT: &str = "000102030405060710111213141516172021222324252627";
+
+#[test]
+fn sha3_fixed_lengths() {
+    assert_eq!(sha3_224(b"").to_vec(), hex("6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7"));
+    assert_eq!(
+        sha3_256(b"abc").to_vec(),
+        hex("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")
+    );
+    assert_eq!(
+        sha3_384(b"abc").to_vec(),
+        hex("ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25")
+    );
+    assert_eq!(
+        sha3_512(b"abc").to_vec(),
+        hex("b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+             10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0")
+    );
+}
+
+#[test]
+fn shake_empty_input() {
+    let mut out = [0u8; 32];
+    shake128(b"", &mut out);
+    assert_eq!(out.to_vec(), hex("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"));
+    shake256(b"", &mut out);
+    assert_eq!(out.to_vec(), hex("46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f"));
+}
+
+#[test]
+fn incremental_matches_one_shot() {
+    let msg = seq(0, 1000);
+    for split in [0, 1, 71, 72, 135, 136, 137, 168, 999] {
+        let mut h = Sha3_384::new();
+        h.update(&msg[..split]);
+        h.update(&msg[split..]);
+        assert_eq!(h.finalize(), sha3_384(&msg));
+
+        let mut one_shot = [0u8; 400];
+        shake128(&msg, &mut one_shot);
+        let mut xof = Shake128::new();
+        xof.update(&msg[..split]);
+        xof.update(&msg[split..]);
+        let mut reader = xof.finalize_xof();
+        let mut out = [0u8; 400];
+        let cut = split % 400;
+        reader.read(&mut out[..cut]);
+        reader.read(&mut out[cut..]);
+        assert_eq!(out, one_shot);
+    }
+}
+
+#[test]
+fn cshake_samples() {
+    let mut out = [0u8; 32];
+    let mut x = CShake128::new(b"", b"Email Signature");
+    x.update(&seq(0, 4));
+    x.finalize_xof().read(&mut out);
+    assert_eq!(out.to_vec(), hex("c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5"));
+
+    let mut x = CShake128::new(b"", b"Email Signature");
+    x.update(&seq(0, 200));
+    x.finalize_xof().read(&mut out);
+    assert_eq!(out.to_vec(), hex("c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b"));
+
+    let mut out = [0u8; 64];
+    let mut x = CShake256::new(b"", b"Email Signature");
+    x.update(&seq(0, 4));
+    x.finalize_xof().read(&mut out);
+    assert_eq!(
+        out.to_vec(),
+        hex("d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+             64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c")
+    );
+
+    // With no name or customization cSHAKE is SHAKE.
+    let mut a = [0u8; 200];
+    let mut b = [0u8; 200];
+    let mut x = CShake256::new(b"", b"");
+    x.update(b"abc");
+    x.finalize_xof().read(&mut a);
+    shake256(b"abc", &mut b);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn kmac_samples() {
+    let key = hex(KMAC_KEY);
+    let mut out = [0u8; 32];
+    let mut mac = Kmac128::new(&key, b"");
+    mac.update(&seq(0, 4));
+    mac.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"));
+
+    let mut mac = Kmac128::new(&key, b"My Tagged Application");
+    mac.update(&seq(0, 200));
+    mac.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230"));
+
+    let mut out = [0u8; 64];
+    let mut mac = Kmac256::new(&key, b"My Tagged Application");
+    mac.update(&seq(0, 4));
+    mac.duplicate().finalize(&mut out);
+    assert_eq!(
+        out.to_vec(),
+        hex("20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7\
+             f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd")
+    );
+    assert_eq!(mac.duplicate().verify(&out), Ok(()));
+    out[63] ^= 1;
+    assert_eq!(mac.duplicate().verify(&out), Err("Invalid MAC"));
+    // The tag length is bound into the output: a truncated tag does not verify.
+    assert_eq!(mac.verify(&out[..32]), Err("Invalid MAC"));
+}
+
+#[test]
+fn kmac_rejects_short_tags() {
+    let mut mac = Kmac256::new(&hex(KMAC_KEY), b"app");
+    mac.update(b"message");
+    let mut tag = [0u8; MIN_TAG_BYTES];
+    mac.duplicate().finalize(&mut tag);
+    assert_eq!(mac.duplicate().verify(&tag), Ok(()));
+    assert_eq!(mac.duplicate().verify(&[]), Err("MAC tag too short"));
+    assert_eq!(mac.duplicate().verify(&tag[..1]), Err("MAC tag too short"));
+    assert_eq!(mac.verify(&tag[..MIN_TAG_BYTES - 1]), Err("MAC tag too short"));
+}
+
+#[test]
+fn keyed_state_debug_is_redacted() {
+    let mac = Kmac256::new(&hex(KMAC_KEY), b"");
+    assert_eq!(format!("{mac:?}"), "Kmac256(<redacted>)");
+    let reader = mac.finalize_xof();
+    assert_eq!(format!("{reader:?}"), "XofReader(<redacted>)");
+
+    let mut hasher = Shake256::default();
+    hasher.update(b"secret seed");
+    let printed = format!("{hasher:?}");
+    assert!(printed.contains("<redacted>"), "{printed}");
+}
+
+#[test]
+fn tuple_hash_samples() {
+    let mut out = [0u8; 32];
+    let mut h = TupleHash128::new(b"");
+    h.push(&seq(0, 3));
+    h.push(&seq(0x10, 6));
+    h.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1"));
+
+    let mut h = TupleHash128::new(b"My Tuple App");
+    h.push(&seq(0, 3));
+    h.push(&seq(0x10, 6));
+    h.push(&seq(0x20, 9));
+    h.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84"));
+
+    let mut out = [0u8; 64];
+    let mut h = TupleHash256::new(b"My Tuple App");
+    h.push(&seq(0, 3));
+    h.push(&seq(0x10, 6));
+    h.finalize(&mut out);
+    assert_eq!(
+        out.to_vec(),
+        hex("147c2191d5ed7efd98dbd96d7ab5a11692576f5fe2a5065f3e33de6bba9f3aa1\
+             c4e9a068a289c61c95aab30aee1e410b0b607de3620e24a4e3bf9852a1d4367e")
+    );
+
+    // Element boundaries are part of the input.
+    let mut a = TupleHash128::new(b"").finalize_xof();
+    let mut h = TupleHash128::new(b"");
+    h.push(b"ab");
+    h.push(b"c");
+    let mut b = h.finalize_xof();
+    let mut h = TupleHash128::new(b"");
+    h.push(b"a");
+    h.push(b"bc");
+    let mut c = h.finalize_xof();
+    let (mut x, mut y, mut z) = ([0u8; 32], [0u8; 32], [0u8; 32]);
+    a.read(&mut x);
+    b.read(&mut y);
+    c.read(&mut z);
+    assert_ne!(x, y);
+    assert_ne!(y, z);
+}
+
+#[test]
+fn parallel_hash_samples() {
+    let input = hex(PARALLEL_INPUT);
+    let mut out = [0u8; 32];
+    let mut h = ParallelHash128::new(8, b"");
+    h.update(&input);
+    h.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5"));
+
+    let mut h = ParallelHash128::new(8, b"Parallel Data");
+    h.update(&input[..13]);
+    h.update(&input[13..]);
+    h.finalize(&mut out);
+    assert_eq!(out.to_vec(), hex("fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206"));
+
+    let mut out = [0u8; 64];
+    let mut h = ParallelHash256::new(8, b"Parallel Data");
+    for chunk in input.chunks(5) {
+        h.update(chunk);
+    }
+    h.finalize(&mut out);
+    assert_eq!(
+        out.to_vec(),
+        hex("cdf15289b54f6212b4bc270528b49526006dd9b54e2b6add1ef6900dda3963bb\
+             33a72491f236969ca8afaea29c682d47a393c065b38e29fae651a2091c833110")
+    );
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn digest_traits() {
+    use pure_dsa::sha3::digest::{Digest, ExtendableOutput, Update, XofReader};
+
+    fn fixed<D: Digest>(data: &[u8]) -> Vec<u8> {
+        D::digest(data).to_vec()
+    }
+
+    fn xof<D: Default + Update + ExtendableOutput>(data: &[u8], len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        let mut reader = D::default().chain(data).finalize_xof();
+        XofReader::read(&mut reader, &mut out);
+        out
+    }
+
+    let msg = seq(7, 300);
+    assert_eq!(fixed::<Sha3_224>(&msg), sha3_224(&msg));
+    assert_eq!(fixed::<Sha3_256>(&msg), sha3_256(&msg));
+    assert_eq!(fixed::<Sha3_384>(&msg), sha3_384(&msg));
+    assert_eq!(fixed::<Sha3_512>(&msg), sha3_512(&msg));
+
+    let mut out = [0u8; 300];
+    shake128(&msg, &mut out);
+    assert_eq!(xof::<Shake128>(&msg, 300), out);
+    shake256(&msg, &mut out);
+    assert_eq!(xof::<Shake256>(&msg, 300), out);
+
+    let mut h = <Sha3_256 as Digest>::new();
+    Digest::update(&mut h, b"abc");
+    assert_eq!(h.finalize_reset().to_vec(), sha3_256(b"abc"));
+    assert_eq!(h.finalize_reset().to_vec(), sha3_256(b""));
+}
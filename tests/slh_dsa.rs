@@ -0,0 +1,148 @@
+//! SLH-DSA against deterministic keygen and signatures from OpenSSL 3.5, plus
+//! the hedged API round trip and the usual rejections.
+#![cfg(feature = "slh_dsa")]
+
+use pure_dsa::sha3::sha3_256;
+use pure_dsa::slh_dsa::{Algorithm, Keypair};
+use rand::{SeedableRng, rngs::StdRng};
+
+const ALL: [Algorithm; 6] = [
+    Algorithm::Shake128s,
+    Algorithm::Shake128f,
+    Algorithm::Shake192s,
+    Algorithm::Shake192f,
+    Algorithm::Shake256s,
+    Algorithm::Shake256f,
+];
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+fn seed(alg: Algorithm) -> Vec<u8> {
+    (0..alg.seed_bytes()).map(|i| (i * 7 + 1) as u8).collect()
+}
+
+/// `PK.root` and SHA3-256 of the deterministic signature of "abc" for the
+/// key generated from `seed(alg)`.
+fn expected(alg: Algorithm) -> (&'static str, &'static str) {
+    match alg {
+        Algorithm::Shake128s => (
+            "57d1c4a892962ae1e1cf6b4b913ac9f9",
+            "2362342b5cb2c5353e233f747a44443858b8c0e5a6b4ce07ff717674a415123a",
+        ),
+        Algorithm::Shake128f => (
+            "774e52add994c5483cafe081caee6fa9",
+            "07870e8d052adb4c4708d20b57c1aa7985a719aab3af6f60a626b76056276438",
+        ),
+        Algorithm::Shake192s => (
+            "ec00e26b3427759db0d2b8b3f24d52f15c87212d354d1270",
+            "46b8b4564f334f08abee9d4708cf2f412687f395ea94afd42ddf6a5c2be1bfe3",
+        ),
+        Algorithm::Shake192f => (
+            "222f4536def161ceeb170ffb967138bb4f18461842d39a4f",
+            "b5ebe6c92df9b63b0a47ac05b33715eb264f60ecbf0e8957f256b7f2839dadb0",
+        ),
+        Algorithm::Shake256s => (
+            "acc58bb6952b8b355878a326705da99fe8dec661a073154b1eb2322e2cec677c",
+            "45478c71019c1de8cc12f90f0f5464f8e86b6d259f641b85b4e02de5538bb6d2",
+        ),
+        Algorithm::Shake256f => (
+            "d7f82f1018a1dcc0c535547cc628377b2b90dbcdb4263eb00b8a6bc9a4fef5b5",
+            "8ca7db864e6dff5a5a9488db5321b7860fc24521474e75e91beb67dbd49e62c7",
+        ),
+    }
+}
+
+#[test]
+fn sizes() {
+    let sizes: Vec<_> = ALL
+        .iter()
+        .map(|a| (a.public_key_bytes(), a.secret_key_bytes(), a.signature_bytes()))
+        .collect();
+    assert_eq!(
+        sizes,
+        [
+            (32, 64, 7856),
+            (32, 64, 17088),
+            (48, 96, 16224),
+            (48, 96, 35664),
+            (64, 128, 29792),
+            (64, 128, 49856),
+        ]
+    );
+}
+
+fn check_against_openssl(alg: Algorithm) {
+    let seed = seed(alg);
+    let (root, sig_hash) = expected(alg);
+    let keypair = alg.generate_from_seed(&seed).unwrap();
+    let n = alg.public_key_bytes() / 2;
+    assert_eq!(keypair.secret()[..3 * n], seed[..], "{alg:?}");
+    assert_eq!(keypair.public()[..n], seed[2 * n..], "{alg:?}");
+    assert_eq!(keypair.public()[n..], hex(root)[..], "{alg:?}");
+
+    let sig = keypair.sign_deterministic(b"abc");
+    assert_eq!(sha3_256(sig.bytes()).to_vec(), hex(sig_hash), "{alg:?}");
+    assert_eq!(alg.verify(&sig, b"abc", keypair.public()), Ok(()));
+}
+
+#[test]
+fn fast_sets_match_openssl() {
+    for alg in [Algorithm::Shake128f, Algorithm::Shake192f, Algorithm::Shake256f] {
+        check_against_openssl(alg);
+    }
+}
+
+#[test]
+#[cfg_attr(debug_assertions, ignore = "signing the small-signature sets is slow without optimizations")]
+fn small_sets_match_openssl() {
+    for alg in [Algorithm::Shake128s, Algorithm::Shake192s, Algorithm::Shake256s] {
+        check_against_openssl(alg);
+    }
+}
+
+#[test]
+fn hedged_round_trip() {
+    let mut rng = StdRng::seed_from_u64(42);
+    for alg in [Algorithm::Shake128f, Algorithm::Shake192f, Algorithm::Shake256f] {
+        let keypair: Keypair = alg.generate_with_rng(&mut rng);
+        let a = keypair.sign_with_rng(b"root of trust", &mut rng);
+        let b = keypair.sign_with_rng(b"root of trust", &mut rng);
+        assert_ne!(a.bytes(), b.bytes());
+        assert_eq!(alg.verify(&a, b"root of trust", keypair.public()), Ok(()));
+        assert_eq!(alg.verify(&b, b"root of trust", keypair.public()), Ok(()));
+        assert!(alg.verify(&a, b"root of trust?", keypair.public()).is_err());
+
+        let mut sig = vec![0u8; alg.signature_bytes()];
+        keypair.sign_into(&mut sig, b"buffers", &mut rng).unwrap();
+        assert_eq!(alg.verify_raw(&sig, b"buffers", keypair.public()), Ok(()));
+        // Flip a bit in R, in FORS, and in the top hypertree layer.
+        for at in [0, alg.public_key_bytes(), sig.len() - 1] {
+            sig[at] ^= 1;
+            assert_eq!(alg.verify_raw(&sig, b"buffers", keypair.public()), Err("Signature verification failed"));
+            sig[at] ^= 1;
+        }
+        assert_eq!(alg.verify_raw(&sig[1..], b"buffers", keypair.public()), Err("Invalid signature length"));
+        assert_eq!(alg.verify_raw(&sig, b"buffers", &keypair.public()[1..]), Err("Invalid public key length"));
+    }
+}
+
+#[test]
+fn key_loading() {
+    let alg = Algorithm::Shake128f;
+    let keypair = alg.generate_with_rng(&mut StdRng::seed_from_u64(7));
+    let (sk, pk) = (keypair.secret().to_vec(), keypair.public().to_vec());
+    assert_eq!(Keypair::from_secret_key(alg, &sk).unwrap().public(), &pk[..]);
+    assert!(alg.load_from_bytes(&sk, &pk).is_ok());
+
+    let mut bad = sk.clone();
+    bad[63] ^= 1;
+    assert_eq!(alg.check_keypair(&bad, &pk), Err("Public key does not match the secret key"));
+    assert_eq!(Keypair::from_secret_key(alg, &bad).unwrap_err(), "PK.root does not match SK.seed");
+    bad = sk.clone();
+    bad[0] ^= 1;
+    assert_eq!(alg.load_from_bytes(&bad, &pk).unwrap_err(), "PK.root does not match SK.seed");
+    assert_eq!(alg.check_keypair(&sk[1..], &pk), Err("Invalid key lengths"));
+    assert!(alg.generate_from_seed(&[0u8; 47]).is_err());
+}
@@ -0,0 +1,137 @@
+//! Strict verification accepts exactly one encoding per signature: every
+//! re-encodable variant of a valid signature, and every other mutation tried
+//! here, must fail.
+#![cfg(feature = "mode2")]
+
+use pure_dsa::Algorithm;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const MSG: &[u8] = b"deduplicated by bytes";
+
+struct Layout {
+    alg: Algorithm,
+    z: usize,
+    h: usize,
+    omega: usize,
+    k: usize,
+}
+
+fn layouts() -> Vec<Layout> {
+    vec![
+        Layout { alg: Algorithm::Mode2, z: 32, h: 32 + 4 * 576, omega: 80, k: 4 },
+        #[cfg(feature = "mode3")]
+        Layout { alg: Algorithm::Mode3, z: 32, h: 32 + 5 * 640, omega: 55, k: 6 },
+        #[cfg(feature = "mode5")]
+        Layout { alg: Algorithm::Mode5, z: 32, h: 32 + 7 * 640, omega: 75, k: 8 },
+    ]
+}
+
+fn signed(l: &Layout, seed: u64) -> (Vec<u8>, Vec<u8>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let keypair = l.alg.generate_with_rng(&mut rng);
+    let sig = keypair.sign_with_rng(MSG, &mut rng);
+    (sig.bytes().to_vec(), keypair.public().to_vec())
+}
+
+/// Any candidate that differs from the original must be refused by strict
+/// verification, and the relaxed verifier must not accept more.
+fn assert_rejected(l: &Layout, original: &[u8], candidate: &[u8], pk: &[u8]) {
+    if candidate == original {
+        return;
+    }
+    assert!(l.alg.verify_raw_strict(candidate, MSG, pk).is_err());
+    assert!(l.alg.verify_raw(candidate, MSG, pk).is_err());
+}
+
+#[test]
+fn valid_signatures_pass() {
+    for l in layouts() {
+        for seed in 0..4 {
+            let (sig, pk) = signed(&l, seed);
+            assert!(l.alg.verify_raw_strict(&sig, MSG, &pk).is_ok());
+        }
+    }
+}
+
+#[test]
+fn hint_section_mutations_rejected() {
+    for l in layouts() {
+        let (sig, pk) = signed(&l, 10);
+        for i in l.h..l.h + l.omega + l.k {
+            for v in [0x00, 0xFF, sig[i] ^ 0x01, sig[i] ^ 0x80, sig[i].wrapping_add(1), sig[i].wrapping_sub(1)] {
+                let mut bad = sig.clone();
+                bad[i] = v;
+                assert_rejected(&l, &sig, &bad, &pk);
+            }
+        }
+    }
+}
+
+#[test]
+fn crafted_hint_encodings_rejected() {
+    let l = &layouts()[0];
+    let (sig, pk) = signed(l, 11);
+    let counts = &sig[l.h + l.omega..l.h + l.omega + l.k];
+    let used = counts[l.k - 1] as usize;
+
+    // Nonzero padding after the last index.
+    assert!(used < l.omega);
+    let mut bad = sig.clone();
+    bad[l.h + l.omega - 1] = 1;
+    assert_rejected(l, &sig, &bad, &pk);
+
+    // Indices within a row out of order, or repeated.
+    let row = (0..l.k).find(|&i| {
+        let start = if i == 0 { 0 } else { counts[i - 1] as usize };
+        counts[i] as usize - start >= 2
+    });
+    let row = row.expect("some row has two hints");
+    let start = if row == 0 { 0 } else { counts[row - 1] as usize };
+    let mut bad = sig.clone();
+    bad.swap(l.h + start, l.h + start + 1);
+    assert_eq!(l.alg.verify_raw_strict(&bad, MSG, &pk), Err("INVALID H"));
+    let mut bad = sig.clone();
+    bad[l.h + start + 1] = bad[l.h + start];
+    assert_eq!(l.alg.verify_raw_strict(&bad, MSG, &pk), Err("INVALID H"));
+
+    // Row counts that decrease or exceed omega.
+    let mut bad = sig.clone();
+    bad[l.h + l.omega + l.k - 1] = l.omega as u8 + 1;
+    assert_eq!(l.alg.verify_raw_strict(&bad, MSG, &pk), Err("INVALID OMEGA"));
+    if counts[0] > 0 {
+        let mut bad = sig.clone();
+        bad[l.h + l.omega + 1] = counts[0] - 1;
+        assert_eq!(l.alg.verify_raw_strict(&bad, MSG, &pk), Err("INVALID OMEGA"));
+    }
+}
+
+#[test]
+fn challenge_and_z_mutations_rejected() {
+    let mut rng = StdRng::seed_from_u64(12);
+    for l in layouts() {
+        let (sig, pk) = signed(&l, 12);
+        for i in 0..l.z {
+            let mut bad = sig.clone();
+            bad[i] ^= 1 << rng.gen_range(0..8);
+            assert_rejected(&l, &sig, &bad, &pk);
+        }
+        for _ in 0..200 {
+            let mut bad = sig.clone();
+            bad[rng.gen_range(l.z..l.h)] ^= 1 << rng.gen_range(0..8);
+            assert_rejected(&l, &sig, &bad, &pk);
+        }
+    }
+}
+
+#[test]
+fn wrong_lengths_rejected_without_panicking() {
+    for l in layouts() {
+        let (sig, pk) = signed(&l, 13);
+        assert_eq!(l.alg.verify_raw_strict(&sig[1..], MSG, &pk), Err("Signature length mismatch"));
+        assert_eq!(l.alg.verify_raw_strict(&sig, MSG, &pk[1..]), Err("Public key length mismatch"));
+        assert_eq!(l.alg.verify_raw(&sig, MSG, &pk[1..]), Err("Public key length mismatch"));
+        let mut long = sig.clone();
+        long.push(0);
+        assert!(l.alg.verify_raw_strict(&long, MSG, &pk).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! dudect-style leakage check (Reparaz, Balasch, Verbauwhede, 2017).
+//!
+//! Times `crypto_sign_signature` (through `Keypair::sign_with_rng`) for one
+//! fixed secret key against a pool of random secret keys, with the two
+//! classes interleaved in random order, and runs Welch's t-test on the
+//! measurements, both in full and cropped at a few percentiles to cut the
+//! tail of long rejection loops. Messages are random in both classes so only
+//! the key separates them.
+//!
+//! Statistical and slow, so ignored by default:
+//! `cargo test --release --test timing_leak -- --ignored --nocapture`
+#![cfg(feature = "mode2")]
+
+use pure_dsa::{Algorithm, Keypair};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::time::Instant;
+
+const SAMPLES: usize = 20_000;
+const POOL: usize = 64;
+const PERCENTILES: [f64; 4] = [0.5, 0.75, 0.9, 1.0];
+/// dudect flags `|t|` above 10 as a definite leak.
+const T_THRESHOLD: f64 = 10.0;
+
+#[derive(Default)]
+struct Welch {
+    n: [f64; 2],
+    mean: [f64; 2],
+    m2: [f64; 2],
+}
+
+impl Welch {
+    fn push(&mut self, class: usize, x: f64) {
+        self.n[class] += 1.0;
+        let delta = x - self.mean[class];
+        self.mean[class] += delta / self.n[class];
+        self.m2[class] += delta * (x - self.mean[class]);
+    }
+
+    fn t(&self) -> f64 {
+        let var = |c: usize| self.m2[c] / (self.n[c] - 1.0);
+        (self.mean[0] - self.mean[1]) / (var(0) / self.n[0] + var(1) / self.n[1]).sqrt()
+    }
+}
+
+#[test]
+#[ignore = "statistical timing harness; run in release with --ignored"]
+fn sign_time_independent_of_secret_key() {
+    let mut rng = StdRng::seed_from_u64(0x6475_6465_6374);
+    let alg = Algorithm::Mode2;
+    let fixed = alg.generate_with_rng(&mut rng);
+    let pool: Vec<Keypair> = (0..POOL).map(|_| alg.generate_with_rng(&mut rng)).collect();
+
+    let mut samples = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        let class = rng.gen_range(0..2usize);
+        let key = if class == 0 { &fixed } else { &pool[rng.gen_range(0..POOL)] };
+        let msg: [u8; 32] = rng.r#gen();
+
+        let start = Instant::now();
+        let sig = key.sign_with_rng(&msg, &mut rng);
+        let elapsed = start.elapsed().as_nanos() as f64;
+        std::hint::black_box(sig);
+        samples.push((class, elapsed));
+    }
+
+    let mut sorted: Vec<f64> = samples.iter().map(|&(_, x)| x).collect();
+    sorted.sort_by(f64::total_cmp);
+    for p in PERCENTILES {
+        let crop = sorted[((sorted.len() - 1) as f64 * p) as usize];
+        let mut welch = Welch::default();
+        for &(class, x) in samples.iter().filter(|&&(_, x)| x <= crop) {
+            welch.push(class, x);
+        }
+        let t = welch.t();
+        println!("p{:>3}: t = {t:+.2}", (p * 100.0) as u32);
+        assert!(t.abs() < T_THRESHOLD, "timing depends on the secret key (p{}: t = {t:.2})", p * 100.0);
+    }
+}
@@ -0,0 +1,137 @@
+//! TurboSHAKE and KangarooTwelve against the RFC 9861 test vectors.
+
+use pure_dsa::sha3::*;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+/// `ptn(n)` from the RFC: `00 01 .. F9 FA 00 01 ..`, `n` bytes long.
+fn ptn(n: usize) -> Vec<u8> {
+    (0..n).map(|i| (i % 251) as u8).collect()
+}
+
+/// The last `expected.len()` bytes of `out_len` bytes of output.
+fn tail(f: impl Fn(&mut [u8]), out_len: usize, expected: &str) {
+    let expected = hex(expected);
+    let mut out = vec![0u8; out_len];
+    f(&mut out);
+    assert_eq!(out[out_len - expected.len()..], expected[..]);
+}
+
+#[test]
+fn turbo_shake128_vectors() {
+    let ts = |m: Vec<u8>, d: u8| move |out: &mut [u8]| turbo_shake128(&m, d, out);
+    tail(ts(vec![], 0x07), 32, "5a223ad30b3b8c66a243048cfced430f54e7529287d15150b973133adfac6a2f");
+    tail(
+        ts(vec![], 0x07),
+        64,
+        "5a223ad30b3b8c66a243048cfced430f54e7529287d15150b973133adfac6a2f\
+         fe2708e73061e09a4000168ba9c8ca1813198f7bbed4984b4185f2c2580ee623",
+    );
+    tail(ts(vec![], 0x07), 10032, "7593a28020a3c4ae0d605fd61f5eb56eccd27cc3d12ff09f78369772a460c55d");
+    tail(ts(ptn(1), 0x07), 32, "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5");
+    tail(ts(ptn(17), 0x07), 32, "acbd4aa57507043bcee55ad3f48504d815e707fe82ee3dad6d5852c8920b905e");
+    tail(ts(ptn(17 * 17), 0x07), 32, "7a4de8b1d927a682b929610103f0e964559bd74542cfad740ee3d9b036469e0a");
+    tail(ts(ptn(17usize.pow(3)), 0x07), 32, "7452ed0ed860aa8fe8e79699ece324f8d93271463610da76801ebcee4fcafe42");
+    tail(ts(ptn(17usize.pow(4)), 0x07), 32, "ca5f1f3eeac992cdc2abebca0e216765dbf779c3c10946055a94ab3272573522");
+    tail(ts(vec![0xff], 0x06), 32, "8ec9c66465ed0d4a6c35d13506718d687a25cb05c74cca1e42501abd83874a67");
+    tail(ts(vec![0xff; 3], 0x06), 32, "3d03988bb59e681851a192f429ae03988e8f444bc06036a3f1a7d2ccd758d174");
+    tail(ts(vec![0xff; 7], 0x06), 32, "05d9ae673d5f0e48bb2b57e88021a1a83d70ba85923aa04c12e8f65ba1f94595");
+}
+
+#[test]
+fn turbo_shake256_vectors() {
+    let ts = |m: Vec<u8>, d: u8| move |out: &mut [u8]| turbo_shake256(&m, d, out);
+    tail(
+        ts(vec![], 0x07),
+        64,
+        "4a555b06ecf8f1538ccf5c9515d0d04970181563a62381c7f0c807a6d1bd9e81\
+         97804bfde2428bf72961eb52b4189c391cef6fee663a3c1ce78b88255bc1acc3",
+    );
+    tail(ts(vec![], 0x07), 10032, "40221ad734f3edc1b106bad50a72949315b352ba39ad98b5b3c2301163adaad0");
+    tail(
+        ts(ptn(17), 0x07),
+        64,
+        "66d378dfe4e902ac4eb78f7c2e5a14f02bc1c849e621bae665796fb3346e6c79\
+         75705bb93c00f3ca8f83bca479f06977ab3a60f39796b136538aaae8bcac8544",
+    );
+    tail(
+        ts(ptn(17usize.pow(3)), 0x07),
+        64,
+        "62a5a0bff06426d71a7a3e9e3f2fd6e252ff3fc188a6a536eca45a49a3437cb3\
+         bc3a0f8149c850e6e7f4747a70627fd2303041c6c33630f943ad92f8e1ff4390",
+    );
+    tail(
+        ts(vec![0xff; 3], 0x06),
+        64,
+        "e5538cdd28302a2e81e41f65fd2a4052014d0cd463df671d1e510a9d95c37d71\
+         35ef2728430a9e317004f836c9a238ef35370280d03dce7f0612f0315b3cbf63",
+    );
+    tail(
+        ts(vec![], 0x0b),
+        64,
+        "c749f7fb23644a021d35653d1bfdf747cece5f9739f9a344ad169f10906c6817\
+         c8ee12784e42ff57814efc1c898789d5e415db49052ea43a09901d7a82a2145c",
+    );
+}
+
+#[test]
+#[should_panic]
+fn turbo_shake_rejects_domain_byte_outside_range() {
+    TurboShake128::new(0x80);
+}
+
+#[test]
+fn kangaroo_twelve_vectors() {
+    let kt = |m: Vec<u8>, c: Vec<u8>| move |out: &mut [u8]| kangaroo_twelve(&m, &c, out);
+    tail(kt(vec![], vec![]), 32, "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5");
+    tail(kt(vec![], vec![]), 10032, "e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d");
+    tail(kt(ptn(1), vec![]), 32, "2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f");
+    tail(kt(ptn(17), vec![]), 32, "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888");
+    tail(kt(vec![], ptn(1)), 32, "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583");
+    tail(kt(vec![0xff], ptn(41)), 32, "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4");
+    // |S| > 8192: the tree encoding with eight leaves.
+    tail(
+        kt(vec![0xff; 7], ptn(41usize.pow(3))),
+        32,
+        "75d2f86a2e644566726b4fbcfc5657b9dbcf070c7b0dca06450ab291d7443bcf",
+    );
+}
+
+#[test]
+fn kangaroo_twelve_incremental() {
+    let msg = ptn(5 * 8192 + 17);
+    let custom = ptn(300);
+    let mut expected = [0u8; 64];
+    kangaroo_twelve(&msg, &custom, &mut expected);
+    for split in [1, 8191, 8192, 8193, 2 * 8192, 5 * 8192] {
+        let mut k = KangarooTwelve::new(&custom);
+        k.update(&msg[..split]);
+        k.update(&msg[split..]);
+        let mut out = [0u8; 64];
+        k.finalize_xof().read(&mut out);
+        assert_eq!(out, expected, "split at {split}");
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn kangaroo_twelve_parallel_matches_serial() {
+    for len in [0, 8192, 8193, 2 * 8192, 37 * 8192 + 100] {
+        let msg = ptn(len);
+        let mut expected = [0u8; 32];
+        kangaroo_twelve(&msg, b"disk", &mut expected);
+        for threads in [1, 2, 5] {
+            for head in [0, 100, 8192] {
+                let head = head.min(len);
+                let mut k = KangarooTwelve::new(b"disk");
+                k.update(&msg[..head]);
+                k.update_parallel(&msg[head..], threads);
+                let mut out = [0u8; 32];
+                k.finalize_xof().read(&mut out);
+                assert_eq!(out, expected, "len {len}, {threads} threads, head {head}");
+            }
+        }
+    }
+}
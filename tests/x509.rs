@@ -0,0 +1,171 @@
+//! Certificates and PKCS#10 requests: a root, intermediate and leaf issued
+//! without outside tooling, the extensions round trip, and tampering.
+#![cfg(all(feature = "x509", feature = "mode2", feature = "mode3"))]
+
+use pure_dsa::x509::*;
+use pure_dsa::{Algorithm, Keypair};
+use rand::{SeedableRng, rngs::StdRng};
+
+/// 2025-01-01T00:00:00Z and 2035-01-01T00:00:00Z.
+const NOT_BEFORE: u64 = 1735689600;
+const NOT_AFTER: u64 = 2051222400;
+
+fn root(rng: &mut StdRng) -> (Keypair, Certificate) {
+    let key = Algorithm::Mode3.generate_with_rng(rng);
+    let mut params = CertificateParams::new(&[1], Name::new().with_country("NL").with_common_name("Root CA"), NOT_BEFORE, NOT_AFTER);
+    params.basic_constraints = Some(BasicConstraints { ca: true, path_len: Some(1) });
+    params.key_usage = Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN);
+    let cert = params.self_signed_with_rng(&key, rng).unwrap();
+    (key, cert)
+}
+
+#[test]
+fn self_signed_root() {
+    let mut rng = StdRng::seed_from_u64(5280);
+    let (key, cert) = root(&mut rng);
+
+    assert_eq!(cert.signature_algorithm(), Algorithm::Mode3);
+    assert_eq!(cert.serial_number(), [1]);
+    assert_eq!(cert.subject().to_string(), "C=NL, CN=Root CA");
+    assert_eq!(cert.subject().common_name(), Some("Root CA"));
+    assert!(cert.is_self_issued());
+    assert_eq!((cert.not_before(), cert.not_after()), (NOT_BEFORE, NOT_AFTER));
+    assert_eq!(cert.public_key().public_key(), key.public());
+    assert_eq!(cert.basic_constraints(), Some(BasicConstraints { ca: true, path_len: Some(1) }));
+    assert!(cert.key_usage().unwrap().contains(KeyUsage::KEY_CERT_SIGN));
+    assert!(!cert.key_usage().unwrap().contains(KeyUsage::DIGITAL_SIGNATURE));
+    assert_eq!(cert.subject_key_id(), Some(&cert.public_key().key_identifier()[..]));
+    assert_eq!(cert.authority_key_id(), cert.subject_key_id());
+    assert!(cert.verify_signature(cert.public_key()).is_ok());
+
+    assert_eq!(Certificate::from_der(cert.to_der()).unwrap(), cert);
+    let pem = cert.to_pem();
+    assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+    assert_eq!(Certificate::from_pem(&pem).unwrap(), cert);
+    let spki = cert.public_key();
+    assert_eq!(&SubjectPublicKeyInfo::from_pem(&spki.to_pem()).unwrap(), spki);
+}
+
+/// RFC 7093 method 1: the leftmost 160 bits of SHA-256 over the key bits.
+/// The expected value was computed with OpenSSL from the SPKI.
+#[test]
+fn key_identifier() {
+    let key = Algorithm::Mode2.generate_from_seed(&[7; 32]).unwrap();
+    let id = SubjectPublicKeyInfo::from_keypair(&key).key_identifier();
+    assert_eq!(&id, b"\x16\xbb\x3c\x27\xf5\xc4\xe8\xaa\x3a\xc4\xa3\x81\xbc\xec\x00\xab\x46\x37\xb0\xbb");
+}
+
+#[test]
+fn chain_from_requests() {
+    let mut rng = StdRng::seed_from_u64(2986);
+    let (root_key, root_cert) = root(&mut rng);
+
+    let ca_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+    let mut params = CertificateParams::new(&[0x80, 0x02], Name::new().with_common_name("Issuing CA"), NOT_BEFORE, NOT_AFTER);
+    params.basic_constraints = Some(BasicConstraints { ca: true, path_len: Some(0) });
+    params.key_usage = Some(KeyUsage::KEY_CERT_SIGN);
+    let ca_cert = params
+        .signed_by_with_rng(&SubjectPublicKeyInfo::from_keypair(&ca_key), &root_cert, &root_key, &mut rng)
+        .unwrap();
+    assert_eq!(ca_cert.serial_number(), [0x80, 0x02]);
+    assert_eq!(ca_cert.issuer(), root_cert.subject());
+    assert_eq!(ca_cert.authority_key_id(), root_cert.subject_key_id());
+    assert!(ca_cert.verify_signature(root_cert.public_key()).is_ok());
+    assert!(ca_cert.verify_signature(ca_cert.public_key()).is_err());
+
+    let leaf_key = Algorithm::Mode2.generate_with_rng(&mut rng);
+    let sans = [
+        GeneralName::Dns("example.org".into()),
+        GeneralName::Email("ops@example.org".into()),
+        GeneralName::Uri("https://example.org/".into()),
+        GeneralName::Ip(vec![192, 0, 2, 1]),
+    ];
+    let subject = Name::new().with_organization("Example").with_common_name("example.org");
+    let csr = CertificateRequest::new_with_rng(subject.clone(), &sans, &leaf_key, &mut rng).unwrap();
+    let csr = CertificateRequest::from_pem(&csr.to_pem()).unwrap();
+    assert!(csr.verify().is_ok());
+    assert_eq!(csr.subject(), &subject);
+    assert_eq!(csr.subject_alt_names(), sans);
+    assert_eq!(csr.public_key().algorithm(), Algorithm::Mode2);
+
+    let mut params = CertificateParams::new(&[3], csr.subject().clone(), NOT_BEFORE, NOT_AFTER);
+    params.key_usage = Some(KeyUsage::DIGITAL_SIGNATURE);
+    params.subject_alt_names = csr.subject_alt_names().to_vec();
+    let leaf = params.signed_by_with_rng(csr.public_key(), &ca_cert, &ca_key, &mut rng).unwrap();
+    assert_eq!(leaf.signature_algorithm(), Algorithm::Mode3);
+    assert_eq!(leaf.public_key().algorithm(), Algorithm::Mode2);
+    assert_eq!(leaf.basic_constraints(), None);
+    assert_eq!(leaf.subject_alt_names(), sans);
+    assert!(!leaf.is_self_issued());
+    assert!(leaf.verify_signature(ca_cert.public_key()).is_ok());
+    assert!(leaf.verify_signature(root_cert.public_key()).is_err());
+
+    // A request with an empty subject puts everything in the SAN.
+    let csr = CertificateRequest::new_with_rng(Name::new(), &sans[..1], &leaf_key, &mut rng).unwrap();
+    assert!(csr.subject().is_empty());
+    assert!(csr.verify().is_ok());
+}
+
+#[test]
+fn tampering_and_errors() {
+    let mut rng = StdRng::seed_from_u64(9881);
+    let (key, cert) = root(&mut rng);
+
+    // Flip a bit of the subject common name inside the signed TBSCertificate.
+    let mut der = cert.to_der().to_vec();
+    let at = der.windows(7).rposition(|w| w == b"Root CA").unwrap();
+    der[at] ^= 0x20;
+    let forged = Certificate::from_der(&der).unwrap();
+    assert_eq!(forged.subject().common_name(), Some("root CA"));
+    assert_eq!(forged.verify_signature(cert.public_key()), Err("Invalid signature"));
+
+    assert!(Certificate::from_der(&der[..der.len() - 1]).is_err());
+    let mut trailing = cert.to_der().to_vec();
+    trailing.push(0);
+    assert_eq!(Certificate::from_der(&trailing), Err("Trailing DER data"));
+
+    let other = Algorithm::Mode3.generate_with_rng(&mut rng);
+    let params = CertificateParams::new(&[2], Name::new().with_common_name("leaf"), NOT_BEFORE, NOT_AFTER);
+    let spki = SubjectPublicKeyInfo::from_keypair(&other);
+    assert_eq!(
+        params.signed_by_with_rng(&spki, &cert, &other, &mut rng).err(),
+        Some("Issuer key does not match the issuer certificate")
+    );
+    assert!(params.signed_by_with_rng(&spki, &cert, &key, &mut rng).is_ok());
+
+    let mut bad = params.clone();
+    bad.serial_number = vec![0, 0];
+    assert_eq!(bad.self_signed_with_rng(&key, &mut rng).err(), Some("Invalid serial number"));
+    bad.serial_number = vec![1; 21];
+    assert_eq!(bad.self_signed_with_rng(&key, &mut rng).err(), Some("Invalid serial number"));
+    let mut bad = params.clone();
+    bad.not_after = NOT_BEFORE - 1;
+    assert_eq!(bad.self_signed_with_rng(&key, &mut rng).err(), Some("Invalid validity period"));
+    let mut bad = params.clone();
+    bad.subject_alt_names = vec![GeneralName::Ip(vec![127, 0, 0])];
+    assert_eq!(bad.self_signed_with_rng(&key, &mut rng).err(), Some("Invalid IP address length"));
+
+    assert_eq!(SubjectPublicKeyInfo::new(Algorithm::Mode2, &[0; 32]), Err("Invalid public key length"));
+}
+
+#[test]
+fn validity_times() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let key = Algorithm::Mode2.generate_with_rng(&mut rng);
+    // UTCTime ends with 2049; from 2050 on the encoding is GeneralizedTime.
+    for (not_before, not_after) in [(0, 2524607999), (2524608000, 253402300799), (951782400, 951868799)] {
+        let params = CertificateParams::new(&[9], Name::new().with_common_name("t"), not_before, not_after);
+        let cert = params.self_signed_with_rng(&key, &mut rng).unwrap();
+        assert_eq!((cert.not_before(), cert.not_after()), (not_before, not_after));
+    }
+    let der = CertificateParams::new(&[9], Name::new(), 2524607999, 2524608000)
+        .self_signed_with_rng(&key, &mut rng)
+        .unwrap()
+        .tbs_certificate()
+        .to_vec();
+    assert!(der.windows(15).any(|w| w == b"\x17\x0d491231235959Z"));
+    assert!(der.windows(17).any(|w| w == b"\x18\x0f20500101000000Z"));
+
+    let params = CertificateParams::new(&[9], Name::new(), 0, 253402300800);
+    assert_eq!(params.self_signed_with_rng(&key, &mut rng).err(), Some("Time out of range"));
+}
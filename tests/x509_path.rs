@@ -0,0 +1,138 @@
+//! RFC 5280 path validation over leaf -> intermediate -> root chains, with
+//! each rejection reported at the certificate that causes it.
+#![cfg(all(feature = "x509", feature = "mode2", feature = "mode3"))]
+
+use pure_dsa::x509::*;
+use pure_dsa::{Algorithm, Keypair};
+use rand::{SeedableRng, rngs::StdRng};
+
+const DAY: u64 = 86400;
+/// 2025-01-01T00:00:00Z.
+const T0: u64 = 1735689600;
+const NOW: u64 = T0 + 100 * DAY;
+
+fn ca_params(serial: u8, cn: &str, path_len: Option<u32>) -> CertificateParams {
+    let mut params = CertificateParams::new(&[serial], Name::new().with_common_name(cn), T0, T0 + 3650 * DAY);
+    params.basic_constraints = Some(BasicConstraints { ca: true, path_len });
+    params.key_usage = Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN);
+    params
+}
+
+fn leaf_params() -> CertificateParams {
+    let mut params = CertificateParams::new(&[9], Name::new().with_common_name("leaf"), T0, T0 + 365 * DAY);
+    params.key_usage = Some(KeyUsage::DIGITAL_SIGNATURE);
+    params
+}
+
+fn issue(rng: &mut StdRng, params: &CertificateParams, issuer: &Certificate, issuer_key: &Keypair) -> (Keypair, Certificate) {
+    let key = Algorithm::Mode2.generate_with_rng(rng);
+    let cert = params.signed_by_with_rng(&SubjectPublicKeyInfo::from_keypair(&key), issuer, issuer_key, rng).unwrap();
+    (key, cert)
+}
+
+/// A root, an intermediate with pathLenConstraint 0 under it, and a leaf
+/// under the intermediate.
+struct Pki {
+    rng: StdRng,
+    root_key: Keypair,
+    root: Certificate,
+    ca_key: Keypair,
+    ca: Certificate,
+    leaf: Certificate,
+}
+
+impl Pki {
+    fn new(root_path_len: Option<u32>) -> Pki {
+        let mut rng = StdRng::seed_from_u64(5280);
+        let root_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+        let root = ca_params(1, "Root", root_path_len).self_signed_with_rng(&root_key, &mut rng).unwrap();
+        let ca_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+        let ca = ca_params(2, "Intermediate", Some(0))
+            .signed_by_with_rng(&SubjectPublicKeyInfo::from_keypair(&ca_key), &root, &root_key, &mut rng)
+            .unwrap();
+        let (_, leaf) = issue(&mut rng, &leaf_params(), &ca, &ca_key);
+        Pki { rng, root_key, root, ca_key, ca, leaf }
+    }
+}
+
+fn err(index: usize, kind: PathErrorKind) -> Result<(), PathError> {
+    Err(PathError { index, kind })
+}
+
+#[test]
+fn valid_chains() {
+    let Pki { root, ca, leaf, .. } = Pki::new(None);
+    assert_eq!(validate_path(&[leaf.clone(), ca.clone()], &root, NOW), Ok(()));
+    assert_eq!(validate_path(&[leaf.clone(), ca.clone(), root.clone()], &root, NOW), Ok(()));
+    assert_eq!(validate_path(std::slice::from_ref(&ca), &root, NOW), Ok(()));
+    // Validity bounds are inclusive.
+    assert_eq!(validate_path(&[leaf.clone(), ca.clone()], &root, T0), Ok(()));
+    assert_eq!(validate_path(&[leaf, ca], &root, T0 + 365 * DAY), Ok(()));
+
+    assert_eq!(validate_path(&[], &root, NOW), err(0, PathErrorKind::EmptyPath));
+    assert_eq!(validate_path(std::slice::from_ref(&root), &root, NOW), err(0, PathErrorKind::EmptyPath));
+}
+
+#[test]
+fn validity_periods() {
+    let Pki { root, ca, leaf, .. } = Pki::new(None);
+    let chain = [leaf, ca];
+    assert_eq!(validate_path(&chain, &root, T0 - 1), err(1, PathErrorKind::NotYetValid));
+    assert_eq!(validate_path(&chain, &root, T0 + 365 * DAY + 1), err(0, PathErrorKind::Expired));
+    assert_eq!(validate_path(&chain, &root, T0 + 3651 * DAY), err(1, PathErrorKind::Expired));
+}
+
+#[test]
+fn signatures_and_names() {
+    let Pki { mut rng, root_key, root, ca, leaf, .. } = Pki::new(None);
+
+    // Same subject name as the intermediate, different key.
+    let (_, impostor) = issue(&mut rng, &ca_params(3, "Intermediate", None), &root, &root_key);
+    assert_eq!(validate_path(&[leaf.clone(), impostor.clone()], &root, NOW), err(0, PathErrorKind::InvalidSignature));
+
+    // Same name as the root, different key.
+    let other_key = Algorithm::Mode3.generate_with_rng(&mut rng);
+    let other = ca_params(1, "Root", None).self_signed_with_rng(&other_key, &mut rng).unwrap();
+    assert_eq!(validate_path(&[leaf.clone(), ca.clone()], &other, NOW), err(1, PathErrorKind::InvalidSignature));
+
+    // The root's key under another name: signatures verify, names do not chain.
+    let renamed = ca_params(1, "Other Root", None).self_signed_with_rng(&root_key, &mut rng).unwrap();
+    assert_eq!(validate_path(&[leaf, ca], &renamed, NOW), err(1, PathErrorKind::IssuerMismatch));
+}
+
+#[test]
+fn ca_constraints() {
+    let Pki { mut rng, root_key, root, ca_key, ca, leaf } = Pki::new(None);
+
+    // An end-entity certificate used as an issuer.
+    let mut not_ca = ca_params(5, "Not a CA", None);
+    not_ca.basic_constraints = None;
+    let (key, cert) = issue(&mut rng, &not_ca, &root, &root_key);
+    let (_, below) = issue(&mut rng, &leaf_params(), &cert, &key);
+    assert_eq!(validate_path(&[below, cert], &root, NOW), err(1, PathErrorKind::NotCa));
+
+    // The intermediate's pathLenConstraint of 0 allows no CA below it.
+    let (sub_key, sub) = issue(&mut rng, &ca_params(6, "Sub CA", None), &ca, &ca_key);
+    let (_, below) = issue(&mut rng, &leaf_params(), &sub, &sub_key);
+    assert_eq!(validate_path(&[sub.clone(), ca.clone()], &root, NOW), Ok(()));
+    assert_eq!(validate_path(&[below, sub, ca.clone()], &root, NOW), err(1, PathErrorKind::PathLenExceeded));
+
+    // A CA whose keyUsage leaves out keyCertSign.
+    let mut no_sign = ca_params(7, "No keyCertSign", None);
+    no_sign.key_usage = Some(KeyUsage::CRL_SIGN | KeyUsage::DIGITAL_SIGNATURE);
+    let (key, cert) = issue(&mut rng, &no_sign, &root, &root_key);
+    let (_, below) = issue(&mut rng, &leaf_params(), &cert, &key);
+    assert_eq!(validate_path(&[below, cert], &root, NOW), err(1, PathErrorKind::KeyUsage));
+    assert_eq!(validate_path(&[leaf, ca], &root, NOW), Ok(()));
+
+    // The root's own pathLenConstraint of 0 allows no intermediate.
+    let strict = Pki::new(Some(0));
+    assert_eq!(validate_path(std::slice::from_ref(&strict.ca), &strict.root, NOW), Ok(()));
+    assert_eq!(
+        validate_path(&[strict.leaf, strict.ca], &strict.root, NOW),
+        err(1, PathErrorKind::PathLenExceeded)
+    );
+
+    let e = PathError { index: 1, kind: PathErrorKind::KeyUsage };
+    assert_eq!(e.to_string(), "certificate 1: Issuing certificate lacks keyCertSign");
+}
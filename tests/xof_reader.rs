@@ -0,0 +1,119 @@
+//! However input and output are split into calls, an XOF must produce the
+//! bytes a single call would, in particular around block boundaries.
+
+use pure_dsa::sha3::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const SHAKE128_RATE: usize = 168;
+const SHAKE256_RATE: usize = 136;
+const LEN: usize = 4 * SHAKE128_RATE + 17;
+
+fn reader(rate: usize, msg: &[u8], splits: &[usize]) -> XofReader {
+    let mut rest = msg;
+    let mut shake128 = Shake128::new();
+    let mut shake256 = Shake256::new();
+    for &n in splits.iter().chain([usize::MAX].iter()) {
+        let (head, tail) = rest.split_at(n.min(rest.len()));
+        shake128.update(head);
+        shake256.update(head);
+        rest = tail;
+    }
+    if rate == SHAKE128_RATE { shake128.finalize_xof() } else { shake256.finalize_xof() }
+}
+
+fn one_shot(rate: usize, msg: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; LEN];
+    if rate == SHAKE128_RATE { shake128(msg, &mut out) } else { shake256(msg, &mut out) }
+    out
+}
+
+fn read_in_pieces(reader: &mut XofReader, sizes: &[usize]) -> Vec<u8> {
+    let mut out = vec![0u8; LEN];
+    let mut pos = 0;
+    for &n in sizes.iter().cycle() {
+        if pos == LEN {
+            break;
+        }
+        let end = (pos + n).min(LEN);
+        reader.read(&mut out[pos..end]);
+        pos = end;
+    }
+    out
+}
+
+/// Piece sizes that land just before, on and just after block boundaries.
+fn boundary_splits(rate: usize) -> Vec<Vec<usize>> {
+    vec![
+        vec![1],
+        vec![rate],
+        vec![rate - 1, 1],
+        vec![rate + 1],
+        vec![rate - 1, 2, rate - 2],
+        vec![7, rate, 3 * rate],
+        vec![2 * rate + 5],
+        vec![0, rate, 0, 1],
+        vec![LEN],
+    ]
+}
+
+#[test]
+fn squeeze_splits_match_one_shot() {
+    let msg = b"split me";
+    for rate in [SHAKE128_RATE, SHAKE256_RATE] {
+        let expected = one_shot(rate, msg);
+        for sizes in boundary_splits(rate) {
+            assert_eq!(read_in_pieces(&mut reader(rate, msg, &[]), &sizes), expected, "{rate} {sizes:?}");
+        }
+    }
+}
+
+#[test]
+fn absorb_splits_match_one_shot() {
+    let msg: Vec<u8> = (0..3 * SHAKE128_RATE + 11).map(|i| i as u8).collect();
+    for rate in [SHAKE128_RATE, SHAKE256_RATE] {
+        let expected = one_shot(rate, &msg);
+        for splits in boundary_splits(rate) {
+            let splits: Vec<usize> = splits.iter().cycle().take(64).copied().collect();
+            assert_eq!(read_in_pieces(&mut reader(rate, &msg, &splits), &[LEN]), expected);
+        }
+    }
+}
+
+#[test]
+fn random_splits_match_one_shot() {
+    let mut rng = StdRng::seed_from_u64(40);
+    for _ in 0..200 {
+        let rate = if rng.r#gen() { SHAKE128_RATE } else { SHAKE256_RATE };
+        let msg: Vec<u8> = (0..rng.gen_range(0..600)).map(|_| rng.r#gen()).collect();
+        let absorb: Vec<usize> = (0..8).map(|_| rng.gen_range(0..2 * rate)).collect();
+        let squeeze: Vec<usize> = (0..8).map(|_| rng.gen_range(1..2 * rate)).collect();
+        let out = read_in_pieces(&mut reader(rate, &msg, &absorb), &squeeze);
+        assert_eq!(out, one_shot(rate, &msg));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reader_implements_io_read() {
+    use std::io::Read;
+
+    let expected = one_shot(SHAKE256_RATE, b"io");
+    let mut xof = reader(SHAKE256_RATE, b"io", &[]);
+    let mut head = [0u8; 100];
+    xof.read_exact(&mut head).unwrap();
+    let mut tail = Vec::new();
+    Read::by_ref(&mut xof).take((LEN - head.len()) as u64).read_to_end(&mut tail).unwrap();
+    assert_eq!([&head[..], &tail[..]].concat(), expected);
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn reader_implements_digest_xof_reader() {
+    let expected = one_shot(SHAKE128_RATE, b"digest");
+    let mut xof = reader(SHAKE128_RATE, b"digest", &[]);
+    let mut out = vec![0u8; LEN];
+    let (a, b) = out.split_at_mut(SHAKE128_RATE + 3);
+    digest::XofReader::read(&mut xof, a);
+    digest::XofReader::read(&mut xof, b);
+    assert_eq!(out, expected);
+}
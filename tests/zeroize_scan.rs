@@ -0,0 +1,257 @@
+//! Looks for key material in memory the library has released.
+//!
+//! Stack: keygen and signing run on a fresh thread; afterwards the dead
+//! region below the stack pointer, where their frames lived, is copied out
+//! with inline assembly (a plain Rust read of dead stack would be undefined
+//! behaviour) and searched for the seed, the signing key `K` and `rhoprime`.
+//! Heap: a wrapping global allocator searches every block as it is freed,
+//! here while a `Keypair` is dropped and, with `ssh` and `jose`, while an
+//! OpenSSH private key or a JWK is written and read back.
+#![cfg(all(feature = "zeroize", feature = "mode2", target_arch = "x86_64"))]
+
+use pure_dsa::{Algorithm, Keypair};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    arch::asm,
+    hint::black_box,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+/// How far below the stack pointer to look; well past the deepest signer
+/// frame even in debug builds.
+const SCAN_BYTES: usize = 1 << 20;
+const NEEDLE: usize = 16;
+const MAX_NEEDLES: usize = 32;
+
+/// Records everything it hands out so the test knows the seed and `rhoprime`.
+struct RecordingRng {
+    inner: StdRng,
+    out: Vec<u8>,
+}
+
+impl RngCore for RecordingRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut b = [0u8; 4];
+        self.fill_bytes(&mut b);
+        u32::from_le_bytes(b)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut b = [0u8; 8];
+        self.fill_bytes(&mut b);
+        u64::from_le_bytes(b)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest);
+        self.out.extend_from_slice(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+static ARMED: AtomicBool = AtomicBool::new(false);
+static HEAP_NEEDLES: [[AtomicU8; NEEDLE]; MAX_NEEDLES] = [const { [const { AtomicU8::new(0) }; NEEDLE] }; MAX_NEEDLES];
+static HEAP_NEEDLE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static HEAP_HIT: AtomicBool = AtomicBool::new(false);
+/// One heap scan at a time; tests run in parallel but share the allocator.
+static HEAP_SCAN: Mutex<()> = Mutex::new(());
+
+struct ScanningAlloc;
+
+unsafe impl GlobalAlloc for ScanningAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if ARMED.load(Ordering::SeqCst) {
+            let block = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+            for slot in &HEAP_NEEDLES[..HEAP_NEEDLE_COUNT.load(Ordering::SeqCst)] {
+                let needle: [u8; NEEDLE] = std::array::from_fn(|i| slot[i].load(Ordering::SeqCst));
+                if contains(block, &needle) {
+                    HEAP_HIT.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: ScanningAlloc = ScanningAlloc;
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Runs `f` while every freed block is searched for `needles`; true if any
+/// of them turned up.
+fn freed_blocks_contain(needles: &[[u8; NEEDLE]], f: impl FnOnce()) -> bool {
+    assert!(needles.len() <= MAX_NEEDLES);
+    let _scan = HEAP_SCAN.lock().unwrap_or_else(PoisonError::into_inner);
+    for (slot, needle) in HEAP_NEEDLES.iter().zip(needles) {
+        for (a, b) in slot.iter().zip(needle) {
+            a.store(*b, Ordering::SeqCst);
+        }
+    }
+    HEAP_NEEDLE_COUNT.store(needles.len(), Ordering::SeqCst);
+    HEAP_HIT.store(false, Ordering::SeqCst);
+    ARMED.store(true, Ordering::SeqCst);
+    f();
+    ARMED.store(false, Ordering::SeqCst);
+    HEAP_HIT.load(Ordering::SeqCst)
+}
+
+/// Overwrites and maps the region the scan will read.
+#[inline(never)]
+fn burn_stack() {
+    let mut buf = [0u8; SCAN_BYTES + 4096];
+    black_box(&mut buf);
+}
+
+/// Copies the `SCAN_BYTES` below this frame's stack pointer into `dst`.
+#[inline(never)]
+fn copy_dead_stack(dst: *mut u8) {
+    unsafe {
+        asm!(
+            "mov rsi, rsp",
+            "sub rsi, {len}",
+            "rep movsb",
+            len = const SCAN_BYTES,
+            inout("rcx") SCAN_BYTES => _,
+            inout("rdi") dst => _,
+            out("rsi") _,
+            options(nostack, preserves_flags),
+        );
+    }
+}
+
+fn dead_stack(buf: &mut Vec<u8>) {
+    buf.clear();
+    copy_dead_stack(buf.as_mut_ptr());
+    unsafe { buf.set_len(SCAN_BYTES) };
+}
+
+#[track_caller]
+fn assert_absent(stack: &[u8], what: &str, secret: &[u8]) {
+    for chunk in secret.chunks_exact(NEEDLE) {
+        assert!(!contains(stack, chunk), "{what} left on the stack");
+    }
+}
+
+#[test]
+fn no_key_material_left_after_keygen_and_signing() {
+    thread::Builder::new()
+        .stack_size(8 * SCAN_BYTES)
+        .spawn(|| {
+            let alg = Algorithm::Mode2;
+            let mut rng = Box::new(RecordingRng { inner: StdRng::seed_from_u64(33), out: Vec::with_capacity(4096) });
+            let mut stack = Vec::with_capacity(SCAN_BYTES);
+            let mut pk = vec![0u8; alg.public_key_bytes()];
+            let mut sk = vec![0u8; alg.secret_key_bytes()];
+
+            burn_stack();
+            alg.generate_into(&mut pk, &mut sk, &mut *rng).unwrap();
+            dead_stack(&mut stack);
+            assert_absent(&stack, "keygen seed", &rng.out[..32]);
+            assert_absent(&stack, "K", &sk[32..64]);
+
+            let keypair = Box::new(alg.load_from_bytes(&sk, &pk).unwrap());
+            burn_stack();
+            let sig = keypair.sign_with_rng(b"scan me", &mut *rng);
+            dead_stack(&mut stack);
+            assert_absent(&stack, "K", &sk[32..64]);
+            #[cfg(feature = "random")]
+            assert_absent(&stack, "rhoprime", &rng.out[32..96]);
+            assert!(alg.verify(&sig, b"scan me", &pk).is_ok());
+
+            let k: [u8; NEEDLE] = sk[32..32 + NEEDLE].try_into().unwrap();
+            assert!(!freed_blocks_contain(&[k], || drop(keypair)), "dropped Keypair left K on the heap");
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn keypair_debug_redacts_secret() {
+    let mut rng = StdRng::seed_from_u64(34);
+    let keypair: Keypair = Algorithm::Mode2.generate_with_rng(&mut rng);
+    let printed = format!("{keypair:?}");
+    assert!(printed.contains("<redacted>"));
+    assert!(!printed.contains(&format!("{:?}", &keypair.secret()[32..40])));
+}
+
+/// The seed itself and windows of its base64 in `alphabet` at each of the
+/// three offsets it can have inside an encoded container.
+#[cfg(any(feature = "ssh", feature = "jose"))]
+fn seed_needles(seed: &[u8; 32], alphabet: &[u8; 64]) -> Vec<[u8; NEEDLE]> {
+    let mut needles = vec![seed[..NEEDLE].try_into().unwrap()];
+    for shift in 0..3 {
+        let mut text = Vec::new();
+        for chunk in seed[shift..].chunks_exact(3) {
+            let n = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+            text.extend((0..4).map(|i| alphabet[(n >> (18 - 6 * i)) as usize & 63]));
+        }
+        for start in (0..=text.len() - NEEDLE).step_by(4) {
+            needles.push(text[start..start + NEEDLE].try_into().unwrap());
+        }
+    }
+    needles
+}
+
+#[cfg(feature = "ssh")]
+#[test]
+fn no_seed_left_after_openssh_round_trip() {
+    use pure_dsa::{SeedKey, ssh::PrivateKey};
+
+    let mut rng = StdRng::seed_from_u64(49);
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let key = PrivateKey::new(SeedKey::from_seed(Algorithm::Mode2, &seed).unwrap()).unwrap();
+    let needles = seed_needles(&seed, b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/");
+
+    let mut pem = String::new();
+    let leaked = freed_blocks_contain(&needles, || pem = key.to_openssh_with_rng(&mut rng));
+    assert!(!leaked, "writing an OpenSSH key left the seed on the heap");
+    // The scan is only meaningful if the armor really carries the needles.
+    let armored: String = pem.lines().collect();
+    assert!(needles[1..].iter().any(|n| contains(armored.as_bytes(), n)));
+
+    let leaked = freed_blocks_contain(&needles, || {
+        let parsed = PrivateKey::from_openssh(&pem).unwrap();
+        assert_eq!(parsed.key().seed(), Some(&seed[..]));
+    });
+    assert!(!leaked, "reading an OpenSSH key left the seed on the heap");
+}
+
+#[cfg(feature = "jose")]
+#[test]
+fn no_seed_left_after_jwk_round_trip() {
+    use pure_dsa::{SeedKey, jose::Jwk};
+
+    let mut rng = StdRng::seed_from_u64(47);
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let jwk = Jwk::from(SeedKey::from_seed(Algorithm::Mode2, &seed).unwrap());
+    let needles = seed_needles(&seed, b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_");
+
+    let mut json = String::new();
+    assert!(!freed_blocks_contain(&needles, || json = jwk.to_json()), "writing a JWK left the seed on the heap");
+    assert!(needles[1..].iter().any(|n| contains(json.as_bytes(), n)));
+
+    let leaked = freed_blocks_contain(&needles, || {
+        let parsed = Jwk::from_json(&json).unwrap();
+        assert_eq!(parsed.key().seed(), Some(&seed[..]));
+    });
+    assert!(!leaked, "reading a JWK left the seed on the heap");
+}